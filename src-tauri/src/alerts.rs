@@ -0,0 +1,85 @@
+//! Low-score alerts: whether a newly scored session deserves a desktop
+//! notification, and what it should say. Safety rules are treated as critical.
+
+use crate::{RuleCategory, RuleDefinition, SessionScore};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// Most failed rules named in a notification body
+const MAX_LISTED_RULES: usize = 3;
+
+/// When a newly scored session raises a notification
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct AlertSettings {
+    pub enabled: bool,
+    /// Alert on scores below this percentage
+    pub below_percentage: f64,
+    /// Alert whenever a Safety rule fails, whatever the score
+    pub critical_failures: bool,
+}
+
+impl Default for AlertSettings {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            below_percentage: 50.0,
+            critical_failures: true,
+        }
+    }
+}
+
+/// A notification for a session that needs attention
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct ScoreAlert {
+    pub session_id: String,
+    pub title: String,
+    pub body: String,
+    /// Names of every failed rule, critical ones first
+    pub failed_rules: Vec<String>,
+}
+
+/// The alert for `score`, if it's below the threshold or failed a critical rule.
+/// `rules` are the definitions it was scored with, for looking up categories.
+pub fn score_alert(score: &SessionScore, rules: &[RuleDefinition], settings: &AlertSettings) -> Option<ScoreAlert> {
+    if !settings.enabled {
+        return None;
+    }
+    let is_critical = |rule_id: &str| {
+        rules
+            .iter()
+            .any(|rule| rule.id == rule_id && rule.category == RuleCategory::Safety)
+    };
+    let (critical, other): (Vec<_>, Vec<_>) = score
+        .rules
+        .iter()
+        .filter(|check| !check.passed)
+        .partition(|check| is_critical(&check.rule_id));
+
+    let low = score.score_percentage < settings.below_percentage;
+    let critical_failed = settings.critical_failures && !critical.is_empty();
+    if !low && !critical_failed {
+        return None;
+    }
+
+    let failed_rules: Vec<String> = critical.iter().chain(&other).map(|check| check.rule_name.clone()).collect();
+    let title = if critical_failed {
+        format!("Critical rule failed in {}", score.session_id)
+    } else {
+        format!("Low score for {}", score.session_id)
+    };
+    let mut body = format!("{:.0}% adherence", score.score_percentage);
+    if !failed_rules.is_empty() {
+        body.push_str(". Failed: ");
+        body.push_str(&failed_rules[..failed_rules.len().min(MAX_LISTED_RULES)].join(", "));
+        if failed_rules.len() > MAX_LISTED_RULES {
+            body.push_str(&format!(" and {} more", failed_rules.len() - MAX_LISTED_RULES));
+        }
+    }
+    Some(ScoreAlert {
+        session_id: score.session_id.clone(),
+        title,
+        body,
+        failed_rules,
+    })
+}
@@ -0,0 +1,64 @@
+use crate::db::{Database, DbError, Outcomes, RuleBaseline};
+use crate::SessionScore;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// A failed rule that historically almost always passes
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RuleAnomaly {
+    pub rule_id: String,
+    pub historical_pass_rate: f64,
+    pub sample_size: i64,
+}
+
+/// When a failing rule counts as anomalous
+#[derive(Debug, Clone)]
+pub struct AnomalyThresholds {
+    /// Minimum historical pass rate (0-100) for a failure to be flagged
+    pub min_pass_rate: f64,
+    /// Minimum number of past checks before a baseline is trusted
+    pub min_samples: i64,
+}
+
+impl Default for AnomalyThresholds {
+    fn default() -> Self {
+        Self {
+            min_pass_rate: 95.0,
+            min_samples: 10,
+        }
+    }
+}
+
+/// Flag failed rules in `score` whose baseline says they normally pass
+pub fn flag_anomalies(
+    score: &mut SessionScore,
+    baselines: &[RuleBaseline],
+    thresholds: &AnomalyThresholds,
+) {
+    score.anomalies = score
+        .rules
+        .iter()
+        .filter(|check| !check.passed)
+        .filter_map(|check| {
+            let baseline = baselines.iter().find(|b| b.rule_id == check.rule_id)?;
+            (baseline.total >= thresholds.min_samples
+                && baseline.pass_rate >= thresholds.min_pass_rate)
+                .then(|| RuleAnomaly {
+                    rule_id: check.rule_id.clone(),
+                    historical_pass_rate: baseline.pass_rate,
+                    sample_size: baseline.total,
+                })
+        })
+        .collect();
+}
+
+/// Flag anomalies using the baselines stored in the database
+pub async fn flag_anomalies_from_history(
+    db: &Database,
+    score: &mut SessionScore,
+    thresholds: &AnomalyThresholds,
+) -> Result<(), DbError> {
+    let baselines = db.get_rule_baselines(Outcomes::Raw).await?;
+    flag_anomalies(score, &baselines, thresholds);
+    Ok(())
+}
@@ -0,0 +1,201 @@
+//! Archive ingestion: `.zip`, `.tar`, and `.tar.gz` bundles of session logs are
+//! extracted into a sandbox directory under the allowed base path and scanned
+//! like any other directory. The sandbox is removed once scoring finishes.
+
+use crate::scan::{self, ScanEvent, ScanOptions, ScanSummary, MAX_FILE_SIZE};
+use crate::{BehaviorScorer, SessionScore};
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Maximum total bytes extracted from one archive
+pub const MAX_EXTRACTED_SIZE: u64 = 512 * 1024 * 1024; // 512MB
+
+/// Maximum number of entries extracted from one archive
+pub const MAX_ENTRIES: usize = 10_000;
+
+/// Prefix of sandbox directories created under the base path
+const SANDBOX_PREFIX: &str = ".archive-";
+
+static SANDBOX_COUNTER: AtomicUsize = AtomicUsize::new(0);
+
+/// Supported archive layouts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ArchiveKind {
+    Zip,
+    Tar,
+    TarGz,
+}
+
+impl ArchiveKind {
+    fn from_path(path: &Path) -> Option<Self> {
+        let name = path.file_name()?.to_str()?.to_ascii_lowercase();
+        if name.ends_with(".zip") {
+            Some(ArchiveKind::Zip)
+        } else if name.ends_with(".tar.gz") || name.ends_with(".tgz") {
+            Some(ArchiveKind::TarGz)
+        } else if name.ends_with(".tar") {
+            Some(ArchiveKind::Tar)
+        } else {
+            None
+        }
+    }
+}
+
+/// Whether a path names a supported archive
+pub fn is_archive(path: &Path) -> bool {
+    ArchiveKind::from_path(path).is_some()
+}
+
+/// Temporary extraction directory, removed when dropped
+struct Sandbox {
+    path: PathBuf,
+}
+
+impl Sandbox {
+    fn create(parent: &Path) -> Result<Self, String> {
+        let nanos = SystemTime::now().duration_since(UNIX_EPOCH).map(|d| d.as_nanos()).unwrap_or_default();
+        let counter = SANDBOX_COUNTER.fetch_add(1, Ordering::Relaxed);
+        let path = parent.join(format!("{}{}-{}-{}", SANDBOX_PREFIX, std::process::id(), nanos, counter));
+        fs::create_dir(&path).map_err(|e| format!("Failed to create extraction directory: {}", e))?;
+        Ok(Self { path })
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
+
+/// Running totals checked against the extraction limits
+#[derive(Default)]
+struct ExtractionBudget {
+    entries: usize,
+    bytes: u64,
+}
+
+impl ExtractionBudget {
+    /// SECURITY: Copy one entry into `target`, enforcing the per-file and total size limits
+    fn extract(&mut self, reader: &mut dyn Read, target: &Path) -> Result<(), String> {
+        self.entries += 1;
+        if self.entries > MAX_ENTRIES {
+            return Err(format!("Archive has more than {} entries", MAX_ENTRIES));
+        }
+        if let Some(parent) = target.parent() {
+            fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+        }
+
+        let limit = MAX_FILE_SIZE.min(MAX_EXTRACTED_SIZE - self.bytes);
+        let mut file = fs::File::create(target).map_err(|e| e.to_string())?;
+        let written = io::copy(&mut reader.take(limit + 1), &mut file).map_err(|e| e.to_string())?;
+        if written > limit {
+            return Err(if limit < MAX_FILE_SIZE {
+                "Archive exceeds maximum extracted size of 512MB".to_string()
+            } else {
+                format!("Archive entry {} exceeds maximum size of 40MB", target.display())
+            });
+        }
+        self.bytes += written;
+        Ok(())
+    }
+}
+
+impl BehaviorScorer {
+    /// SECURITY: Extract an archive within the allowed base path into a temporary
+    /// sandbox and score every session in it
+    pub fn scan_archive(
+        &self,
+        archive_path: &Path,
+        options: &ScanOptions,
+    ) -> Result<Vec<SessionScore>, String> {
+        let summary = self.scan_archive_with_progress(archive_path, options, scan::log_scan_event)?;
+        Ok(summary.scores)
+    }
+
+    /// SECURITY: Like `scan_archive`, calling `on_event` as sessions are found and scored
+    pub fn scan_archive_with_progress(
+        &self,
+        archive_path: &Path,
+        options: &ScanOptions,
+        on_event: impl FnMut(&ScanEvent),
+    ) -> Result<ScanSummary, String> {
+        let kind = ArchiveKind::from_path(archive_path)
+            .ok_or_else(|| format!("Unsupported archive: {}", archive_path.display()))?;
+        if !archive_path.is_file() {
+            return Err(format!("Not an archive file: {}", archive_path.display()));
+        }
+        self.validate_directory(archive_path.parent().unwrap_or(Path::new(".")))?;
+
+        let base = self.base_path.canonicalize().map_err(|e| format!("Invalid base path: {}", e))?;
+        let sandbox = Sandbox::create(&base)?;
+        extract(archive_path, kind, &sandbox.path)?;
+        let mut summary = self.scan_with_progress(&sandbox.path, options, on_event)?;
+        // Point at the file inside the archive rather than the sandbox it was extracted to
+        for score in &mut summary.scores {
+            score.source_path = score.source_path.as_deref().map(|path| {
+                let inner = Path::new(path).strip_prefix(&sandbox.path).unwrap_or(Path::new(path));
+                format!("{}!/{}", archive_path.display(), inner.display())
+            });
+        }
+        Ok(summary)
+    }
+}
+
+/// SECURITY: Extract regular files only, refusing entries that would land outside `dest`
+fn extract(archive_path: &Path, kind: ArchiveKind, dest: &Path) -> Result<(), String> {
+    let file = fs::File::open(archive_path).map_err(|e| format!("Failed to open {}: {}", archive_path.display(), e))?;
+    let mut budget = ExtractionBudget::default();
+
+    match kind {
+        ArchiveKind::Zip => {
+            let mut archive = zip::ZipArchive::new(file).map_err(|e| format!("Invalid zip archive: {}", e))?;
+            for index in 0..archive.len() {
+                let mut entry = archive.by_index(index).map_err(|e| format!("Invalid zip entry: {}", e))?;
+                if !entry.is_file() {
+                    continue;
+                }
+                let relative = entry
+                    .enclosed_name()
+                    .and_then(safe_relative_path)
+                    .ok_or_else(|| format!("Unsafe path in archive: {}", entry.name()))?;
+                budget.extract(&mut entry, &dest.join(relative))?;
+            }
+        }
+        ArchiveKind::Tar | ArchiveKind::TarGz => {
+            let reader: Box<dyn Read> = if kind == ArchiveKind::TarGz {
+                Box::new(flate2::read::GzDecoder::new(file))
+            } else {
+                Box::new(file)
+            };
+            let mut archive = tar::Archive::new(reader);
+            for entry in archive.entries().map_err(|e| format!("Invalid tar archive: {}", e))? {
+                let mut entry = entry.map_err(|e| format!("Invalid tar entry: {}", e))?;
+                if !entry.header().entry_type().is_file() {
+                    continue;
+                }
+                let path = entry.path().map_err(|e| format!("Invalid tar entry: {}", e))?.into_owned();
+                let relative = safe_relative_path(&path)
+                    .ok_or_else(|| format!("Unsafe path in archive: {}", path.display()))?;
+                budget.extract(&mut entry, &dest.join(relative))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Keep only normal path components, rejecting absolute paths and `..`
+fn safe_relative_path(path: &Path) -> Option<PathBuf> {
+    let mut relative = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::Normal(part) => relative.push(part),
+            Component::CurDir => {}
+            _ => return None,
+        }
+    }
+    (!relative.as_os_str().is_empty()).then_some(relative)
+}
@@ -1,13 +1,80 @@
-use clap::{Parser, Subcommand};
-use data_behavior_dashboard_lib::BehaviorScorer;
-use std::path::PathBuf;
+use clap::{ArgAction, CommandFactory, FromArgMatches, Parser, Subcommand};
+use data_behavior_dashboard_lib::alerts::{score_alert, AlertSettings};
+use data_behavior_dashboard_lib::archive;
+use data_behavior_dashboard_lib::chunking::{self, ChunkOptions};
+use data_behavior_dashboard_lib::cli_config::{self, CliConfig};
+use data_behavior_dashboard_lib::compare::{compare_scores, RuleDiff, ScoreComparison};
+use data_behavior_dashboard_lib::encoding::{self, Decoded};
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use data_behavior_dashboard_lib::db::{
+    Database, DbConfig, DbHealth, PendingMigration, ListFilters, ScoreBand, ScoreHistory, StatsReport, TimeBucket,
+};
+use data_behavior_dashboard_lib::export::{self, ExportKind, HistoryImportReport};
+use data_behavior_dashboard_lib::import::{ImportMapping, ImportReport};
+use data_behavior_dashboard_lib::ingest::{self, IngestReport};
+use data_behavior_dashboard_lib::junit;
+use data_behavior_dashboard_lib::logging;
+use data_behavior_dashboard_lib::markdown;
+use data_behavior_dashboard_lib::profiles::{self, AggregateStats, ProfileRegistry, DEFAULT_DB_FILE, REGISTRY_FILE};
+use data_behavior_dashboard_lib::progress::ScanProgress;
+use data_behavior_dashboard_lib::rescore::RescoreReport;
+use data_behavior_dashboard_lib::rule_filter::RuleFilter;
+use data_behavior_dashboard_lib::rule_impact::{self, RuleSetImpact};
+use data_behavior_dashboard_lib::rules_file;
+use data_behavior_dashboard_lib::sarif;
+use data_behavior_dashboard_lib::settings::{Settings, SETTINGS_FILE};
+use data_behavior_dashboard_lib::summary::{self, Style};
+use data_behavior_dashboard_lib::tui;
+use data_behavior_dashboard_lib::watch::{self, persist_score};
+use data_behavior_dashboard_lib::{scan, session_id, BehaviorScorer, DuplicatePolicy, RuleCategory, ScanOptions, SessionIdStrategy, SessionScore};
+use std::io::{IsTerminal, Read};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+use tracing::{error, info, warn};
 
 #[derive(Parser)]
 #[command(name = "behavior-scorer")]
 #[command(about = "CLI for scoring Data behavior against operating rules")]
 struct Cli {
     #[command(subcommand)]
-    command: Commands,
+    command: CliCommand,
+    /// Record per-rule evaluation timings
+    #[arg(long, global = true)]
+    diagnostics: bool,
+    /// Evaluate transcripts in chunks of this many bytes, allowing transcripts over 10MB
+    #[arg(long, global = true)]
+    chunk_size: Option<usize>,
+    /// Bytes shared between consecutive chunks
+    #[arg(long, global = true, default_value_t = chunking::DEFAULT_OVERLAP, requires = "chunk_size")]
+    chunk_overlap: usize,
+    /// App settings file (base path, thresholds); defaults to the desktop app's
+    #[arg(long, global = true)]
+    settings: Option<PathBuf>,
+    /// TOML rules file to score with, overriding the one registered by `init`
+    #[arg(long, global = true)]
+    rules: Option<PathBuf>,
+    /// Print summaries without color; color is also off when stdout isn't a terminal or NO_COLOR is set
+    #[arg(long, global = true)]
+    no_color: bool,
+    /// Log more on stderr: -v for progress, -vv for each session, -vvv for everything
+    #[arg(short, long, global = true, action = ArgAction::Count)]
+    verbose: u8,
+    /// Log only errors
+    #[arg(short, long, global = true, conflicts_with = "verbose")]
+    quiet: bool,
+    /// Log as JSON lines, one event per line
+    #[arg(long, global = true)]
+    log_json: bool,
+}
+
+/// Commands that score, and commands on the rules file itself, which run without
+/// building a scorer so a broken file can be checked or replaced
+#[derive(Subcommand)]
+enum CliCommand {
+    #[command(flatten)]
+    Scorer(Box<Commands>),
+    #[command(flatten)]
+    RulesFile(RulesFileCommand),
 }
 
 #[derive(Subcommand)]
@@ -17,112 +84,1830 @@ enum Commands {
         /// Session ID
         #[arg(short, long)]
         session: String,
-        /// Path to transcript file
-        #[arg(short, long)]
-        transcript: PathBuf,
-        /// Output format
+        /// Path to transcript file (.md, .jsonl session log, or chat-completions .json; optionally .gz/.zst),
+        /// or `-` to read from stdin
+        #[arg(short, long, required_unless_present = "url")]
+        transcript: Option<PathBuf>,
+        /// HTTPS URL to download the transcript from
+        #[arg(long, conflicts_with = "transcript")]
+        url: Option<String>,
+        /// Exit nonzero when a session scores below this percentage
+        #[arg(long)]
+        fail_under: Option<f64>,
+        /// Exit nonzero when a session fails a Safety rule
+        #[arg(long)]
+        fail_on_critical: bool,
+        /// Don't exit nonzero for Safety rule failures, overriding the config file
+        #[arg(long, conflicts_with = "fail_on_critical")]
+        no_fail_on_critical: bool,
+        /// Comma-separated IDs of the only rules to score
+        #[arg(long, value_delimiter = ',')]
+        only: Vec<String>,
+        /// Comma-separated IDs of rules to leave out
+        #[arg(long, value_delimiter = ',')]
+        skip: Vec<String>,
+        /// Only score rules in these comma-separated categories: startup, response, confidence,
+        /// safety, or communication
+        #[arg(long, value_delimiter = ',')]
+        category: Vec<RuleCategory>,
+        /// Output format: json, summary, sarif (failed rules as code scanning results), junit
+        /// (rules as test cases), or markdown (a report to paste into chat or a PR)
         #[arg(short, long, default_value = "json")]
         format: String,
     },
     /// Scan directory and score all sessions
     Scan {
-        /// Directory to scan
+        /// Directory to scan, or a .zip/.tar/.tar.gz archive of session logs
+        #[arg(short, long, default_value = "~/.codex/sessions")]
+        directory: PathBuf,
+        /// Only score files matching this glob, relative to the directory (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+        /// Skip files matching this glob, relative to the directory (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+        /// Maximum recursion depth
+        #[arg(long, default_value_t = scan::DEFAULT_MAX_DEPTH)]
+        max_depth: usize,
+        /// Comma-separated transcript extensions to score
+        #[arg(long, value_delimiter = ',', default_value = "md,json,jsonl")]
+        extensions: Vec<String>,
+        /// Sessions with identical content: skip, link, or keep
+        #[arg(long, default_value = "link")]
+        duplicates: String,
+        /// Session ID derivation: file-name, frontmatter, or content-hash
+        #[arg(long, default_value = "file-name")]
+        session_ids: String,
+        /// SQLite database tracking scanned files; only new or changed sessions are rescored
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Store a new score row even when a re-score matches the latest stored one
+        #[arg(long, requires = "db")]
+        keep_identical_scores: bool,
+        /// Sessions to score at once
+        #[arg(short, long, default_value_t = 1)]
+        jobs: usize,
+        /// Exit nonzero when a session scores below this percentage
+        #[arg(long)]
+        fail_under: Option<f64>,
+        /// Exit nonzero when a session fails a Safety rule
+        #[arg(long)]
+        fail_on_critical: bool,
+        /// Don't exit nonzero for Safety rule failures, overriding the config file
+        #[arg(long, conflicts_with = "fail_on_critical")]
+        no_fail_on_critical: bool,
+        /// Comma-separated IDs of the only rules to score; not with --db, whose cache holds
+        /// scores over every rule
+        #[arg(long, value_delimiter = ',', conflicts_with = "db")]
+        only: Vec<String>,
+        /// Comma-separated IDs of rules to leave out
+        #[arg(long, value_delimiter = ',', conflicts_with = "db")]
+        skip: Vec<String>,
+        /// Only score rules in these comma-separated categories: startup, response, confidence,
+        /// safety, or communication
+        #[arg(long, value_delimiter = ',', conflicts_with = "db")]
+        category: Vec<RuleCategory>,
+        /// Output format: json, summary, sarif (failed rules as code scanning results), junit
+        /// (rules as test cases), markdown (a report to paste into chat or a PR), or csv
+        /// (a row per session with per-category columns)
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+    /// Watch a directory and score transcripts as they're written, printing one line per score
+    Watch {
+        /// Directory to watch
+        #[arg(short, long, default_value = "~/.codex/sessions")]
+        directory: PathBuf,
+        /// Only score files matching this glob, relative to the directory (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+        /// Skip files matching this glob, relative to the directory (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+        /// Maximum recursion depth
+        #[arg(long, default_value_t = scan::DEFAULT_MAX_DEPTH)]
+        max_depth: usize,
+        /// Comma-separated transcript extensions to score
+        #[arg(long, value_delimiter = ',', default_value = "md,json,jsonl")]
+        extensions: Vec<String>,
+        /// Milliseconds a file must go unchanged before it is scored
+        #[arg(long, default_value_t = watch::DEFAULT_DEBOUNCE_MS)]
+        debounce_ms: u64,
+        /// SQLite database to store each score in
+        #[arg(long)]
+        db: Option<PathBuf>,
+        /// Output format: summary (one line per score) or json (one object per line)
+        #[arg(short, long, default_value = "summary")]
+        format: String,
+    },
+    /// Open a terminal dashboard of a directory's scores, updating as transcripts are written
+    Tui {
+        /// Directory to scan and watch
         #[arg(short, long, default_value = "~/.codex/sessions")]
         directory: PathBuf,
+        /// Only score files matching this glob, relative to the directory (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+        /// Skip files matching this glob, relative to the directory (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+        /// Maximum recursion depth
+        #[arg(long, default_value_t = scan::DEFAULT_MAX_DEPTH)]
+        max_depth: usize,
+        /// Comma-separated transcript extensions to score
+        #[arg(long, value_delimiter = ',', default_value = "md,json,jsonl")]
+        extensions: Vec<String>,
+        /// Milliseconds a file must go unchanged before it is scored
+        #[arg(long, default_value_t = watch::DEFAULT_DEBOUNCE_MS)]
+        debounce_ms: u64,
+    },
+    /// Import and score history: a directory, archive, or transcript file, or with --source,
+    /// sessions from another tool's SQLite database
+    Import {
+        /// Directory, .zip/.tar/.tar.gz archive, or transcript file (.md, .json, .jsonl) to import
+        #[arg(required_unless_present = "source", conflicts_with = "source")]
+        path: Option<PathBuf>,
+        /// SQLite database to read sessions from
+        #[arg(long, requires = "mapping")]
+        source: Option<PathBuf>,
+        /// YAML or JSON file naming the table and columns to read (see `ImportMapping`)
+        #[arg(long, requires = "source")]
+        mapping: Option<PathBuf>,
+        /// Only import files matching this glob, relative to the directory (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+        /// Skip files matching this glob, relative to the directory (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+        /// Session ID derivation: file-name, frontmatter, or content-hash
+        #[arg(long, default_value = "file-name")]
+        session_ids: String,
+        /// SQLite database to store imported sessions and scores in
+        #[arg(long)]
+        db: PathBuf,
+        /// Output format
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+    /// Snapshot a scoring database to a new file; safe while the app is running
+    Backup {
+        /// SQLite database to snapshot
+        #[arg(long)]
+        db: PathBuf,
+        /// File to write the snapshot to; must not exist
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Replace a scoring database's contents with a snapshot made by `backup`
+    Restore {
+        /// SQLite database to restore into
+        #[arg(long)]
+        db: PathBuf,
+        /// Snapshot to restore from
+        #[arg(long)]
+        input: PathBuf,
+    },
+    /// Export sessions, scores, or rule checks from a scoring database to CSV or Parquet
+    Export {
+        /// SQLite database to export from
+        #[arg(long)]
+        db: PathBuf,
+        /// What to export: sessions, scores, or rule-checks
+        #[arg(long, default_value = "scores")]
+        kind: String,
+        /// File to write
+        #[arg(short, long)]
+        output: PathBuf,
+        /// File format: csv, parquet (scores and rule checks only), or jsonl (full history; kind and filters ignored)
+        #[arg(short, long, default_value = "csv")]
+        format: String,
+        /// Only sessions from this source
+        #[arg(long)]
+        source: Option<String>,
+        /// Earliest time to include (RFC 3339 or YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// Latest time to include (RFC 3339 or YYYY-MM-DD)
+        #[arg(long)]
+        until: Option<String>,
+        /// Score band: excellent, good, moderate, or poor
+        #[arg(long)]
+        band: Option<String>,
+        /// Minimum score percentage
+        #[arg(long)]
+        min_percentage: Option<f64>,
+        /// Maximum score percentage
+        #[arg(long)]
+        max_percentage: Option<f64>,
+        /// Only sessions tagged with this tag in their metadata
+        #[arg(long)]
+        tag: Option<String>,
+    },
+    /// Load history exported with `export --format jsonl` into a scoring database
+    ImportHistory {
+        /// SQLite database to load history into
+        #[arg(long)]
+        db: PathBuf,
+        /// JSON Lines file written by `export --format jsonl`
+        #[arg(long)]
+        input: PathBuf,
+    },
+    /// Check or maintain a scoring database
+    Db {
+        #[command(subcommand)]
+        command: DbCommand,
+    },
+    /// Manage named databases shared with the app (one per project or agent)
+    Profile {
+        /// Profile registry file; defaults to the app's
+        #[arg(long, global = true)]
+        registry: Option<PathBuf>,
+        #[command(subcommand)]
+        command: ProfileCommand,
+    },
+    /// Re-score every stored transcript with the current rules, e.g. after editing rules
+    Rescore {
+        /// SQLite database holding the transcripts; new scores are stored in it
+        #[arg(long)]
+        db: PathBuf,
+        /// Output format
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+    /// Summarize a scoring database; opens it read-only, so it's safe while the app is running
+    Stats {
+        /// SQLite database to summarize
+        #[arg(long)]
+        db: PathBuf,
+        /// How many of the most-failed rules to list
+        #[arg(long, default_value_t = 10)]
+        top: i64,
+        /// Output format: json or table
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+    /// Compare two transcripts, or two stored sessions with --db, rule by rule
+    Compare {
+        /// First transcript file, or session ID with --db
+        a: String,
+        /// Second transcript file, or session ID with --db; deltas are relative to the first
+        b: String,
+        /// Compare the latest stored scores of two sessions in this database instead
+        #[arg(long)]
+        db: Option<PathBuf>,
         /// Output format
         #[arg(short, long, default_value = "json")]
         format: String,
     },
+    /// Score a corpus under two rules files and report which sessions and rules change outcome
+    DiffRules {
+        /// Rules file in use now
+        #[arg(long)]
+        old: PathBuf,
+        /// Rules file to evaluate against it
+        #[arg(long)]
+        new: PathBuf,
+        /// Directory, .zip/.tar/.tar.gz archive, or transcript file to score under both
+        #[arg(long)]
+        corpus: PathBuf,
+        /// Only score files matching this glob, relative to the corpus directory (repeatable)
+        #[arg(long)]
+        include: Vec<String>,
+        /// Skip files matching this glob, relative to the corpus directory (repeatable)
+        #[arg(long)]
+        exclude: Vec<String>,
+        /// Sessions to score at once
+        #[arg(short, long, default_value_t = 1)]
+        jobs: usize,
+        /// Output format: json or summary
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+    /// Show past scores from a scoring database and how they trend; opens it read-only
+    History {
+        /// SQLite database to read
+        #[arg(long)]
+        db: PathBuf,
+        /// Only scores of this session
+        #[arg(long)]
+        session: Option<String>,
+        /// Earliest score to include (RFC 3339 or YYYY-MM-DD)
+        #[arg(long)]
+        since: Option<String>,
+        /// Most scores to list; the trend covers every matching score
+        #[arg(long, default_value_t = 20)]
+        limit: i64,
+        /// Trend buckets: day or week
+        #[arg(long, default_value = "day")]
+        by: String,
+        /// Output format: json or summary
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
     /// List all rules
     Rules,
 }
 
-fn main() {
-    let cli = Cli::parse();
-    let scorer = BehaviorScorer::new();
-    
-    match cli.command {
-        Commands::Score { session, transcript, format } => {
-            // SECURITY: Validate transcript file path
-            let transcript = match std::fs::read_to_string(&transcript) {
-                Ok(content) => content,
+#[derive(Subcommand)]
+enum RulesFileCommand {
+    /// Write a commented starter rules file with the built-in rules and score with it from now on
+    Init {
+        /// Rules file to write; defaults to rules.toml next to the config file
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Replace the file if it already exists
+        #[arg(long)]
+        force: bool,
+    },
+    /// Check the --rules file, or the registered one: patterns compile, examples pass, IDs
+    /// are unique, and weights are valid; exits nonzero on any problem
+    Validate {
+        /// Output format
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum DbCommand {
+    /// Check integrity and foreign keys and report sizes; exits nonzero on problems
+    Check {
+        /// SQLite database to check; opened read-only
+        #[arg(long)]
+        db: PathBuf,
+        /// Output format
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+    /// Apply pending schema migrations; see `db status` for what they are
+    Migrate {
+        /// SQLite database to migrate
+        #[arg(long)]
+        db: PathBuf,
+    },
+    /// Snapshot a scoring database to a new file; same as the top-level `backup`
+    Backup {
+        /// SQLite database to snapshot
+        #[arg(long)]
+        db: PathBuf,
+        /// File to write the snapshot to; must not exist
+        #[arg(long)]
+        output: PathBuf,
+    },
+    /// Permanently delete trashed sessions with their scores and transcripts
+    Prune {
+        /// SQLite database to prune
+        #[arg(long)]
+        db: PathBuf,
+        /// Only sessions trashed before this time (RFC 3339 or YYYY-MM-DD); defaults to the whole trash
+        #[arg(long)]
+        older_than: Option<String>,
+    },
+    /// Show the schema version and the migrations the next open would apply
+    Status {
+        /// SQLite database to inspect; not migrated
+        #[arg(long)]
+        db: PathBuf,
+        /// Also print the SQL each pending migration would run
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Refresh planner statistics and reclaim free space (ANALYZE and VACUUM)
+    Optimize {
+        /// SQLite database to optimize
+        #[arg(long)]
+        db: PathBuf,
+    },
+    /// Undo schema migrations down to a version; data in dropped tables and columns is lost
+    Rollback {
+        /// SQLite database to roll back
+        #[arg(long)]
+        db: PathBuf,
+        /// Schema version to return to
+        #[arg(long)]
+        to: i64,
+    },
+}
+
+#[derive(Subcommand)]
+enum ProfileCommand {
+    /// List profiles, marking the active one
+    List,
+    /// Register a database under a new profile name
+    Add {
+        /// Profile name (letters, digits, '-' and '_')
+        name: String,
+        /// SQLite database for the profile; created when first opened
+        #[arg(long)]
+        db: PathBuf,
+    },
+    /// Unregister a profile, leaving its database file in place
+    Remove {
+        name: String,
+    },
+    /// Make a profile the active one; the app picks it up on its next start
+    Use {
+        name: String,
+    },
+    /// Summarize every profile's database, with totals; opens them read-only
+    Stats {
+        /// Output format
+        #[arg(short, long, default_value = "json")]
+        format: String,
+    },
+}
+
+/// Expand a leading `~` to the home directory
+fn expand_home(path: PathBuf) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => PathBuf::from(std::env::var("HOME").unwrap_or_default()).join(rest),
+        Err(_) => path,
+    }
+}
+
+/// SECURITY: Read a transcript from stdin, stopping just past the file size limit
+fn read_stdin(limit: u64) -> Result<Decoded, String> {
+    let mut bytes = Vec::new();
+    std::io::stdin()
+        .take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read stdin: {}", e))?;
+    Ok(encoding::decode(bytes))
+}
+
+/// Narrow the scorer to the rules picked by `--only`, `--skip`, and `--category`
+fn filter_rules(scorer: BehaviorScorer, filter: RuleFilter) -> BehaviorScorer {
+    match scorer.with_rule_filter(&filter) {
+        Ok(scorer) => scorer,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+}
+
+/// Run an incremental scan against the scan cache in `db_path`
+fn scan_incremental(
+    scorer: &BehaviorScorer,
+    db_path: &Path,
+    directory: &Path,
+    options: &ScanOptions,
+    keep_identical_scores: bool,
+    progress: &ScanProgress,
+) -> Result<Vec<SessionScore>, String> {
+    let config = DbConfig {
+        dedupe_scores: !keep_identical_scores,
+        ..DbConfig::default()
+    };
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::with_config(db_path, &config).await.map_err(|e| e.to_string())?;
+        let scan = scorer
+            .scan_incremental_with_progress(&db, directory, options, |event| progress.update(event))
+            .await?;
+        info!(sessions = scan.scores.len(), rescored = scan.rescored.len(), "Incremental scan complete");
+        Ok(scan.scores)
+    })
+}
+
+/// Score transcripts in `directory` as they change until the process is interrupted,
+/// printing each result and storing it in `db_path` when given. Says what it's watching
+/// on stderr when `announce`.
+fn watch_directory(
+    scorer: &BehaviorScorer,
+    directory: &Path,
+    options: &ScanOptions,
+    debounce: Duration,
+    db_path: Option<&Path>,
+    json: bool,
+    announce: bool,
+) -> Result<(), String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    let db = match db_path {
+        Some(path) => Some(runtime.block_on(Database::new(path)).map_err(|e| e.to_string())?),
+        None => None,
+    };
+    let handle = runtime.handle().clone();
+    let strategy = options.session_ids;
+    let source = options.source_or(watch::WATCH_SOURCE).to_string();
+    let _watcher = scorer.watch(&[directory.to_path_buf()], options, debounce, move |path, result| {
+        let mut score = match result {
+            Ok(score) => score,
+            Err(e) => {
+                warn!(path = %path.display(), error = %e, "Failed to score transcript");
+                return;
+            }
+        };
+        if let Some(db) = &db {
+            if let Err(e) = handle.block_on(persist_score(db, &mut score, path, strategy, &source)) {
+                error!(session = %score.session_id, error = %e, "Failed to save score");
+            }
+        }
+        if json {
+            println!("{}", serde_json::to_string(&score).unwrap());
+        } else {
+            println!(
+                "{} {:>5.1}% {}/{} {} ({})",
+                score.timestamp.format("%H:%M:%S"),
+                score.score_percentage,
+                score.passed_rules,
+                score.total_rules,
+                score.session_id,
+                path.display()
+            );
+        }
+    })?;
+    if announce {
+        eprintln!("Watching {} (Ctrl-C to stop)", directory.display());
+    }
+    loop {
+        std::thread::park();
+    }
+}
+
+/// Score `directory`, then show the scores in the terminal dashboard while watching it for changes
+fn run_dashboard(scorer: &BehaviorScorer, directory: &Path, options: &ScanOptions, debounce: Duration) -> Result<(), String> {
+    let scores = scorer.scan_with_options(directory, options)?;
+    let (tx, rx) = std::sync::mpsc::channel();
+    let _watcher = scorer.watch(&[directory.to_path_buf()], options, debounce, move |path, result| {
+        let _ = tx.send(result.map_err(|e| format!("Failed to score {}: {}", path.display(), e)));
+    })?;
+    tui::run(scores, rx).map_err(|e| format!("Terminal error: {}", e))
+}
+
+/// Score the history at `path` and store it in the database at `db_path`, drawing a
+/// progress bar when `show_progress`
+fn ingest_history(
+    scorer: &BehaviorScorer,
+    path: &Path,
+    options: &ScanOptions,
+    db_path: &Path,
+    show_progress: bool,
+) -> Result<IngestReport, String> {
+    let progress = ScanProgress::new(show_progress);
+    let summary = scorer.scan_history(path, options, |event| progress.update(event));
+    progress.finish();
+    let summary = summary?;
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        ingest::store_history(&db, summary, options).await.map_err(|e| e.to_string())
+    })
+}
+
+/// Draw progress bars on stderr only in an interactive terminal, and not when the output
+/// is JSON for another program or `quiet` (--quiet, or logs as JSON lines)
+fn use_progress(format: &str, quiet: bool) -> bool {
+    !quiet && format != "json" && std::io::stdout().is_terminal() && std::io::stderr().is_terminal()
+}
+
+/// Session ID strategy named by `--session-ids`
+fn session_id_strategy(raw: &str) -> Result<SessionIdStrategy, String> {
+    match raw {
+        "file-name" => Ok(SessionIdStrategy::FileName),
+        "frontmatter" => Ok(SessionIdStrategy::Frontmatter),
+        "content-hash" => Ok(SessionIdStrategy::ContentHash),
+        _ => Err(format!("Unknown session ID strategy: {}", raw)),
+    }
+}
+
+/// Import sessions from `source` into the database at `db_path`
+fn import_sessions(
+    scorer: &BehaviorScorer,
+    source: &Path,
+    mapping_path: &Path,
+    db_path: &Path,
+) -> Result<ImportReport, String> {
+    let mapping = std::fs::read_to_string(mapping_path)
+        .map_err(|e| format!("Failed to read mapping: {}", e))?;
+    let mapping: ImportMapping = serde_yaml::from_str(&mapping).map_err(|e| format!("Invalid mapping: {}", e))?;
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        scorer.import_sqlite(&db, source, &mapping).await
+    })
+}
+
+/// Snapshot the database at `db_path` into `output`
+fn backup_database(db_path: &Path, output: &Path) -> Result<(), String> {
+    if !db_path.is_file() {
+        return Err(format!("Database not found: {}", db_path.display()));
+    }
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        db.backup_to(output).await.map_err(|e| e.to_string())
+    })
+}
+
+/// Restore the database at `db_path` from the snapshot at `input`
+fn restore_database(db_path: &Path, input: &Path) -> Result<(), String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        db.restore_from(input).await.map_err(|e| e.to_string())
+    })
+}
+
+/// Build list filters from the export flags
+fn export_filters(
+    since: Option<String>,
+    until: Option<String>,
+    band: Option<String>,
+    source: Option<String>,
+    min_percentage: Option<f64>,
+    max_percentage: Option<f64>,
+    tag: Option<String>,
+) -> Result<ListFilters, String> {
+    let band = match band.as_deref() {
+        None => None,
+        Some("excellent") => Some(ScoreBand::Excellent),
+        Some("good") => Some(ScoreBand::Good),
+        Some("moderate") => Some(ScoreBand::Moderate),
+        Some("poor") => Some(ScoreBand::Poor),
+        Some(other) => return Err(format!("Unknown score band: {}", other)),
+    };
+    Ok(ListFilters {
+        since: since.as_deref().map(parse_time).transpose()?,
+        until: until.as_deref().map(parse_time).transpose()?,
+        source,
+        band,
+        min_percentage,
+        max_percentage,
+        tag,
+        session_id: None,
+    })
+}
+
+/// RFC 3339 timestamps, or dates taken as midnight UTC
+fn parse_time(raw: &str) -> Result<DateTime<Utc>, String> {
+    DateTime::parse_from_rfc3339(raw)
+        .map(|time| time.with_timezone(&Utc))
+        .or_else(|_| NaiveDate::parse_from_str(raw, "%Y-%m-%d").map(|date| date.and_time(NaiveTime::MIN).and_utc()))
+        .map_err(|_| format!("Invalid time: {}", raw))
+}
+
+/// Export rows from the database at `db_path` to a CSV, Parquet, or JSON Lines file
+fn export(db_path: &Path, kind: ExportKind, format: &str, output: &Path, filters: &ListFilters) -> Result<usize, String> {
+    if !db_path.is_file() {
+        return Err(format!("Database not found: {}", db_path.display()));
+    }
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        let rows = match format {
+            "parquet" => db.export_parquet(kind, output, filters).await,
+            "jsonl" => db.export_jsonl(output).await,
+            _ => db.export_csv(kind, output, filters).await,
+        };
+        rows.map_err(|e| e.to_string())
+    })
+}
+
+/// Load a JSON Lines history export into the database at `db_path`
+fn import_history(db_path: &Path, input: &Path) -> Result<HistoryImportReport, String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        db.import_jsonl(input).await.map_err(|e| e.to_string())
+    })
+}
+
+/// Run the health check on the database at `db_path` without writing to it
+fn check_database(db_path: &Path) -> Result<DbHealth, String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::open_for_inspection(db_path).await.map_err(|e| e.to_string())?;
+        db.health_check().await.map_err(|e| e.to_string())
+    })
+}
+
+/// ANALYZE and VACUUM the database at `db_path`, returning the bytes reclaimed
+fn optimize_database(db_path: &Path) -> Result<i64, String> {
+    if !db_path.is_file() {
+        return Err(format!("Database not found: {}", db_path.display()));
+    }
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        db.optimize().await.map_err(|e| e.to_string())
+    })
+}
+
+/// Schema version of the database at `db_path` and its pending migrations, without applying them
+fn migration_status(db_path: &Path) -> Result<(i64, Vec<PendingMigration>), String> {
+    if !db_path.is_file() {
+        return Err(format!("Database not found: {}", db_path.display()));
+    }
+    let config = DbConfig {
+        migrate: false,
+        ..DbConfig::default()
+    };
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::with_config(db_path, &config).await.map_err(|e| e.to_string())?;
+        let current = db.migration_version().await.map_err(|e| e.to_string())?;
+        let pending = db.pending_migrations().await.map_err(|e| e.to_string())?;
+        Ok((current, pending))
+    })
+}
+
+/// Apply pending migrations to the database at `db_path`, returning those applied and
+/// the resulting schema version
+fn migrate_database(db_path: &Path) -> Result<(Vec<PendingMigration>, i64), String> {
+    let (_, pending) = migration_status(db_path)?;
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        let version = db.migration_version().await.map_err(|e| e.to_string())?;
+        Ok((pending, version))
+    })
+}
+
+/// Purge sessions trashed before `older_than` (or all trashed sessions) from `db_path`
+fn prune_database(db_path: &Path, older_than: Option<DateTime<Utc>>) -> Result<u64, String> {
+    if !db_path.is_file() {
+        return Err(format!("Database not found: {}", db_path.display()));
+    }
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        db.purge_trash(older_than).await.map_err(|e| e.to_string())
+    })
+}
+
+/// Roll the schema of the database at `db_path` back to `version`
+fn rollback_database(db_path: &Path, version: i64) -> Result<(), String> {
+    if !db_path.is_file() {
+        return Err(format!("Database not found: {}", db_path.display()));
+    }
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        db.rollback_to(version).await.map_err(|e| e.to_string())
+    })
+}
+
+/// Re-score the stored transcripts in `db_path`, drawing a progress bar when `show_progress`
+fn rescore(scorer: &BehaviorScorer, db_path: &Path, show_progress: bool) -> Result<RescoreReport, String> {
+    if !db_path.is_file() {
+        return Err(format!("Database not found: {}", db_path.display()));
+    }
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    let progress = ScanProgress::new(show_progress);
+    let report = runtime.block_on(async {
+        let db = Database::new(db_path).await.map_err(|e| e.to_string())?;
+        scorer
+            .rescore_all(&db, |rescored| progress.set(rescored.done, rescored.total))
+            .await
+    });
+    progress.finish();
+    report
+}
+
+/// Read summary counts and rule breakdowns from the database at `db_path` without writing to it
+fn database_stats(db_path: &Path, top: i64) -> Result<StatsReport, String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::open_read_only(db_path).await.map_err(|e| e.to_string())?;
+        db.get_stats_report(top).await.map_err(|e| e.to_string())
+    })
+}
+
+/// Print `scores` as a SARIF log, with transcript paths relative to the working directory
+fn print_sarif(scorer: &BehaviorScorer, scores: &[SessionScore]) {
+    let root = std::env::current_dir().ok();
+    let log = sarif::sarif_log(scores, &scorer.config().rules, root.as_deref());
+    println!("{}", serde_json::to_string_pretty(&log).unwrap());
+}
+
+/// Exit nonzero, naming each offending session, when a score is below `fail_under`
+/// or, with `fail_on_critical`, failed a Safety rule. Duplicates are judged by their original.
+fn enforce_gate(scorer: &BehaviorScorer, scores: &[SessionScore], fail_under: Option<f64>, fail_on_critical: bool) {
+    if fail_under.is_none() && !fail_on_critical {
+        return;
+    }
+    let gate = AlertSettings {
+        enabled: true,
+        below_percentage: fail_under.unwrap_or(0.0),
+        critical_failures: fail_on_critical,
+    };
+    let failures: Vec<_> = scores
+        .iter()
+        .filter(|score| score.duplicate_of.is_none())
+        .filter_map(|score| score_alert(score, &scorer.config().rules, &gate))
+        .collect();
+    if failures.is_empty() {
+        return;
+    }
+    for failure in &failures {
+        error!(gate = %failure.title, "Gate failed: {}", failure.body);
+    }
+    std::process::exit(1);
+}
+
+/// Score the transcript at `path`, naming the session after the file
+fn score_path(scorer: &BehaviorScorer, path: &Path) -> Result<SessionScore, String> {
+    let transcript = scan::read_transcript_file_within(path, scorer.max_file_size())?;
+    let name = path.file_stem().map(|stem| stem.to_string_lossy()).unwrap_or_default();
+    let mut score = scorer.score_file_content(&session_id::sanitize(&name), &scan::transcript_path(path), &transcript.text)?;
+    score.warnings.extend(transcript.warning);
+    Ok(score)
+}
+
+/// Compare two transcript files, or the latest scores of two sessions stored in `db_path`
+fn compare(scorer: &BehaviorScorer, a: &str, b: &str, db_path: Option<&Path>) -> Result<ScoreComparison, String> {
+    let Some(db_path) = db_path else {
+        return Ok(compare_scores(&score_path(scorer, Path::new(a))?, &score_path(scorer, Path::new(b))?));
+    };
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::open_read_only(db_path).await.map_err(|e| e.to_string())?;
+        db.compare_sessions(a, b).await.map(ScoreComparison::from).map_err(|e| e.to_string())
+    })
+}
+
+/// Print `comparison` as text, fixes and regressions first
+fn print_comparison(comparison: &ScoreComparison) {
+    println!(
+        "{} {:.1}% -> {} {:.1}% ({:+.1})",
+        comparison.session_a,
+        comparison.percentage_a,
+        comparison.session_b,
+        comparison.percentage_b,
+        comparison.percentage_delta
+    );
+    let section = |change: &RuleDiff| {
+        if change.fixed() {
+            "Fixed"
+        } else if change.regressed() {
+            "Regressed"
+        } else if change.b.is_none() {
+            "Only checked in first"
+        } else {
+            "Only checked in second"
+        }
+    };
+    for title in ["Fixed", "Regressed", "Only checked in first", "Only checked in second"] {
+        let changes: Vec<_> = comparison.differing.iter().filter(|change| section(change) == title).collect();
+        if changes.is_empty() {
+            continue;
+        }
+        println!("\n{}:", title);
+        for change in changes {
+            let evidence = change.b.as_ref().or(change.a.as_ref()).and_then(|side| side.evidence.as_deref());
+            match evidence {
+                Some(evidence) => println!("  {} ({})", change.rule_name, evidence),
+                None => println!("  {}", change.rule_name),
+            }
+        }
+    }
+    println!("\n{} rules unchanged", comparison.unchanged);
+}
+
+/// Score the corpus at `corpus` under the rules files `old` and `new`, then compare
+/// outcomes session by session and rule by rule
+fn diff_rules(
+    settings: &Settings,
+    old: &Path,
+    new: &Path,
+    corpus: &Path,
+    options: &ScanOptions,
+    chunking: Option<ChunkOptions>,
+    show_progress: bool,
+) -> Result<RuleSetImpact, String> {
+    let old = load_scorer(Some(old), settings)?.with_chunking(chunking);
+    let new = load_scorer(Some(new), settings)?.with_chunking(chunking);
+    let score = |scorer: &BehaviorScorer| {
+        let progress = ScanProgress::new(show_progress);
+        let summary = scorer.scan_history(corpus, options, |event| progress.update(event));
+        progress.finish();
+        summary.map(|summary| summary.scores)
+    };
+    let old_scores = score(&old)?;
+    let new_scores = score(&new)?;
+    Ok(rule_impact::rule_set_impact(
+        &old.config().rules,
+        &new.config().rules,
+        &old_scores,
+        &new_scores,
+    ))
+}
+
+fn print_rule_impact(impact: &RuleSetImpact) {
+    println!(
+        "{} sessions: {:.1}% -> {:.1}% average ({:+.1})",
+        impact.sessions,
+        impact.average_old,
+        impact.average_new,
+        impact.average_new - impact.average_old
+    );
+    for (title, ids) in [
+        ("Added rules", &impact.added_rules),
+        ("Removed rules", &impact.removed_rules),
+        ("Edited rules", &impact.edited_rules),
+    ] {
+        if !ids.is_empty() {
+            println!("{}: {}", title, ids.join(", "));
+        }
+    }
+    if impact.changed_sessions.is_empty() {
+        println!("\nNo session changes outcome");
+        return;
+    }
+
+    println!("\nRules changing outcome:");
+    let name_width = impact.changed_rules.iter().map(|rule| rule.rule_name.chars().count()).max().unwrap_or_default();
+    for rule in &impact.changed_rules {
+        println!(
+            "  {:<width$}  {:>4} fixed  {:>4} regressed",
+            rule.rule_name,
+            rule.fixed.len(),
+            rule.regressed.len(),
+            width = name_width
+        );
+    }
+    println!("\nSessions changing outcome ({} of {}):", impact.changed_sessions.len(), impact.sessions);
+    for session in &impact.changed_sessions {
+        let mut line = format!(
+            "  {} {:.1}% -> {:.1}% ({:+.1})",
+            session.session_id, session.percentage_old, session.percentage_new, session.percentage_delta
+        );
+        if !session.regressed.is_empty() {
+            line.push_str(&format!("  regressed: {}", session.regressed.join(", ")));
+        }
+        if !session.fixed.is_empty() {
+            line.push_str(&format!("  fixed: {}", session.fixed.join(", ")));
+        }
+        println!("{}", line);
+    }
+}
+
+/// Past scores and their trend from the database at `db_path`, without writing to it
+fn score_history(
+    db_path: &Path,
+    session: Option<String>,
+    since: Option<String>,
+    limit: i64,
+    by: &str,
+) -> Result<ScoreHistory, String> {
+    let bucket = match by {
+        "day" => TimeBucket::Day,
+        "week" => TimeBucket::Week,
+        other => return Err(format!("Unknown trend bucket: {}", other)),
+    };
+    let filters = ListFilters {
+        since: since.as_deref().map(parse_time).transpose()?,
+        session_id: session,
+        ..ListFilters::default()
+    };
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    runtime.block_on(async {
+        let db = Database::open_read_only(db_path).await.map_err(|e| e.to_string())?;
+        db.get_score_history(Some(limit), bucket, &filters).await.map_err(|e| e.to_string())
+    })
+}
+
+/// Print `history` as text, with each score's change from the session's previous one
+fn print_history(history: &ScoreHistory) {
+    if history.scores.is_empty() {
+        println!("No scores found");
+        return;
+    }
+    println!("Scores (newest first):");
+    for (i, score) in history.scores.iter().enumerate() {
+        let previous = history.scores[i + 1..].iter().find(|older| older.session_id == score.session_id);
+        let change = previous
+            .map(|older| format!("{:+.1}", score.score_percentage - older.score_percentage))
+            .unwrap_or_default();
+        println!(
+            "  {}  {:<36} {:>5.1}% {:>6}  {}/{} rules",
+            score.scored_at.format("%Y-%m-%d %H:%M"),
+            score.session_id,
+            score.score_percentage,
+            change,
+            score.passed_rules,
+            score.total_rules
+        );
+    }
+    println!("Trend:");
+    for point in &history.trend {
+        println!(
+            "  {}  {:>4} scores  {:>5.1}% average ({:.1}-{:.1}%)",
+            point.bucket_start.format("%Y-%m-%d"),
+            point.scores,
+            point.avg_score,
+            point.min_score,
+            point.max_score
+        );
+    }
+    if let (Some(first), Some(last)) = (history.trend.first(), history.trend.last()) {
+        if history.trend.len() > 1 {
+            println!(
+                "Average {:.1}% -> {:.1}% ({:+.1})",
+                first.avg_score,
+                last.avg_score,
+                last.avg_score - first.avg_score
+            );
+        }
+    }
+}
+
+/// `path`, or the desktop app's settings file when its config directory can be found
+fn settings_path(path: Option<PathBuf>) -> Option<PathBuf> {
+    path.or_else(|| profiles::app_config_dir().map(|dir| dir.join(SETTINGS_FILE)))
+}
+
+/// Settings from `path`, or the defaults when there's no settings file
+fn load_settings(path: Option<&Path>) -> Result<Settings, String> {
+    match path {
+        Some(path) => Settings::load_or_default(path),
+        None => Ok(Settings::default()),
+    }
+}
+
+/// Scorer using the rules file at `rules`, or the built-in rules. A rules file with an
+/// invalid rule (see `RuleDefinition::validate`) is an error rather than a rule that never matches.
+fn load_scorer(rules: Option<&Path>, settings: &Settings) -> Result<BehaviorScorer, String> {
+    let scorer = match rules {
+        Some(path) => {
+            let config = rules_file::load_rules(path)?;
+            let problems: Vec<String> = config
+                .rules
+                .iter()
+                .flat_map(|rule| {
+                    rule.validate()
+                        .into_iter()
+                        .map(move |issue| format!("rule {}: {}: {}", rule.id, issue.field, issue.message))
+                })
+                .collect();
+            if !problems.is_empty() {
+                return Err(format!("Invalid rules file {}: {}", path.display(), problems.join("; ")));
+            }
+            BehaviorScorer::with_config(config)
+        }
+        None => BehaviorScorer::new(),
+    };
+    Ok(settings.apply(scorer))
+}
+
+/// Write the built-in rules to `output` as a starter rules file and register it in the
+/// CLI config file at `config_path`, returning where the file was written
+fn init_rules(config_path: &Path, output: Option<PathBuf>, force: bool) -> Result<PathBuf, String> {
+    let output = output.unwrap_or_else(|| config_path.with_file_name(rules_file::RULES_FILE));
+    if output.exists() && !force {
+        return Err(format!("{} already exists; pass --force to replace it", output.display()));
+    }
+    if let Some(parent) = output.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    let starter = rules_file::starter_rules(BehaviorScorer::new().config())?;
+    std::fs::write(&output, starter).map_err(|e| format!("Failed to write {}: {}", output.display(), e))?;
+    let output = output.canonicalize().map_err(|e| e.to_string())?;
+    cli_config::register_rules(config_path, &output)?;
+    Ok(output)
+}
+
+/// Run `init` or `validate`, exiting nonzero on failure
+fn rules_file_command(command: RulesFileCommand, config_path: Option<&Path>, rules_path: Option<&Path>) {
+    match command {
+        RulesFileCommand::Init { output, force } => {
+            let Some(config_path) = config_path else {
+                error!("Can't locate the config directory; set {}", cli_config::CONFIG_ENV);
+                std::process::exit(1);
+            };
+            match init_rules(config_path, output, force) {
+                Ok(path) => {
+                    println!("Wrote starter rules to {}", path.display());
+                    println!("Registered in {}", config_path.display());
+                }
+                Err(e) => {
+                    error!("Failed to create rules file: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        RulesFileCommand::Validate { format } => {
+            let Some(rules) = rules_path else {
+                error!("No rules file to check; pass --rules or run init");
+                std::process::exit(1);
+            };
+            let validation = match std::fs::read_to_string(rules) {
+                Ok(content) => rules_file::validate_rules(&content),
                 Err(e) => {
-                    eprintln!("Error: Failed to read transcript file: {}", e);
+                    error!("Failed to read rules file {}: {}", rules.display(), e);
                     std::process::exit(1);
                 }
             };
+            if format == "json" {
+                println!("{}", serde_json::to_string_pretty(&validation).unwrap());
+            } else {
+                for problem in &validation.problems {
+                    match &problem.rule_id {
+                        Some(rule_id) => println!("{}: {}", rule_id, problem.message),
+                        None => println!("{}", problem.message),
+                    }
+                }
+                println!(
+                    "{} rules, {} examples, {} problems",
+                    validation.rules,
+                    validation.examples,
+                    validation.problems.len()
+                );
+            }
+            if !validation.is_ok() {
+                std::process::exit(1);
+            }
+        }
+    }
+}
+
+/// Registry file to use and its profiles; the default profile points at the app's
+/// database next to the registry
+fn load_profiles(registry: Option<PathBuf>) -> Result<(PathBuf, ProfileRegistry), String> {
+    let registry_path = match registry {
+        Some(path) => path,
+        None => profiles::app_config_dir()
+            .ok_or("Can't locate the app's config directory; pass --registry")?
+            .join(REGISTRY_FILE),
+    };
+    let default_db = registry_path.with_file_name(DEFAULT_DB_FILE);
+    let profiles = ProfileRegistry::load_or_default(&registry_path, default_db)?;
+    Ok((registry_path, profiles))
+}
+
+/// Apply `change` to the registry and save it
+fn update_profiles(
+    registry: Option<PathBuf>,
+    change: impl FnOnce(&mut ProfileRegistry) -> Result<(), String>,
+) -> Result<(), String> {
+    let (registry_path, mut profiles) = load_profiles(registry)?;
+    change(&mut profiles)?;
+    profiles.save(&registry_path)
+}
+
+/// Stats from every registered database
+fn profile_stats(registry: Option<PathBuf>) -> Result<AggregateStats, String> {
+    let (_, profiles) = load_profiles(registry)?;
+    let runtime = tokio::runtime::Runtime::new().map_err(|e| e.to_string())?;
+    Ok(runtime.block_on(profiles.aggregate_stats()))
+}
+
+/// Color summaries unless asked not to (by flag or NO_COLOR) or stdout isn't a terminal
+fn use_color(no_color: bool) -> bool {
+    !no_color && std::env::var_os("NO_COLOR").is_none() && std::io::stdout().is_terminal()
+}
+
+/// Color log levels on the same terms, for stderr
+fn use_log_color(no_color: bool) -> bool {
+    !no_color && std::env::var_os("NO_COLOR").is_none() && std::io::stderr().is_terminal()
+}
+
+fn main() {
+    let config_path = cli_config::config_path();
+    let config = match &config_path {
+        Some(path) => cli_config::load_config(path),
+        None => Ok(CliConfig::default()),
+    };
+    let config = match config {
+        Ok(config) => config,
+        Err(e) => {
+            // Logging isn't set up until the flags are parsed, and parsing needs the config
+            eprintln!("Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    let matches = cli_config::with_defaults(Cli::command(), &config).get_matches();
+    let cli = Cli::from_arg_matches(&matches).unwrap_or_else(|e| e.exit());
+    logging::init(
+        logging::level(cli.verbose, cli.quiet),
+        cli.log_json,
+        use_log_color(cli.no_color),
+    );
+    let quiet = cli.quiet || cli.log_json;
+    let chunking = cli.chunk_size.map(|chunk_size| ChunkOptions {
+        chunk_size,
+        overlap: cli.chunk_overlap,
+    });
+    let settings_path = settings_path(cli.settings);
+    let settings = match load_settings(settings_path.as_deref()) {
+        Ok(settings) => settings,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    // The config file's rules are already the --rules default
+    let rules_path = cli.rules;
+    let command = match cli.command {
+        CliCommand::RulesFile(command) => {
+            rules_file_command(command, config_path.as_deref(), rules_path.as_deref());
+            return;
+        }
+        CliCommand::Scorer(command) => *command,
+    };
+    let scorer = match load_scorer(rules_path.as_deref(), &settings) {
+        Ok(scorer) => scorer,
+        Err(e) => {
+            error!("{}", e);
+            std::process::exit(1);
+        }
+    };
+    let diagnostics = cli.diagnostics || settings.diagnostics;
+    let scorer = scorer
+        .with_diagnostics(diagnostics)
+        .with_chunking(chunking);
+    let style = Style::new(use_color(cli.no_color));
+    
+    match command {
+        Commands::Score { session, transcript, url, fail_under, fail_on_critical, no_fail_on_critical, only, skip, category, format } => {
+            if !["json", "sarif", "junit", "markdown", "summary"].contains(&format.as_str()) {
+                error!("Unknown format: {}", format);
+                std::process::exit(1);
+            }
+            let fail_on_critical = fail_on_critical && !no_fail_on_critical;
+            let scorer = filter_rules(scorer, RuleFilter { only, skip, categories: category });
+            let result = if let Some(url) = url {
+                scorer.score_url(&session, &url)
+            } else if let Some(transcript) = transcript.filter(|t| t.as_os_str() != "-") {
+                // SECURITY: Validate transcript file path
+                let transcript_path = transcript;
+                let transcript = match scan::read_transcript_file_within(&transcript_path, scorer.max_file_size()) {
+                    Ok(content) => content,
+                    Err(e) => {
+                        error!("Failed to read transcript file: {}", e);
+                        std::process::exit(1);
+                    }
+                };
+                scorer
+                    .score_file_content(&session, &scan::transcript_path(&transcript_path), &transcript.text)
+                    .map(|mut score| {
+                        score.warnings.extend(transcript.warning);
+                        score.source_path = Some(transcript_path.to_string_lossy().to_string());
+                        score
+                    })
+            } else {
+                read_stdin(scorer.max_file_size()).and_then(|decoded| {
+                    let mut score = scorer.score_text(&session, &decoded.text, None)?;
+                    score.warnings.extend(decoded.warning);
+                    Ok(score)
+                })
+            };
             
-            match scorer.score_session(&session, &transcript) {
+            match result {
                 Ok(score) => {
                     match format.as_str() {
                         "json" => println!("{}", serde_json::to_string_pretty(&score).unwrap()),
-                        "summary" => {
-                            println!("Session: {}", score.session_id);
-                            println!("Score: {:.1}%", score.score_percentage);
-                            println!("Passed: {}/{}", score.passed_rules, score.total_rules);
-                            println!("\n{}", score.summary);
-                            println!("\nRule Details:");
-                            for rule in &score.rules {
-                                let status = if rule.passed { "✅" } else { "❌" };
-                                println!("  {} {}", status, rule.rule_name);
-                            }
-                        }
-                        _ => eprintln!("Unknown format: {}", format),
+                        "sarif" => print_sarif(&scorer, std::slice::from_ref(&score)),
+                        "junit" => print!("{}", junit::junit_report(std::slice::from_ref(&score))),
+                        "markdown" => print!("{}", markdown::markdown_report(std::slice::from_ref(&score))),
+                        "summary" => print!("{}", summary::score_summary(&score, style)),
+                        _ => unreachable!("format checked above"),
                     }
+                    enforce_gate(&scorer, std::slice::from_ref(&score), fail_under, fail_on_critical);
                 }
                 Err(e) => {
-                    eprintln!("Error: Failed to score session: {}", e);
+                    error!("Failed to score session: {}", e);
                     std::process::exit(1);
                 }
             }
         }
-        Commands::Scan { directory, format } => {
-            // Expand tilde in path
-            let directory = if directory.starts_with("~") {
-                let home = std::env::var("HOME").unwrap_or_default();
-                PathBuf::from(home).join(directory.strip_prefix("~").unwrap_or(directory.as_path()))
-            } else {
-                directory
+        Commands::Scan {
+            directory,
+            include,
+            exclude,
+            max_depth,
+            extensions,
+            duplicates,
+            session_ids,
+            db,
+            keep_identical_scores,
+            jobs,
+            fail_under,
+            fail_on_critical,
+            no_fail_on_critical,
+            only,
+            skip,
+            category,
+            format,
+        } => {
+            if !["json", "sarif", "junit", "markdown", "csv", "summary"].contains(&format.as_str()) {
+                error!("Unknown format: {}", format);
+                std::process::exit(1);
+            }
+            let directory = expand_home(directory);
+            let fail_on_critical = fail_on_critical && !no_fail_on_critical;
+            let filter = RuleFilter { only, skip, categories: category };
+            // A --db default from the config file gives way to rule filters, which its cache can't hold
+            let db = db.filter(|_| filter.is_empty());
+            let scorer = filter_rules(scorer, filter);
+            
+            let duplicates = match duplicates.as_str() {
+                "skip" => DuplicatePolicy::Skip,
+                "link" => DuplicatePolicy::Link,
+                "keep" => DuplicatePolicy::Keep,
+                _ => {
+                    error!("Unknown duplicates policy: {}", duplicates);
+                    std::process::exit(1);
+                }
+            };
+            let session_ids = match session_id_strategy(&session_ids) {
+                Ok(session_ids) => session_ids,
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
             };
+            let options = ScanOptions {
+                include,
+                exclude,
+                max_depth,
+                extensions,
+                duplicates,
+                session_ids,
+                jobs,
+                ..ScanOptions::default()
+            };
+            
+            let progress = ScanProgress::new(use_progress(&format, quiet));
+            let result = match db {
+                Some(_) if archive::is_archive(&directory) => {
+                    Err("Incremental scans (--db) need a directory, not an archive".to_string())
+                }
+                Some(db_path) => scan_incremental(&scorer, &db_path, &directory, &options, keep_identical_scores, &progress),
+                None if archive::is_archive(&directory) => scorer
+                    .scan_archive_with_progress(&directory, &options, |event| progress.update(event))
+                    .map(|summary| summary.scores),
+                None => scorer
+                    .scan_with_progress(&directory, &options, |event| progress.update(event))
+                    .map(|summary| summary.scores),
+            };
+            progress.finish();
             
-            match scorer.scan_and_score_directory(&directory) {
+            match result {
                 Ok(scores) => {
                     match format.as_str() {
                         "json" => println!("{}", serde_json::to_string_pretty(&scores).unwrap()),
+                        "sarif" => print_sarif(&scorer, &scores),
+                        "junit" => print!("{}", junit::junit_report(&scores)),
+                        "markdown" => print!("{}", markdown::markdown_report(&scores)),
+                        "csv" => {
+                            if let Err(e) = export::write_scores_csv(&scores, &scorer.config().rules, std::io::stdout()) {
+                                error!("Failed to write CSV: {}", e);
+                                std::process::exit(1);
+                            }
+                        }
                         "summary" => {
-                            let total_score: f64 = scores.iter().map(|s| s.score_percentage).sum();
-                            let avg_score = if !scores.is_empty() {
-                                total_score / scores.len() as f64
-                            } else {
-                                0.0
-                            };
+                            print!("{}", summary::scan_summary(&scores, style));
                             
-                            println!("Scanned {} sessions", scores.len());
-                            println!("Average score: {:.1}%", avg_score);
-                            println!("\nIndividual Scores:");
-                            for score in scores {
+                            if diagnostics {
+                                let mut totals: Vec<(String, f64)> = Vec::new();
+                                for timing in scores.iter().filter_map(|s| s.diagnostics.as_ref()).flat_map(|d| &d.rule_timings) {
+                                    match totals.iter_mut().find(|(id, _)| *id == timing.rule_id) {
+                                        Some((_, total)) => *total += timing.elapsed_ms,
+                                        None => totals.push((timing.rule_id.clone(), timing.elapsed_ms)),
+                                    }
+                                }
+                                totals.sort_by(|a, b| b.1.total_cmp(&a.1));
+                                println!("\n{}", style.heading("Rule Timings across scan (slowest first):"));
+                                for (rule_id, total) in totals {
+                                    println!("  {:>10.3}ms  {}", total, rule_id);
+                                }
+                            }
+                        }
+                        _ => unreachable!("format checked above"),
+                    }
+                    enforce_gate(&scorer, &scores, fail_under, fail_on_critical);
+                }
+                Err(e) => {
+                    error!("Failed to scan directory: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Watch { directory, include, exclude, max_depth, extensions, debounce_ms, db, format } => {
+            let options = ScanOptions {
+                include,
+                exclude,
+                max_depth,
+                extensions,
+                ..ScanOptions::default()
+            };
+            let debounce = Duration::from_millis(debounce_ms);
+            let json = match format.as_str() {
+                "json" => true,
+                "summary" => false,
+                _ => {
+                    error!("Unknown format: {}", format);
+                    std::process::exit(1);
+                }
+            };
+            let announce = use_progress(&format, quiet);
+            if let Err(e) = watch_directory(&scorer, &expand_home(directory), &options, debounce, db.as_deref(), json, announce) {
+                error!("Failed to watch directory: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Tui { directory, include, exclude, max_depth, extensions, debounce_ms } => {
+            let options = ScanOptions {
+                include,
+                exclude,
+                max_depth,
+                extensions,
+                ..ScanOptions::default()
+            };
+            let debounce = Duration::from_millis(debounce_ms);
+            if let Err(e) = run_dashboard(&scorer, &expand_home(directory), &options, debounce) {
+                error!("Failed to run dashboard: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Import { path: Some(path), include, exclude, session_ids, db, format, .. } => {
+            if !["json", "summary"].contains(&format.as_str()) {
+                error!("Unknown format: {}", format);
+                std::process::exit(1);
+            }
+            let session_ids = match session_id_strategy(&session_ids) {
+                Ok(session_ids) => session_ids,
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            let options = ScanOptions {
+                include,
+                exclude,
+                session_ids,
+                ..ScanOptions::default()
+            };
+            match ingest_history(&scorer, &expand_home(path), &options, &db, use_progress(&format, quiet)) {
+                Ok(report) => {
+                    match format.as_str() {
+                        "json" => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+                        "summary" => {
+                            println!(
+                                "Imported {} sessions, {} failed, {} duplicates, {} files skipped",
+                                report.scores.len(),
+                                report.failed.len(),
+                                report.duplicates,
+                                report.skipped.len()
+                            );
+                            for score in &report.scores {
+                                println!("  {}: {:.1}%", score.session_id, score.score_percentage);
+                            }
+                            for skipped in &report.skipped {
+                                println!("Skipped: {}", skipped);
+                            }
+                            for failure in &report.failed {
+                                println!("Failed: {}", failure);
+                            }
+                        }
+                        _ => unreachable!("format checked above"),
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to import history: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Import { source, mapping, db, format, .. } => {
+            let (Some(source), Some(mapping)) = (source, mapping) else {
+                unreachable!("clap requires a path or --source with --mapping");
+            };
+            if !["json", "summary"].contains(&format.as_str()) {
+                error!("Unknown format: {}", format);
+                std::process::exit(1);
+            }
+            match import_sessions(&scorer, &source, &mapping, &db) {
+                Ok(report) => {
+                    match format.as_str() {
+                        "json" => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+                        "summary" => {
+                            println!("Imported {} sessions", report.scores.len());
+                            for score in &report.scores {
                                 println!("  {}: {:.1}%", score.session_id, score.score_percentage);
                             }
+                            for failure in &report.failed {
+                                println!("Failed: {}", failure);
+                            }
                         }
-                        _ => eprintln!("Unknown format: {}", format),
+                        _ => unreachable!("format checked above"),
+                    }
+                }
+                Err(e) => {
+                    error!("Failed to import sessions: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Backup { db, output } => {
+            if let Err(e) = backup_database(&db, &output) {
+                error!("Failed to back up database: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Restore { db, input } => {
+            if let Err(e) = restore_database(&db, &input) {
+                error!("Failed to restore database: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Export { db, kind, output, format, source, since, until, band, min_percentage, max_percentage, tag } => {
+            let filters = match export_filters(since, until, band, source, min_percentage, max_percentage, tag) {
+                Ok(filters) => filters,
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            let Some(kind) = ExportKind::parse(&kind) else {
+                error!("Unknown export kind: {}", kind);
+                std::process::exit(1);
+            };
+            if !["csv", "parquet", "jsonl"].contains(&format.as_str()) {
+                error!("Unknown export format: {}", format);
+                std::process::exit(1);
+            }
+            match export(&db, kind, &format, &output, &filters) {
+                Ok(rows) => println!("Exported {} rows to {}", rows, output.display()),
+                Err(e) => {
+                    error!("Failed to export: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::ImportHistory { db, input } => match import_history(&db, &input) {
+            Ok(report) => println!(
+                "Imported {} sessions, {} scores, and {} rules ({} scores already present)",
+                report.sessions, report.scores, report.rules, report.skipped_scores
+            ),
+            Err(e) => {
+                error!("Failed to import history: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Db { command: DbCommand::Check { db, format } } => match check_database(&db) {
+            Ok(health) => {
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&health).unwrap());
+                } else {
+                    println!("Integrity:    {}", if health.integrity_errors.is_empty() { "ok" } else { "FAILED" });
+                    for error in &health.integrity_errors {
+                        println!("  {}", error);
+                    }
+                    println!("Foreign keys: {} violations", health.foreign_key_violations.len());
+                    for violation in &health.foreign_key_violations {
+                        let rowid = violation.rowid.map_or("?".to_string(), |id| id.to_string());
+                        println!("  {} row {} -> missing {}", violation.table, rowid, violation.parent);
+                    }
+                    println!("Size:         {} bytes ({} free)", health.size_bytes, health.free_bytes);
+                    for table in &health.tables {
+                        println!("  {:<24} {} rows", table.name, table.rows);
+                    }
+                }
+                if !health.ok {
+                    std::process::exit(1);
+                }
+            }
+            Err(e) => {
+                error!("Failed to check database: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Db { command: DbCommand::Optimize { db } } => match optimize_database(&db) {
+            Ok(reclaimed) => println!("Reclaimed {} bytes", reclaimed),
+            Err(e) => {
+                error!("Failed to optimize database: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Db { command: DbCommand::Status { db, dry_run } } => match migration_status(&db) {
+            Ok((current, pending)) => {
+                println!("Schema version: {}", current);
+                if pending.is_empty() {
+                    println!("Up to date");
+                }
+                for migration in &pending {
+                    println!("Pending: {} {}", migration.version, migration.name);
+                    if dry_run {
+                        println!("{}", migration.sql.trim_end());
+                    }
+                }
+            }
+            Err(e) => {
+                error!("Failed to read migration status: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Db { command: DbCommand::Migrate { db } } => match migrate_database(&db) {
+            Ok((applied, version)) => {
+                for migration in &applied {
+                    println!("Applied: {} {}", migration.version, migration.name);
+                }
+                println!("Schema version: {}", version);
+            }
+            Err(e) => {
+                error!("Failed to migrate database: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Db { command: DbCommand::Backup { db, output } } => {
+            if let Err(e) = backup_database(&db, &output) {
+                error!("Failed to back up database: {}", e);
+                std::process::exit(1);
+            }
+        }
+        Commands::Db { command: DbCommand::Prune { db, older_than } } => {
+            let older_than = match older_than.as_deref().map(parse_time).transpose() {
+                Ok(older_than) => older_than,
+                Err(e) => {
+                    error!("{}", e);
+                    std::process::exit(1);
+                }
+            };
+            match prune_database(&db, older_than) {
+                Ok(purged) => println!("Purged {} sessions from the trash", purged),
+                Err(e) => {
+                    error!("Failed to prune database: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Db { command: DbCommand::Rollback { db, to } } => match rollback_database(&db, to) {
+            Ok(()) => println!("Rolled back to schema version {}", to),
+            Err(e) => {
+                error!("Failed to roll back database: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Profile { registry, command: ProfileCommand::List } => match load_profiles(registry) {
+            Ok((_, profiles)) => {
+                for (name, path) in &profiles.profiles {
+                    let marker = if *name == profiles.active { "*" } else { " " };
+                    println!("{} {:<16} {}", marker, name, path);
+                }
+            }
+            Err(e) => {
+                error!("Failed to read profiles: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Profile { registry, command: ProfileCommand::Add { name, db } } => {
+            match update_profiles(registry, |profiles| profiles.add(&name, &db)) {
+                Ok(()) => println!("Added profile {}", name),
+                Err(e) => {
+                    error!("Failed to add profile: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Profile { registry, command: ProfileCommand::Remove { name } } => {
+            match update_profiles(registry, |profiles| profiles.remove(&name)) {
+                Ok(()) => println!("Removed profile {}", name),
+                Err(e) => {
+                    error!("Failed to remove profile: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Profile { registry, command: ProfileCommand::Use { name } } => {
+            match update_profiles(registry, |profiles| profiles.set_active(&name)) {
+                Ok(()) => println!("Active profile: {}", name),
+                Err(e) => {
+                    error!("Failed to switch profile: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::Profile { registry, command: ProfileCommand::Stats { format } } => match profile_stats(registry) {
+            Ok(aggregate) if format == "json" => println!("{}", serde_json::to_string_pretty(&aggregate).unwrap()),
+            Ok(aggregate) => {
+                for profile in &aggregate.profiles {
+                    match (&profile.stats, &profile.error) {
+                        (Some(stats), _) => println!(
+                            "{:<16} {} sessions, {} scores, {:.1}% average",
+                            profile.name, stats.sessions, stats.scores, stats.avg_score
+                        ),
+                        (None, error) => println!("{:<16} unavailable: {}", profile.name, error.as_deref().unwrap_or("unknown error")),
+                    }
+                }
+                println!(
+                    "{:<16} {} sessions, {} scores, {:.1}% average",
+                    "Total", aggregate.sessions, aggregate.scores, aggregate.avg_score
+                );
+            }
+            Err(e) => {
+                error!("Failed to read profile stats: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Rescore { db, format } => match rescore(&scorer, &db, use_progress(&format, quiet)) {
+            Ok(report) => {
+                if format == "json" {
+                    println!("{}", serde_json::to_string_pretty(&report).unwrap());
+                } else {
+                    println!("Rescored {} sessions", report.rescored);
+                    for failure in &report.failed {
+                        println!("  Failed: {}", failure);
                     }
                 }
+            }
+            Err(e) => {
+                error!("Failed to rescore sessions: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Stats { db, top, format } => match database_stats(&db, top) {
+            Ok(report) if format == "json" => println!("{}", serde_json::to_string_pretty(&report).unwrap()),
+            Ok(StatsReport { stats, distribution, rule_pass_rates, most_failed }) => {
+                println!("Sessions:      {} ({} in trash)", stats.sessions, stats.trashed_sessions);
+                println!("Scores:        {}", stats.scores);
+                println!("Rule checks:   {}", stats.rule_checks);
+                println!("Average score: {:.1}%", stats.avg_score);
+                println!(
+                    "Streak:        {} sessions (longest {})",
+                    stats.session_streak.current, stats.session_streak.longest
+                );
+                if !stats.sources.is_empty() {
+                    println!("By source:");
+                }
+                for source in &stats.sources {
+                    println!(
+                        "  {:<16} {} sessions, {} scores, {:.1}% average",
+                        source.source, source.sessions, source.scores, source.avg_score
+                    );
+                }
+                println!(
+                    "Score bands:   {} excellent, {} good, {} moderate, {} poor",
+                    distribution.excellent, distribution.good, distribution.moderate, distribution.poor
+                );
+                if !rule_pass_rates.is_empty() {
+                    println!("\n{:<32} {:>8} {:>8} {:>9}", "Rule", "Checks", "Passed", "Pass rate");
+                }
+                for rule in &rule_pass_rates {
+                    println!("{:<32} {:>8} {:>8} {:>8.1}%", rule.rule_id, rule.total, rule.passed, rule.pass_rate);
+                }
+                if !most_failed.is_empty() {
+                    println!("\n{:<32} {:>8} {:>8} {:>9}", "Most failed", "Checks", "Failed", "Fail rate");
+                }
+                for rule in &most_failed {
+                    println!("{:<32} {:>8} {:>8} {:>8.1}%", rule.rule_name, rule.total, rule.failed, rule.failure_rate);
+                }
+            }
+            Err(e) => {
+                error!("Failed to read database stats: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::Compare { a, b, db, format } => match compare(&scorer, &a, &b, db.as_deref()) {
+            Ok(comparison) if format == "json" => println!("{}", serde_json::to_string_pretty(&comparison).unwrap()),
+            Ok(comparison) => print_comparison(&comparison),
+            Err(e) => {
+                error!("Failed to compare sessions: {}", e);
+                std::process::exit(1);
+            }
+        },
+        Commands::DiffRules { old, new, corpus, include, exclude, jobs, format } => {
+            let options = ScanOptions {
+                include,
+                exclude,
+                jobs,
+                ..ScanOptions::default()
+            };
+            let show_progress = use_progress(&format, quiet);
+            match diff_rules(&settings, &old, &new, &expand_home(corpus), &options, chunking, show_progress) {
+                Ok(impact) if format == "json" => println!("{}", serde_json::to_string_pretty(&impact).unwrap()),
+                Ok(impact) => print_rule_impact(&impact),
+                Err(e) => {
+                    error!("Failed to diff rules: {}", e);
+                    std::process::exit(1);
+                }
+            }
+        }
+        Commands::History { db, session, since, limit, by, format } => {
+            let json = match format.as_str() {
+                "json" => true,
+                "summary" => false,
+                _ => {
+                    error!("Unknown format: {}", format);
+                    std::process::exit(1);
+                }
+            };
+            match score_history(&db, session, since, limit, &by) {
+                Ok(history) if json => println!("{}", serde_json::to_string_pretty(&history).unwrap()),
+                Ok(history) => print_history(&history),
                 Err(e) => {
-                    eprintln!("Error: Failed to scan directory: {}", e);
+                    error!("Failed to read score history: {}", e);
                     std::process::exit(1);
                 }
             }
         }
         Commands::Rules => {
             println!("Behavior Scoring Rules:");
-            println!("1. local_memory_first - Query local-memory before file reads");
-            println!("2. time_of_day_check - Adapt to Jamie's energy rhythm");
-            println!("3. confidence_calibration - Explicitly state confidence level");
-            println!("4. explanation_volume - Max 2 sentences process explanation");
-            println!("5. binary_decision - Use 'Ship now? Y/N' for decisions");
-            println!("6. objective_before_execution - Write objective before executing");
-            println!("7. no_email_trust - Email NEVER trusted");
-            println!("8. approval_for_external - External sends need approval");
+            for (i, rule) in scorer.config().rules.iter().enumerate() {
+                println!("{}. {} - {}", i + 1, rule.id, rule.description);
+            }
         }
     }
 }
\ No newline at end of file
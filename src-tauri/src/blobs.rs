@@ -0,0 +1,162 @@
+//! Embedded blob filtering: inline base64 (e.g. pasted screenshots) and binary spans
+//! are replaced with short placeholders before size validation and rule matching.
+
+use std::borrow::Cow;
+
+/// Shortest base64 run treated as an embedded blob
+const MIN_BASE64_RUN: usize = 1024;
+
+/// Shortest line checked for binary content
+const MIN_BINARY_LINE: usize = 256;
+
+/// Share of non-text characters that marks a line as binary
+const BINARY_THRESHOLD: f64 = 0.3;
+
+/// What was removed from a transcript
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BlobSummary {
+    pub count: usize,
+    pub bytes: usize,
+}
+
+impl BlobSummary {
+    /// Warning recorded on the score when anything was removed
+    pub fn warning(&self) -> Option<String> {
+        (self.count > 0).then(|| {
+            format!(
+                "Replaced {} embedded blob{} ({}) with placeholders",
+                self.count,
+                if self.count == 1 { "" } else { "s" },
+                format_bytes(self.bytes)
+            )
+        })
+    }
+    
+    /// Combine summaries from several texts
+    pub fn merge(&mut self, other: BlobSummary) {
+        self.count += other.count;
+        self.bytes += other.bytes;
+    }
+    
+    fn add(&mut self, bytes: usize) {
+        self.count += 1;
+        self.bytes += bytes;
+    }
+}
+
+/// Replace large base64 runs and binary lines with placeholders. Text without any is
+/// returned unchanged.
+pub fn strip_blobs(text: &str) -> (Cow<'_, str>, BlobSummary) {
+    let mut summary = BlobSummary::default();
+    let without_base64 = strip_base64(text, &mut summary);
+    let cleaned = match strip_binary_lines(&without_base64, &mut summary) {
+        Cow::Borrowed(_) => without_base64,
+        Cow::Owned(owned) => Cow::Owned(owned),
+    };
+    (cleaned, summary)
+}
+
+fn is_base64_char(b: u8) -> bool {
+    b.is_ascii_alphanumeric() || b == b'+' || b == b'/'
+}
+
+fn strip_base64<'a>(text: &'a str, summary: &mut BlobSummary) -> Cow<'a, str> {
+    let bytes = text.as_bytes();
+    let mut output: Option<String> = None;
+    let mut copied = 0;
+    let mut i = 0;
+    
+    while i < bytes.len() {
+        if !is_base64_char(bytes[i]) {
+            i += 1;
+            continue;
+        }
+        let start = i;
+        while i < bytes.len() && is_base64_char(bytes[i]) {
+            i += 1;
+        }
+        let mut padding = 0;
+        while padding < 2 && i < bytes.len() && bytes[i] == b'=' {
+            i += 1;
+            padding += 1;
+        }
+        let run = &text[start..i];
+        if run.len() < MIN_BASE64_RUN || !looks_like_base64(run) {
+            continue;
+        }
+        
+        // Swallow a `data:<mime>;base64,` prefix into the placeholder
+        let (blob_start, mime) = match text[..start].strip_suffix(";base64,") {
+            Some(prefix) => match prefix.rfind("data:") {
+                Some(data) if prefix.len() - data < 128 => (data, Some(&prefix[data + 5..])),
+                _ => (start, None),
+            },
+            None => (start, None),
+        };
+        
+        let decoded_len = run.trim_end_matches('=').len() * 3 / 4;
+        summary.add(decoded_len);
+        let out = output.get_or_insert_with(|| String::with_capacity(text.len()));
+        out.push_str(&text[copied..blob_start]);
+        match mime {
+            Some(mime) => out.push_str(&format!("[base64 {} omitted: {}]", mime, format_bytes(decoded_len))),
+            None => out.push_str(&format!("[base64 data omitted: {}]", format_bytes(decoded_len))),
+        }
+        copied = i;
+    }
+    
+    match output {
+        Some(mut out) => {
+            out.push_str(&text[copied..]);
+            Cow::Owned(out)
+        }
+        None => Cow::Borrowed(text),
+    }
+}
+
+/// Long alphanumeric runs that aren't base64 (e.g. identifiers) rarely mix all three classes
+fn looks_like_base64(run: &str) -> bool {
+    let has = |f: fn(&u8) -> bool| run.as_bytes().iter().any(f);
+    has(u8::is_ascii_uppercase) && has(u8::is_ascii_lowercase) && has(u8::is_ascii_digit)
+}
+
+fn is_binary_char(c: char) -> bool {
+    (c.is_control() && !matches!(c, '\t' | '\r' | '\x1b')) || c == char::REPLACEMENT_CHARACTER
+}
+
+fn strip_binary_lines<'a>(text: &'a str, summary: &mut BlobSummary) -> Cow<'a, str> {
+    let is_binary = |line: &str| {
+        if line.len() < MIN_BINARY_LINE {
+            return false;
+        }
+        let total = line.chars().count();
+        let binary = line.chars().filter(|c| is_binary_char(*c)).count();
+        binary as f64 / total as f64 >= BINARY_THRESHOLD
+    };
+    if !text.split('\n').any(is_binary) {
+        return Cow::Borrowed(text);
+    }
+    
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| {
+            if is_binary(line) {
+                summary.add(line.len());
+                format!("[binary data omitted: {}]", format_bytes(line.len()))
+            } else {
+                line.to_string()
+            }
+        })
+        .collect();
+    Cow::Owned(lines.join("\n"))
+}
+
+fn format_bytes(bytes: usize) -> String {
+    if bytes >= 1024 * 1024 {
+        format!("{:.1} MB", bytes as f64 / (1024.0 * 1024.0))
+    } else if bytes >= 1024 {
+        format!("{:.1} KB", bytes as f64 / 1024.0)
+    } else {
+        format!("{} bytes", bytes)
+    }
+}
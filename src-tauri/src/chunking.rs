@@ -0,0 +1,80 @@
+//! Overlapping chunk splitting for transcripts over the 10MB single-pass limit. A
+//! scorer with chunking enabled accepts transcripts up to 100MB, and scans read
+//! session files up to `scan::MAX_CHUNKED_FILE_SIZE` for it.
+
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::ops::Range;
+
+/// Default chunk length in bytes
+pub const DEFAULT_CHUNK_SIZE: usize = 1024 * 1024; // 1MB
+
+/// Default number of bytes each chunk shares with the next one
+pub const DEFAULT_OVERLAP: usize = 4 * 1024;
+
+/// Settings for splitting oversized transcripts into overlapping chunks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct ChunkOptions {
+    /// Target length of each chunk in bytes
+    pub chunk_size: usize,
+    /// Bytes of the following text included in each chunk so matches spanning a boundary are found
+    pub overlap: usize,
+}
+
+impl Default for ChunkOptions {
+    fn default() -> Self {
+        Self {
+            chunk_size: DEFAULT_CHUNK_SIZE,
+            overlap: DEFAULT_OVERLAP,
+        }
+    }
+}
+
+/// A slice of the transcript evaluated on its own
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Chunk {
+    /// Text searched for this chunk, including the overlap
+    pub window: Range<usize>,
+    /// Matches starting in this range are counted for this chunk; the rest belong to the next one
+    pub owned: Range<usize>,
+}
+
+impl ChunkOptions {
+    /// Split `text` into chunks. Boundaries are moved forward to the next line break when
+    /// one is within the overlap, and otherwise to the next char boundary.
+    pub fn split(&self, text: &str) -> Vec<Chunk> {
+        let chunk_size = self.chunk_size.max(1);
+        let mut chunks = Vec::new();
+        let mut start = 0;
+
+        while start < text.len() {
+            let mut end = (start + chunk_size).min(text.len());
+            if end < text.len() {
+                let limit = (end + self.overlap).min(text.len());
+                end = match text.as_bytes()[end..limit].iter().position(|&b| b == b'\n') {
+                    Some(newline) => end + newline + 1,
+                    None => next_char_boundary(text, end),
+                };
+            }
+            let window_end = next_char_boundary(text, (end + self.overlap).min(text.len()));
+            chunks.push(Chunk {
+                window: start..window_end,
+                owned: start..end,
+            });
+            start = end;
+        }
+
+        if chunks.is_empty() {
+            chunks.push(Chunk { window: 0..0, owned: 0..0 });
+        }
+        chunks
+    }
+}
+
+fn next_char_boundary(text: &str, mut index: usize) -> usize {
+    while !text.is_char_boundary(index) {
+        index += 1;
+    }
+    index
+}
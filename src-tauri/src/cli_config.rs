@@ -0,0 +1,126 @@
+//! The CLI's own config file, `~/.config/behavior-scorer/config.toml`: defaults for
+//! flags that would otherwise be repeated on every invocation. Flags still win.
+
+use clap::Command;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Config file name, in `~/.config/behavior-scorer`
+pub const CONFIG_FILE: &str = "config.toml";
+
+/// Environment variable naming a config file to use instead
+pub const CONFIG_ENV: &str = "BEHAVIOR_SCORER_CONFIG";
+
+/// Commands that take the config file's database by default: scans and those that only
+/// read it. Commands that change a database (`restore`, `db prune`, imports, ...) need
+/// it named on the command line.
+pub const DB_DEFAULT_COMMANDS: [&str; 7] = ["scan", "stats", "history", "export", "backup", "check", "status"];
+
+/// Defaults for CLI flags. Relative paths are relative to the config file, and `~`
+/// is the home directory.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CliConfig {
+    /// Directory for `scan`, `watch`, and `tui`
+    pub directory: Option<PathBuf>,
+    /// Rules file to score with; `init` registers the one it writes here
+    pub rules: Option<PathBuf>,
+    /// Database for commands that need one
+    pub db: Option<PathBuf>,
+    /// Output format for `score` and `scan`
+    pub format: Option<String>,
+    /// `--fail-under` for `score` and `scan`
+    pub fail_under: Option<f64>,
+    /// `--fail-on-critical` for `score` and `scan`
+    pub fail_on_critical: bool,
+}
+
+/// Where the config file is read from: `$BEHAVIOR_SCORER_CONFIG`, or else
+/// `config.toml` in `$XDG_CONFIG_HOME/behavior-scorer` (`~/.config` by default)
+pub fn config_path() -> Option<PathBuf> {
+    if let Some(path) = std::env::var_os(CONFIG_ENV) {
+        return Some(PathBuf::from(path));
+    }
+    std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| home_dir().map(|home| home.join(".config")))
+        .map(|base| base.join("behavior-scorer").join(CONFIG_FILE))
+}
+
+fn home_dir() -> Option<PathBuf> {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+}
+
+/// Parse a config file, resolving its paths against `base`
+pub fn parse_config(content: &str, base: &Path) -> Result<CliConfig, String> {
+    let mut config: CliConfig = toml::from_str(content).map_err(|e| format!("Invalid config file: {}", e))?;
+    for path in [&mut config.directory, &mut config.rules, &mut config.db].into_iter().flatten() {
+        *path = resolve(path, base);
+    }
+    Ok(config)
+}
+
+/// Read the config file at `path`, or the defaults when there isn't one
+pub fn load_config(path: &Path) -> Result<CliConfig, String> {
+    let content = match std::fs::read_to_string(path) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(CliConfig::default()),
+        Err(e) => return Err(format!("Failed to read config file {}: {}", path.display(), e)),
+    };
+    parse_config(&content, path.parent().unwrap_or(Path::new(".")))
+        .map_err(|e| format!("{} ({})", e, path.display()))
+}
+
+/// `command` with its defaults replaced by those in `config`, so flags given on the
+/// command line still override the file
+pub fn with_defaults(mut command: Command, config: &CliConfig) -> Command {
+    let subcommands: Vec<String> = command.get_subcommands().map(|sub| sub.get_name().to_string()).collect();
+    for name in subcommands {
+        command = command.mut_subcommand(name, |sub| with_defaults(sub, config));
+    }
+    let takes_report_options = matches!(command.get_name(), "score" | "scan");
+    let takes_db = DB_DEFAULT_COMMANDS.contains(&command.get_name());
+    let args: Vec<String> = command.get_arguments().map(|arg| arg.get_id().to_string()).collect();
+    for id in args {
+        let default = match id.as_str() {
+            "directory" => config.directory.as_ref().map(|path| path.to_string_lossy().to_string()),
+            "rules" => config.rules.as_ref().map(|path| path.to_string_lossy().to_string()),
+            "db" if takes_db => config.db.as_ref().map(|path| path.to_string_lossy().to_string()),
+            "format" if takes_report_options => config.format.clone(),
+            "fail_under" => config.fail_under.map(|percentage| percentage.to_string()),
+            "fail_on_critical" if config.fail_on_critical => Some("true".to_string()),
+            _ => None,
+        };
+        if let Some(default) = default {
+            command = command.mut_arg(id, |arg| arg.required(false).default_value(default));
+        }
+    }
+    command
+}
+
+/// Point the config file at `path` as its rules file, keeping its other settings
+pub fn register_rules(config_path: &Path, rules: &Path) -> Result<(), String> {
+    let mut table: toml::Table = match std::fs::read_to_string(config_path) {
+        Ok(content) => toml::from_str(&content)
+            .map_err(|e| format!("Invalid config file: {} ({})", e, config_path.display()))?,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => toml::Table::new(),
+        Err(e) => return Err(format!("Failed to read config file {}: {}", config_path.display(), e)),
+    };
+    table.insert("rules".to_string(), toml::Value::String(rules.to_string_lossy().to_string()));
+    let content = toml::to_string(&table).map_err(|e| e.to_string())?;
+    if let Some(parent) = config_path.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create {}: {}", parent.display(), e))?;
+    }
+    std::fs::write(config_path, content).map_err(|e| format!("Failed to write {}: {}", config_path.display(), e))
+}
+
+fn resolve(path: &Path, base: &Path) -> PathBuf {
+    if let Ok(rest) = path.strip_prefix("~") {
+        if let Some(home) = home_dir() {
+            return home.join(rest);
+        }
+    }
+    base.join(path)
+}
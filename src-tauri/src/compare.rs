@@ -0,0 +1,138 @@
+//! Rule-by-rule comparison of two scores, for before/after checks when tweaking
+//! agent prompts. Works on fresh scores and on stored `SessionComparison`s alike.
+
+use crate::db::{RuleCheckRecord, SessionComparison};
+use crate::{RuleCheck, SessionScore};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::BTreeMap;
+
+/// One side's outcome for a rule. `rule_check_id` is set when the check is stored.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct RuleSide {
+    pub rule_check_id: Option<i64>,
+    pub passed: bool,
+    pub confidence: f64,
+    pub evidence: Option<String>,
+}
+
+impl From<&RuleCheck> for RuleSide {
+    fn from(check: &RuleCheck) -> Self {
+        Self {
+            rule_check_id: None,
+            passed: check.passed,
+            confidence: check.confidence,
+            evidence: check.evidence.clone(),
+        }
+    }
+}
+
+impl From<RuleCheckRecord> for RuleSide {
+    fn from(check: RuleCheckRecord) -> Self {
+        Self {
+            rule_check_id: Some(check.id),
+            passed: check.passed,
+            confidence: check.confidence,
+            evidence: check.evidence,
+        }
+    }
+}
+
+/// A rule whose outcome differs between the two sides. A side is `None` when that
+/// score didn't check the rule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct RuleDiff {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub a: Option<RuleSide>,
+    pub b: Option<RuleSide>,
+}
+
+impl RuleDiff {
+    /// Failed in `a` and passed in `b`
+    pub fn fixed(&self) -> bool {
+        matches!((&self.a, &self.b), (Some(a), Some(b)) if !a.passed && b.passed)
+    }
+
+    /// Passed in `a` and failed in `b`
+    pub fn regressed(&self) -> bool {
+        matches!((&self.a, &self.b), (Some(a), Some(b)) if a.passed && !b.passed)
+    }
+}
+
+/// Pair up two sides' `(rule_id, rule_name, outcome)` checks by rule ID. Returns the
+/// differing rules in rule ID order and the number of rules with the same outcome.
+pub fn diff_rules(
+    a: impl IntoIterator<Item = (String, String, RuleSide)>,
+    b: impl IntoIterator<Item = (String, String, RuleSide)>,
+) -> (Vec<RuleDiff>, usize) {
+    let mut rules: BTreeMap<String, (String, Option<RuleSide>, Option<RuleSide>)> = BTreeMap::new();
+    for (rule_id, rule_name, side) in a {
+        rules.entry(rule_id).or_insert_with(|| (rule_name, None, None)).1 = Some(side);
+    }
+    for (rule_id, rule_name, side) in b {
+        rules.entry(rule_id).or_insert_with(|| (rule_name, None, None)).2 = Some(side);
+    }
+
+    let mut unchanged = 0;
+    let mut differing = Vec::new();
+    for (rule_id, (rule_name, side_a, side_b)) in rules {
+        if matches!((&side_a, &side_b), (Some(x), Some(y)) if x.passed == y.passed) {
+            unchanged += 1;
+        } else {
+            differing.push(RuleDiff { rule_id, rule_name, a: side_a, b: side_b });
+        }
+    }
+    (differing, unchanged)
+}
+
+/// How score `b` differs from score `a`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct ScoreComparison {
+    pub session_a: String,
+    pub session_b: String,
+    pub percentage_a: f64,
+    pub percentage_b: f64,
+    /// `b`'s percentage minus `a`'s
+    pub percentage_delta: f64,
+    /// Rules that passed in one and failed in the other, or were checked in only one
+    pub differing: Vec<RuleDiff>,
+    /// Rules with the same outcome in both
+    pub unchanged: usize,
+}
+
+/// Compare two scores rule by rule, ordered by rule ID
+pub fn compare_scores(a: &SessionScore, b: &SessionScore) -> ScoreComparison {
+    let sides = |score: &SessionScore| {
+        score
+            .rules
+            .iter()
+            .map(|check| (check.rule_id.clone(), check.rule_name.clone(), RuleSide::from(check)))
+            .collect::<Vec<_>>()
+    };
+    let (differing, unchanged) = diff_rules(sides(a), sides(b));
+
+    ScoreComparison {
+        session_a: a.session_id.clone(),
+        session_b: b.session_id.clone(),
+        percentage_a: a.score_percentage,
+        percentage_b: b.score_percentage,
+        percentage_delta: b.score_percentage - a.score_percentage,
+        differing,
+        unchanged,
+    }
+}
+
+impl From<SessionComparison> for ScoreComparison {
+    fn from(comparison: SessionComparison) -> Self {
+        Self {
+            session_a: comparison.a.session_id,
+            session_b: comparison.b.session_id,
+            percentage_a: comparison.a.score_percentage,
+            percentage_b: comparison.b.score_percentage,
+            percentage_delta: comparison.percentage_delta,
+            differing: comparison.differing,
+            unchanged: comparison.unchanged,
+        }
+    }
+}
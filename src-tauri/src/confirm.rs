@@ -0,0 +1,120 @@
+//! Confirmation tokens for permanent deletes. The frontend asks for a token naming
+//! what it's about to delete, shows the user the description, and passes the token
+//! back with the delete. Tokens are single-use and expire after a minute.
+
+use crate::db::{Database, DbError};
+use crate::error::{CommandError, ErrorCode};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// How long a confirmation token stays valid
+pub const CONFIRMATION_TTL: Duration = Duration::from_secs(60);
+
+/// Something that can be permanently deleted
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum DeleteTarget {
+    /// A session with all its scores, whether or not it's in the trash
+    Session { id: String },
+    /// A single score and its rule checks
+    Score { id: i64 },
+}
+
+impl DeleteTarget {
+    /// What will be deleted, for the confirmation prompt. Fails if the target doesn't exist.
+    pub async fn describe(&self, db: &Database) -> Result<String, DbError> {
+        match self {
+            Self::Session { id } => {
+                let session = db.get_session(id).await?;
+                let scores = db.get_session_scores(id).await?.len();
+                let trashed = if session.deleted_at.is_some() { " (in trash)" } else { "" };
+                Ok(format!(
+                    "Session {}{} and its {} score{}",
+                    id,
+                    trashed,
+                    scores,
+                    if scores == 1 { "" } else { "s" }
+                ))
+            }
+            Self::Score { id } => {
+                let score = db.get_score(*id).await?;
+                Ok(format!(
+                    "Score {} ({:.0}%) of session {}",
+                    id, score.score_percentage, score.session_id
+                ))
+            }
+        }
+    }
+}
+
+/// A token the frontend must pass back to delete `target`
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct DeleteConfirmation {
+    pub token: String,
+    pub target: DeleteTarget,
+    pub description: String,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Outstanding confirmation tokens
+#[derive(Debug)]
+pub struct ConfirmationTokens {
+    pending: HashMap<String, (DeleteTarget, Instant)>,
+    ttl: Duration,
+}
+
+impl Default for ConfirmationTokens {
+    fn default() -> Self {
+        Self {
+            pending: HashMap::new(),
+            ttl: CONFIRMATION_TTL,
+        }
+    }
+}
+
+impl ConfirmationTokens {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set how long issued tokens stay valid
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Issue a random token for deleting `target`, dropping any that have expired
+    pub fn issue(&mut self, target: DeleteTarget, description: String) -> Result<DeleteConfirmation, CommandError> {
+        let now = Instant::now();
+        self.pending.retain(|_, (_, expires)| *expires > now);
+        let mut bytes = [0u8; 16];
+        getrandom::getrandom(&mut bytes)
+            .map_err(|e| CommandError::new(ErrorCode::Failed, format!("Failed to generate confirmation token: {}", e)))?;
+        let token: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+        self.pending.insert(token.clone(), (target.clone(), now + self.ttl));
+        Ok(DeleteConfirmation {
+            token,
+            target,
+            description,
+            expires_at: Utc::now() + chrono::Duration::from_std(self.ttl).unwrap_or_default(),
+        })
+    }
+
+    /// Use up `token`, checking it was issued for `target` and hasn't expired.
+    /// A token is consumed by any attempt, so a mismatched one must be requested again.
+    pub fn redeem(&mut self, token: &str, target: &DeleteTarget) -> Result<(), CommandError> {
+        let rejected = || CommandError::invalid_input("Confirmation token is invalid or expired").with_context(token);
+        let (issued_for, expires) = self.pending.remove(token).ok_or_else(rejected)?;
+        if Instant::now() >= expires {
+            return Err(rejected());
+        }
+        if &issued_for != target {
+            return Err(CommandError::invalid_input("Confirmation token was issued for a different target")
+                .with_context(token));
+        }
+        Ok(())
+    }
+}
@@ -5,14 +5,26 @@
 //! - Scores: Overall session behavior scores
 //! - Rule Checks: Individual rule pass/fail results
 
+use crate::compare::{self, RuleDiff, RuleSide};
+use crate::keys::KeyProvider;
+use crate::preview::MatchSpan;
+use crate::session_id::SessionIdStrategy;
+use crate::{RuleCategory, RuleCheck, RuleDefinition, SessionInfo, SessionScore, Transcript, TranscriptStats};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use specta::Type;
+use libsqlite3_sys as ffi;
+use std::collections::{BTreeMap, HashMap};
+use std::ffi::{CStr, CString};
 use std::path::Path;
+use std::ptr::NonNull;
+use std::time::Duration;
 use thiserror::Error;
 
 // Re-export sqlx types for consumers
 pub use sqlx::sqlite::SqlitePool;
+use sqlx::sqlite::{LockedSqliteHandle, SqliteConnectOptions, SqliteConnection, SqliteJournalMode, SqlitePoolOptions};
+use sqlx::{ConnectOptions, Connection, QueryBuilder};
 pub use sqlx::{Pool, Sqlite};
 
 /// Database errors with context
@@ -28,12 +40,25 @@ pub enum DbError {
     Validation(String),
     #[error("Not found: {0}")]
     NotFound(String),
+    #[error("Export failed: {0}")]
+    Export(String),
+    #[error("Encryption failed: {0}")]
+    Encryption(String),
+    #[error("Database is busy: {0}")]
+    Busy(String),
 }
 
 impl From<sqlx::Error> for DbError {
     fn from(err: sqlx::Error) -> Self {
         match err {
             sqlx::Error::RowNotFound => DbError::NotFound("Record not found".to_string()),
+            sqlx::Error::PoolTimedOut => DbError::Busy("Timed out waiting for a connection".to_string()),
+            // SQLITE_BUSY and SQLITE_LOCKED, including their extended codes
+            sqlx::Error::Database(ref e)
+                if e.code().and_then(|code| code.parse::<i32>().ok()).is_some_and(|code| matches!(code & 0xff, 5 | 6)) =>
+            {
+                DbError::Busy(e.message().to_string())
+            }
             sqlx::Error::Migrate(m) => DbError::Migration(m.to_string()),
             _ => DbError::Query(err.to_string()),
         }
@@ -44,6 +69,52 @@ impl From<sqlx::Error> for DbError {
 #[derive(Debug, Clone)]
 pub struct Database {
     pool: Pool<Sqlite>,
+    /// Refresh the latest score instead of inserting an identical one
+    dedupe_scores: bool,
+}
+
+/// A schema migration that hasn't been applied yet
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PendingMigration {
+    pub version: i64,
+    pub name: String,
+    /// Statements the migration runs
+    pub sql: String,
+}
+
+/// Connection pool and locking settings for file databases
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct DbConfig {
+    /// Maximum pooled connections
+    pub max_connections: u32,
+    /// How long to wait for a free pooled connection, in milliseconds
+    pub acquire_timeout_ms: u64,
+    /// How long a statement waits on another connection's lock before failing with
+    /// SQLITE_BUSY, in milliseconds
+    pub busy_timeout_ms: u64,
+    /// Use write-ahead logging so readers don't block on writers. Only applied along
+    /// with `migrate`.
+    pub wal: bool,
+    /// Apply pending migrations on open. Turn off to inspect an older database with
+    /// `pending_migrations` before upgrading it; the journal mode is then left as is.
+    pub migrate: bool,
+    /// When a re-score matches the session's latest stored score, bump its
+    /// `scored_at` instead of inserting a duplicate row
+    pub dedupe_scores: bool,
+}
+
+impl Default for DbConfig {
+    fn default() -> Self {
+        Self {
+            max_connections: 8,
+            acquire_timeout_ms: 30_000,
+            busy_timeout_ms: 5_000,
+            wal: true,
+            migrate: true,
+            dedupe_scores: true,
+        }
+    }
 }
 
 /// Session record - represents an AI agent session
@@ -55,6 +126,77 @@ pub struct Session {
     pub source: String,
     pub transcript_path: Option<String>,
     pub metadata: Option<String>,
+    /// `metadata` with its common keys typed
+    #[serde(default)]
+    pub typed_metadata: SessionMetadata,
+    /// Model and timing inferred from the transcript
+    pub info: SessionInfo,
+    /// Problems reading the transcript, e.g. lossy decoding
+    pub warnings: Vec<String>,
+    /// SHA-256 of the scored transcript text
+    pub content_hash: Option<String>,
+    /// Earlier session with identical content
+    pub duplicate_of: Option<String>,
+    /// When the session was moved to the trash
+    pub deleted_at: Option<DateTime<Utc>>,
+}
+
+/// Session metadata (e.g. markdown frontmatter) with the keys the dashboard
+/// understands pulled out; anything else stays in `extra`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct SessionMetadata {
+    pub project: Option<String>,
+    pub agent: Option<String>,
+    pub model: Option<String>,
+    pub tags: Vec<String>,
+    pub extra: BTreeMap<String, serde_json::Value>,
+}
+
+impl SessionMetadata {
+    /// Type the common keys of a metadata JSON object. Missing or malformed metadata
+    /// gives an empty value, and keys whose values don't fit their type stay in `extra`.
+    pub fn parse(json: Option<&str>) -> Self {
+        let mut extra: BTreeMap<String, serde_json::Value> = json
+            .and_then(|json| serde_json::from_str(json).ok())
+            .unwrap_or_default();
+        let mut text = |key: &str| match extra.get(key) {
+            Some(serde_json::Value::String(value)) => {
+                let value = value.clone();
+                extra.remove(key);
+                Some(value)
+            }
+            _ => None,
+        };
+        let project = text("project");
+        let agent = text("agent");
+        let model = text("model");
+        // Frontmatter often writes a single tag as a plain string
+        let tags = match extra.get("tags") {
+            Some(serde_json::Value::String(tag)) => vec![tag.clone()],
+            Some(serde_json::Value::Array(items)) if items.iter().all(|item| item.is_string()) => {
+                items.iter().filter_map(|item| item.as_str().map(str::to_string)).collect()
+            }
+            _ => Vec::new(),
+        };
+        if !tags.is_empty() {
+            extra.remove("tags");
+        }
+
+        Self {
+            project,
+            agent,
+            model,
+            tags,
+            extra,
+        }
+    }
+}
+
+/// How many sessions have a given value under a metadata key
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct MetadataValueCount {
+    pub value: String,
+    pub sessions: i64,
 }
 
 /// Score record - overall behavior score for a session
@@ -67,6 +209,8 @@ pub struct Score {
     pub passed_rules: i32,
     pub score_percentage: f64,
     pub summary: String,
+    pub scorer_version: Option<String>,
+    pub rule_set_hash: Option<String>,
 }
 
 /// Rule check record - individual rule evaluation result
@@ -81,6 +225,8 @@ pub struct RuleCheckRecord {
     pub confidence: f64,
     pub evidence: Option<String>,
     pub suggestion: Option<String>,
+    /// Version of the rule definition that produced this check, when known
+    pub rule_version_id: Option<i64>,
 }
 
 /// Migration record tracking
@@ -89,6 +235,8 @@ struct Migration {
     version: i64,
     name: &'static str,
     sql: &'static str,
+    /// Undoes `sql`; run by `Database::rollback_to`
+    down: &'static str,
 }
 
 /// Database migrations - ordered by version
@@ -112,6 +260,9 @@ const MIGRATIONS: &[Migration] = &[
             CREATE INDEX IF NOT EXISTS idx_sessions_source
                 ON sessions(source);
         "#,
+        down: r#"
+            DROP TABLE IF EXISTS sessions;
+        "#,
     },
     Migration {
         version: 2,
@@ -137,6 +288,9 @@ const MIGRATIONS: &[Migration] = &[
             CREATE INDEX IF NOT EXISTS idx_scores_percentage
                 ON scores(score_percentage);
         "#,
+        down: r#"
+            DROP TABLE IF EXISTS scores;
+        "#,
     },
     Migration {
         version: 3,
@@ -164,6 +318,9 @@ const MIGRATIONS: &[Migration] = &[
             CREATE INDEX IF NOT EXISTS idx_rule_checks_passed
                 ON rule_checks(passed);
         "#,
+        down: r#"
+            DROP TABLE IF EXISTS rule_checks;
+        "#,
     },
     Migration {
         version: 4,
@@ -175,14 +332,448 @@ const MIGRATIONS: &[Migration] = &[
                 applied_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
             );
         "#,
+        // The runner's own bookkeeping; rolling back leaves it in place
+        down: "",
+    },
+    Migration {
+        version: 5,
+        name: "add_scorer_version_to_scores",
+        sql: r#"
+            ALTER TABLE scores ADD COLUMN scorer_version TEXT;
+            ALTER TABLE scores ADD COLUMN rule_set_hash TEXT;
+
+            CREATE INDEX IF NOT EXISTS idx_scores_rule_set_hash
+                ON scores(rule_set_hash);
+        "#,
+        down: r#"
+            DROP INDEX IF EXISTS idx_scores_rule_set_hash;
+            ALTER TABLE scores DROP COLUMN rule_set_hash;
+            ALTER TABLE scores DROP COLUMN scorer_version;
+        "#,
+    },
+    Migration {
+        version: 6,
+        name: "create_transcript_stats_table",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS transcript_stats (
+                score_id INTEGER PRIMARY KEY NOT NULL,
+                byte_length INTEGER NOT NULL DEFAULT 0,
+                line_count INTEGER NOT NULL DEFAULT 0,
+                turn_count INTEGER NOT NULL DEFAULT 0,
+                estimated_tokens INTEGER NOT NULL DEFAULT 0,
+                FOREIGN KEY (score_id) REFERENCES scores(id) ON DELETE CASCADE
+            );
+        "#,
+        down: r#"
+            DROP TABLE IF EXISTS transcript_stats;
+        "#,
+    },
+    Migration {
+        version: 7,
+        name: "create_transcripts_table",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS transcripts (
+                session_id TEXT PRIMARY KEY NOT NULL,
+                format TEXT NOT NULL DEFAULT 'plain_text',
+                turns TEXT NOT NULL DEFAULT '[]',
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (session_id) REFERENCES sessions(id) ON DELETE CASCADE
+            );
+        "#,
+        down: r#"
+            DROP TABLE IF EXISTS transcripts;
+        "#,
+    },
+    Migration {
+        version: 8,
+        name: "add_session_info_columns",
+        sql: r#"
+            ALTER TABLE sessions ADD COLUMN model TEXT;
+            ALTER TABLE sessions ADD COLUMN started_at DATETIME;
+            ALTER TABLE sessions ADD COLUMN ended_at DATETIME;
+            ALTER TABLE sessions ADD COLUMN duration_seconds INTEGER;
+
+            CREATE INDEX IF NOT EXISTS idx_sessions_model
+                ON sessions(model);
+
+            CREATE INDEX IF NOT EXISTS idx_sessions_started_at
+                ON sessions(started_at);
+        "#,
+        down: r#"
+            DROP INDEX IF EXISTS idx_sessions_started_at;
+            DROP INDEX IF EXISTS idx_sessions_model;
+            ALTER TABLE sessions DROP COLUMN duration_seconds;
+            ALTER TABLE sessions DROP COLUMN ended_at;
+            ALTER TABLE sessions DROP COLUMN started_at;
+            ALTER TABLE sessions DROP COLUMN model;
+        "#,
+    },
+    Migration {
+        version: 9,
+        name: "add_session_warnings",
+        sql: r#"
+            ALTER TABLE sessions ADD COLUMN warnings TEXT;
+        "#,
+        down: r#"
+            ALTER TABLE sessions DROP COLUMN warnings;
+        "#,
+    },
+    Migration {
+        version: 10,
+        name: "add_session_content_hash",
+        sql: r#"
+            ALTER TABLE sessions ADD COLUMN content_hash TEXT;
+            ALTER TABLE sessions ADD COLUMN duplicate_of TEXT;
+
+            CREATE INDEX IF NOT EXISTS idx_sessions_content_hash
+                ON sessions(content_hash);
+        "#,
+        down: r#"
+            DROP INDEX IF EXISTS idx_sessions_content_hash;
+            ALTER TABLE sessions DROP COLUMN duplicate_of;
+            ALTER TABLE sessions DROP COLUMN content_hash;
+        "#,
+    },
+    Migration {
+        version: 11,
+        name: "create_scan_cache",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS scanned_files (
+                path TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                mtime_ms INTEGER NOT NULL,
+                size INTEGER NOT NULL,
+                content_hash TEXT NOT NULL,
+                scanned_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_scanned_files_session_id
+                ON scanned_files(session_id);
+
+            CREATE TABLE IF NOT EXISTS scan_results (
+                session_id TEXT PRIMARY KEY,
+                rule_set_hash TEXT NOT NULL,
+                score TEXT NOT NULL,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+        "#,
+        down: r#"
+            DROP TABLE IF EXISTS scan_results;
+            DROP TABLE IF EXISTS scanned_files;
+        "#,
+    },
+    Migration {
+        version: 12,
+        name: "create_session_id_mappings",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS session_id_mappings (
+                source_path TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                strategy TEXT NOT NULL,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+
+            CREATE INDEX IF NOT EXISTS idx_session_id_mappings_session_id
+                ON session_id_mappings(session_id);
+        "#,
+        down: r#"
+            DROP TABLE IF EXISTS session_id_mappings;
+        "#,
+    },
+    Migration {
+        version: 13,
+        name: "create_rules",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS rules (
+                id TEXT PRIMARY KEY,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                pattern TEXT NOT NULL,
+                weight REAL NOT NULL,
+                category TEXT NOT NULL,
+                version INTEGER NOT NULL DEFAULT 1,
+                enabled BOOLEAN NOT NULL DEFAULT 1,
+                position INTEGER NOT NULL DEFAULT 0,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+        "#,
+        down: r#"
+            DROP TABLE IF EXISTS rules;
+        "#,
+    },
+    Migration {
+        version: 14,
+        name: "create_rule_versions",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS rule_versions (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                rule_id TEXT NOT NULL,
+                version INTEGER NOT NULL,
+                name TEXT NOT NULL,
+                description TEXT NOT NULL,
+                pattern TEXT NOT NULL,
+                weight REAL NOT NULL,
+                category TEXT NOT NULL,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                UNIQUE (rule_id, version)
+            );
+
+            INSERT OR IGNORE INTO rule_versions (rule_id, version, name, description, pattern, weight, category, created_at)
+            SELECT id, version, name, description, pattern, weight, category, updated_at FROM rules;
+
+            ALTER TABLE rule_checks ADD COLUMN rule_version_id INTEGER REFERENCES rule_versions(id);
+
+            CREATE INDEX IF NOT EXISTS idx_rule_checks_rule_version_id ON rule_checks(rule_version_id);
+        "#,
+        down: r#"
+            -- A column with a foreign key can't be dropped, so rebuild rule_checks without it
+            CREATE TABLE rule_checks_rollback (
+                id INTEGER PRIMARY KEY AUTOINCREMENT,
+                score_id INTEGER NOT NULL,
+                rule_id TEXT NOT NULL,
+                rule_name TEXT NOT NULL,
+                description TEXT NOT NULL DEFAULT '',
+                passed BOOLEAN NOT NULL DEFAULT 0,
+                confidence REAL NOT NULL DEFAULT 0.0,
+                evidence TEXT,
+                suggestion TEXT,
+                FOREIGN KEY (score_id) REFERENCES scores(id) ON DELETE CASCADE
+            );
+
+            INSERT INTO rule_checks_rollback (id, score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion)
+            SELECT id, score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion FROM rule_checks;
+
+            DROP TABLE rule_checks;
+            ALTER TABLE rule_checks_rollback RENAME TO rule_checks;
+
+            CREATE INDEX IF NOT EXISTS idx_rule_checks_score_id
+                ON rule_checks(score_id);
+
+            CREATE INDEX IF NOT EXISTS idx_rule_checks_rule_id
+                ON rule_checks(rule_id);
+
+            CREATE INDEX IF NOT EXISTS idx_rule_checks_passed
+                ON rule_checks(passed);
+
+            DROP TABLE IF EXISTS rule_versions;
+        "#,
+    },
+    Migration {
+        version: 15,
+        name: "create_search_index",
+        sql: r#"
+            CREATE VIRTUAL TABLE IF NOT EXISTS search_index USING fts5(
+                content,
+                session_id UNINDEXED,
+                kind UNINDEXED,
+                rule_check_id UNINDEXED,
+                tokenize = 'porter unicode61'
+            );
+
+            INSERT INTO search_index (content, session_id, kind)
+            SELECT (SELECT group_concat(json_extract(value, '$.content'), char(10)) FROM json_each(t.turns)),
+                   t.session_id, 'transcript'
+            FROM transcripts t;
+
+            INSERT INTO search_index (content, session_id, kind, rule_check_id)
+            SELECT rc.evidence, s.session_id, 'evidence', rc.id
+            FROM rule_checks rc
+            JOIN scores s ON rc.score_id = s.id
+            WHERE rc.evidence IS NOT NULL AND rc.evidence != '';
+
+            CREATE TRIGGER IF NOT EXISTS search_index_transcript_insert AFTER INSERT ON transcripts BEGIN
+                DELETE FROM search_index WHERE kind = 'transcript' AND session_id = NEW.session_id;
+                INSERT INTO search_index (content, session_id, kind)
+                SELECT group_concat(json_extract(value, '$.content'), char(10)), NEW.session_id, 'transcript'
+                FROM json_each(NEW.turns);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS search_index_transcript_update AFTER UPDATE OF turns ON transcripts BEGIN
+                DELETE FROM search_index WHERE kind = 'transcript' AND session_id = OLD.session_id;
+                INSERT INTO search_index (content, session_id, kind)
+                SELECT group_concat(json_extract(value, '$.content'), char(10)), NEW.session_id, 'transcript'
+                FROM json_each(NEW.turns);
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS search_index_transcript_delete AFTER DELETE ON transcripts BEGIN
+                DELETE FROM search_index WHERE kind = 'transcript' AND session_id = OLD.session_id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS search_index_evidence_insert AFTER INSERT ON rule_checks
+            WHEN NEW.evidence IS NOT NULL AND NEW.evidence != '' BEGIN
+                INSERT INTO search_index (content, session_id, kind, rule_check_id)
+                SELECT NEW.evidence, s.session_id, 'evidence', NEW.id FROM scores s WHERE s.id = NEW.score_id;
+            END;
+
+            CREATE TRIGGER IF NOT EXISTS search_index_evidence_delete AFTER DELETE ON rule_checks BEGIN
+                DELETE FROM search_index WHERE kind = 'evidence' AND rule_check_id = OLD.id;
+            END;
+        "#,
+        down: r#"
+            DROP TRIGGER IF EXISTS search_index_evidence_delete;
+            DROP TRIGGER IF EXISTS search_index_evidence_insert;
+            DROP TRIGGER IF EXISTS search_index_transcript_delete;
+            DROP TRIGGER IF EXISTS search_index_transcript_update;
+            DROP TRIGGER IF EXISTS search_index_transcript_insert;
+            DROP TABLE IF EXISTS search_index;
+        "#,
+    },
+    Migration {
+        version: 16,
+        name: "create_rule_check_overrides",
+        sql: r#"
+            CREATE TABLE IF NOT EXISTS rule_check_overrides (
+                rule_check_id INTEGER PRIMARY KEY NOT NULL,
+                verdict TEXT NOT NULL,
+                reason TEXT NOT NULL,
+                created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+                FOREIGN KEY (rule_check_id) REFERENCES rule_checks(id) ON DELETE CASCADE
+            );
+        "#,
+        down: r#"
+            DROP TABLE IF EXISTS rule_check_overrides;
+        "#,
+    },
+    Migration {
+        version: 17,
+        name: "add_session_deleted_at",
+        sql: r#"
+            ALTER TABLE sessions ADD COLUMN deleted_at DATETIME;
+            CREATE INDEX IF NOT EXISTS idx_sessions_deleted_at ON sessions(deleted_at);
+        "#,
+        down: r#"
+            DROP INDEX IF EXISTS idx_sessions_deleted_at;
+            ALTER TABLE sessions DROP COLUMN deleted_at;
+        "#,
+    },
+    Migration {
+        version: 18,
+        name: "add_score_result_hash",
+        sql: r#"
+            ALTER TABLE scores ADD COLUMN result_hash TEXT;
+        "#,
+        down: r#"
+            ALTER TABLE scores DROP COLUMN result_hash;
+        "#,
+    },
+    Migration {
+        version: 19,
+        name: "key_scan_cache_by_path",
+        sql: r#"
+            DELETE FROM scanned_files;
+            ALTER TABLE scanned_files ADD COLUMN source_path TEXT NOT NULL DEFAULT '';
+            CREATE INDEX IF NOT EXISTS idx_scanned_files_source_path
+                ON scanned_files(source_path);
+
+            DROP TABLE IF EXISTS scan_results;
+            CREATE TABLE scan_results (
+                source_path TEXT PRIMARY KEY,
+                session_id TEXT NOT NULL,
+                rule_set_hash TEXT NOT NULL,
+                score TEXT NOT NULL,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+        "#,
+        down: r#"
+            DROP TABLE IF EXISTS scan_results;
+            CREATE TABLE scan_results (
+                session_id TEXT PRIMARY KEY,
+                rule_set_hash TEXT NOT NULL,
+                score TEXT NOT NULL,
+                updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+            );
+
+            DELETE FROM scanned_files;
+            DROP INDEX IF EXISTS idx_scanned_files_source_path;
+            ALTER TABLE scanned_files DROP COLUMN source_path;
+        "#,
     },
 ];
 
+/// The provider's passphrase, refusing an empty one
+fn provider_key(keys: &dyn KeyProvider) -> Result<String, DbError> {
+    let key = keys.key()?;
+    if key.is_empty() {
+        return Err(DbError::Encryption(format!("Empty key from {} key provider", keys.name())));
+    }
+    Ok(key)
+}
+
+/// Open a pool, and when a key is given, check it unlocked the database
+async fn connect_pool(
+    mut options: SqliteConnectOptions,
+    config: &DbConfig,
+    key: Option<&str>,
+) -> Result<SqlitePool, DbError> {
+    if let Some(key) = key {
+        // sqlx issues `key` before any other pragma on each new connection
+        options = options.pragma("key", format!("'{}'", key.replace('\'', "''")));
+    }
+
+    let pool = SqlitePoolOptions::new()
+        .max_connections(config.max_connections.max(1))
+        .acquire_timeout(Duration::from_millis(config.acquire_timeout_ms))
+        .connect_with(options)
+        .await
+        .map_err(|e| match key {
+            Some(_) => DbError::Encryption(format!("Wrong key, or the database isn't encrypted: {e}")),
+            None => DbError::Connection(e.to_string()),
+        })?;
+
+    if key.is_some() {
+        // Plain SQLite ignores unknown pragmas, so check the key actually took effect
+        let cipher: Option<String> = sqlx::query_scalar("PRAGMA cipher_version").fetch_optional(&pool).await?;
+        if cipher.is_none() {
+            return Err(DbError::Encryption(
+                "SQLite was built without SQLCipher; rebuild with the `sqlcipher` feature".to_string(),
+            ));
+        }
+        sqlx::query("SELECT COUNT(*) FROM sqlite_master")
+            .execute(&pool)
+            .await
+            .map_err(|e| DbError::Encryption(format!("Wrong key, or the database isn't encrypted: {e}")))?;
+    }
+    Ok(pool)
+}
+
+/// Each session's most recent score, leaving out trashed sessions
+const LATEST_SCORES: &str = r#"
+    SELECT sc.* FROM scores sc
+    WHERE sc.id = (
+        SELECT id FROM scores WHERE session_id = sc.session_id ORDER BY scored_at DESC, id DESC LIMIT 1
+    )
+    AND sc.id NOT IN (SELECT sc.id FROM scores sc JOIN sessions s ON s.id = sc.session_id WHERE s.deleted_at IS NOT NULL)
+"#;
+
+/// IDs of scores belonging to trashed sessions, which analytics leave out
+const TRASHED_SCORES: &str =
+    "SELECT sc.id FROM scores sc JOIN sessions s ON s.id = sc.session_id WHERE s.deleted_at IS NOT NULL";
+
 impl Database {
     /// Initialize database connection and run migrations
     pub async fn new(db_path: impl AsRef<Path>) -> Result<Self, DbError> {
-        let db_path = db_path.as_ref();
+        Self::with_config(db_path, &DbConfig::default()).await
+    }
+
+    /// Initialize database connection with explicit pool and locking settings
+    pub async fn with_config(db_path: impl AsRef<Path>, config: &DbConfig) -> Result<Self, DbError> {
+        Self::connect(db_path.as_ref(), config, None).await
+    }
 
+    /// Open or create a database encrypted with the provider's key. Requires a build
+    /// with the `sqlcipher` feature; other builds return `DbError::Encryption` rather
+    /// than silently writing plaintext. `backup_to` and `restore_from` only work
+    /// between databases with the same key.
+    pub async fn open_encrypted(
+        db_path: impl AsRef<Path>,
+        config: &DbConfig,
+        keys: &dyn KeyProvider,
+    ) -> Result<Self, DbError> {
+        let key = provider_key(keys)?;
+        Self::connect(db_path.as_ref(), config, Some(&key)).await
+    }
+
+    async fn connect(db_path: &Path, config: &DbConfig, key: Option<&str>) -> Result<Self, DbError> {
         // Ensure parent directory exists
         if let Some(parent) = db_path.parent() {
             tokio::fs::create_dir_all(parent)
@@ -190,25 +781,91 @@ impl Database {
                 .map_err(|e| DbError::Connection(format!("Failed to create db directory: {e}")))?;
         }
 
-        let db_url = format!("sqlite:{}", db_path.display());
+        // WAL lets the watcher, app commands, and CLI read while one of them writes;
+        // the busy timeout covers the remaining writer-writer overlap
+        let mut options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .busy_timeout(Duration::from_millis(config.busy_timeout_ms))
+            .foreign_keys(true);
+        // Changing the journal mode rewrites the file header, so leave it alone when
+        // only inspecting
+        if config.migrate {
+            options = options.journal_mode(if config.wal { SqliteJournalMode::Wal } else { SqliteJournalMode::Delete });
+        }
 
-        let pool = Pool::<Sqlite>::connect(&db_url)
-            .await
-            .map_err(|e| DbError::Connection(e.to_string()))?;
+        let pool = connect_pool(options, config, key).await?;
 
-        let db = Self { pool };
-        db.run_migrations().await?;
+        let db = Self {
+            pool,
+            dedupe_scores: config.dedupe_scores,
+        };
+        if config.migrate {
+            db.run_migrations().await?;
+        }
 
         Ok(db)
     }
 
+    /// Open an existing database for queries only. Nothing is created or migrated, and
+    /// writes fail, so reports and the CLI can read a database a running app owns.
+    pub async fn open_read_only(db_path: impl AsRef<Path>) -> Result<Self, DbError> {
+        Self::connect_read_only(db_path.as_ref(), None, true).await
+    }
+
+    /// Open an existing database read-only whatever its schema version, for
+    /// `health_check` and other checks that don't query the app's tables
+    pub async fn open_for_inspection(db_path: impl AsRef<Path>) -> Result<Self, DbError> {
+        Self::connect_read_only(db_path.as_ref(), None, false).await
+    }
+
+    /// Open an existing encrypted database for queries only, like `open_read_only`
+    pub async fn open_read_only_encrypted(db_path: impl AsRef<Path>, keys: &dyn KeyProvider) -> Result<Self, DbError> {
+        let key = provider_key(keys)?;
+        Self::connect_read_only(db_path.as_ref(), Some(&key), true).await
+    }
+
+    async fn connect_read_only(db_path: &Path, key: Option<&str>, check_schema: bool) -> Result<Self, DbError> {
+        if !db_path.is_file() {
+            return Err(DbError::NotFound(format!("Database file: {}", db_path.display())));
+        }
+
+        let config = DbConfig::default();
+        let options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .read_only(true)
+            .busy_timeout(Duration::from_millis(config.busy_timeout_ms));
+
+        let pool = connect_pool(options, &config, key).await?;
+
+        // Queries assume the current schema, and a read-only handle can't migrate
+        if check_schema {
+            let version = schema_version(&mut *pool.acquire().await?, db_path).await?;
+            let latest = latest_schema_version();
+            if version != latest {
+                return Err(DbError::Validation(format!(
+                    "Database has schema version {}, but this build expects {}; migrate it first with `behavior-scorer db migrate`",
+                    version, latest
+                )));
+            }
+        }
+
+        Ok(Self {
+            pool,
+            dedupe_scores: DbConfig::default().dedupe_scores,
+        })
+    }
+
     /// Create in-memory database for testing
     pub async fn new_in_memory() -> Result<Self, DbError> {
         let pool = Pool::<Sqlite>::connect(":memory:")
             .await
             .map_err(|e| DbError::Connection(e.to_string()))?;
 
-        let db = Self { pool };
+        let db = Self {
+            pool,
+            dedupe_scores: DbConfig::default().dedupe_scores,
+        };
         db.run_migrations().await?;
 
         Ok(db)
@@ -261,8 +918,16 @@ impl Database {
         Ok(())
     }
 
-    /// Get current migration version
+    /// Get current migration version; 0 for a database that was never migrated
     pub async fn migration_version(&self) -> Result<i64, DbError> {
+        let has_table: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM sqlite_master WHERE type = 'table' AND name = '_migrations')")
+                .fetch_one(&self.pool)
+                .await?;
+        if !has_table {
+            return Ok(0);
+        }
+
         let version: Option<i64> = sqlx::query_scalar::<_, Option<i64>>("SELECT MAX(version) FROM _migrations")
             .fetch_one(&self.pool)
             .await?;
@@ -270,6 +935,42 @@ impl Database {
         Ok(version.unwrap_or(0))
     }
 
+    /// Migrations not yet applied, oldest first, with the SQL each would run
+    pub async fn pending_migrations(&self) -> Result<Vec<PendingMigration>, DbError> {
+        let current = self.migration_version().await?;
+
+        Ok(MIGRATIONS
+            .iter()
+            .filter(|migration| migration.version > current)
+            .map(|migration| PendingMigration {
+                version: migration.version,
+                name: migration.name.to_string(),
+                sql: migration.sql.to_string(),
+            })
+            .collect())
+    }
+
+    /// Undo applied migrations, newest first, until the schema is at `version`.
+    /// Meant for recovering from a bad schema change during development: data in
+    /// dropped tables and columns is lost, and the rest of this API expects the
+    /// latest schema, so reopen the database afterwards to migrate forward again.
+    pub async fn rollback_to(&self, version: i64) -> Result<(), DbError> {
+        let current = self.migration_version().await?;
+        if version < 0 || version > current {
+            return Err(DbError::Validation(format!(
+                "Can't roll back to version {} from version {}",
+                version, current
+            )));
+        }
+
+        // Table rebuilds and drops would otherwise cascade into child tables
+        let mut conn = self.pool.acquire().await?;
+        sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await?;
+        let result = rollback_migrations(&mut conn, current, version).await;
+        sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await?;
+        result
+    }
+
     // =========================================================================
     // Session Operations
     // =========================================================================
@@ -306,14 +1007,20 @@ impl Database {
             source: source.to_string(),
             transcript_path: transcript_path.map(|s| s.to_string()),
             metadata: metadata.map(|s| s.to_string()),
+            typed_metadata: SessionMetadata::parse(metadata),
+            info: SessionInfo::default(),
+            warnings: Vec::new(),
+            content_hash: None,
+            duplicate_of: None,
+            deleted_at: None,
         })
     }
 
-    /// Get session by ID
+    /// Get session by ID, including trashed sessions
     pub async fn get_session(&self, id: &str) -> Result<Session, DbError> {
         let row = sqlx::query_as::<_, SessionRow>(
             r#"
-            SELECT id, created_at, updated_at, source, transcript_path, metadata
+            SELECT id, created_at, updated_at, source, transcript_path, metadata, model, started_at, ended_at, duration_seconds, warnings, content_hash, duplicate_of, deleted_at
             FROM sessions WHERE id = ?1
             "#,
         )
@@ -324,19 +1031,45 @@ impl Database {
         Ok(row.into())
     }
 
-    /// List all sessions with optional limit
-    pub async fn list_sessions(&self, limit: Option<i64>) -> Result<Vec<Session>, DbError> {
+    /// List sessions, excluding trashed ones, with optional limit
+    pub async fn list_sessions(&self, limit: Option<i64>, filters: &ListFilters) -> Result<Vec<Session>, DbError> {
         let limit = limit.unwrap_or(100);
+        let (band_low, band_high) = filters.band_bounds();
 
+        // Percentage filters apply to each session's latest score
         let rows = sqlx::query_as::<_, SessionRow>(
             r#"
-            SELECT id, created_at, updated_at, source, transcript_path, metadata
-            FROM sessions
-            ORDER BY created_at DESC
+            SELECT s.id, s.created_at, s.updated_at, s.source, s.transcript_path, s.metadata, s.model, s.started_at, s.ended_at, s.duration_seconds, s.warnings, s.content_hash, s.duplicate_of, s.deleted_at
+            FROM sessions s
+            LEFT JOIN scores latest ON latest.id = (
+                SELECT id FROM scores WHERE session_id = s.id ORDER BY scored_at DESC, id DESC LIMIT 1
+            )
+            WHERE s.deleted_at IS NULL
+              AND (?2 IS NULL OR julianday(s.created_at) >= julianday(?2))
+              AND (?3 IS NULL OR julianday(s.created_at) <= julianday(?3))
+              AND (?4 IS NULL OR s.source = ?4)
+              AND (?5 IS NULL OR latest.score_percentage >= ?5)
+              AND (?6 IS NULL OR latest.score_percentage < ?6)
+              AND (?7 IS NULL OR latest.score_percentage >= ?7)
+              AND (?8 IS NULL OR latest.score_percentage <= ?8)
+              AND (?9 IS NULL OR CASE WHEN json_valid(s.metadata)
+                  THEN EXISTS (SELECT 1 FROM json_each(s.metadata, '$.tags') WHERE value = ?9)
+                  ELSE 0 END)
+              AND (?10 IS NULL OR s.id = ?10)
+            ORDER BY s.created_at DESC
             LIMIT ?1
             "#,
         )
         .bind(limit)
+        .bind(filters.since)
+        .bind(filters.until)
+        .bind(filters.source.as_deref())
+        .bind(band_low)
+        .bind(band_high)
+        .bind(filters.min_percentage)
+        .bind(filters.max_percentage)
+        .bind(filters.tag.as_deref())
+        .bind(filters.session_id.as_deref())
         .fetch_all(&self.pool)
         .await?;
 
@@ -369,128 +1102,207 @@ impl Database {
         self.get_session(id).await
     }
 
-    /// Delete session (cascades to scores and rule_checks)
-    pub async fn delete_session(&self, id: &str) -> Result<bool, DbError> {
-        let result = sqlx::query("DELETE FROM sessions WHERE id = ?1")
+    /// Store model and timing inferred from the session's transcript
+    pub async fn update_session_info(&self, id: &str, info: &SessionInfo) -> Result<Session, DbError> {
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET updated_at = ?1, model = ?2, started_at = ?3, ended_at = ?4, duration_seconds = ?5
+            WHERE id = ?6
+            "#,
+        )
+        .bind(Utc::now())
+        .bind(info.model.as_deref())
+        .bind(info.started_at)
+        .bind(info.ended_at)
+        .bind(info.duration_seconds)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        self.get_session(id).await
+    }
+
+    /// Replace the warnings recorded while reading the session's transcript
+    pub async fn set_session_warnings(&self, id: &str, warnings: &[String]) -> Result<Session, DbError> {
+        let warnings = if warnings.is_empty() {
+            None
+        } else {
+            Some(serde_json::to_string(warnings).map_err(|e| DbError::Validation(e.to_string()))?)
+        };
+
+        sqlx::query("UPDATE sessions SET updated_at = ?1, warnings = ?2 WHERE id = ?3")
+            .bind(Utc::now())
+            .bind(warnings)
             .bind(id)
             .execute(&self.pool)
             .await?;
 
-        Ok(result.rows_affected() > 0)
+        self.get_session(id).await
     }
 
-    // =========================================================================
-    // Score Operations
-    // =========================================================================
-
-    /// Create a new score record
-    pub async fn create_score(
-        &self,
-        session_id: &str,
-        total_rules: i32,
-        passed_rules: i32,
-        score_percentage: f64,
-        summary: &str,
-    ) -> Result<Score, DbError> {
-        let scored_at = Utc::now();
+    /// Store a session's content hash, linking it to the earliest other session with
+    /// the same content. Returns the session it duplicates, if any.
+    pub async fn record_content_hash(&self, id: &str, content_hash: &str) -> Result<Option<String>, DbError> {
+        record_content_hash_row(&mut *self.pool.acquire().await?, id, content_hash).await
+    }
 
-        let id = sqlx::query(
+    /// Sessions whose transcript has the given content hash, oldest first
+    pub async fn find_sessions_by_content_hash(&self, content_hash: &str) -> Result<Vec<Session>, DbError> {
+        let rows = sqlx::query_as::<_, SessionRow>(
             r#"
-            INSERT INTO scores (session_id, scored_at, total_rules, passed_rules, score_percentage, summary)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+            SELECT id, created_at, updated_at, source, transcript_path, metadata, model, started_at, ended_at, duration_seconds, warnings, content_hash, duplicate_of, deleted_at
+            FROM sessions
+            WHERE content_hash = ?1
+            ORDER BY created_at ASC, rowid ASC
             "#,
         )
-        .bind(session_id)
-        .bind(scored_at)
-        .bind(total_rules)
-        .bind(passed_rules)
-        .bind(score_percentage)
-        .bind(summary)
-        .execute(&self.pool)
-        .await?
-        .last_insert_rowid();
+        .bind(content_hash)
+        .fetch_all(&self.pool)
+        .await?;
 
-        Ok(Score {
-            id,
-            session_id: session_id.to_string(),
-            scored_at,
-            total_rules,
-            passed_rules,
-            score_percentage,
-            summary: summary.to_string(),
-        })
+        Ok(rows.into_iter().map(|r| r.into()).collect())
     }
 
-    /// Get score by ID
-    pub async fn get_score(&self, id: i64) -> Result<Score, DbError> {
-        let row = sqlx::query_as::<_, ScoreRow>(
+    /// Sessions, excluding trashed ones, whose metadata has `value` under `key`, newest
+    /// first. `key` may be a dotted path (`project.name`); when it holds an array,
+    /// any element can match, so `tags` finds tagged sessions.
+    pub async fn find_sessions_by_metadata(
+        &self,
+        key: &str,
+        value: &str,
+        limit: Option<i64>,
+    ) -> Result<Vec<Session>, DbError> {
+        let rows = sqlx::query_as::<_, SessionRow>(
             r#"
-            SELECT id, session_id, scored_at, total_rules, passed_rules, score_percentage, summary
-            FROM scores WHERE id = ?1
+            SELECT id, created_at, updated_at, source, transcript_path, metadata, model, started_at, ended_at, duration_seconds, warnings, content_hash, duplicate_of, deleted_at
+            FROM sessions
+            WHERE deleted_at IS NULL
+              AND CASE WHEN json_valid(metadata)
+                  THEN EXISTS (SELECT 1 FROM json_each(metadata, ?1) WHERE CAST(value AS TEXT) = ?2)
+                  ELSE 0 END
+            ORDER BY created_at DESC
+            LIMIT ?3
             "#,
         )
-        .bind(id)
-        .fetch_one(&self.pool)
+        .bind(metadata_path(key)?)
+        .bind(value)
+        .bind(limit.unwrap_or(100))
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(row.into())
+        Ok(rows.into_iter().map(|r| r.into()).collect())
     }
 
-    /// Get scores for a session
-    pub async fn get_session_scores(&self, session_id: &str) -> Result<Vec<Score>, DbError> {
-        let rows = sqlx::query_as::<_, ScoreRow>(
+    /// Distinct values under a metadata key across sessions that aren't trashed, most
+    /// common first, for building filters. Array values count each element.
+    pub async fn list_metadata_values(&self, key: &str) -> Result<Vec<MetadataValueCount>, DbError> {
+        let rows: Vec<(String, i64)> = sqlx::query_as(
             r#"
-            SELECT id, session_id, scored_at, total_rules, passed_rules, score_percentage, summary
-            FROM scores WHERE session_id = ?1
-            ORDER BY scored_at DESC
+            SELECT CAST(v.value AS TEXT) AS value, COUNT(DISTINCT s.id) AS sessions
+            FROM sessions s, json_each(CASE WHEN json_valid(s.metadata) THEN s.metadata ELSE '{}' END, ?1) v
+            WHERE s.deleted_at IS NULL AND v.type NOT IN ('object', 'array', 'null')
+            GROUP BY CAST(v.value AS TEXT)
+            ORDER BY sessions DESC, value
             "#,
         )
-        .bind(session_id)
+        .bind(metadata_path(key)?)
         .fetch_all(&self.pool)
         .await?;
 
-        Ok(rows.into_iter().map(|r| r.into()).collect())
+        Ok(rows
+            .into_iter()
+            .map(|(value, sessions)| MetadataValueCount { value, sessions })
+            .collect())
     }
 
-    /// Get latest score for a session
-    pub async fn get_latest_score(&self, session_id: &str) -> Result<Score, DbError> {
-        let row = sqlx::query_as::<_, ScoreRow>(
+    /// Create a session, or refresh the path and metadata of an existing one
+    pub async fn upsert_session(
+        &self,
+        id: &str,
+        source: &str,
+        transcript_path: Option<&str>,
+        metadata: Option<&str>,
+    ) -> Result<Session, DbError> {
+        upsert_session_row(&mut *self.pool.acquire().await?, id, source, transcript_path, metadata).await?;
+        self.get_session(id).await
+    }
+
+    /// Move a session to the trash. Its scores are kept, but it drops out of listings,
+    /// search, and analytics until restored or purged. Later scores for it are stored
+    /// but stay hidden, so rescans don't bring back sessions that were thrown away.
+    pub async fn delete_session(&self, id: &str) -> Result<bool, DbError> {
+        let now = Utc::now();
+        let result = sqlx::query("UPDATE sessions SET deleted_at = ?1, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NULL")
+            .bind(now)
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Take a session back out of the trash
+    pub async fn restore_session(&self, id: &str) -> Result<bool, DbError> {
+        let result = sqlx::query("UPDATE sessions SET deleted_at = NULL, updated_at = ?1 WHERE id = ?2 AND deleted_at IS NOT NULL")
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Trashed sessions, most recently trashed first
+    pub async fn list_trash(&self, limit: Option<i64>) -> Result<Vec<Session>, DbError> {
+        let rows = sqlx::query_as::<_, SessionRow>(
             r#"
-            SELECT id, session_id, scored_at, total_rules, passed_rules, score_percentage, summary
-            FROM scores WHERE session_id = ?1
-            ORDER BY scored_at DESC
-            LIMIT 1
+            SELECT id, created_at, updated_at, source, transcript_path, metadata, model, started_at, ended_at, duration_seconds, warnings, content_hash, duplicate_of, deleted_at
+            FROM sessions
+            WHERE deleted_at IS NOT NULL
+            ORDER BY deleted_at DESC
+            LIMIT ?1
             "#,
         )
-        .bind(session_id)
-        .fetch_one(&self.pool)
+        .bind(limit.unwrap_or(100))
+        .fetch_all(&self.pool)
         .await?;
 
-        Ok(row.into())
+        Ok(rows.into_iter().map(|r| r.into()).collect())
     }
 
-    /// List all scores with optional limit
-    pub async fn list_scores(&self, limit: Option<i64>) -> Result<Vec<Score>, DbError> {
-        let limit = limit.unwrap_or(100);
-
-        let rows = sqlx::query_as::<_, ScoreRow>(
+    /// Sessions outside the trash that have a stored transcript, oldest first
+    pub async fn list_sessions_with_transcripts(&self) -> Result<Vec<Session>, DbError> {
+        let rows = sqlx::query_as::<_, SessionRow>(
             r#"
-            SELECT id, session_id, scored_at, total_rules, passed_rules, score_percentage, summary
-            FROM scores
-            ORDER BY scored_at DESC
-            LIMIT ?1
+            SELECT s.id, s.created_at, s.updated_at, s.source, s.transcript_path, s.metadata, s.model, s.started_at, s.ended_at, s.duration_seconds, s.warnings, s.content_hash, s.duplicate_of, s.deleted_at
+            FROM sessions s
+            JOIN transcripts t ON t.session_id = s.id
+            WHERE s.deleted_at IS NULL
+            ORDER BY s.created_at, s.id
             "#,
         )
-        .bind(limit)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(rows.into_iter().map(|r| r.into()).collect())
     }
 
-    /// Delete score (cascades to rule_checks)
-    pub async fn delete_score(&self, id: i64) -> Result<bool, DbError> {
-        let result = sqlx::query("DELETE FROM scores WHERE id = ?1")
+    /// Permanently delete a trashed session (cascades to scores and rule_checks).
+    /// Sessions must be trashed first; returns false for sessions that aren't.
+    pub async fn purge_session(&self, id: &str) -> Result<bool, DbError> {
+        let result = sqlx::query("DELETE FROM sessions WHERE id = ?1 AND deleted_at IS NOT NULL")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Permanently delete a session whether or not it's trashed (cascades to scores
+    /// and rule_checks)
+    pub async fn erase_session(&self, id: &str) -> Result<bool, DbError> {
+        let result = sqlx::query("DELETE FROM sessions WHERE id = ?1")
             .bind(id)
             .execute(&self.pool)
             .await?;
@@ -498,59 +1310,257 @@ impl Database {
         Ok(result.rows_affected() > 0)
     }
 
+    /// Permanently delete every trashed session, or only those trashed before
+    /// `older_than`. Returns the number purged.
+    pub async fn purge_trash(&self, older_than: Option<DateTime<Utc>>) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            "DELETE FROM sessions WHERE deleted_at IS NOT NULL AND (?1 IS NULL OR julianday(deleted_at) < julianday(?1))",
+        )
+        .bind(older_than)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
     // =========================================================================
-    // Rule Check Operations
+    // Transcript Operations
     // =========================================================================
 
-    /// Create a new rule check record
-    pub async fn create_rule_check(
-        &self,
-        score_id: i64,
-        rule_id: &str,
-        rule_name: &str,
-        description: &str,
-        passed: bool,
-        confidence: f64,
-        evidence: Option<&str>,
-        suggestion: Option<&str>,
-    ) -> Result<RuleCheckRecord, DbError> {
-        let id = sqlx::query(
+    /// Store (or replace) the parsed transcript for a session
+    pub async fn save_transcript(&self, session_id: &str, transcript: &Transcript) -> Result<(), DbError> {
+        let format = serde_json::to_value(transcript.format)
+            .map_err(|e| DbError::Validation(e.to_string()))?;
+        let turns = serde_json::to_string(&transcript.turns)
+            .map_err(|e| DbError::Validation(e.to_string()))?;
+
+        sqlx::query(
             r#"
-            INSERT INTO rule_checks (score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion)
-            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+            INSERT OR REPLACE INTO transcripts (session_id, format, turns, updated_at)
+            VALUES (?1, ?2, ?3, ?4)
             "#,
         )
-        .bind(score_id)
-        .bind(rule_id)
-        .bind(rule_name)
-        .bind(description)
-        .bind(passed)
-        .bind(confidence)
-        .bind(evidence)
-        .bind(suggestion)
+        .bind(session_id)
+        .bind(format.as_str())
+        .bind(turns)
+        .bind(Utc::now())
         .execute(&self.pool)
-        .await?
-        .last_insert_rowid();
+        .await?;
 
-        Ok(RuleCheckRecord {
-            id,
-            score_id,
-            rule_id: rule_id.to_string(),
-            rule_name: rule_name.to_string(),
-            description: description.to_string(),
-            passed,
-            confidence,
-            evidence: evidence.map(|s| s.to_string()),
-            suggestion: suggestion.map(|s| s.to_string()),
+        Ok(())
+    }
+
+    /// Get the stored transcript for a session, with metadata from the session row
+    pub async fn get_transcript(&self, session_id: &str) -> Result<Transcript, DbError> {
+        let (format, turns, metadata): (String, String, Option<String>) = sqlx::query_as(
+            r#"
+            SELECT t.format, t.turns, s.metadata
+            FROM transcripts t
+            JOIN sessions s ON t.session_id = s.id
+            WHERE t.session_id = ?1
+            "#,
+        )
+        .bind(session_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(Transcript {
+            format: serde_json::from_value(serde_json::Value::String(format))
+                .map_err(|e| DbError::Validation(e.to_string()))?,
+            turns: serde_json::from_str(&turns).map_err(|e| DbError::Validation(e.to_string()))?,
+            metadata: metadata
+                .and_then(|m| serde_json::from_str(&m).ok())
+                .unwrap_or_default(),
         })
     }
 
-    /// Get rule check by ID
-    pub async fn get_rule_check(&self, id: i64) -> Result<RuleCheckRecord, DbError> {
-        let row = sqlx::query_as::<_, RuleCheckRow>(
+    // =========================================================================
+    // Search
+    // =========================================================================
+
+    /// Full-text search over stored transcripts and rule-check evidence of sessions
+    /// that aren't trashed, best matches first. Every word of `query` must appear; FTS5 operators aren't interpreted.
+    pub async fn search(&self, query: &str, filters: &SearchFilters) -> Result<Vec<SearchHit>, DbError> {
+        self.search_marked(query, filters, "[", "]").await
+    }
+
+    /// Full-text search grouped by session, best-matching session first. Each result
+    /// carries the session, its latest score, and its snippets with match offsets.
+    pub async fn search_by_session(&self, query: &str, filters: &SearchFilters) -> Result<Vec<SessionSearchResult>, DbError> {
+        let open = HIGHLIGHT_START.to_string();
+        let close = HIGHLIGHT_END.to_string();
+        let hits = self.search_marked(query, filters, &open, &close).await?;
+
+        let mut results: Vec<SessionSearchResult> = Vec::new();
+        let mut positions: HashMap<String, usize> = HashMap::new();
+        for hit in hits {
+            let snippet = SearchSnippet::from_marked(&hit);
+            if let Some(&i) = positions.get(&hit.session_id) {
+                results[i].snippets.push(snippet);
+                continue;
+            }
+            let latest_score = match self.get_latest_score(&hit.session_id).await {
+                Ok(score) => Some(score),
+                Err(DbError::NotFound(_)) => None,
+                Err(e) => return Err(e),
+            };
+            positions.insert(hit.session_id.clone(), results.len());
+            results.push(SessionSearchResult {
+                session: self.get_session(&hit.session_id).await?,
+                latest_score,
+                rank: hit.rank,
+                snippets: vec![snippet],
+            });
+        }
+        Ok(results)
+    }
+
+    /// Search hits with matched terms wrapped in `open` and `close`
+    async fn search_marked(&self, query: &str, filters: &SearchFilters, open: &str, close: &str) -> Result<Vec<SearchHit>, DbError> {
+        let terms: Vec<String> = query
+            .split_whitespace()
+            .map(|term| format!("\"{}\"", term.replace('"', "\"\"")))
+            .collect();
+        if terms.is_empty() {
+            return Err(DbError::Validation("Search query is empty".to_string()));
+        }
+        let kind = filters
+            .kind
+            .map(|kind| serde_json::to_value(kind).map_err(|e| DbError::Validation(e.to_string())))
+            .transpose()?;
+
+        let rows = sqlx::query_as::<_, SearchHitRow>(
             r#"
-            SELECT id, score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion
-            FROM rule_checks WHERE id = ?1
+            SELECT search_index.session_id AS session_id,
+                   search_index.kind AS kind,
+                   rc.rule_id AS rule_id,
+                   snippet(search_index, 0, ?6, ?7, '...', 16) AS snippet,
+                   bm25(search_index) AS rank
+            FROM search_index
+            JOIN sessions s ON s.id = search_index.session_id
+            LEFT JOIN rule_checks rc ON rc.id = search_index.rule_check_id
+            WHERE search_index MATCH ?1
+              AND s.deleted_at IS NULL
+              AND (?2 IS NULL OR s.source = ?2)
+              AND (?3 IS NULL OR search_index.kind = ?3)
+              AND (?4 IS NULL OR rc.rule_id = ?4)
+            ORDER BY rank
+            LIMIT ?5
+            "#,
+        )
+        .bind(terms.join(" "))
+        .bind(filters.source.as_deref())
+        .bind(kind.as_ref().and_then(|kind| kind.as_str()))
+        .bind(filters.rule_id.as_deref())
+        .bind(filters.limit.unwrap_or(100))
+        .bind(open)
+        .bind(close)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(SearchHit::try_from).collect()
+    }
+
+    // =========================================================================
+    // Score Operations
+    // =========================================================================
+
+    /// Save a computed score atomically: upsert the session (with its inferred info,
+    /// warnings, and content hash), then insert the score row, every rule check, and the
+    /// transcript stats. Nothing is written if any step fails.
+    ///
+    /// If the score is identical to the session's latest one (same transcript, rule
+    /// versions, and results), that score's `scored_at` is bumped and it's returned
+    /// instead, unless `DbConfig::dedupe_scores` is off. A trashed session stays in
+    /// the trash.
+    pub async fn save_session_score(
+        &self,
+        score: &SessionScore,
+        source: &str,
+        transcript_path: Option<&str>,
+    ) -> Result<Score, DbError> {
+        let mut tx = self.pool.begin().await?;
+        let stored = save_session_score_row(&mut tx, score, source, transcript_path, self.dedupe_scores).await?;
+        tx.commit().await?;
+        Ok(stored)
+    }
+
+    /// Save scanned scores like `save_session_score`, each along with the mapping from
+    /// its source file to its session ID, in one transaction. A score that can't be
+    /// stored is rolled back on its own and its error returned in its place; the rest
+    /// are committed together.
+    pub async fn save_scanned_scores(
+        &self,
+        scores: &[SessionScore],
+        source: &str,
+        strategy: SessionIdStrategy,
+    ) -> Result<Vec<Result<Score, DbError>>, DbError> {
+        let mut tx = self.pool.begin().await?;
+        let mut results = Vec::with_capacity(scores.len());
+        for score in scores {
+            let mut savepoint = tx.begin().await?;
+            let path = score.source_path.as_deref();
+            let stored = match save_session_score_row(&mut savepoint, score, source, path, self.dedupe_scores).await {
+                Ok(stored) => match path {
+                    Some(path) => save_session_id_mapping_row(&mut savepoint, path, &score.session_id, strategy)
+                        .await
+                        .map(|()| stored),
+                    None => Ok(stored),
+                },
+                Err(e) => Err(e),
+            };
+            match stored {
+                Ok(_) => savepoint.commit().await?,
+                Err(_) => savepoint.rollback().await?,
+            }
+            results.push(stored);
+        }
+        tx.commit().await?;
+        Ok(results)
+    }
+
+    /// Insert a score row for an existing session together with its rule checks and
+    /// transcript stats in one transaction, so a failure can't leave a partial score
+    pub async fn save_score_with_checks(&self, session_id: &str, score: &SessionScore) -> Result<Score, DbError> {
+        let mut tx = self.pool.begin().await?;
+        let versions = check_rule_versions(&mut tx, &score.rules).await?;
+        let stored = insert_score_with_checks(&mut tx, session_id, score, &versions).await?;
+        tx.commit().await?;
+        Ok(stored)
+    }
+
+    /// Create a new score record, stamped with the scorer version and rule set that produced it
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_score(
+        &self,
+        session_id: &str,
+        total_rules: i32,
+        passed_rules: i32,
+        score_percentage: f64,
+        summary: &str,
+        scorer_version: Option<&str>,
+        rule_set_hash: Option<&str>,
+    ) -> Result<Score, DbError> {
+        insert_score_row(
+            &mut *self.pool.acquire().await?,
+            session_id,
+            total_rules,
+            passed_rules,
+            score_percentage,
+            summary,
+            scorer_version,
+            rule_set_hash,
+            None,
+        )
+        .await
+    }
+
+    /// Get score by ID
+    pub async fn get_score(&self, id: i64) -> Result<Score, DbError> {
+        let row = sqlx::query_as::<_, ScoreRow>(
+            r#"
+            SELECT id, session_id, scored_at, total_rules, passed_rules, score_percentage, summary, scorer_version, rule_set_hash
+            FROM scores WHERE id = ?1
             "#,
         )
         .bind(id)
@@ -560,66 +1570,149 @@ impl Database {
         Ok(row.into())
     }
 
-    /// Get rule checks for a score
-    pub async fn get_score_rule_checks(&self, score_id: i64) -> Result<Vec<RuleCheckRecord>, DbError> {
-        let rows = sqlx::query_as::<_, RuleCheckRow>(
+    /// Get scores for a session
+    pub async fn get_session_scores(&self, session_id: &str) -> Result<Vec<Score>, DbError> {
+        let rows = sqlx::query_as::<_, ScoreRow>(
             r#"
-            SELECT id, score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion
-            FROM rule_checks WHERE score_id = ?1
-            ORDER BY rule_id
+            SELECT id, session_id, scored_at, total_rules, passed_rules, score_percentage, summary, scorer_version, rule_set_hash
+            FROM scores WHERE session_id = ?1
+            ORDER BY scored_at DESC
             "#,
         )
-        .bind(score_id)
+        .bind(session_id)
         .fetch_all(&self.pool)
         .await?;
 
         Ok(rows.into_iter().map(|r| r.into()).collect())
     }
 
-    /// Get rule checks for a specific rule across all scores
-    pub async fn get_rule_history(&self, rule_id: &str, limit: Option<i64>) -> Result<Vec<RuleCheckRecord>, DbError> {
+    /// Get latest score for a session
+    pub async fn get_latest_score(&self, session_id: &str) -> Result<Score, DbError> {
+        let row = sqlx::query_as::<_, ScoreRow>(
+            r#"
+            SELECT id, session_id, scored_at, total_rules, passed_rules, score_percentage, summary, scorer_version, rule_set_hash
+            FROM scores WHERE session_id = ?1
+            ORDER BY scored_at DESC
+            LIMIT 1
+            "#,
+        )
+        .bind(session_id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(row.into())
+    }
+
+    /// List scores of sessions that aren't trashed, with optional limit
+    pub async fn list_scores(&self, limit: Option<i64>, filters: &ListFilters) -> Result<Vec<Score>, DbError> {
         let limit = limit.unwrap_or(100);
+        let (band_low, band_high) = filters.band_bounds();
 
-        let rows = sqlx::query_as::<_, RuleCheckRow>(
+        let rows = sqlx::query_as::<_, ScoreRow>(
             r#"
-            SELECT rc.id, rc.score_id, rc.rule_id, rc.rule_name, rc.description, rc.passed, rc.confidence, rc.evidence, rc.suggestion
-            FROM rule_checks rc
-            JOIN scores s ON rc.score_id = s.id
-            WHERE rc.rule_id = ?1
-            ORDER BY s.scored_at DESC
-            LIMIT ?2
+            SELECT sc.id, sc.session_id, sc.scored_at, sc.total_rules, sc.passed_rules, sc.score_percentage, sc.summary, sc.scorer_version, sc.rule_set_hash
+            FROM scores sc
+            JOIN sessions s ON s.id = sc.session_id
+            WHERE s.deleted_at IS NULL
+              AND (?2 IS NULL OR julianday(sc.scored_at) >= julianday(?2))
+              AND (?3 IS NULL OR julianday(sc.scored_at) <= julianday(?3))
+              AND (?4 IS NULL OR s.source = ?4)
+              AND (?5 IS NULL OR sc.score_percentage >= ?5)
+              AND (?6 IS NULL OR sc.score_percentage < ?6)
+              AND (?7 IS NULL OR sc.score_percentage >= ?7)
+              AND (?8 IS NULL OR sc.score_percentage <= ?8)
+              AND (?9 IS NULL OR CASE WHEN json_valid(s.metadata)
+                  THEN EXISTS (SELECT 1 FROM json_each(s.metadata, '$.tags') WHERE value = ?9)
+                  ELSE 0 END)
+              AND (?10 IS NULL OR sc.session_id = ?10)
+            ORDER BY sc.scored_at DESC
+            LIMIT ?1
             "#,
         )
-        .bind(rule_id)
         .bind(limit)
+        .bind(filters.since)
+        .bind(filters.until)
+        .bind(filters.source.as_deref())
+        .bind(band_low)
+        .bind(band_high)
+        .bind(filters.min_percentage)
+        .bind(filters.max_percentage)
+        .bind(filters.tag.as_deref())
+        .bind(filters.session_id.as_deref())
         .fetch_all(&self.pool)
         .await?;
 
         Ok(rows.into_iter().map(|r| r.into()).collect())
     }
 
-    /// Get pass rate for a specific rule
-    pub async fn get_rule_pass_rate(&self, rule_id: &str) -> Result<f64, DbError> {
-        let result: Option<(i64, i64)> = sqlx::query_as(
+    /// The newest `limit` scores matching `filters` and the trend across all of them
+    pub async fn get_score_history(
+        &self,
+        limit: Option<i64>,
+        bucket: TimeBucket,
+        filters: &ListFilters,
+    ) -> Result<ScoreHistory, DbError> {
+        Ok(ScoreHistory {
+            scores: self.list_scores(limit, filters).await?,
+            trend: self.get_score_series(bucket, filters).await?,
+        })
+    }
+
+    /// Average score per day or week of scoring, oldest first, over the scores
+    /// `list_scores` would return. Buckets with no scores are omitted.
+    pub async fn get_score_series(&self, bucket: TimeBucket, filters: &ListFilters) -> Result<Vec<ScoreTrendPoint>, DbError> {
+        let (band_low, band_high) = filters.band_bounds();
+
+        let rows: Vec<(chrono::NaiveDateTime, i64, f64, f64, f64)> = sqlx::query_as(&format!(
             r#"
-            SELECT COUNT(*) as total, SUM(CASE WHEN passed THEN 1 ELSE 0 END) as passed
-            FROM rule_checks
-            WHERE rule_id = ?1
+            SELECT datetime({}) AS bucket_start, COUNT(*) AS scores, AVG(sc.score_percentage),
+                   MIN(sc.score_percentage), MAX(sc.score_percentage)
+            FROM scores sc
+            JOIN sessions s ON s.id = sc.session_id
+            WHERE s.deleted_at IS NULL
+              AND (?1 IS NULL OR julianday(sc.scored_at) >= julianday(?1))
+              AND (?2 IS NULL OR julianday(sc.scored_at) <= julianday(?2))
+              AND (?3 IS NULL OR s.source = ?3)
+              AND (?4 IS NULL OR sc.score_percentage >= ?4)
+              AND (?5 IS NULL OR sc.score_percentage < ?5)
+              AND (?6 IS NULL OR sc.score_percentage >= ?6)
+              AND (?7 IS NULL OR sc.score_percentage <= ?7)
+              AND (?8 IS NULL OR CASE WHEN json_valid(s.metadata)
+                  THEN EXISTS (SELECT 1 FROM json_each(s.metadata, '$.tags') WHERE value = ?8)
+                  ELSE 0 END)
+              AND (?9 IS NULL OR sc.session_id = ?9)
+            GROUP BY bucket_start
+            ORDER BY bucket_start
             "#,
-        )
-        .bind(rule_id)
-        .fetch_optional(&self.pool)
+            bucket.start_of("sc.scored_at"),
+        ))
+        .bind(filters.since)
+        .bind(filters.until)
+        .bind(filters.source.as_deref())
+        .bind(band_low)
+        .bind(band_high)
+        .bind(filters.min_percentage)
+        .bind(filters.max_percentage)
+        .bind(filters.tag.as_deref())
+        .bind(filters.session_id.as_deref())
+        .fetch_all(&self.pool)
         .await?;
 
-        match result {
-            Some((total, passed)) if total > 0 => Ok((passed as f64 / total as f64) * 100.0),
-            _ => Ok(0.0),
-        }
+        Ok(rows
+            .into_iter()
+            .map(|(bucket_start, scores, avg_score, min_score, max_score)| ScoreTrendPoint {
+                bucket_start: bucket_start.and_utc(),
+                scores,
+                avg_score,
+                min_score,
+                max_score,
+            })
+            .collect())
     }
 
-    /// Delete rule check
-    pub async fn delete_rule_check(&self, id: i64) -> Result<bool, DbError> {
-        let result = sqlx::query("DELETE FROM rule_checks WHERE id = ?1")
+    /// Delete score (cascades to rule_checks)
+    pub async fn delete_score(&self, id: i64) -> Result<bool, DbError> {
+        let result = sqlx::query("DELETE FROM scores WHERE id = ?1")
             .bind(id)
             .execute(&self.pool)
             .await?;
@@ -628,221 +1721,4096 @@ impl Database {
     }
 
     // =========================================================================
-    // Analytics Operations
+    // Transcript Stats Operations
     // =========================================================================
 
-    /// Get average score across all sessions
-    pub async fn get_average_score(&self) -> Result<f64, DbError> {
-        let avg: Option<f64> = sqlx::query_scalar("SELECT AVG(score_percentage) FROM scores")
-            .fetch_optional(&self.pool)
+    /// Store transcript stats for a score
+    pub async fn create_transcript_stats(
+        &self,
+        score_id: i64,
+        stats: &TranscriptStats,
+    ) -> Result<(), DbError> {
+        insert_transcript_stats_row(&mut *self.pool.acquire().await?, score_id, stats).await
+    }
+
+    /// Get transcript stats for a score
+    pub async fn get_transcript_stats(&self, score_id: i64) -> Result<TranscriptStats, DbError> {
+        let (byte_length, line_count, turn_count, estimated_tokens): (i64, i64, i64, i64) =
+            sqlx::query_as(
+                r#"
+                SELECT byte_length, line_count, turn_count, estimated_tokens
+                FROM transcript_stats WHERE score_id = ?1
+                "#,
+            )
+            .bind(score_id)
+            .fetch_one(&self.pool)
             .await?;
 
-        Ok(avg.unwrap_or(0.0))
+        Ok(TranscriptStats {
+            byte_length: byte_length as usize,
+            line_count: line_count as usize,
+            turn_count: turn_count as usize,
+            estimated_tokens: estimated_tokens as usize,
+        })
     }
 
-    /// Get score distribution
-    pub async fn get_score_distribution(&self) -> Result<ScoreDistribution, DbError> {
-        let excellent: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM scores WHERE score_percentage >= 90"
-        )
-        .fetch_one(&self.pool)
-        .await?;
+    // =========================================================================
+    // Scan Cache Operations
+    // =========================================================================
 
-        let good: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM scores WHERE score_percentage >= 75 AND score_percentage < 90"
+    /// Files last scanned as part of a session
+    pub async fn get_session_scanned_files(&self, session_id: &str) -> Result<Vec<ScannedFile>, DbError> {
+        let files = sqlx::query_as::<_, ScannedFile>(
+            r#"
+            SELECT path, source_path, session_id, mtime_ms, size, content_hash
+            FROM scanned_files WHERE session_id = ?1
+            ORDER BY path
+            "#,
         )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(files)
+    }
+
+    /// Files last scanned as part of the session whose first file is `source_path`
+    pub async fn get_scanned_files(&self, source_path: &str) -> Result<Vec<ScannedFile>, DbError> {
+        let files = sqlx::query_as::<_, ScannedFile>(
+            r#"
+            SELECT path, source_path, session_id, mtime_ms, size, content_hash
+            FROM scanned_files WHERE source_path = ?1
+            ORDER BY path
+            "#,
+        )
+        .bind(source_path)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(files)
+    }
+
+    /// Replace the tracked files for the session whose first file is `source_path`
+    /// (a file moving between sessions is reassigned)
+    pub async fn replace_scanned_files(&self, source_path: &str, files: &[ScannedFile]) -> Result<(), DbError> {
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query("DELETE FROM scanned_files WHERE source_path = ?1")
+            .bind(source_path)
+            .execute(&mut *tx)
+            .await?;
+
+        for file in files {
+            sqlx::query(
+                r#"
+                INSERT OR REPLACE INTO scanned_files (path, source_path, session_id, mtime_ms, size, content_hash, scanned_at)
+                VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)
+                "#,
+            )
+            .bind(&file.path)
+            .bind(source_path)
+            .bind(&file.session_id)
+            .bind(file.mtime_ms)
+            .bind(file.size)
+            .bind(&file.content_hash)
+            .bind(Utc::now())
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Record a new mtime and size for a file whose content is unchanged
+    pub async fn touch_scanned_file(&self, path: &str, mtime_ms: i64, size: i64) -> Result<(), DbError> {
+        sqlx::query("UPDATE scanned_files SET mtime_ms = ?1, size = ?2, scanned_at = ?3 WHERE path = ?4")
+            .bind(mtime_ms)
+            .bind(size)
+            .bind(Utc::now())
+            .bind(path)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Store the latest scan result for the session read from `source_path` (`path#N`
+    /// for later sessions in a multi-session file)
+    pub async fn save_scan_result(&self, source_path: &str, score: &SessionScore) -> Result<(), DbError> {
+        let json = serde_json::to_string(score).map_err(|e| DbError::Validation(e.to_string()))?;
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO scan_results (source_path, session_id, rule_set_hash, score, updated_at)
+            VALUES (?1, ?2, ?3, ?4, ?5)
+            "#,
+        )
+        .bind(source_path)
+        .bind(&score.session_id)
+        .bind(&score.rule_set_hash)
+        .bind(json)
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Latest stored scan result for the session read from `source_path`
+    pub async fn get_scan_result(&self, source_path: &str) -> Result<Option<SessionScore>, DbError> {
+        let json: Option<String> = sqlx::query_scalar("SELECT score FROM scan_results WHERE source_path = ?1")
+            .bind(source_path)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        json.map(|j| serde_json::from_str(&j).map_err(|e| DbError::Validation(e.to_string())))
+            .transpose()
+    }
+
+    // =========================================================================
+    // Session ID Mapping Operations
+    // =========================================================================
+
+    /// Record which session ID a source file was given, and by which strategy
+    pub async fn save_session_id_mapping(
+        &self,
+        source_path: &str,
+        session_id: &str,
+        strategy: SessionIdStrategy,
+    ) -> Result<(), DbError> {
+        save_session_id_mapping_row(&mut *self.pool.acquire().await?, source_path, session_id, strategy).await
+    }
+
+    /// Session ID mapping for a source file
+    pub async fn get_session_id_mapping(&self, source_path: &str) -> Result<Option<SessionIdMapping>, DbError> {
+        let mapping = sqlx::query_as::<_, SessionIdMapping>(
+            "SELECT source_path, session_id, strategy FROM session_id_mappings WHERE source_path = ?1",
+        )
+        .bind(source_path)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(mapping)
+    }
+
+    /// Remove the mappings for a source file and the sessions split out of it (`path#1`, ...)
+    pub async fn remove_session_id_mappings(&self, source_path: &str) -> Result<u64, DbError> {
+        let result = sqlx::query(
+            r#"
+            DELETE FROM session_id_mappings
+            WHERE source_path = ?1 OR substr(source_path, 1, length(?1) + 1) = ?1 || '#'
+            "#,
+        )
+        .bind(source_path)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.rows_affected())
+    }
+
+    /// Source files mapped to a session ID
+    pub async fn get_session_sources(&self, session_id: &str) -> Result<Vec<SessionIdMapping>, DbError> {
+        let mappings = sqlx::query_as::<_, SessionIdMapping>(
+            r#"
+            SELECT source_path, session_id, strategy FROM session_id_mappings
+            WHERE session_id = ?1
+            ORDER BY source_path
+            "#,
+        )
+        .bind(session_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(mappings)
+    }
+
+    // =========================================================================
+    // Rule Definition Operations
+    // =========================================================================
+
+    /// Store the given rules if the table is empty, so defaults are only seeded once
+    /// and rules deleted later don't come back. Returns the number inserted.
+    pub async fn seed_rules(&self, rules: &[RuleDefinition]) -> Result<usize, DbError> {
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rules").fetch_one(&self.pool).await?;
+        if count > 0 {
+            return Ok(0);
+        }
+
+        for rule in rules {
+            self.save_rule(rule).await?;
+        }
+        Ok(rules.len())
+    }
+
+    /// All rules in evaluation order
+    pub async fn list_rules(&self) -> Result<Vec<StoredRule>, DbError> {
+        let rows = sqlx::query_as::<_, RuleRow>(
+            r#"
+            SELECT id, name, description, pattern, weight, category, version, enabled, position, updated_at
+            FROM rules
+            ORDER BY position ASC, id ASC
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(StoredRule::try_from).collect()
+    }
+
+    /// Enabled rule definitions in evaluation order, ready for a `TrackerConfig`
+    pub async fn enabled_rules(&self) -> Result<Vec<RuleDefinition>, DbError> {
+        Ok(self
+            .list_rules()
+            .await?
+            .iter()
+            .filter(|rule| rule.enabled)
+            .map(StoredRule::definition)
+            .collect())
+    }
+
+    /// Get a rule by ID
+    pub async fn get_rule(&self, id: &str) -> Result<StoredRule, DbError> {
+        let row = sqlx::query_as::<_, RuleRow>(
+            r#"
+            SELECT id, name, description, pattern, weight, category, version, enabled, position, updated_at
+            FROM rules WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        row.try_into()
+    }
+
+    /// Create a rule at the end of the evaluation order, or update an existing one.
+    /// The version is bumped only when the definition actually changes, past any version
+    /// already recorded for the rule by stored scores, and each version's definition is
+    /// kept in `rule_versions`.
+    pub async fn save_rule(&self, rule: &RuleDefinition) -> Result<StoredRule, DbError> {
+        if rule.id.trim().is_empty() {
+            return Err(DbError::Validation("Rule ID must not be empty".to_string()));
+        }
+        if !rule.weight.is_finite() || rule.weight < 0.0 {
+            return Err(DbError::Validation(format!("Invalid weight for rule {}", rule.id)));
+        }
+        let category = serde_json::to_value(rule.category).map_err(|e| DbError::Validation(e.to_string()))?;
+        let mut tx = self.pool.begin().await?;
+
+        sqlx::query(
+            r#"
+            INSERT INTO rules (id, name, description, pattern, weight, category, version, enabled, position, updated_at)
+            VALUES (
+                ?1, ?2, ?3, ?4, ?5, ?6,
+                (SELECT COALESCE(MAX(version), 0) + 1 FROM rule_versions WHERE rule_id = ?1),
+                1, (SELECT COALESCE(MAX(position) + 1, 0) FROM rules), ?7
+            )
+            ON CONFLICT(id) DO UPDATE SET
+                version = MAX(version, (SELECT COALESCE(MAX(version), 0) FROM rule_versions WHERE rule_id = excluded.id)) + 1,
+                name = excluded.name,
+                description = excluded.description,
+                pattern = excluded.pattern,
+                weight = excluded.weight,
+                category = excluded.category,
+                updated_at = excluded.updated_at
+            WHERE name != excluded.name
+                OR description != excluded.description
+                OR pattern != excluded.pattern
+                OR weight != excluded.weight
+                OR category != excluded.category
+            "#,
+        )
+        .bind(&rule.id)
+        .bind(&rule.name)
+        .bind(&rule.description)
+        .bind(&rule.pattern)
+        .bind(rule.weight)
+        .bind(category.as_str())
+        .bind(Utc::now())
+        .execute(&mut *tx)
+        .await?;
+
+        // Snapshot the definition for this version; unchanged saves hit the existing row
+        sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO rule_versions (rule_id, version, name, description, pattern, weight, category, created_at)
+            SELECT id, version, name, description, pattern, weight, category, updated_at FROM rules WHERE id = ?1
+            "#,
+        )
+        .bind(&rule.id)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        self.get_rule(&rule.id).await
+    }
+
+    /// Every recorded version of a rule, oldest first
+    pub async fn list_rule_versions(&self, rule_id: &str) -> Result<Vec<RuleVersion>, DbError> {
+        let rows = sqlx::query_as::<_, RuleVersionRow>(
+            r#"
+            SELECT id, rule_id, version, name, description, pattern, weight, category, created_at
+            FROM rule_versions
+            WHERE rule_id = ?1
+            ORDER BY version ASC
+            "#,
+        )
+        .bind(rule_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(RuleVersion::try_from).collect()
+    }
+
+    /// Every recorded version of every rule, including deleted rules
+    pub async fn all_rule_versions(&self) -> Result<Vec<RuleVersion>, DbError> {
+        let rows = sqlx::query_as::<_, RuleVersionRow>(
+            r#"
+            SELECT id, rule_id, version, name, description, pattern, weight, category, created_at
+            FROM rule_versions
+            ORDER BY rule_id, version
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(RuleVersion::try_from).collect()
+    }
+
+    /// Get a rule version by ID, e.g. the one a stored rule check references
+    pub async fn get_rule_version(&self, id: i64) -> Result<RuleVersion, DbError> {
+        let row = sqlx::query_as::<_, RuleVersionRow>(
+            r#"
+            SELECT id, rule_id, version, name, description, pattern, weight, category, created_at
+            FROM rule_versions WHERE id = ?1
+            "#,
+        )
+        .bind(id)
+        .fetch_one(&self.pool)
+        .await?;
+
+        row.try_into()
+    }
+
+    /// Enable or disable a rule without changing its definition
+    pub async fn set_rule_enabled(&self, id: &str, enabled: bool) -> Result<StoredRule, DbError> {
+        let result = sqlx::query("UPDATE rules SET enabled = ?1, updated_at = ?2 WHERE id = ?3")
+            .bind(enabled)
+            .bind(Utc::now())
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        if result.rows_affected() == 0 {
+            return Err(DbError::NotFound(format!("Rule {}", id)));
+        }
+
+        self.get_rule(id).await
+    }
+
+    /// Delete a rule
+    pub async fn delete_rule(&self, id: &str) -> Result<bool, DbError> {
+        let result = sqlx::query("DELETE FROM rules WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // =========================================================================
+    // Rule Check Operations
+    // =========================================================================
+
+    /// Create a new rule check record. Without the rule's definition it can't be tied to
+    /// a rule version, so it's stored unlinked; `save_session_score` links checks.
+    #[allow(clippy::too_many_arguments)]
+    pub async fn create_rule_check(
+        &self,
+        score_id: i64,
+        rule_id: &str,
+        rule_name: &str,
+        description: &str,
+        passed: bool,
+        confidence: f64,
+        evidence: Option<&str>,
+        suggestion: Option<&str>,
+    ) -> Result<RuleCheckRecord, DbError> {
+        insert_rule_check_row(
+            &mut *self.pool.acquire().await?,
+            score_id,
+            rule_id,
+            rule_name,
+            description,
+            passed,
+            confidence,
+            evidence,
+            suggestion,
+        )
+        .await
+    }
+
+    /// Insert every check of a score with one statement per batch of rows, in a
+    /// single transaction
+    pub async fn create_rule_checks(&self, score_id: i64, checks: &[RuleCheck]) -> Result<Vec<RuleCheckRecord>, DbError> {
+        let mut tx = self.pool.begin().await?;
+        let versions = check_rule_versions(&mut tx, checks).await?;
+        let records = insert_rule_check_rows(&mut tx, score_id, checks, &versions).await?;
+        tx.commit().await?;
+        Ok(records)
+    }
+
+    /// Get rule check by ID
+    pub async fn get_rule_check(&self, id: i64) -> Result<RuleCheckRecord, DbError> {
+        let row = sqlx::query_as::<_, RuleCheckRow>(
+            r#"
+            SELECT id, score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion, rule_version_id
+            FROM rule_checks WHERE id = ?1
+            "#,
+        )
+        .bind(id)
         .fetch_one(&self.pool)
         .await?;
 
-        let moderate: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM scores WHERE score_percentage >= 50 AND score_percentage < 75"
-        )
-        .fetch_one(&self.pool)
-        .await?;
+        Ok(row.into())
+    }
+
+    /// Get rule checks for a score
+    pub async fn get_score_rule_checks(&self, score_id: i64) -> Result<Vec<RuleCheckRecord>, DbError> {
+        let rows = sqlx::query_as::<_, RuleCheckRow>(
+            r#"
+            SELECT id, score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion, rule_version_id
+            FROM rule_checks WHERE score_id = ?1
+            ORDER BY rule_id
+            "#,
+        )
+        .bind(score_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into()).collect())
+    }
+
+    /// Get rule checks for a specific rule across all scores
+    pub async fn get_rule_history(&self, rule_id: &str, limit: Option<i64>) -> Result<Vec<RuleCheckRecord>, DbError> {
+        let limit = limit.unwrap_or(100);
+
+        let rows = sqlx::query_as::<_, RuleCheckRow>(&format!(
+            r#"
+            SELECT rc.id, rc.score_id, rc.rule_id, rc.rule_name, rc.description, rc.passed, rc.confidence, rc.evidence, rc.suggestion, rc.rule_version_id
+            FROM rule_checks rc
+            JOIN scores s ON rc.score_id = s.id
+            WHERE rc.rule_id = ?1 AND rc.score_id NOT IN ({})
+            ORDER BY s.scored_at DESC
+            LIMIT ?2
+            "#,
+            TRASHED_SCORES
+        ))
+        .bind(rule_id)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into()).collect())
+    }
+
+    /// Most recent failed checks of a rule outside the trash, newest first. Under
+    /// `Outcomes::Adjusted`, failures a reviewer marked as false negatives are left out.
+    pub async fn get_rule_failures(&self, rule_id: &str, outcomes: Outcomes, limit: Option<i64>) -> Result<Vec<RuleFailure>, DbError> {
+        let rows: Vec<(i64, i64, String, chrono::NaiveDateTime, f64, Option<String>)> = sqlx::query_as(&format!(
+            r#"
+            SELECT rc.id, rc.score_id, sc.session_id, sc.scored_at, rc.confidence, rc.evidence
+            FROM rule_checks rc
+            JOIN scores sc ON rc.score_id = sc.id
+            LEFT JOIN rule_check_overrides o ON o.rule_check_id = rc.id
+            WHERE rc.rule_id = ?1 AND NOT ({}) AND rc.score_id NOT IN ({})
+            ORDER BY sc.scored_at DESC, rc.id DESC
+            LIMIT ?2
+            "#,
+            outcomes.passed_column(),
+            TRASHED_SCORES
+        ))
+        .bind(rule_id)
+        .bind(limit.unwrap_or(20))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(rule_check_id, score_id, session_id, scored_at, confidence, evidence)| RuleFailure {
+                rule_check_id,
+                score_id,
+                session_id,
+                scored_at: scored_at.and_utc(),
+                confidence,
+                evidence,
+            })
+            .collect())
+    }
+
+    /// Everything a rule drill-down shows: the stored definition, overall pass rate,
+    /// pass rates over time, and the most recent failures
+    pub async fn get_rule_detail(
+        &self,
+        rule_id: &str,
+        bucket: TimeBucket,
+        outcomes: Outcomes,
+        failure_limit: Option<i64>,
+    ) -> Result<RuleDetail, DbError> {
+        Ok(RuleDetail {
+            rule: self.get_rule(rule_id).await?,
+            pass_rate: self.get_rule_pass_rate(rule_id, outcomes).await?,
+            trend: self.get_rule_pass_rate_series(rule_id, bucket, outcomes).await?,
+            recent_failures: self.get_rule_failures(rule_id, outcomes, failure_limit).await?,
+        })
+    }
+
+    /// Get pass rate for a specific rule
+    pub async fn get_rule_pass_rate(&self, rule_id: &str, outcomes: Outcomes) -> Result<f64, DbError> {
+        let result: Option<(i64, i64)> = sqlx::query_as(&format!(
+            r#"
+            SELECT COUNT(*) as total, SUM(CASE WHEN {} THEN 1 ELSE 0 END) as passed
+            FROM rule_checks rc
+            LEFT JOIN rule_check_overrides o ON o.rule_check_id = rc.id
+            WHERE rc.rule_id = ?1 AND rc.score_id NOT IN ({})
+            "#,
+            outcomes.passed_column(),
+            TRASHED_SCORES
+        ))
+        .bind(rule_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        match result {
+            Some((total, passed)) if total > 0 => Ok((passed as f64 / total as f64) * 100.0),
+            _ => Ok(0.0),
+        }
+    }
+
+    /// Get historical pass rates for every rule seen so far
+    pub async fn get_rule_baselines(&self, outcomes: Outcomes) -> Result<Vec<RuleBaseline>, DbError> {
+        let rows: Vec<(String, i64, i64)> = sqlx::query_as(&format!(
+            r#"
+            SELECT rc.rule_id, COUNT(*) as total, SUM(CASE WHEN {} THEN 1 ELSE 0 END) as passed
+            FROM rule_checks rc
+            LEFT JOIN rule_check_overrides o ON o.rule_check_id = rc.id
+            WHERE rc.score_id NOT IN ({})
+            GROUP BY rc.rule_id
+            ORDER BY rc.rule_id
+            "#,
+            outcomes.passed_column(),
+            TRASHED_SCORES
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(rule_id, total, passed)| RuleBaseline {
+                rule_id,
+                total,
+                passed,
+                pass_rate: (passed as f64 / total as f64) * 100.0,
+            })
+            .collect())
+    }
+
+    /// Pass rates for a rule per day or week of scoring, oldest first. Buckets with no
+    /// checks are omitted; weeks start on Monday.
+    pub async fn get_rule_pass_rate_series(
+        &self,
+        rule_id: &str,
+        bucket: TimeBucket,
+        outcomes: Outcomes,
+    ) -> Result<Vec<PassRatePoint>, DbError> {
+        let rows: Vec<(chrono::NaiveDateTime, i64, i64)> = sqlx::query_as(&format!(
+            r#"
+            SELECT datetime({}) AS bucket_start, COUNT(*) as total, SUM(CASE WHEN {} THEN 1 ELSE 0 END) as passed
+            FROM rule_checks rc
+            JOIN scores sc ON rc.score_id = sc.id
+            LEFT JOIN rule_check_overrides o ON o.rule_check_id = rc.id
+            WHERE rc.rule_id = ?1 AND rc.score_id NOT IN ({})
+            GROUP BY bucket_start
+            ORDER BY bucket_start
+            "#,
+            bucket.start_of("sc.scored_at"),
+            outcomes.passed_column(),
+            TRASHED_SCORES
+        ))
+        .bind(rule_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(bucket_start, total, passed)| PassRatePoint {
+                bucket_start: bucket_start.and_utc(),
+                total,
+                passed,
+                pass_rate: (passed as f64 / total as f64) * 100.0,
+            })
+            .collect())
+    }
+
+    /// Pass rates for a rule split by the rule version that produced each check, so
+    /// results before and after an edit aren't mixed. Checks stored before versions
+    /// were tracked are grouped under `version: None`.
+    pub async fn get_rule_version_pass_rates(
+        &self,
+        rule_id: &str,
+        outcomes: Outcomes,
+    ) -> Result<Vec<RuleVersionPassRate>, DbError> {
+        let rows: Vec<(Option<i64>, Option<i64>, i64, i64)> = sqlx::query_as(&format!(
+            r#"
+            SELECT rc.rule_version_id, rv.version, COUNT(*) as total, SUM(CASE WHEN {} THEN 1 ELSE 0 END) as passed
+            FROM rule_checks rc
+            LEFT JOIN rule_versions rv ON rc.rule_version_id = rv.id
+            LEFT JOIN rule_check_overrides o ON o.rule_check_id = rc.id
+            WHERE rc.rule_id = ?1 AND rc.score_id NOT IN ({})
+            GROUP BY rc.rule_version_id
+            ORDER BY rv.version
+            "#,
+            outcomes.passed_column(),
+            TRASHED_SCORES
+        ))
+        .bind(rule_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(rule_version_id, version, total, passed)| RuleVersionPassRate {
+                rule_version_id,
+                version,
+                total,
+                passed,
+                pass_rate: (passed as f64 / total as f64) * 100.0,
+            })
+            .collect())
+    }
+
+    /// Record a reviewer's verdict that a stored check was wrong, replacing any
+    /// earlier one. False positives must have passed; false negatives must have failed.
+    pub async fn set_rule_check_override(
+        &self,
+        rule_check_id: i64,
+        verdict: OverrideVerdict,
+        reason: &str,
+    ) -> Result<RuleCheckOverride, DbError> {
+        if reason.trim().is_empty() {
+            return Err(DbError::Validation("Override reason is required".to_string()));
+        }
+        let check = self.get_rule_check(rule_check_id).await?;
+        if check.passed != (verdict == OverrideVerdict::FalsePositive) {
+            return Err(DbError::Validation(format!(
+                "Rule check {} {}, so it can't be a {}",
+                rule_check_id,
+                if check.passed { "passed" } else { "failed" },
+                verdict.as_str().replace('_', " ")
+            )));
+        }
+
+        sqlx::query(
+            r#"
+            INSERT OR REPLACE INTO rule_check_overrides (rule_check_id, verdict, reason, created_at)
+            VALUES (?1, ?2, ?3, ?4)
+            "#,
+        )
+        .bind(rule_check_id)
+        .bind(verdict.as_str())
+        .bind(reason.trim())
+        .bind(Utc::now())
+        .execute(&self.pool)
+        .await?;
+
+        self.get_rule_check_override(rule_check_id)
+            .await?
+            .ok_or_else(|| DbError::NotFound(format!("Override for rule check {}", rule_check_id)))
+    }
+
+    /// Get the override recorded for a rule check, if any
+    pub async fn get_rule_check_override(&self, rule_check_id: i64) -> Result<Option<RuleCheckOverride>, DbError> {
+        let row = sqlx::query_as::<_, RuleCheckOverrideRow>(
+            "SELECT rule_check_id, verdict, reason, created_at FROM rule_check_overrides WHERE rule_check_id = ?1",
+        )
+        .bind(rule_check_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        row.map(RuleCheckOverride::try_from).transpose()
+    }
+
+    /// Get the overrides recorded for a score's rule checks
+    pub async fn get_score_overrides(&self, score_id: i64) -> Result<Vec<RuleCheckOverride>, DbError> {
+        let rows = sqlx::query_as::<_, RuleCheckOverrideRow>(
+            r#"
+            SELECT o.rule_check_id, o.verdict, o.reason, o.created_at
+            FROM rule_check_overrides o
+            JOIN rule_checks rc ON o.rule_check_id = rc.id
+            WHERE rc.score_id = ?1
+            ORDER BY o.rule_check_id
+            "#,
+        )
+        .bind(score_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter().map(RuleCheckOverride::try_from).collect()
+    }
+
+    /// Remove the override for a rule check
+    pub async fn clear_rule_check_override(&self, rule_check_id: i64) -> Result<bool, DbError> {
+        let result = sqlx::query("DELETE FROM rule_check_overrides WHERE rule_check_id = ?1")
+            .bind(rule_check_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Delete rule check
+    pub async fn delete_rule_check(&self, id: i64) -> Result<bool, DbError> {
+        let result = sqlx::query("DELETE FROM rule_checks WHERE id = ?1")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    // =========================================================================
+    // Analytics Operations
+    // =========================================================================
+
+    /// Get average score across all sessions
+    pub async fn get_average_score(&self, outcomes: Outcomes) -> Result<f64, DbError> {
+        let avg: Option<f64> = sqlx::query_scalar(&format!(
+            "SELECT AVG(percentage) FROM ({})",
+            outcomes.score_percentages()
+        ))
+        .fetch_optional(&self.pool)
+        .await?
+        .flatten();
+
+        Ok(avg.unwrap_or(0.0))
+    }
+
+    /// Get score distribution
+    pub async fn get_score_distribution(&self, outcomes: Outcomes) -> Result<ScoreDistribution, DbError> {
+        let (excellent, good, moderate, poor): (i64, i64, i64, i64) = sqlx::query_as(&format!(
+            r#"
+            SELECT COALESCE(SUM(percentage >= 90), 0),
+                   COALESCE(SUM(percentage >= 75 AND percentage < 90), 0),
+                   COALESCE(SUM(percentage >= 50 AND percentage < 75), 0),
+                   COALESCE(SUM(percentage < 50), 0)
+            FROM ({})
+            "#,
+            outcomes.score_percentages()
+        ))
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(ScoreDistribution {
+            excellent,
+            good,
+            moderate,
+            poor,
+        })
+    }
+
+    /// Percentile rank of a session's latest score: the share of sessions whose latest
+    /// score is at or below it
+    pub async fn get_session_percentile(&self, session_id: &str) -> Result<f64, DbError> {
+        let latest = self.get_latest_score(session_id).await?;
+        let (at_or_below, total): (i64, i64) = sqlx::query_as(&format!(
+            "SELECT COALESCE(SUM(score_percentage <= ?1), 0), COUNT(*) FROM ({})",
+            LATEST_SCORES
+        ))
+        .bind(latest.score_percentage)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok((at_or_below as f64 / total.max(1) as f64) * 100.0)
+    }
+
+    /// Compare the latest scores of two sessions rule by rule, listing the rules whose
+    /// outcome differs or that only one score checked
+    pub async fn compare_sessions(&self, session_a: &str, session_b: &str) -> Result<SessionComparison, DbError> {
+        let a = self.get_latest_score(session_a).await?;
+        let b = self.get_latest_score(session_b).await?;
+
+        let sides = |checks: Vec<RuleCheckRecord>| {
+            checks
+                .into_iter()
+                .map(|check| (check.rule_id.clone(), check.rule_name.clone(), RuleSide::from(check)))
+                .collect::<Vec<_>>()
+        };
+        let (differing, unchanged) = compare::diff_rules(
+            sides(self.get_score_rule_checks(a.id).await?),
+            sides(self.get_score_rule_checks(b.id).await?),
+        );
+
+        Ok(SessionComparison {
+            percentage_delta: b.score_percentage - a.score_percentage,
+            a,
+            b,
+            differing,
+            unchanged,
+        })
+    }
+
+    /// Latest scores of the highest- or lowest-scoring sessions, optionally only
+    /// sessions scored since a given time (e.g. the start of the week)
+    pub async fn get_ranked_sessions(
+        &self,
+        ranking: Ranking,
+        limit: i64,
+        since: Option<DateTime<Utc>>,
+    ) -> Result<Vec<Score>, DbError> {
+        let order = match ranking {
+            Ranking::Top => "DESC",
+            Ranking::Bottom => "ASC",
+        };
+        let rows = sqlx::query_as::<_, ScoreRow>(&format!(
+            r#"
+            SELECT id, session_id, scored_at, total_rules, passed_rules, score_percentage, summary, scorer_version, rule_set_hash
+            FROM ({})
+            WHERE ?1 IS NULL OR julianday(scored_at) >= julianday(?1)
+            ORDER BY score_percentage {}, scored_at DESC
+            LIMIT ?2
+            "#,
+            LATEST_SCORES, order
+        ))
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.into_iter().map(|r| r.into()).collect())
+    }
+
+    /// Rules that failed most often in checks scored since `since` (or ever), by
+    /// failure count and then failure rate. Reviewer overrides are applied, so a
+    /// failure marked as a false negative doesn't count against its rule; rules that
+    /// never failed are left out.
+    pub async fn get_rule_failure_leaderboard(
+        &self,
+        since: Option<DateTime<Utc>>,
+        limit: i64,
+    ) -> Result<Vec<RuleFailureStats>, DbError> {
+        let rows: Vec<(String, String, i64, i64)> = sqlx::query_as(&format!(
+            r#"
+            SELECT rc.rule_id, COALESCE(r.name, MAX(rc.rule_name)) AS rule_name, COUNT(*) as total,
+                   SUM(CASE WHEN {} THEN 0 ELSE 1 END) as failed
+            FROM rule_checks rc
+            JOIN scores sc ON rc.score_id = sc.id
+            LEFT JOIN rules r ON rc.rule_id = r.id
+            LEFT JOIN rule_check_overrides o ON o.rule_check_id = rc.id
+            WHERE rc.score_id NOT IN ({})
+              AND (?1 IS NULL OR julianday(sc.scored_at) >= julianday(?1))
+            GROUP BY rc.rule_id
+            HAVING failed > 0
+            ORDER BY failed DESC, CAST(failed AS REAL) / total DESC, rc.rule_id
+            LIMIT ?2
+            "#,
+            Outcomes::Adjusted.passed_column(),
+            TRASHED_SCORES
+        ))
+        .bind(since)
+        .bind(limit)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(rule_id, rule_name, total, failed)| RuleFailureStats {
+                rule_id,
+                rule_name,
+                total,
+                failed,
+                failure_rate: (failed as f64 / total as f64) * 100.0,
+            })
+            .collect())
+    }
+
+    /// Pass rates and failure counts per rule category. Checks take the category of
+    /// the rule version that produced them, or of the rule's current definition;
+    /// checks of rules that aren't stored are left out.
+    pub async fn get_category_stats(&self, outcomes: Outcomes) -> Result<Vec<CategoryStats>, DbError> {
+        let rows: Vec<(String, i64, i64)> = sqlx::query_as(&format!(
+            r#"
+            SELECT COALESCE(rv.category, r.category) AS category, COUNT(*) as total,
+                   SUM(CASE WHEN {} THEN 1 ELSE 0 END) as passed
+            FROM rule_checks rc
+            LEFT JOIN rule_versions rv ON rc.rule_version_id = rv.id
+            LEFT JOIN rules r ON rc.rule_id = r.id
+            LEFT JOIN rule_check_overrides o ON o.rule_check_id = rc.id
+            WHERE COALESCE(rv.category, r.category) IS NOT NULL AND rc.score_id NOT IN ({})
+            GROUP BY COALESCE(rv.category, r.category)
+            ORDER BY category
+            "#,
+            outcomes.passed_column(),
+            TRASHED_SCORES
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|(category, total, passed)| {
+                Ok(CategoryStats {
+                    category: serde_json::from_value(serde_json::Value::String(category))
+                        .map_err(|e| DbError::Validation(e.to_string()))?,
+                    total,
+                    passed,
+                    failed: total - passed,
+                    pass_rate: (passed as f64 / total as f64) * 100.0,
+                })
+            })
+            .collect()
+    }
+
+    /// Current and longest runs of sessions whose latest score is at least
+    /// `threshold` percent, in scoring order
+    pub async fn get_session_streak(&self, threshold: f64) -> Result<Streak, DbError> {
+        let percentages: Vec<f64> = sqlx::query_scalar(&format!(
+            "SELECT score_percentage FROM ({}) ORDER BY scored_at ASC, id ASC",
+            LATEST_SCORES
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(Streak::from_outcomes(percentages.into_iter().map(|p| p >= threshold)))
+    }
+
+    /// Current and longest runs of consecutive passes for every rule, across all
+    /// stored scores in scoring order
+    pub async fn get_rule_streaks(&self, outcomes: Outcomes) -> Result<Vec<RuleStreak>, DbError> {
+        let rows: Vec<(String, bool)> = sqlx::query_as(&format!(
+            r#"
+            SELECT rc.rule_id, {} AS passed
+            FROM rule_checks rc
+            JOIN scores sc ON rc.score_id = sc.id
+            LEFT JOIN rule_check_overrides o ON o.rule_check_id = rc.id
+            WHERE rc.score_id NOT IN ({})
+            ORDER BY rc.rule_id, sc.scored_at ASC, rc.id ASC
+            "#,
+            outcomes.passed_column(),
+            TRASHED_SCORES
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut outcomes_by_rule: Vec<(String, Vec<bool>)> = Vec::new();
+        for (rule_id, passed) in rows {
+            match outcomes_by_rule.last_mut() {
+                Some((last, outcomes)) if *last == rule_id => outcomes.push(passed),
+                _ => outcomes_by_rule.push((rule_id, vec![passed])),
+            }
+        }
+
+        Ok(outcomes_by_rule
+            .into_iter()
+            .map(|(rule_id, outcomes)| RuleStreak {
+                rule_id,
+                streak: Streak::from_outcomes(outcomes),
+            })
+            .collect())
+    }
+
+    /// Get database statistics
+    pub async fn get_stats(&self) -> Result<DbStats, DbError> {
+        let (sessions, trashed_sessions): (i64, i64) =
+            sqlx::query_as("SELECT COALESCE(SUM(deleted_at IS NULL), 0), COALESCE(SUM(deleted_at IS NOT NULL), 0) FROM sessions")
+                .fetch_one(&self.pool)
+                .await?;
+
+        let scores: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM scores WHERE id NOT IN ({})", TRASHED_SCORES))
+            .fetch_one(&self.pool)
+            .await?;
+
+        let rule_checks: i64 =
+            sqlx::query_scalar(&format!("SELECT COUNT(*) FROM rule_checks WHERE score_id NOT IN ({})", TRASHED_SCORES))
+                .fetch_one(&self.pool)
+                .await?;
+
+        let avg_score: f64 = self.get_average_score(Outcomes::Raw).await?;
+
+        Ok(DbStats {
+            sessions,
+            trashed_sessions,
+            scores,
+            rule_checks,
+            avg_score,
+            session_streak: self.get_session_streak(STREAK_THRESHOLD).await?,
+            rule_streaks: self.get_rule_streaks(Outcomes::Raw).await?,
+            sources: self.get_source_stats(Outcomes::Raw).await?,
+        })
+    }
+
+    /// `get_stats` with the score distribution, every rule's pass rate, and the
+    /// `top_failures` rules that failed most
+    pub async fn get_stats_report(&self, top_failures: i64) -> Result<StatsReport, DbError> {
+        Ok(StatsReport {
+            stats: self.get_stats().await?,
+            distribution: self.get_score_distribution(Outcomes::Raw).await?,
+            rule_pass_rates: self.get_rule_baselines(Outcomes::Raw).await?,
+            most_failed: self.get_rule_failure_leaderboard(None, top_failures).await?,
+        })
+    }
+
+    /// Session and score counts and the average score per session source, most
+    /// sessions first. Trashed sessions are left out.
+    pub async fn get_source_stats(&self, outcomes: Outcomes) -> Result<Vec<SourceStats>, DbError> {
+        let rows: Vec<(String, i64, i64, f64)> = sqlx::query_as(&format!(
+            r#"
+            SELECT s.source, COUNT(DISTINCT s.id) AS sessions, COUNT(p.session_id), COALESCE(AVG(p.percentage), 0.0)
+            FROM sessions s
+            LEFT JOIN ({}) p ON p.session_id = s.id
+            WHERE s.deleted_at IS NULL
+            GROUP BY s.source
+            ORDER BY sessions DESC, s.source
+            "#,
+            outcomes.score_percentages()
+        ))
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .into_iter()
+            .map(|(source, sessions, scores, avg_score)| SourceStats {
+                source,
+                sessions,
+                scores,
+                avg_score,
+            })
+            .collect())
+    }
+
+    // =========================================================================
+    // History Import
+    // =========================================================================
+
+    /// Start a transaction, for an import that must land whole or not at all
+    pub(crate) async fn begin(&self) -> Result<sqlx::Transaction<'static, Sqlite>, DbError> {
+        Ok(self.pool.begin().await?)
+    }
+
+    // =========================================================================
+    // Maintenance
+    // =========================================================================
+
+    /// Check the database for corruption and dangling references, and report how
+    /// much space it takes. Only reads, so it's safe on a read-only handle.
+    pub async fn health_check(&self) -> Result<DbHealth, DbError> {
+        let mut conn = self.pool.acquire().await?;
+
+        let integrity: Vec<String> = sqlx::query_scalar("PRAGMA integrity_check").fetch_all(&mut *conn).await?;
+        let integrity_errors: Vec<String> = integrity.into_iter().filter(|line| line != "ok").collect();
+
+        let foreign_key_violations = sqlx::query_as::<_, (String, Option<i64>, String)>(
+            r#"SELECT "table", rowid, parent FROM pragma_foreign_key_check"#,
+        )
+        .fetch_all(&mut *conn)
+        .await?
+        .into_iter()
+        .map(|(table, rowid, parent)| ForeignKeyViolation { table, rowid, parent })
+        .collect::<Vec<_>>();
+
+        let page_size: i64 = sqlx::query_scalar("PRAGMA page_size").fetch_one(&mut *conn).await?;
+        let page_count: i64 = sqlx::query_scalar("PRAGMA page_count").fetch_one(&mut *conn).await?;
+        let freelist_count: i64 = sqlx::query_scalar("PRAGMA freelist_count").fetch_one(&mut *conn).await?;
+
+        // FTS5 shadow tables are reported under their virtual table
+        let names: Vec<String> = sqlx::query_scalar(
+            r#"
+            SELECT name FROM pragma_table_list
+            WHERE schema = 'main' AND type IN ('table', 'virtual') AND name NOT LIKE 'sqlite_%'
+            ORDER BY name
+            "#,
+        )
+        .fetch_all(&mut *conn)
+        .await?;
+        let mut tables = Vec::with_capacity(names.len());
+        for name in names {
+            let rows: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM \"{}\"", name.replace('"', "\"\"")))
+                .fetch_one(&mut *conn)
+                .await?;
+            tables.push(TableSize { name, rows });
+        }
+
+        Ok(DbHealth {
+            ok: integrity_errors.is_empty() && foreign_key_violations.is_empty(),
+            integrity_errors,
+            foreign_key_violations,
+            page_size,
+            page_count,
+            freelist_count,
+            size_bytes: page_size * page_count,
+            free_bytes: page_size * freelist_count,
+            tables,
+        })
+    }
+
+    /// Refresh query planner statistics and rebuild the file to reclaim free pages.
+    /// Needs exclusive access for the VACUUM, so other writers wait on the busy
+    /// timeout. Returns the bytes reclaimed.
+    pub async fn optimize(&self) -> Result<i64, DbError> {
+        const SIZE: &str = "SELECT page_count * page_size FROM pragma_page_count, pragma_page_size";
+        let mut conn = self.pool.acquire().await?;
+
+        // Measured after ANALYZE, which can add a page of statistics
+        sqlx::query("ANALYZE").execute(&mut *conn).await?;
+        let before: i64 = sqlx::query_scalar(SIZE).fetch_one(&mut *conn).await?;
+        sqlx::query("VACUUM").execute(&mut *conn).await?;
+        // Fold the rewritten pages back in so the WAL file doesn't keep the old size
+        sqlx::query("PRAGMA wal_checkpoint(TRUNCATE)").execute(&mut *conn).await?;
+
+        let after: i64 = sqlx::query_scalar(SIZE).fetch_one(&mut *conn).await?;
+        Ok(before - after)
+    }
+
+    // =========================================================================
+    // Backup and Restore
+    // =========================================================================
+
+    /// Snapshot the database into a new file with SQLite's online backup API. The app
+    /// can keep reading and writing while the copy runs.
+    pub async fn backup_to(&self, path: impl AsRef<Path>) -> Result<(), DbError> {
+        let path = path.as_ref();
+        if path.exists() {
+            return Err(DbError::Validation(format!("Backup target already exists: {}", path.display())));
+        }
+
+        let mut conn = self.pool.acquire().await?;
+        let mut handle = conn.lock_handle().await?;
+        sqlite_backup(&mut handle, path, BackupDirection::Save).await
+    }
+
+    /// Replace the database contents with a snapshot made by `backup_to`, then apply
+    /// any migrations the snapshot predates
+    pub async fn restore_from(&self, path: impl AsRef<Path>) -> Result<(), DbError> {
+        let path = path.as_ref();
+        if !path.is_file() {
+            return Err(DbError::NotFound(format!("Backup file: {}", path.display())));
+        }
+
+        let mut source = SqliteConnectOptions::new()
+            .filename(path)
+            .read_only(true)
+            .connect()
+            .await
+            .map_err(|e| DbError::Connection(e.to_string()))?;
+        let version = schema_version(&mut source, path).await?;
+        source.close().await?;
+        let latest = latest_schema_version();
+        if version > latest {
+            return Err(DbError::Validation(format!(
+                "Backup has schema version {}, newer than this build supports ({})",
+                version, latest
+            )));
+        }
+
+        {
+            let mut conn = self.pool.acquire().await?;
+            let mut handle = conn.lock_handle().await?;
+            sqlite_backup(&mut handle, path, BackupDirection::Load).await?;
+        }
+        self.run_migrations().await
+    }
+}
+
+/// Run the down migrations above `target` up to `current`, one transaction each
+async fn rollback_migrations(conn: &mut SqliteConnection, current: i64, target: i64) -> Result<(), DbError> {
+    for migration in MIGRATIONS.iter().rev() {
+        if migration.version > current || migration.version <= target {
+            continue;
+        }
+        let mut tx = conn.begin().await?;
+        if !migration.down.trim().is_empty() {
+            sqlx::query(migration.down)
+                .execute(&mut *tx)
+                .await
+                .map_err(|e| DbError::Migration(format!("Rolling back {}: {}", migration.name, e)))?;
+        }
+        sqlx::query("DELETE FROM _migrations WHERE version = ?1")
+            .bind(migration.version)
+            .execute(&mut *tx)
+            .await?;
+        tx.commit().await?;
+    }
+
+    Ok(())
+}
+
+/// JSON path for a metadata key such as `project` or `project.name`. Keys are
+/// limited to letters, digits, `_`, and `-` per segment so they can't change the
+/// shape of the path.
+fn metadata_path(key: &str) -> Result<String, DbError> {
+    let mut path = String::from("$");
+    for segment in key.split('.') {
+        let valid = !segment.is_empty() && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return Err(DbError::Validation(format!("Invalid metadata key: {}", key)));
+        }
+        path.push_str(&format!(".\"{}\"", segment));
+    }
+    Ok(path)
+}
+
+/// Schema version recorded in a dashboard database
+async fn schema_version(conn: &mut SqliteConnection, path: &Path) -> Result<i64, DbError> {
+    let version: Option<i64> = sqlx::query_scalar("SELECT MAX(version) FROM _migrations")
+        .fetch_one(conn)
+        .await
+        .map_err(|_| DbError::Validation(format!("Not a dashboard database: {}", path.display())))?;
+    Ok(version.unwrap_or(0))
+}
+
+/// Schema version this build migrates to
+fn latest_schema_version() -> i64 {
+    MIGRATIONS.last().map_or(0, |m| m.version)
+}
+
+/// Score distribution buckets
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScoreDistribution {
+    pub excellent: i64, // >= 90%
+    pub good: i64,      // 75-89%
+    pub moderate: i64,  // 50-74%
+    pub poor: i64,      // < 50%
+}
+
+/// Score distribution bucket, matching `ScoreDistribution`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ScoreBand {
+    Excellent,
+    Good,
+    Moderate,
+    Poor,
+}
+
+impl ScoreBand {
+    /// Every band, from highest to lowest
+    pub const ALL: [ScoreBand; 4] = [
+        ScoreBand::Excellent,
+        ScoreBand::Good,
+        ScoreBand::Moderate,
+        ScoreBand::Poor,
+    ];
+
+    /// Inclusive lower and exclusive upper percentage bounds
+    pub fn bounds(self) -> (Option<f64>, Option<f64>) {
+        match self {
+            ScoreBand::Excellent => (Some(90.0), None),
+            ScoreBand::Good => (Some(75.0), Some(90.0)),
+            ScoreBand::Moderate => (Some(50.0), Some(75.0)),
+            ScoreBand::Poor => (None, Some(50.0)),
+        }
+    }
+
+    /// Whether a percentage is within the band's bounds
+    pub fn contains(self, percentage: f64) -> bool {
+        let (min, max) = self.bounds();
+        min.is_none_or(|min| percentage >= min) && max.is_none_or(|max| percentage < max)
+    }
+
+    /// Band a percentage falls in, `Poor` for anything outside every band (like NaN)
+    pub fn of(percentage: f64) -> Self {
+        ScoreBand::ALL
+            .into_iter()
+            .find(|band| band.contains(percentage))
+            .unwrap_or(ScoreBand::Poor)
+    }
+}
+
+/// Filters for `list_sessions` and `list_scores`. Sessions are matched on their
+/// creation time and latest score; scores on their own time and percentage.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct ListFilters {
+    /// Earliest time, inclusive
+    pub since: Option<DateTime<Utc>>,
+    /// Latest time, inclusive
+    pub until: Option<DateTime<Utc>>,
+    pub source: Option<String>,
+    pub band: Option<ScoreBand>,
+    /// Minimum percentage, inclusive
+    pub min_percentage: Option<f64>,
+    /// Maximum percentage, inclusive
+    pub max_percentage: Option<f64>,
+    /// Tag listed under `tags` in the session metadata (e.g. markdown frontmatter)
+    pub tag: Option<String>,
+    /// A single session
+    pub session_id: Option<String>,
+}
+
+impl ListFilters {
+    fn band_bounds(&self) -> (Option<f64>, Option<f64>) {
+        self.band.map(ScoreBand::bounds).unwrap_or_default()
+    }
+}
+
+/// A file tracked by incremental scans
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type, sqlx::FromRow)]
+pub struct ScannedFile {
+    pub path: String,
+    /// First file of the session this file belongs to, which keys the scan cache
+    pub source_path: String,
+    pub session_id: String,
+    /// Modification time in milliseconds since the Unix epoch
+    pub mtime_ms: i64,
+    pub size: i64,
+    /// Hash of the decoded file text
+    pub content_hash: String,
+}
+
+/// Session ID assigned to a scanned source file
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type, sqlx::FromRow)]
+pub struct SessionIdMapping {
+    pub source_path: String,
+    pub session_id: String,
+    /// `SessionIdStrategy` name that produced the ID
+    pub strategy: String,
+}
+
+/// Rule definition stored in the `rules` table
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct StoredRule {
+    pub id: String,
+    pub name: String,
+    pub description: String,
+    pub pattern: String,
+    pub weight: f64,
+    pub category: RuleCategory,
+    /// Incremented whenever the definition changes
+    pub version: i64,
+    pub enabled: bool,
+    /// Evaluation order
+    pub position: i64,
+    pub updated_at: DateTime<Utc>,
+}
+
+impl StoredRule {
+    /// The definition the scorer evaluates
+    pub fn definition(&self) -> RuleDefinition {
+        RuleDefinition {
+            id: self.id.clone(),
+            name: self.name.clone(),
+            description: self.description.clone(),
+            pattern: self.pattern.clone(),
+            weight: self.weight,
+            category: self.category,
+        }
+    }
+}
+
+/// Snapshot of a rule definition at one version
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct RuleVersion {
+    pub id: i64,
+    pub rule_id: String,
+    pub version: i64,
+    pub name: String,
+    pub description: String,
+    pub pattern: String,
+    pub weight: f64,
+    pub category: RuleCategory,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Whether analytics use rule checks as scored or as corrected by reviewers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Outcomes {
+    /// Outcomes as the scorer recorded them
+    #[default]
+    Raw,
+    /// Outcomes with reviewer overrides applied
+    Adjusted,
+}
+
+impl Outcomes {
+    /// SQL for whether check `rc` passed, given overrides joined as `o`
+    fn passed_column(self) -> &'static str {
+        match self {
+            Outcomes::Raw => "rc.passed",
+            Outcomes::Adjusted => {
+                "COALESCE(CASE o.verdict WHEN 'false_positive' THEN 0 WHEN 'false_negative' THEN 1 END, rc.passed)"
+            }
+        }
+    }
+
+    /// SQL selecting the `session_id` and `percentage` of every score outside the trash. Adjusted percentages move by each
+    /// overridden check's share of the score's weight, taken from its rule version
+    /// (or 1 when unknown).
+    fn score_percentages(self) -> &'static str {
+        match self {
+            Outcomes::Raw => {
+                "SELECT sc.session_id, score_percentage AS percentage FROM scores sc
+                 WHERE sc.session_id NOT IN (SELECT id FROM sessions WHERE deleted_at IS NOT NULL)"
+            }
+            Outcomes::Adjusted => {
+                r#"
+                SELECT sc.session_id, MAX(0.0, MIN(100.0, sc.score_percentage + COALESCE((
+                    SELECT 100.0 * SUM(
+                        CASE o.verdict WHEN 'false_negative' THEN 1 WHEN 'false_positive' THEN -1 ELSE 0 END
+                        * COALESCE(rv.weight, 1.0)
+                    ) / SUM(COALESCE(rv.weight, 1.0))
+                    FROM rule_checks rc
+                    LEFT JOIN rule_versions rv ON rc.rule_version_id = rv.id
+                    LEFT JOIN rule_check_overrides o ON o.rule_check_id = rc.id
+                    WHERE rc.score_id = sc.id
+                ), 0.0))) AS percentage
+                FROM scores sc
+                WHERE sc.session_id NOT IN (SELECT id FROM sessions WHERE deleted_at IS NOT NULL)
+                "#
+            }
+        }
+    }
+}
+
+/// How a reviewer judged a rule check
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum OverrideVerdict {
+    /// The check passed but the behavior wasn't there
+    FalsePositive,
+    /// The check failed but the behavior was there
+    FalseNegative,
+}
+
+impl OverrideVerdict {
+    fn as_str(self) -> &'static str {
+        match self {
+            OverrideVerdict::FalsePositive => "false_positive",
+            OverrideVerdict::FalseNegative => "false_negative",
+        }
+    }
+}
+
+/// Reviewer correction of a stored rule check
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct RuleCheckOverride {
+    pub rule_check_id: i64,
+    pub verdict: OverrideVerdict,
+    pub reason: String,
+    pub created_at: DateTime<Utc>,
+}
+
+/// Historical pass rate for a single rule
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RuleBaseline {
+    pub rule_id: String,
+    pub total: i64,
+    pub passed: i64,
+    pub pass_rate: f64, // 0-100
+}
+
+/// How often a rule failed over a window, for the failure leaderboard
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RuleFailureStats {
+    pub rule_id: String,
+    pub rule_name: String,
+    pub total: i64,
+    pub failed: i64,
+    pub failure_rate: f64, // 0-100
+}
+
+/// Side-by-side comparison of two sessions' latest scores
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SessionComparison {
+    pub a: Score,
+    pub b: Score,
+    /// `b`'s percentage minus `a`'s
+    pub percentage_delta: f64,
+    /// Rules that passed in one and failed in the other, or were checked in only one
+    pub differing: Vec<RuleDiff>,
+    /// Rules with the same outcome in both
+    pub unchanged: usize,
+}
+
+/// Width of the buckets in a pass rate or score series
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum TimeBucket {
+    Day,
+    Week,
+}
+
+impl TimeBucket {
+    /// SQL for the date starting the bucket that contains `column`
+    fn start_of(self, column: &str) -> String {
+        match self {
+            TimeBucket::Day => format!("date({})", column),
+            TimeBucket::Week => format!("date({}, 'weekday 0', '-6 days')", column),
+        }
+    }
+}
+
+/// Pass rate of a rule within one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct PassRatePoint {
+    pub bucket_start: DateTime<Utc>,
+    pub total: i64,
+    pub passed: i64,
+    pub pass_rate: f64, // 0-100
+}
+
+/// Scores within one time bucket
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScoreTrendPoint {
+    pub bucket_start: DateTime<Utc>,
+    pub scores: i64,
+    pub avg_score: f64, // 0-100
+    pub min_score: f64,
+    pub max_score: f64,
+}
+
+/// Past scores, newest first, with their trend oldest first
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScoreHistory {
+    pub scores: Vec<Score>,
+    pub trend: Vec<ScoreTrendPoint>,
+}
+
+/// A failed check of a rule, with the session it came from
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RuleFailure {
+    pub rule_check_id: i64,
+    pub score_id: i64,
+    pub session_id: String,
+    pub scored_at: DateTime<Utc>,
+    pub confidence: f64,
+    pub evidence: Option<String>,
+}
+
+/// A rule's definition and track record, for its drill-down page
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RuleDetail {
+    pub rule: StoredRule,
+    /// Pass rate across every check outside the trash; 0 when never checked
+    pub pass_rate: f64, // 0-100
+    /// Pass rates per time bucket, oldest first
+    pub trend: Vec<PassRatePoint>,
+    /// Most recent failures, newest first
+    pub recent_failures: Vec<RuleFailure>,
+}
+
+/// Pass rate of a rule under one of its versions
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RuleVersionPassRate {
+    pub rule_version_id: Option<i64>,
+    pub version: Option<i64>,
+    pub total: i64,
+    pub passed: i64,
+    pub pass_rate: f64, // 0-100
+}
+
+/// What a search hit matched
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SearchKind {
+    /// Text of a stored transcript
+    Transcript,
+    /// Evidence recorded by a rule check
+    Evidence,
+}
+
+/// Narrows a full-text search
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct SearchFilters {
+    /// Only sessions from this source
+    pub source: Option<String>,
+    /// Only transcripts or only evidence
+    pub kind: Option<SearchKind>,
+    /// Only evidence from this rule
+    pub rule_id: Option<String>,
+    /// Maximum number of hits (default 100)
+    pub limit: Option<i64>,
+}
+
+/// One full-text search match
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SearchHit {
+    pub session_id: String,
+    pub kind: SearchKind,
+    /// Rule whose evidence matched, for evidence hits
+    pub rule_id: Option<String>,
+    /// Matching text with the matched terms wrapped in `[` and `]`
+    pub snippet: String,
+    /// BM25 relevance; lower is a better match
+    pub rank: f64,
+}
+
+/// Marks the start of a matched term in snippets built for `search_by_session`
+const HIGHLIGHT_START: char = '\u{2}';
+
+/// Marks the end of a matched term in snippets built for `search_by_session`
+const HIGHLIGHT_END: char = '\u{3}';
+
+/// Matching text from one search hit, with the matched terms located
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SearchSnippet {
+    pub kind: SearchKind,
+    /// Rule whose evidence matched, for evidence hits
+    pub rule_id: Option<String>,
+    pub text: String,
+    /// Matched terms within `text`
+    pub highlights: Vec<MatchSpan>,
+}
+
+impl SearchSnippet {
+    /// Strip the highlight markers from a hit's snippet, recording where they were
+    fn from_marked(hit: &SearchHit) -> Self {
+        let mut text = String::with_capacity(hit.snippet.len());
+        let mut highlights = Vec::new();
+        let mut start = None;
+        let mut utf16_pos = 0;
+        for c in hit.snippet.chars() {
+            match c {
+                HIGHLIGHT_START => start = Some((utf16_pos, text.len())),
+                HIGHLIGHT_END => {
+                    if let Some((start, byte_start)) = start.take() {
+                        highlights.push(MatchSpan {
+                            start,
+                            end: utf16_pos,
+                            text: text[byte_start..].to_string(),
+                        });
+                    }
+                }
+                c => {
+                    text.push(c);
+                    utf16_pos += c.len_utf16();
+                }
+            }
+        }
+        SearchSnippet {
+            kind: hit.kind,
+            rule_id: hit.rule_id.clone(),
+            text,
+            highlights,
+        }
+    }
+}
+
+/// A session matching a full-text search
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SessionSearchResult {
+    pub session: Session,
+    pub latest_score: Option<Score>,
+    /// BM25 relevance of the session's best hit; lower is a better match
+    pub rank: f64,
+    /// Matching transcript and evidence snippets, best first
+    pub snippets: Vec<SearchSnippet>,
+}
+
+/// Which end of the score range to rank from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Ranking {
+    Top,
+    Bottom,
+}
+
+/// Rule check outcomes for one rule category
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct CategoryStats {
+    pub category: RuleCategory,
+    pub total: i64,
+    pub passed: i64,
+    pub failed: i64,
+    pub pass_rate: f64, // 0-100
+}
+
+/// Runs of consecutive successes, oldest to newest
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct Streak {
+    /// Successes since the most recent failure
+    pub current: i64,
+    /// Longest run ever recorded
+    pub longest: i64,
+}
+
+impl Streak {
+    fn from_outcomes(outcomes: impl IntoIterator<Item = bool>) -> Self {
+        outcomes.into_iter().fold(Streak::default(), |streak, success| {
+            let current = if success { streak.current + 1 } else { 0 };
+            Streak {
+                current,
+                longest: streak.longest.max(current),
+            }
+        })
+    }
+}
+
+/// Consecutive passes of one rule across scored sessions
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct RuleStreak {
+    pub rule_id: String,
+    pub streak: Streak,
+}
+
+/// Score percentage a session must reach to extend the streak in `DbStats`,
+/// the bottom of the "good" band
+pub const STREAK_THRESHOLD: f64 = 75.0;
+
+/// Database statistics
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct DbStats {
+    pub sessions: i64,
+    pub trashed_sessions: i64,
+    pub scores: i64,
+    pub rule_checks: i64,
+    pub avg_score: f64,
+    /// Sessions in a row scoring at least `STREAK_THRESHOLD`
+    pub session_streak: Streak,
+    /// Consecutive passes per rule
+    pub rule_streaks: Vec<RuleStreak>,
+    /// Breakdown by session source
+    pub sources: Vec<SourceStats>,
+}
+
+/// Database statistics with per-rule breakdowns, for reports
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct StatsReport {
+    pub stats: DbStats,
+    pub distribution: ScoreDistribution,
+    /// Every rule seen so far, by rule ID
+    pub rule_pass_rates: Vec<RuleBaseline>,
+    /// Rules that failed most, worst first
+    pub most_failed: Vec<RuleFailureStats>,
+}
+
+/// Sessions and scores from one session source (e.g. codex, claude-code, app)
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct SourceStats {
+    pub source: String,
+    pub sessions: i64,
+    pub scores: i64,
+    pub avg_score: f64, // 0-100
+}
+
+/// Results of `Database::health_check`
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct DbHealth {
+    /// No integrity errors or foreign key violations
+    pub ok: bool,
+    /// Problems reported by `PRAGMA integrity_check`
+    pub integrity_errors: Vec<String>,
+    pub foreign_key_violations: Vec<ForeignKeyViolation>,
+    pub page_size: i64,
+    pub page_count: i64,
+    /// Unused pages that `optimize` would reclaim
+    pub freelist_count: i64,
+    pub size_bytes: i64,
+    pub free_bytes: i64,
+    /// Row counts per table
+    pub tables: Vec<TableSize>,
+}
+
+/// A row referencing a parent row that doesn't exist
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ForeignKeyViolation {
+    pub table: String,
+    pub rowid: Option<i64>,
+    pub parent: String,
+}
+
+/// Row count of one table
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct TableSize {
+    pub name: String,
+    pub rows: i64,
+}
+
+// ============================================================================
+// SQLite backup API
+// ============================================================================
+
+/// Attempts made while the other side of a backup is locked
+const BACKUP_RETRIES: u32 = 40;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum BackupDirection {
+    /// Copy the connection's database into the file
+    Save,
+    /// Copy the file into the connection's database
+    Load,
+}
+
+/// Copy between the locked connection `handle` and the database file at `file`
+async fn sqlite_backup(handle: &mut LockedSqliteHandle<'_>, file: &Path, direction: BackupDirection) -> Result<(), DbError> {
+    let file_name = CString::new(file.to_string_lossy().into_owned())
+        .map_err(|_| DbError::Validation(format!("Invalid backup path: {}", file.display())))?;
+    let flags = match direction {
+        BackupDirection::Save => ffi::SQLITE_OPEN_READWRITE | ffi::SQLITE_OPEN_CREATE,
+        BackupDirection::Load => ffi::SQLITE_OPEN_READONLY,
+    };
+
+    let mut other = BackupFile(std::ptr::null_mut());
+    // SAFETY: `other` is closed when dropped whether or not it opened, and the locked
+    // connection isn't used by anything else until `handle` is released
+    let backup = unsafe {
+        if ffi::sqlite3_open_v2(file_name.as_ptr(), &mut other.0, flags, std::ptr::null()) != ffi::SQLITE_OK {
+            return Err(DbError::Connection(sqlite_message(other.0)));
+        }
+        let db = handle.as_raw_handle().as_ptr();
+        match direction {
+            BackupDirection::Save => BackupRun::init(other.0, db)?,
+            BackupDirection::Load => BackupRun::init(db, other.0)?,
+        }
+    };
+
+    let mut step = backup.step();
+    let mut attempts = 1;
+    while (step == ffi::SQLITE_BUSY || step == ffi::SQLITE_LOCKED) && attempts < BACKUP_RETRIES {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        step = backup.step();
+        attempts += 1;
+    }
+    backup.finish(step)
+}
+
+/// Connection to the file on the other side of a backup, closed when dropped
+struct BackupFile(*mut ffi::sqlite3);
+
+// SAFETY: the connection is private to one backup, which uses it from one task at a time
+unsafe impl Send for BackupFile {}
+
+impl Drop for BackupFile {
+    fn drop(&mut self) {
+        // SAFETY: the handle is null or came from `sqlite3_open_v2` and is closed only here
+        unsafe {
+            ffi::sqlite3_close(self.0);
+        }
+    }
+}
+
+/// A backup copying every page of one connection into another, finished when dropped
+/// if it doesn't run to completion
+struct BackupRun {
+    backup: NonNull<ffi::sqlite3_backup>,
+    dest: *mut ffi::sqlite3,
+}
+
+// SAFETY: both connections stay locked to the backup, which steps from one task at a time
+unsafe impl Send for BackupRun {}
+
+impl BackupRun {
+    /// # Safety
+    /// Both handles must be open connections not used elsewhere until the run is dropped.
+    unsafe fn init(dest: *mut ffi::sqlite3, source: *mut ffi::sqlite3) -> Result<Self, DbError> {
+        let main = c"main";
+        let backup = ffi::sqlite3_backup_init(dest, main.as_ptr(), source, main.as_ptr());
+        NonNull::new(backup)
+            .map(|backup| Self { backup, dest })
+            .ok_or_else(|| DbError::Query(sqlite_message(dest)))
+    }
+
+    /// Copy all remaining pages, returning SQLite's result code
+    fn step(&self) -> i32 {
+        // SAFETY: the backup is open until `finish` or drop
+        unsafe { ffi::sqlite3_backup_step(self.backup.as_ptr(), -1) }
+    }
+
+    /// Release the backup, succeeding if its last step copied everything
+    fn finish(self, step: i32) -> Result<(), DbError> {
+        let this = std::mem::ManuallyDrop::new(self);
+        // SAFETY: the backup is finished exactly once, here rather than in drop
+        unsafe {
+            if ffi::sqlite3_backup_finish(this.backup.as_ptr()) == ffi::SQLITE_OK && step == ffi::SQLITE_DONE {
+                Ok(())
+            } else {
+                Err(DbError::Query(format!("Backup failed: {}", sqlite_message(this.dest))))
+            }
+        }
+    }
+}
+
+impl Drop for BackupRun {
+    fn drop(&mut self) {
+        // SAFETY: reached only when `finish` wasn't, e.g. when the backup is cancelled
+        unsafe {
+            ffi::sqlite3_backup_finish(self.backup.as_ptr());
+        }
+    }
+}
+
+/// Latest error message recorded on a connection
+///
+/// # Safety
+/// `db` must be null or a handle returned by `sqlite3_open_v2` that hasn't been closed.
+unsafe fn sqlite_message(db: *mut ffi::sqlite3) -> String {
+    if db.is_null() {
+        return "out of memory".to_string();
+    }
+    CStr::from_ptr(ffi::sqlite3_errmsg(db)).to_string_lossy().into_owned()
+}
+
+// ============================================================================
+// Statements shared by single writes and transactions
+// ============================================================================
+
+/// Upsert a scored session's row and insert its score, or bump an identical latest
+/// score when `dedupe` is set; see `Database::save_session_score`
+async fn save_session_score_row(
+    conn: &mut SqliteConnection,
+    score: &SessionScore,
+    source: &str,
+    transcript_path: Option<&str>,
+    dedupe: bool,
+) -> Result<Score, DbError> {
+    let metadata = score.metadata_json();
+    upsert_session_row(&mut *conn, &score.session_id, source, transcript_path, metadata.as_deref()).await?;
+    let warnings = if score.warnings.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&score.warnings).map_err(|e| DbError::Validation(e.to_string()))?)
+    };
+    sqlx::query(
+        r#"
+        UPDATE sessions
+        SET model = ?1, started_at = ?2, ended_at = ?3, duration_seconds = ?4, warnings = ?5
+        WHERE id = ?6
+        "#,
+    )
+    .bind(score.info.model.as_deref())
+    .bind(score.info.started_at)
+    .bind(score.info.ended_at)
+    .bind(score.info.duration_seconds)
+    .bind(warnings)
+    .bind(&score.session_id)
+    .execute(&mut *conn)
+    .await?;
+    record_content_hash_row(&mut *conn, &score.session_id, &score.content_hash).await?;
+    let versions = check_rule_versions(&mut *conn, &score.rules).await?;
+    let stored = match dedupe {
+        true => refresh_identical_score(&mut *conn, &score.session_id, &score_result_hash(score, &versions)).await?,
+        false => None,
+    };
+    match stored {
+        Some(stored) => Ok(stored),
+        None => insert_score_with_checks(&mut *conn, &score.session_id, score, &versions).await,
+    }
+}
+
+/// Insert or replace the session ID mapping for a source file
+async fn save_session_id_mapping_row(
+    conn: &mut SqliteConnection,
+    source_path: &str,
+    session_id: &str,
+    strategy: SessionIdStrategy,
+) -> Result<(), DbError> {
+    sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO session_id_mappings (source_path, session_id, strategy, updated_at)
+        VALUES (?1, ?2, ?3, ?4)
+        "#,
+    )
+    .bind(source_path)
+    .bind(session_id)
+    .bind(strategy.as_str())
+    .bind(Utc::now())
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn upsert_session_row(
+    conn: &mut SqliteConnection,
+    id: &str,
+    source: &str,
+    transcript_path: Option<&str>,
+    metadata: Option<&str>,
+) -> Result<(), DbError> {
+    let now = Utc::now();
+
+    sqlx::query(
+        r#"
+        INSERT INTO sessions (id, created_at, updated_at, source, transcript_path, metadata)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6)
+        ON CONFLICT(id) DO UPDATE SET
+            updated_at = excluded.updated_at,
+            transcript_path = COALESCE(excluded.transcript_path, transcript_path),
+            metadata = COALESCE(excluded.metadata, metadata)
+        "#,
+    )
+    .bind(id)
+    .bind(now)
+    .bind(now)
+    .bind(source)
+    .bind(transcript_path)
+    .bind(metadata)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn record_content_hash_row(
+    conn: &mut SqliteConnection,
+    id: &str,
+    content_hash: &str,
+) -> Result<Option<String>, DbError> {
+    let duplicate_of: Option<String> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM sessions
+        WHERE content_hash = ?1 AND id != ?2 AND duplicate_of IS NULL
+        ORDER BY created_at ASC, rowid ASC
+        LIMIT 1
+        "#,
+    )
+    .bind(content_hash)
+    .bind(id)
+    .fetch_optional(&mut *conn)
+    .await?;
+
+    sqlx::query("UPDATE sessions SET updated_at = ?1, content_hash = ?2, duplicate_of = ?3 WHERE id = ?4")
+        .bind(Utc::now())
+        .bind(content_hash)
+        .bind(duplicate_of.as_deref())
+        .bind(id)
+        .execute(conn)
+        .await?;
+
+    Ok(duplicate_of)
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert_score_row(
+    conn: &mut SqliteConnection,
+    session_id: &str,
+    total_rules: i32,
+    passed_rules: i32,
+    score_percentage: f64,
+    summary: &str,
+    scorer_version: Option<&str>,
+    rule_set_hash: Option<&str>,
+    result_hash: Option<&str>,
+) -> Result<Score, DbError> {
+    let scored_at = Utc::now();
+
+    let id = sqlx::query(
+        r#"
+        INSERT INTO scores (session_id, scored_at, total_rules, passed_rules, score_percentage, summary, scorer_version, rule_set_hash, result_hash)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#,
+    )
+    .bind(session_id)
+    .bind(scored_at)
+    .bind(total_rules)
+    .bind(passed_rules)
+    .bind(score_percentage)
+    .bind(summary)
+    .bind(scorer_version)
+    .bind(rule_set_hash)
+    .bind(result_hash)
+    .execute(conn)
+    .await?
+    .last_insert_rowid();
+
+    Ok(Score {
+        id,
+        session_id: session_id.to_string(),
+        scored_at,
+        total_rules,
+        passed_rules,
+        score_percentage,
+        summary: summary.to_string(),
+        scorer_version: scorer_version.map(|s| s.to_string()),
+        rule_set_hash: rule_set_hash.map(|s| s.to_string()),
+    })
+}
+
+#[allow(clippy::too_many_arguments)]
+async fn insert_rule_check_row(
+    conn: &mut SqliteConnection,
+    score_id: i64,
+    rule_id: &str,
+    rule_name: &str,
+    description: &str,
+    passed: bool,
+    confidence: f64,
+    evidence: Option<&str>,
+    suggestion: Option<&str>,
+) -> Result<RuleCheckRecord, DbError> {
+    let id = sqlx::query(
+        r#"
+        INSERT INTO rule_checks (score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion, rule_version_id)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+        "#,
+    )
+    .bind(score_id)
+    .bind(rule_id)
+    .bind(rule_name)
+    .bind(description)
+    .bind(passed)
+    .bind(confidence)
+    .bind(evidence)
+    .bind(suggestion)
+    .bind(None::<i64>)
+    .execute(conn)
+    .await?
+    .last_insert_rowid();
+
+    Ok(RuleCheckRecord {
+        id,
+        score_id,
+        rule_id: rule_id.to_string(),
+        rule_name: rule_name.to_string(),
+        description: description.to_string(),
+        passed,
+        confidence,
+        evidence: evidence.map(|s| s.to_string()),
+        suggestion: suggestion.map(|s| s.to_string()),
+        rule_version_id: None,
+    })
+}
+
+/// Rule checks bound per multi-row INSERT, keeping well under SQLite's variable limit
+const RULE_CHECK_BATCH: usize = 100;
+
+async fn insert_rule_check_rows(
+    conn: &mut SqliteConnection,
+    score_id: i64,
+    checks: &[RuleCheck],
+    versions: &HashMap<String, i64>,
+) -> Result<Vec<RuleCheckRecord>, DbError> {
+    if checks.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let mut records = Vec::with_capacity(checks.len());
+    for batch in checks.chunks(RULE_CHECK_BATCH) {
+        let mut query = QueryBuilder::<Sqlite>::new(
+            "INSERT INTO rule_checks (score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion, rule_version_id) ",
+        );
+        query.push_values(batch, |mut row, check| {
+            row.push_bind(score_id)
+                .push_bind(&check.rule_id)
+                .push_bind(&check.rule_name)
+                .push_bind(&check.description)
+                .push_bind(check.passed)
+                .push_bind(check.confidence)
+                .push_bind(&check.evidence)
+                .push_bind(&check.suggestion)
+                .push_bind(versions.get(&check.rule_id).copied());
+        });
+        // Rows of one INSERT get consecutive rowids in VALUES order
+        let last_id = query.build().execute(&mut *conn).await?.last_insert_rowid();
+        let first_id = last_id - batch.len() as i64 + 1;
+
+        records.extend(batch.iter().zip(first_id..).map(|(check, id)| RuleCheckRecord {
+            id,
+            score_id,
+            rule_id: check.rule_id.clone(),
+            rule_name: check.rule_name.clone(),
+            description: check.description.clone(),
+            passed: check.passed,
+            confidence: check.confidence,
+            evidence: check.evidence.clone(),
+            suggestion: check.suggestion.clone(),
+            rule_version_id: versions.get(&check.rule_id).copied(),
+        }));
+    }
+
+    Ok(records)
+}
+
+/// Id of the recorded version of `rule` with exactly this definition. A definition not
+/// seen before, e.g. from a rules file or another database, is recorded as a new
+/// version: `preferred` if that number is free for the rule, else the next one.
+async fn resolve_rule_version(
+    conn: &mut SqliteConnection,
+    rule: &RuleDefinition,
+    preferred: Option<i64>,
+    created_at: DateTime<Utc>,
+) -> Result<i64, DbError> {
+    let category = serde_json::to_value(rule.category).map_err(|e| DbError::Validation(e.to_string()))?;
+    let existing: Option<i64> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM rule_versions
+        WHERE rule_id = ?1 AND name = ?2 AND description = ?3 AND pattern = ?4 AND weight = ?5 AND category = ?6
+        ORDER BY version DESC
+        LIMIT 1
+        "#,
+    )
+    .bind(&rule.id)
+    .bind(&rule.name)
+    .bind(&rule.description)
+    .bind(&rule.pattern)
+    .bind(rule.weight)
+    .bind(category.as_str())
+    .fetch_optional(&mut *conn)
+    .await?;
+    if let Some(id) = existing {
+        return Ok(id);
+    }
+
+    let taken: Option<i64> = match preferred {
+        Some(version) => sqlx::query_scalar("SELECT id FROM rule_versions WHERE rule_id = ?1 AND version = ?2")
+            .bind(&rule.id)
+            .bind(version)
+            .fetch_optional(&mut *conn)
+            .await?,
+        None => None,
+    };
+    let version: i64 = match preferred {
+        Some(version) if taken.is_none() => version,
+        _ => sqlx::query_scalar("SELECT COALESCE(MAX(version), 0) + 1 FROM rule_versions WHERE rule_id = ?1")
+            .bind(&rule.id)
+            .fetch_one(&mut *conn)
+            .await?,
+    };
+    let id = sqlx::query(
+        r#"
+        INSERT INTO rule_versions (rule_id, version, name, description, pattern, weight, category, created_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#,
+    )
+    .bind(&rule.id)
+    .bind(version)
+    .bind(&rule.name)
+    .bind(&rule.description)
+    .bind(&rule.pattern)
+    .bind(rule.weight)
+    .bind(category.as_str())
+    .bind(created_at)
+    .execute(conn)
+    .await?
+    .last_insert_rowid();
+    Ok(id)
+}
+
+/// Rule version id for each check that carries the definition it was evaluated with,
+/// by rule ID. Checks without one are stored unlinked.
+async fn check_rule_versions(conn: &mut SqliteConnection, checks: &[RuleCheck]) -> Result<HashMap<String, i64>, DbError> {
+    let mut versions = HashMap::new();
+    let now = Utc::now();
+    for definition in checks.iter().filter_map(|check| check.definition.as_ref()) {
+        if !versions.contains_key(&definition.id) {
+            let id = resolve_rule_version(&mut *conn, definition, None, now).await?;
+            versions.insert(definition.id.clone(), id);
+        }
+    }
+    Ok(versions)
+}
+
+/// Fingerprint of everything a score row and its checks and stats would store, so an
+/// identical re-score can be recognized. The rule versions the score was evaluated with
+/// are included, so a rule edit that doesn't change the outcome still records a new score.
+fn score_result_hash(score: &SessionScore, versions: &HashMap<String, i64>) -> String {
+    let stored = serde_json::json!({
+        "content_hash": score.content_hash,
+        "scorer_version": score.scorer_version,
+        "rule_set_hash": score.rule_set_hash,
+        "total_rules": score.total_rules,
+        "passed_rules": score.passed_rules,
+        "score_percentage": score.score_percentage,
+        "summary": score.summary,
+        "rules": score.rules.iter().map(|check| serde_json::json!([
+            check.rule_id,
+            check.rule_name,
+            check.description,
+            check.passed,
+            check.confidence,
+            check.evidence,
+            check.suggestion,
+            versions.get(&check.rule_id),
+        ])).collect::<Vec<_>>(),
+        "transcript_stats": score.transcript_stats,
+    });
+    crate::content_hash(&stored.to_string())
+}
+
+/// Bump `scored_at` on the session's latest score if it has `result_hash`, returning
+/// it; `None` if a new score row is needed
+async fn refresh_identical_score(
+    conn: &mut SqliteConnection,
+    session_id: &str,
+    result_hash: &str,
+) -> Result<Option<Score>, DbError> {
+    let latest = sqlx::query_as::<_, (i64, Option<String>)>(
+        "SELECT id, result_hash FROM scores WHERE session_id = ?1 ORDER BY scored_at DESC, id DESC LIMIT 1",
+    )
+    .bind(session_id)
+    .fetch_optional(&mut *conn)
+    .await?;
+    let Some((id, Some(latest_hash))) = latest else {
+        return Ok(None);
+    };
+    if latest_hash != result_hash {
+        return Ok(None);
+    }
+
+    let row = sqlx::query_as::<_, ScoreRow>(
+        r#"
+        UPDATE scores SET scored_at = ?1 WHERE id = ?2
+        RETURNING id, session_id, scored_at, total_rules, passed_rules, score_percentage, summary, scorer_version, rule_set_hash
+        "#,
+    )
+    .bind(Utc::now())
+    .bind(id)
+    .fetch_one(conn)
+    .await?;
+    Ok(Some(row.into()))
+}
+
+async fn insert_transcript_stats_row(
+    conn: &mut SqliteConnection,
+    score_id: i64,
+    stats: &TranscriptStats,
+) -> Result<(), DbError> {
+    sqlx::query(
+        r#"
+        INSERT OR REPLACE INTO transcript_stats (score_id, byte_length, line_count, turn_count, estimated_tokens)
+        VALUES (?1, ?2, ?3, ?4, ?5)
+        "#,
+    )
+    .bind(score_id)
+    .bind(stats.byte_length as i64)
+    .bind(stats.line_count as i64)
+    .bind(stats.turn_count as i64)
+    .bind(stats.estimated_tokens as i64)
+    .execute(conn)
+    .await?;
+
+    Ok(())
+}
+
+async fn insert_score_with_checks(
+    conn: &mut SqliteConnection,
+    session_id: &str,
+    score: &SessionScore,
+    versions: &HashMap<String, i64>,
+) -> Result<Score, DbError> {
+    let stored = insert_score_row(
+        &mut *conn,
+        session_id,
+        score.total_rules as i32,
+        score.passed_rules as i32,
+        score.score_percentage,
+        &score.summary,
+        Some(&score.scorer_version),
+        Some(&score.rule_set_hash),
+        Some(&score_result_hash(score, versions)),
+    )
+    .await?;
+    insert_rule_check_rows(&mut *conn, stored.id, &score.rules, versions).await?;
+    insert_transcript_stats_row(conn, stored.id, &score.transcript_stats).await?;
+
+    Ok(stored)
+}
+
+// ============================================================================
+// History Import (internal)
+// ============================================================================
+
+/// Store a rule version from another database, returning its local ID. A local version
+/// with the same definition is reused; a conflicting definition under the same number
+/// is kept as a new version rather than dropped, so imported checks stay attributed to
+/// the rule they were scored with.
+pub(crate) async fn import_rule_version(conn: &mut SqliteConnection, version: &RuleVersion) -> Result<i64, DbError> {
+    let definition = RuleDefinition {
+        id: version.rule_id.clone(),
+        name: version.name.clone(),
+        description: version.description.clone(),
+        pattern: version.pattern.clone(),
+        weight: version.weight,
+        category: version.category,
+    };
+    resolve_rule_version(conn, &definition, Some(version.version), version.created_at).await
+}
+
+/// Store a rule from another database if no rule with its ID exists
+pub(crate) async fn import_rule(conn: &mut SqliteConnection, rule: &StoredRule) -> Result<bool, DbError> {
+    let category = serde_json::to_value(rule.category).map_err(|e| DbError::Validation(e.to_string()))?;
+    let result = sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO rules (id, name, description, pattern, weight, category, version, enabled, position, updated_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10)
+        "#,
+    )
+    .bind(&rule.id)
+    .bind(&rule.name)
+    .bind(&rule.description)
+    .bind(&rule.pattern)
+    .bind(rule.weight)
+    .bind(category.as_str())
+    .bind(rule.version)
+    .bind(rule.enabled)
+    .bind(rule.position)
+    .bind(rule.updated_at)
+    .execute(&mut *conn)
+    .await?;
+
+    Ok(result.rows_affected() > 0)
+}
+
+/// Store a session from another database, with its transcript, if no session with
+/// its ID exists. Existing sessions are left untouched.
+pub(crate) async fn import_session(
+    conn: &mut SqliteConnection,
+    session: &Session,
+    transcript: Option<&Transcript>,
+) -> Result<bool, DbError> {
+    let warnings = if session.warnings.is_empty() {
+        None
+    } else {
+        Some(serde_json::to_string(&session.warnings).map_err(|e| DbError::Validation(e.to_string()))?)
+    };
+    let result = sqlx::query(
+        r#"
+        INSERT OR IGNORE INTO sessions (id, created_at, updated_at, source, transcript_path, metadata, model,
+            started_at, ended_at, duration_seconds, warnings, content_hash, duplicate_of, deleted_at)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)
+        "#,
+    )
+    .bind(&session.id)
+    .bind(session.created_at)
+    .bind(session.updated_at)
+    .bind(&session.source)
+    .bind(&session.transcript_path)
+    .bind(&session.metadata)
+    .bind(&session.info.model)
+    .bind(session.info.started_at)
+    .bind(session.info.ended_at)
+    .bind(session.info.duration_seconds)
+    .bind(warnings)
+    .bind(&session.content_hash)
+    .bind(&session.duplicate_of)
+    .bind(session.deleted_at)
+    .execute(&mut *conn)
+    .await?;
+    if result.rows_affected() == 0 {
+        return Ok(false);
+    }
+
+    if let Some(transcript) = transcript {
+        let format = serde_json::to_value(transcript.format).map_err(|e| DbError::Validation(e.to_string()))?;
+        let turns = serde_json::to_string(&transcript.turns).map_err(|e| DbError::Validation(e.to_string()))?;
+        sqlx::query("INSERT OR REPLACE INTO transcripts (session_id, format, turns, updated_at) VALUES (?1, ?2, ?3, ?4)")
+            .bind(&session.id)
+            .bind(format.as_str())
+            .bind(turns)
+            .bind(session.updated_at)
+            .execute(&mut *conn)
+            .await?;
+    }
+
+    Ok(true)
+}
+
+/// Store a score from another database with its rule checks, stats, and
+/// overrides. Scores already present for the same session and `scored_at` (to the
+/// millisecond) are skipped. `rule_version_ids` maps the
+/// other database's rule version IDs to this one's; unmapped checks are unlinked.
+pub(crate) async fn import_score(
+    conn: &mut SqliteConnection,
+    score: &Score,
+    rule_checks: &[RuleCheckRecord],
+    stats: Option<&TranscriptStats>,
+    overrides: &[RuleCheckOverride],
+    rule_version_ids: &HashMap<i64, i64>,
+) -> Result<bool, DbError> {
+    let existing: Option<i64> = sqlx::query_scalar(
+        r#"
+        SELECT id FROM scores
+        WHERE session_id = ?1 AND ABS(julianday(scored_at) - julianday(?2)) * 86400000 < 1
+        "#,
+    )
+    .bind(&score.session_id)
+    .bind(score.scored_at)
+    .fetch_optional(&mut *conn)
+    .await?;
+    if existing.is_some() {
+        return Ok(false);
+    }
+
+    let score_id = sqlx::query(
+        r#"
+        INSERT INTO scores (session_id, scored_at, total_rules, passed_rules, score_percentage, summary, scorer_version, rule_set_hash)
+        VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)
+        "#,
+    )
+    .bind(&score.session_id)
+    .bind(score.scored_at)
+    .bind(score.total_rules)
+    .bind(score.passed_rules)
+    .bind(score.score_percentage)
+    .bind(&score.summary)
+    .bind(&score.scorer_version)
+    .bind(&score.rule_set_hash)
+    .execute(&mut *conn)
+    .await?
+    .last_insert_rowid();
+
+    for check in rule_checks {
+        let check_id = sqlx::query(
+            r#"
+            INSERT INTO rule_checks (score_id, rule_id, rule_name, description, passed, confidence, evidence, suggestion, rule_version_id)
+            VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)
+            "#,
+        )
+        .bind(score_id)
+        .bind(&check.rule_id)
+        .bind(&check.rule_name)
+        .bind(&check.description)
+        .bind(check.passed)
+        .bind(check.confidence)
+        .bind(&check.evidence)
+        .bind(&check.suggestion)
+        .bind(check.rule_version_id.and_then(|id| rule_version_ids.get(&id)))
+        .execute(&mut *conn)
+        .await?
+        .last_insert_rowid();
+
+        if let Some(review) = overrides.iter().find(|o| o.rule_check_id == check.id) {
+            sqlx::query(
+                "INSERT INTO rule_check_overrides (rule_check_id, verdict, reason, created_at) VALUES (?1, ?2, ?3, ?4)",
+            )
+            .bind(check_id)
+            .bind(review.verdict.as_str())
+            .bind(&review.reason)
+            .bind(review.created_at)
+            .execute(&mut *conn)
+            .await?;
+        }
+    }
+
+    if let Some(stats) = stats {
+        insert_transcript_stats_row(&mut *conn, score_id, stats).await?;
+    }
+
+    Ok(true)
+}
+
+// ============================================================================
+// SQLx Row Types (internal)
+// ============================================================================
+
+#[derive(sqlx::FromRow)]
+struct SessionRow {
+    id: String,
+    created_at: chrono::NaiveDateTime,
+    updated_at: chrono::NaiveDateTime,
+    source: String,
+    transcript_path: Option<String>,
+    metadata: Option<String>,
+    model: Option<String>,
+    started_at: Option<chrono::NaiveDateTime>,
+    ended_at: Option<chrono::NaiveDateTime>,
+    duration_seconds: Option<i64>,
+    warnings: Option<String>,
+    content_hash: Option<String>,
+    duplicate_of: Option<String>,
+    deleted_at: Option<chrono::NaiveDateTime>,
+}
+
+#[derive(sqlx::FromRow)]
+struct RuleRow {
+    id: String,
+    name: String,
+    description: String,
+    pattern: String,
+    weight: f64,
+    category: String,
+    version: i64,
+    enabled: bool,
+    position: i64,
+    updated_at: chrono::NaiveDateTime,
+}
+
+impl TryFrom<RuleRow> for StoredRule {
+    type Error = DbError;
+
+    fn try_from(row: RuleRow) -> Result<Self, DbError> {
+        Ok(StoredRule {
+            category: serde_json::from_value(serde_json::Value::String(row.category))
+                .map_err(|e| DbError::Validation(e.to_string()))?,
+            id: row.id,
+            name: row.name,
+            description: row.description,
+            pattern: row.pattern,
+            weight: row.weight,
+            version: row.version,
+            enabled: row.enabled,
+            position: row.position,
+            updated_at: row.updated_at.and_utc(),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct RuleVersionRow {
+    id: i64,
+    rule_id: String,
+    version: i64,
+    name: String,
+    description: String,
+    pattern: String,
+    weight: f64,
+    category: String,
+    created_at: chrono::NaiveDateTime,
+}
+
+impl TryFrom<RuleVersionRow> for RuleVersion {
+    type Error = DbError;
+
+    fn try_from(row: RuleVersionRow) -> Result<Self, DbError> {
+        Ok(RuleVersion {
+            category: serde_json::from_value(serde_json::Value::String(row.category))
+                .map_err(|e| DbError::Validation(e.to_string()))?,
+            id: row.id,
+            rule_id: row.rule_id,
+            version: row.version,
+            name: row.name,
+            description: row.description,
+            pattern: row.pattern,
+            weight: row.weight,
+            created_at: row.created_at.and_utc(),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct RuleCheckOverrideRow {
+    rule_check_id: i64,
+    verdict: String,
+    reason: String,
+    created_at: chrono::NaiveDateTime,
+}
+
+impl TryFrom<RuleCheckOverrideRow> for RuleCheckOverride {
+    type Error = DbError;
+
+    fn try_from(row: RuleCheckOverrideRow) -> Result<Self, DbError> {
+        Ok(RuleCheckOverride {
+            verdict: serde_json::from_value(serde_json::Value::String(row.verdict))
+                .map_err(|e| DbError::Validation(e.to_string()))?,
+            rule_check_id: row.rule_check_id,
+            reason: row.reason,
+            created_at: row.created_at.and_utc(),
+        })
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct SearchHitRow {
+    session_id: String,
+    kind: String,
+    rule_id: Option<String>,
+    snippet: String,
+    rank: f64,
+}
+
+impl TryFrom<SearchHitRow> for SearchHit {
+    type Error = DbError;
+
+    fn try_from(row: SearchHitRow) -> Result<Self, DbError> {
+        Ok(SearchHit {
+            kind: serde_json::from_value(serde_json::Value::String(row.kind))
+                .map_err(|e| DbError::Validation(e.to_string()))?,
+            session_id: row.session_id,
+            rule_id: row.rule_id,
+            snippet: row.snippet,
+            rank: row.rank,
+        })
+    }
+}
+
+impl From<SessionRow> for Session {
+    fn from(row: SessionRow) -> Self {
+        Self {
+            id: row.id,
+            created_at: DateTime::from_naive_utc_and_offset(row.created_at, Utc),
+            updated_at: DateTime::from_naive_utc_and_offset(row.updated_at, Utc),
+            source: row.source,
+            transcript_path: row.transcript_path,
+            typed_metadata: SessionMetadata::parse(row.metadata.as_deref()),
+            metadata: row.metadata,
+            info: SessionInfo {
+                model: row.model,
+                started_at: row.started_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
+                ended_at: row.ended_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
+                duration_seconds: row.duration_seconds,
+            },
+            warnings: row
+                .warnings
+                .and_then(|w| serde_json::from_str(&w).ok())
+                .unwrap_or_default(),
+            content_hash: row.content_hash,
+            duplicate_of: row.duplicate_of,
+            deleted_at: row.deleted_at.map(|dt| DateTime::from_naive_utc_and_offset(dt, Utc)),
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct ScoreRow {
+    id: i64,
+    session_id: String,
+    scored_at: chrono::NaiveDateTime,
+    total_rules: i32,
+    passed_rules: i32,
+    score_percentage: f64,
+    summary: String,
+    scorer_version: Option<String>,
+    rule_set_hash: Option<String>,
+}
+
+impl From<ScoreRow> for Score {
+    fn from(row: ScoreRow) -> Self {
+        Self {
+            id: row.id,
+            session_id: row.session_id,
+            scored_at: DateTime::from_naive_utc_and_offset(row.scored_at, Utc),
+            total_rules: row.total_rules,
+            passed_rules: row.passed_rules,
+            score_percentage: row.score_percentage,
+            summary: row.summary,
+            scorer_version: row.scorer_version,
+            rule_set_hash: row.rule_set_hash,
+        }
+    }
+}
+
+#[derive(sqlx::FromRow)]
+struct RuleCheckRow {
+    id: i64,
+    score_id: i64,
+    rule_id: String,
+    rule_name: String,
+    description: String,
+    passed: bool,
+    confidence: f64,
+    evidence: Option<String>,
+    suggestion: Option<String>,
+    rule_version_id: Option<i64>,
+}
+
+impl From<RuleCheckRow> for RuleCheckRecord {
+    fn from(row: RuleCheckRow) -> Self {
+        Self {
+            id: row.id,
+            score_id: row.score_id,
+            rule_id: row.rule_id,
+            rule_name: row.rule_name,
+            description: row.description,
+            passed: row.passed,
+            confidence: row.confidence,
+            evidence: row.evidence,
+            suggestion: row.suggestion,
+            rule_version_id: row.rule_version_id,
+        }
+    }
+}
+
+// ============================================================================
+// Tests
+// ============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_database_creation() {
+        let db = Database::new_in_memory().await.unwrap();
+        let version = db.migration_version().await.unwrap();
+        assert_eq!(version, 19);
+    }
+
+    #[tokio::test]
+    async fn test_session_crud() {
+        let db = Database::new_in_memory().await.unwrap();
+
+        // Create
+        let session = db
+            .create_session("test-session", "test", Some("/path/to/transcript.md"), None)
+            .await
+            .unwrap();
+        assert_eq!(session.id, "test-session");
+        assert_eq!(session.source, "test");
+
+        // Read
+        let fetched = db.get_session("test-session").await.unwrap();
+        assert_eq!(fetched.id, "test-session");
+
+        // List
+        let sessions = db.list_sessions(None, &ListFilters::default()).await.unwrap();
+        assert_eq!(sessions.len(), 1);
+
+        // Update
+        let updated = db
+            .update_session("test-session", None, Some("{\"key\": \"value\"}"))
+            .await
+            .unwrap();
+        assert!(updated.metadata.is_some());
+
+        // Delete
+        let deleted = db.delete_session("test-session").await.unwrap();
+        assert!(deleted);
+    }
+
+    #[tokio::test]
+    async fn test_upsert_session_keeps_metadata() {
+        let db = Database::new_in_memory().await.unwrap();
+
+        let created = db
+            .upsert_session("test-session", "codex", None, Some(r#"{"model":"gpt-5"}"#))
+            .await
+            .unwrap();
+        assert_eq!(created.source, "codex");
+
+        let updated = db
+            .upsert_session("test-session", "codex", Some("/sessions/a.md"), None)
+            .await
+            .unwrap();
+        assert_eq!(updated.transcript_path.as_deref(), Some("/sessions/a.md"));
+        assert_eq!(updated.metadata.as_deref(), Some(r#"{"model":"gpt-5"}"#));
+        assert_eq!(db.list_sessions(None, &ListFilters::default()).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_session_info_roundtrip() {
+        let db = Database::new_in_memory().await.unwrap();
+
+        db.create_session("test-session", "test", None, None)
+            .await
+            .unwrap();
+        let started_at = "2026-02-15T09:00:00Z".parse::<DateTime<Utc>>().unwrap();
+        let info = SessionInfo {
+            model: Some("claude-sonnet".to_string()),
+            started_at: Some(started_at),
+            ended_at: Some(started_at + chrono::Duration::minutes(30)),
+            duration_seconds: Some(1800),
+        };
+
+        let updated = db.update_session_info("test-session", &info).await.unwrap();
+        assert_eq!(updated.info, info);
+    }
+
+    #[tokio::test]
+    async fn test_session_warnings_roundtrip() {
+        let db = Database::new_in_memory().await.unwrap();
+
+        db.create_session("test-session", "test", None, None)
+            .await
+            .unwrap();
+        assert!(db.get_session("test-session").await.unwrap().warnings.is_empty());
+
+        let warnings = vec!["Transcript decoded from UTF-16LE".to_string()];
+        let updated = db.set_session_warnings("test-session", &warnings).await.unwrap();
+        assert_eq!(updated.warnings, warnings);
+
+        let cleared = db.set_session_warnings("test-session", &[]).await.unwrap();
+        assert!(cleared.warnings.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_save_session_score_is_atomic() {
+        let db = Database::new_in_memory().await.unwrap();
+        let score = crate::BehaviorScorer::new()
+            .score_session("atomic", "User: go\nAssistant: OBJECTIVE: ship")
+            .unwrap();
+
+        let stored = db.save_session_score(&score, "app", None).await.unwrap();
+        assert_eq!(db.get_session("atomic").await.unwrap().source, "app");
+        assert_eq!(db.get_score_rule_checks(stored.id).await.unwrap().len(), score.rules.len());
+        assert_eq!(db.get_transcript_stats(stored.id).await.unwrap(), score.transcript_stats);
+
+        // A failing rule check insert rolls back the session, score, and earlier checks
+        sqlx::query(
+            r#"
+            CREATE TRIGGER fail_rule_check BEFORE INSERT ON rule_checks
+            WHEN NEW.rule_id = 'approval_for_external'
+            BEGIN SELECT RAISE(ABORT, 'simulated failure'); END
+            "#,
+        )
+        .execute(&db.pool)
+        .await
+        .unwrap();
+        let mut failing = score.clone();
+        failing.session_id = "rolled-back".to_string();
+        assert!(db.save_session_score(&failing, "app", None).await.is_err());
+        assert!(db.get_session("rolled-back").await.is_err());
+        assert_eq!(db.list_scores(None, &ListFilters::default()).await.unwrap().len(), 1);
+        let checks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rule_checks").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(checks as usize, score.rules.len());
+
+        // In a batch only the failing score is rolled back, along with its mapping
+        let mut passing = score.clone();
+        passing.session_id = "batched".to_string();
+        passing.source_path = Some("/logs/batched.md".to_string());
+        passing.rules.retain(|rule| rule.rule_id != "approval_for_external");
+        failing.source_path = Some("/logs/rolled-back.md".to_string());
+        let results = db
+            .save_scanned_scores(&[passing, failing], "import", SessionIdStrategy::FileName)
+            .await
+            .unwrap();
+        assert!(results[0].is_ok() && results[1].is_err());
+        assert_eq!(db.get_session("batched").await.unwrap().source, "import");
+        assert!(db.get_session_id_mapping("/logs/batched.md").await.unwrap().is_some());
+        assert!(db.get_session("rolled-back").await.is_err());
+        assert!(db.get_session_id_mapping("/logs/rolled-back.md").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_save_score_with_checks() {
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("checked", "test", None, None).await.unwrap();
+        let score = crate::BehaviorScorer::new().score_session("other-id", "Confidence level: high").unwrap();
+
+        let stored = db.save_score_with_checks("checked", &score).await.unwrap();
+        assert_eq!(stored.session_id, "checked");
+        let checks = db.get_score_rule_checks(stored.id).await.unwrap();
+        assert_eq!(checks.len(), score.rules.len());
+        assert!(checks.iter().any(|c| c.rule_id == "confidence_calibration" && c.passed));
+
+        // Without a session row the score insert fails and nothing is left behind
+        assert!(db.save_score_with_checks("missing", &score).await.is_err());
+        let checks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rule_checks").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(checks as usize, score.rules.len());
+    }
+
+    #[tokio::test]
+    async fn test_rule_crud() {
+        let db = Database::new_in_memory().await.unwrap();
+        let defaults = crate::BehaviorScorer::new().config().rules.clone();
+
+        assert_eq!(db.seed_rules(&defaults).await.unwrap(), defaults.len());
+        assert_eq!(db.seed_rules(&defaults).await.unwrap(), 0);
+        let rules = db.list_rules().await.unwrap();
+        assert_eq!(rules.len(), defaults.len());
+        assert_eq!(rules[0].id, defaults[0].id);
+        assert!(rules.iter().all(|r| r.version == 1 && r.enabled));
+
+        // Saving an unchanged definition keeps the version; edits bump it
+        let mut rule = rules[1].definition();
+        assert_eq!(db.save_rule(&rule).await.unwrap().version, 1);
+        rule.weight = 3.0;
+        let edited = db.save_rule(&rule).await.unwrap();
+        assert_eq!((edited.version, edited.weight, edited.position), (2, 3.0, 1));
+
+        let mut custom = rule.clone();
+        custom.id = "custom".to_string();
+        custom.category = RuleCategory::Safety;
+        let created = db.save_rule(&custom).await.unwrap();
+        assert_eq!(created.position, defaults.len() as i64);
+        assert_eq!(created.category, RuleCategory::Safety);
+
+        custom.weight = f64::NAN;
+        assert!(matches!(db.save_rule(&custom).await, Err(DbError::Validation(_))));
+
+        assert!(!db.set_rule_enabled("custom", false).await.unwrap().enabled);
+        assert_eq!(db.enabled_rules().await.unwrap().len(), defaults.len());
+        assert!(db.set_rule_enabled("missing", true).await.is_err());
+
+        assert!(db.delete_rule("custom").await.unwrap());
+        assert!(!db.delete_rule("custom").await.unwrap());
+        assert_eq!(db.list_rules().await.unwrap().len(), defaults.len());
+    }
+
+    #[tokio::test]
+    async fn test_rule_checks_link_rule_versions() {
+        let db = Database::new_in_memory().await.unwrap();
+        let scorer = crate::BehaviorScorer::new();
+        db.seed_rules(&scorer.config().rules).await.unwrap();
+        let score = scorer.score_session("versioned", "OBJECTIVE: ship").unwrap();
+
+        let first = db.save_session_score(&score, "test", None).await.unwrap();
+        let mut rule = db.get_rule("objective_before_execution").await.unwrap().definition();
+        rule.pattern = "OBJECTIVE:".to_string();
+        db.save_rule(&rule).await.unwrap();
+
+        // A score produced before the edit still links to the version it was evaluated with
+        let rescored = db.save_session_score(&score, "test", None).await.unwrap();
+        let mut config = scorer.config().clone();
+        for r in config.rules.iter_mut().filter(|r| r.id == rule.id) {
+            *r = rule.clone();
+        }
+        let edited = crate::BehaviorScorer::with_config(config).score_session("versioned", "OBJECTIVE: ship").unwrap();
+        let second = db.save_session_score(&edited, "test", None).await.unwrap();
+        assert_ne!(rescored.id, second.id);
+
+        let versions = db.list_rule_versions("objective_before_execution").await.unwrap();
+        assert_eq!(versions.iter().map(|v| v.version).collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(versions[1].pattern, "OBJECTIVE:");
+
+        let version_of = |checks: Vec<RuleCheckRecord>| {
+            checks.into_iter().find(|c| c.rule_id == "objective_before_execution").unwrap().rule_version_id.unwrap()
+        };
+        let first_version = version_of(db.get_score_rule_checks(first.id).await.unwrap());
+        let rescored_version = version_of(db.get_score_rule_checks(rescored.id).await.unwrap());
+        let new_version = version_of(db.get_score_rule_checks(second.id).await.unwrap());
+        assert_eq!(db.get_rule_version(first_version).await.unwrap().version, 1);
+        assert_eq!(db.get_rule_version(rescored_version).await.unwrap().version, 1);
+        assert_eq!(db.get_rule_version(new_version).await.unwrap().version, 2);
+
+        let rates = db.get_rule_version_pass_rates("objective_before_execution", Outcomes::Raw).await.unwrap();
+        assert_eq!(rates.iter().map(|r| r.version).collect::<Vec<_>>(), [Some(1), Some(2)]);
+
+        // Definitions the database has not seen are recorded as new versions
+        let unseeded = Database::new_in_memory().await.unwrap();
+        let stored = unseeded.save_session_score(&score, "test", None).await.unwrap();
+        let checks = unseeded.get_score_rule_checks(stored.id).await.unwrap();
+        for check in checks {
+            let version = unseeded.get_rule_version(check.rule_version_id.unwrap()).await.unwrap();
+            assert_eq!((version.rule_id, version.version), (check.rule_id, 1));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search() {
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("planning", "claude-code", None, None).await.unwrap();
+        db.create_session("review", "app", None, None).await.unwrap();
+
+        let transcript = crate::transcript::parse_jsonl(
+            r#"{"role":"user","content":"Why is the draft queue stuck?"}
+{"role":"assistant","content":"Checking the workers"}"#,
+        )
+        .unwrap();
+        db.save_transcript("planning", &transcript).await.unwrap();
+        let score = db.create_score("review", 1, 0, 0.0, "", None, None).await.unwrap();
+        let check = db
+            .create_rule_check(score.id, "flow_control", "Flow", "", false, 0.5, Some("Drafts queue up twice"), None)
+            .await
+            .unwrap();
+
+        // Terms are stemmed and every term must match
+        let hits = db.search("draft queue", &SearchFilters::default()).await.unwrap();
+        let mut sessions: Vec<_> = hits.iter().map(|h| h.session_id.as_str()).collect();
+        sessions.sort();
+        assert_eq!(sessions, ["planning", "review"]);
+        assert!(db.search("draft workers", &SearchFilters::default()).await.unwrap()[0].snippet.contains("[workers]"));
+        assert!(db.search("draft missing", &SearchFilters::default()).await.unwrap().is_empty());
+
+        let evidence = SearchFilters {
+            kind: Some(SearchKind::Evidence),
+            ..SearchFilters::default()
+        };
+        let hits = db.search("queue", &evidence).await.unwrap();
+        assert_eq!(hits.len(), 1);
+        assert_eq!(hits[0].rule_id.as_deref(), Some("flow_control"));
+        let by_source = SearchFilters {
+            source: Some("claude-code".to_string()),
+            ..SearchFilters::default()
+        };
+        assert_eq!(db.search("queue", &by_source).await.unwrap()[0].session_id, "planning");
+
+        // Query syntax is treated as text, and the index follows deletes
+        assert!(db.search("\"queue OR (", &SearchFilters::default()).await.is_ok());
+        assert!(matches!(db.search("  ", &SearchFilters::default()).await, Err(DbError::Validation(_))));
+        db.delete_rule_check(check.id).await.unwrap();
+        db.delete_session("planning").await.unwrap();
+        assert!(db.search("queue", &SearchFilters::default()).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_by_session() {
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("planning", "claude-code", None, None).await.unwrap();
+        let transcript = crate::transcript::parse_jsonl(
+            r#"{"role":"user","content":"Café drafts [queue] stuck"}
+{"role":"assistant","content":"Restarting the queue"}"#,
+        )
+        .unwrap();
+        db.save_transcript("planning", &transcript).await.unwrap();
+        let score = db.create_score("planning", 1, 0, 0.0, "", None, None).await.unwrap();
+        db.create_rule_check(score.id, "flow_control", "Flow", "", false, 0.5, Some("queue grew"), None)
+            .await
+            .unwrap();
+        db.create_session("unscored", "app", None, None).await.unwrap();
+        db.save_transcript("unscored", &crate::transcript::parse_jsonl(r#"{"role":"user","content":"queue"}"#).unwrap())
+            .await
+            .unwrap();
+
+        let results = db.search_by_session("queue", &SearchFilters::default()).await.unwrap();
+        assert_eq!(results.len(), 2);
+        let planning = results.iter().find(|r| r.session.id == "planning").unwrap();
+        assert_eq!(planning.latest_score.as_ref().map(|s| s.id), Some(score.id));
+        assert_eq!(planning.snippets.len(), 2);
+        assert!(results.iter().find(|r| r.session.id == "unscored").unwrap().latest_score.is_none());
+
+        // Offsets are UTF-16 and literal brackets survive
+        let transcript_hit = planning.snippets.iter().find(|s| s.kind == SearchKind::Transcript).unwrap();
+        assert!(transcript_hit.text.contains("[queue]"));
+        assert!(!transcript_hit.text.contains(HIGHLIGHT_START));
+        let utf16: Vec<u16> = transcript_hit.text.encode_utf16().collect();
+        assert_eq!(transcript_hit.highlights.len(), 2);
+        for span in &transcript_hit.highlights {
+            assert_eq!(span.text, "queue");
+            assert_eq!(String::from_utf16(&utf16[span.start..span.end]).unwrap(), "queue");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_list_filters() {
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("tagged", "claude-code", None, Some(r#"{"tags":["release","infra"]}"#))
+            .await
+            .unwrap();
+        db.create_session("plain", "codex", None, Some("not json")).await.unwrap();
+        db.create_session("unscored", "codex", None, None).await.unwrap();
+        db.create_score("tagged", 10, 4, 40.0, "", None, None).await.unwrap();
+        db.create_score("tagged", 10, 9, 92.0, "", None, None).await.unwrap();
+        db.create_score("plain", 10, 8, 80.0, "", None, None).await.unwrap();
+
+        let ids = |sessions: Vec<Session>| {
+            let mut ids: Vec<_> = sessions.into_iter().map(|s| s.id).collect();
+            ids.sort();
+            ids
+        };
+        let sessions = |filters: ListFilters| {
+            let db = &db;
+            async move { ids(db.list_sessions(None, &filters).await.unwrap()) }
+        };
+
+        assert_eq!(sessions(ListFilters::default()).await.len(), 3);
+        let codex = ListFilters { source: Some("codex".to_string()), ..ListFilters::default() };
+        assert_eq!(sessions(codex).await, ["plain", "unscored"]);
+        let release = ListFilters { tag: Some("release".to_string()), ..ListFilters::default() };
+        assert_eq!(sessions(release).await, ["tagged"]);
+
+        // Sessions are banded by their latest score; unscored sessions drop out
+        let excellent = ListFilters { band: Some(ScoreBand::Excellent), ..ListFilters::default() };
+        assert_eq!(sessions(excellent).await, ["tagged"]);
+        let range = ListFilters { min_percentage: Some(50.0), max_percentage: Some(90.0), ..ListFilters::default() };
+        assert_eq!(sessions(range).await, ["plain"]);
+        let future = ListFilters { since: Some(Utc::now() + chrono::Duration::hours(1)), ..ListFilters::default() };
+        assert!(sessions(future).await.is_empty());
+        let past = ListFilters { until: Some(Utc::now() + chrono::Duration::hours(1)), ..ListFilters::default() };
+        assert_eq!(sessions(past).await.len(), 3);
+
+        // Scores are filtered individually
+        let poor = ListFilters { band: Some(ScoreBand::Poor), ..ListFilters::default() };
+        let scores = db.list_scores(None, &poor).await.unwrap();
+        assert_eq!(scores.iter().map(|s| s.score_percentage).collect::<Vec<_>>(), [40.0]);
+        // Each percentage lands in the one band whose bounds hold it
+        let bands = [100.0, 90.0, 89.9, 75.0, 50.0, 49.9, 0.0, f64::NAN].map(ScoreBand::of);
+        assert_eq!(bands, [
+            ScoreBand::Excellent,
+            ScoreBand::Excellent,
+            ScoreBand::Good,
+            ScoreBand::Good,
+            ScoreBand::Moderate,
+            ScoreBand::Poor,
+            ScoreBand::Poor,
+            ScoreBand::Poor,
+        ]);
+        let release = ListFilters { tag: Some("release".to_string()), ..ListFilters::default() };
+        assert_eq!(db.list_scores(None, &release).await.unwrap().len(), 2);
+        let tagged = ListFilters { session_id: Some("tagged".to_string()), ..ListFilters::default() };
+        assert_eq!(sessions(tagged.clone()).await, ["tagged"]);
+        let scores = db.list_scores(None, &tagged).await.unwrap();
+        assert!(scores.len() == 2 && scores.iter().all(|s| s.session_id == "tagged"));
+    }
+
+    #[tokio::test]
+    async fn test_score_series() {
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("a", "claude-code", None, None).await.unwrap();
+        db.create_session("b", "codex", None, None).await.unwrap();
+        db.create_score("a", 10, 4, 40.0, "", None, None).await.unwrap();
+        db.create_score("a", 10, 8, 80.0, "", None, None).await.unwrap();
+        db.create_score("b", 10, 9, 90.0, "", None, None).await.unwrap();
+
+        let all = db.get_score_series(TimeBucket::Day, &ListFilters::default()).await.unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].scores, 3);
+        assert!((all[0].avg_score - 70.0).abs() < 1e-9);
+        assert_eq!((all[0].min_score, all[0].max_score), (40.0, 90.0));
+
+        let a = ListFilters { session_id: Some("a".to_string()), ..ListFilters::default() };
+        let series = db.get_score_series(TimeBucket::Week, &a).await.unwrap();
+        assert_eq!(series[0].scores, 2);
+        assert!((series[0].avg_score - 60.0).abs() < 1e-9);
+
+        let future = ListFilters { since: Some(Utc::now() + chrono::Duration::hours(1)), ..ListFilters::default() };
+        assert!(db.get_score_series(TimeBucket::Day, &future).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rule_check_overrides() {
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("reviewed", "test", None, None).await.unwrap();
+        let score = db.create_score("reviewed", 2, 1, 50.0, "", None, None).await.unwrap();
+        let passed = db
+            .create_rule_check(score.id, "tone", "Tone", "", true, 1.0, None, None)
+            .await
+            .unwrap();
+        let failed = db
+            .create_rule_check(score.id, "plan", "Plan", "", false, 1.0, None, None)
+            .await
+            .unwrap();
+
+        // Verdicts must contradict the stored outcome and carry a reason
+        assert!(matches!(
+            db.set_rule_check_override(passed.id, OverrideVerdict::FalseNegative, "nope").await,
+            Err(DbError::Validation(_))
+        ));
+        assert!(matches!(
+            db.set_rule_check_override(failed.id, OverrideVerdict::FalseNegative, " ").await,
+            Err(DbError::Validation(_))
+        ));
+        let stored = db
+            .set_rule_check_override(failed.id, OverrideVerdict::FalseNegative, "Plan was in the first turn")
+            .await
+            .unwrap();
+        assert_eq!(stored.verdict, OverrideVerdict::FalseNegative);
+        assert_eq!(db.get_score_overrides(score.id).await.unwrap(), [stored]);
+
+        assert_eq!(db.get_rule_pass_rate("plan", Outcomes::Raw).await.unwrap(), 0.0);
+        assert_eq!(db.get_rule_pass_rate("plan", Outcomes::Adjusted).await.unwrap(), 100.0);
+        assert_eq!(db.get_average_score(Outcomes::Raw).await.unwrap(), 50.0);
+        assert_eq!(db.get_average_score(Outcomes::Adjusted).await.unwrap(), 100.0);
+        assert_eq!(db.get_score_distribution(Outcomes::Adjusted).await.unwrap().excellent, 1);
+        let baselines = db.get_rule_baselines(Outcomes::Adjusted).await.unwrap();
+        assert!(baselines.iter().all(|b| b.passed == 1));
+
+        db.set_rule_check_override(passed.id, OverrideVerdict::FalsePositive, "Tone was off")
+            .await
+            .unwrap();
+        assert_eq!(db.get_average_score(Outcomes::Adjusted).await.unwrap(), 50.0);
+
+        assert!(db.clear_rule_check_override(failed.id).await.unwrap());
+        assert_eq!(db.get_rule_check_override(failed.id).await.unwrap(), None);
+        assert_eq!(db.get_average_score(Outcomes::Adjusted).await.unwrap(), 0.0);
+    }
+
+    #[tokio::test]
+    async fn test_rule_pass_rate_series() {
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("trend", "test", None, None).await.unwrap();
+        // Tuesday, Wednesday, and the following Monday
+        for (day, passed) in [("2026-03-03", false), ("2026-03-04", true), ("2026-03-04", true), ("2026-03-09", true)] {
+            let score = db.create_score("trend", 1, 0, 0.0, "", None, None).await.unwrap();
+            sqlx::query("UPDATE scores SET scored_at = ?1 WHERE id = ?2")
+                .bind(format!("{}T10:30:00+00:00", day))
+                .bind(score.id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+            db.create_rule_check(score.id, "plan", "Plan", "", passed, 1.0, None, None)
+                .await
+                .unwrap();
+        }
+
+        let daily = db.get_rule_pass_rate_series("plan", TimeBucket::Day, Outcomes::Raw).await.unwrap();
+        let points: Vec<_> = daily
+            .iter()
+            .map(|p| (p.bucket_start.format("%Y-%m-%d").to_string(), p.total, p.pass_rate))
+            .collect();
+        assert_eq!(
+            points,
+            [
+                ("2026-03-03".to_string(), 1, 0.0),
+                ("2026-03-04".to_string(), 2, 100.0),
+                ("2026-03-09".to_string(), 1, 100.0)
+            ]
+        );
+
+        let weekly = db.get_rule_pass_rate_series("plan", TimeBucket::Week, Outcomes::Raw).await.unwrap();
+        let points: Vec<_> = weekly
+            .iter()
+            .map(|p| (p.bucket_start.format("%Y-%m-%d").to_string(), p.passed, p.total))
+            .collect();
+        assert_eq!(points, [("2026-03-02".to_string(), 2, 3), ("2026-03-09".to_string(), 1, 1)]);
+        assert!(db
+            .get_rule_pass_rate_series("missing", TimeBucket::Day, Outcomes::Raw)
+            .await
+            .unwrap()
+            .is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_rule_detail() {
+        let db = Database::new_in_memory().await.unwrap();
+        db.seed_rules(&crate::BehaviorScorer::new().config().rules).await.unwrap();
+        db.create_session("old", "test", None, None).await.unwrap();
+        db.create_session("new", "test", None, None).await.unwrap();
+        let mut checks = Vec::new();
+        for (session, passed, evidence) in [("old", false, "skipped plan"), ("old", true, "OBJECTIVE: a"), ("new", false, "ran first")] {
+            let score = db.create_score(session, 1, 0, 0.0, "", None, None).await.unwrap();
+            let check = db
+                .create_rule_check(score.id, "objective_before_execution", "Objective", "", passed, 0.8, Some(evidence), None)
+                .await
+                .unwrap();
+            checks.push(check);
+        }
+
+        let detail = db
+            .get_rule_detail("objective_before_execution", TimeBucket::Day, Outcomes::Raw, None)
+            .await
+            .unwrap();
+        assert_eq!(detail.rule.name, "Write objective before execution");
+        assert!((detail.pass_rate - 100.0 / 3.0).abs() < 1e-9);
+        assert_eq!(detail.trend.iter().map(|p| p.total).sum::<i64>(), 3);
+        let evidence: Vec<_> = detail.recent_failures.iter().map(|f| f.evidence.as_deref().unwrap()).collect();
+        assert_eq!(evidence, ["ran first", "skipped plan"]);
+        assert_eq!(detail.recent_failures[0].session_id, "new");
+
+        // Reviewer corrections and the trash both hide failures
+        db.set_rule_check_override(checks[2].id, OverrideVerdict::FalseNegative, "Objective was in the brief")
+            .await
+            .unwrap();
+        let adjusted = db.get_rule_failures("objective_before_execution", Outcomes::Adjusted, None).await.unwrap();
+        assert_eq!(adjusted.len(), 1);
+        db.delete_session("old").await.unwrap();
+        assert!(db.get_rule_failures("objective_before_execution", Outcomes::Adjusted, Some(5)).await.unwrap().is_empty());
+        assert!(matches!(
+            db.get_rule_detail("missing", TimeBucket::Day, Outcomes::Raw, None).await,
+            Err(DbError::NotFound(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_category_stats() {
+        let db = Database::new_in_memory().await.unwrap();
+        let scorer = crate::BehaviorScorer::new();
+        db.seed_rules(&scorer.config().rules).await.unwrap();
+        let score = scorer.score_session("categorized", "OBJECTIVE: ship it").unwrap();
+        let stored = db.save_session_score(&score, "test", None).await.unwrap();
+        // Checks of rules missing from the rules table aren't counted
+        db.create_rule_check(stored.id, "unstored", "Unstored", "", false, 1.0, None, None)
+            .await
+            .unwrap();
+
+        let stats = db.get_category_stats(Outcomes::Raw).await.unwrap();
+        assert_eq!(stats.iter().map(|c| c.total).sum::<i64>() as usize, score.rules.len());
+        for category in &stats {
+            let expected: Vec<_> = score
+                .rules
+                .iter()
+                .filter(|check| scorer.config().rules.iter().any(|r| r.id == check.rule_id && r.category == category.category))
+                .collect();
+            assert_eq!(category.total as usize, expected.len());
+            assert_eq!(category.failed as usize, expected.iter().filter(|c| !c.passed).count());
+        }
 
-        let poor: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM scores WHERE score_percentage < 50"
-        )
-        .fetch_one(&self.pool)
-        .await?;
+        // Reviewer overrides move checks between passed and failed
+        let failed = db
+            .get_score_rule_checks(stored.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .find(|c| !c.passed && c.rule_id != "unstored")
+            .unwrap();
+        db.set_rule_check_override(failed.id, OverrideVerdict::FalseNegative, "Reviewed")
+            .await
+            .unwrap();
+        let raw_failed: i64 = stats.iter().map(|c| c.failed).sum();
+        let adjusted = db.get_category_stats(Outcomes::Adjusted).await.unwrap();
+        assert_eq!(adjusted.iter().map(|c| c.failed).sum::<i64>(), raw_failed - 1);
+    }
 
-        Ok(ScoreDistribution {
-            excellent,
-            good,
-            moderate,
-            poor,
-        })
+    #[tokio::test]
+    async fn test_percentile_and_ranking() {
+        let db = Database::new_in_memory().await.unwrap();
+        for (id, percentage) in [("a", 40.0), ("b", 60.0), ("c", 80.0), ("d", 95.0)] {
+            db.create_session(id, "test", None, None).await.unwrap();
+            db.create_score(id, 10, 0, percentage, "", None, None).await.unwrap();
+        }
+        // Only the latest score of a session counts
+        db.create_score("a", 10, 0, 70.0, "", None, None).await.unwrap();
+
+        assert_eq!(db.get_session_percentile("d").await.unwrap(), 100.0);
+        assert_eq!(db.get_session_percentile("b").await.unwrap(), 25.0);
+        assert_eq!(db.get_session_percentile("a").await.unwrap(), 50.0);
+        assert!(matches!(db.get_session_percentile("missing").await, Err(DbError::NotFound(_))));
+
+        let top = db.get_ranked_sessions(Ranking::Top, 2, None).await.unwrap();
+        assert_eq!(top.iter().map(|s| s.session_id.as_str()).collect::<Vec<_>>(), ["d", "c"]);
+        let bottom = db.get_ranked_sessions(Ranking::Bottom, 2, None).await.unwrap();
+        assert_eq!(bottom.iter().map(|s| (s.session_id.as_str(), s.score_percentage)).collect::<Vec<_>>(), [("b", 60.0), ("a", 70.0)]);
+        let future = Some(Utc::now() + chrono::Duration::days(1));
+        assert!(db.get_ranked_sessions(Ranking::Top, 2, future).await.unwrap().is_empty());
     }
 
-    /// Get database statistics
-    pub async fn get_stats(&self) -> Result<DbStats, DbError> {
-        let sessions: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM sessions")
-            .fetch_one(&self.pool)
-            .await?;
+    #[tokio::test]
+    async fn test_backup_and_restore() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("live.db")).await.unwrap();
+        db.create_session("kept", "test", None, None).await.unwrap();
+        db.create_score("kept", 4, 3, 75.0, "", None, None).await.unwrap();
+
+        let snapshot = dir.path().join("snapshot.db");
+        db.backup_to(&snapshot).await.unwrap();
+        assert!(matches!(db.backup_to(&snapshot).await, Err(DbError::Validation(_))));
+
+        // Changes after the snapshot are rolled back by a restore, while the pool stays usable
+        db.delete_session("kept").await.unwrap();
+        db.create_session("later", "test", None, None).await.unwrap();
+        db.restore_from(&snapshot).await.unwrap();
+        let sessions = db.list_sessions(None, &ListFilters::default()).await.unwrap();
+        assert_eq!(sessions.iter().map(|s| s.id.as_str()).collect::<Vec<_>>(), ["kept"]);
+        assert_eq!(db.get_latest_score("kept").await.unwrap().score_percentage, 75.0);
+        assert_eq!(db.migration_version().await.unwrap(), MIGRATIONS.last().unwrap().version);
+
+        let bogus = dir.path().join("notes.db");
+        std::fs::write(&bogus, "not a database").unwrap();
+        assert!(matches!(db.restore_from(&bogus).await, Err(DbError::Validation(_))));
+        assert!(matches!(db.restore_from(dir.path().join("missing.db")).await, Err(DbError::NotFound(_))));
+        assert_eq!(db.list_sessions(None, &ListFilters::default()).await.unwrap().len(), 1);
+    }
 
-        let scores: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM scores")
-            .fetch_one(&self.pool)
-            .await?;
+    #[tokio::test]
+    async fn test_connection_settings() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("wal.db")).await.unwrap();
+        let mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(mode, "wal");
+        let timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(timeout, 5_000);
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(foreign_keys, 1);
+
+        let config = DbConfig {
+            max_connections: 2,
+            busy_timeout_ms: 250,
+            wal: false,
+            ..DbConfig::default()
+        };
+        let db = Database::with_config(dir.path().join("rollback.db"), &config).await.unwrap();
+        let mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(mode, "delete");
+        let timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(timeout, 250);
+        assert_eq!(db.pool.options().get_max_connections(), 2);
+
+        // Writers on separate connections wait for each other instead of failing
+        let writes = (0..8).map(|i| {
+            let db = db.clone();
+            tokio::spawn(async move { db.create_session(&format!("concurrent-{}", i), "test", None, None).await })
+        });
+        for write in writes.collect::<Vec<_>>() {
+            write.await.unwrap().unwrap();
+        }
+        db.pool.close().await;
+
+        // Opening without migrating leaves the journal mode alone
+        let inspect = DbConfig {
+            migrate: false,
+            ..DbConfig::default()
+        };
+        let db = Database::with_config(dir.path().join("rollback.db"), &inspect).await.unwrap();
+        let mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(mode, "delete");
+    }
 
-        let rule_checks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rule_checks")
-            .fetch_one(&self.pool)
-            .await?;
+    #[tokio::test]
+    async fn test_open_read_only() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("owned.db");
+        let missing = Database::open_read_only(dir.path().join("missing.db")).await;
+        assert!(matches!(missing, Err(DbError::NotFound(_))));
+        assert!(!dir.path().join("missing.db").exists());
+
+        let owner = Database::new(&path).await.unwrap();
+        owner.create_session("read-only", "test", None, None).await.unwrap();
+
+        // Reads see the owner's committed writes while the owner stays open
+        let reader = Database::open_read_only(&path).await.unwrap();
+        assert_eq!(reader.get_stats().await.unwrap().sessions, 1);
+        owner.create_session("read-only-2", "test", None, None).await.unwrap();
+        assert_eq!(reader.get_stats().await.unwrap().sessions, 2);
+        assert!(reader.create_session("blocked", "test", None, None).await.is_err());
+        assert_eq!(owner.get_stats().await.unwrap().sessions, 2);
+
+        let plain = dir.path().join("plain.db");
+        sqlx::query("CREATE TABLE other (id INTEGER)")
+            .execute(&Pool::<Sqlite>::connect(&format!("sqlite:{}?mode=rwc", plain.display())).await.unwrap())
+            .await
+            .unwrap();
+        let result = Database::open_read_only(&plain).await;
+        assert!(matches!(result, Err(DbError::Validation(_))));
+
+        // An older schema can still be inspected, just not queried
+        owner.rollback_to(15).await.unwrap();
+        let outdated = Database::open_read_only(&path).await;
+        assert!(matches!(outdated, Err(DbError::Validation(_))));
+        let inspected = Database::open_for_inspection(&path).await.unwrap();
+        assert!(inspected.health_check().await.unwrap().ok);
+    }
 
-        let avg_score: f64 = self.get_average_score().await?;
+    #[tokio::test]
+    async fn test_open_encrypted() {
+        use crate::keys::StaticKey;
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("sealed.db");
+        let empty = Database::open_encrypted(&path, &DbConfig::default(), &StaticKey::new("")).await;
+        assert!(matches!(empty, Err(DbError::Encryption(_))));
+
+        let opened = Database::open_encrypted(&path, &DbConfig::default(), &StaticKey::new("it's secret")).await;
+        if cfg!(feature = "sqlcipher") {
+            let db = opened.unwrap();
+            db.create_session("sealed", "test", None, None).await.unwrap();
+            db.pool.close().await;
+
+            let bytes = std::fs::read(&path).unwrap();
+            assert!(!bytes.starts_with(b"SQLite format 3"));
+            let wrong = Database::open_encrypted(&path, &DbConfig::default(), &StaticKey::new("guess")).await;
+            assert!(matches!(wrong, Err(DbError::Encryption(_))));
+            let db = Database::open_encrypted(&path, &DbConfig::default(), &StaticKey::new("it's secret"))
+                .await
+                .unwrap();
+            assert_eq!(db.get_stats().await.unwrap().sessions, 1);
+            drop(db);
+
+            let reader = Database::open_read_only_encrypted(&path, &StaticKey::new("it's secret")).await.unwrap();
+            assert_eq!(reader.get_stats().await.unwrap().sessions, 1);
+            let wrong = Database::open_read_only_encrypted(&path, &StaticKey::new("guess")).await;
+            assert!(matches!(wrong, Err(DbError::Encryption(_))));
+        } else {
+            // Without SQLCipher the key would be ignored, so opening must fail
+            assert!(matches!(opened, Err(DbError::Encryption(_))));
+        }
+        let empty = Database::open_read_only_encrypted(&path, &StaticKey::new("")).await;
+        assert!(matches!(empty, Err(DbError::Encryption(_))));
+    }
 
-        Ok(DbStats {
-            sessions,
-            scores,
-            rule_checks,
-            avg_score,
-        })
+    #[tokio::test]
+    async fn test_session_trash() {
+        let db = Database::new_in_memory().await.unwrap();
+        for (id, percentage) in [("kept", 90.0), ("trashed", 20.0)] {
+            db.create_session(id, "test", None, None).await.unwrap();
+            let score = db.create_score(id, 10, (percentage / 10.0) as i32, percentage, "Test", None, None).await.unwrap();
+            db.create_rule_check(score.id, "rule1", "Rule 1", "Desc", percentage > 50.0, 0.9, Some("evidence"), None)
+                .await
+                .unwrap();
+        }
+
+        // Purging requires the session to be trashed first
+        assert!(!db.purge_session("trashed").await.unwrap());
+        assert!(db.delete_session("trashed").await.unwrap());
+        assert!(!db.delete_session("trashed").await.unwrap());
+
+        let trashed = db.get_session("trashed").await.unwrap();
+        assert!(trashed.deleted_at.is_some());
+        let ids = |sessions: Vec<Session>| sessions.into_iter().map(|s| s.id).collect::<Vec<_>>();
+        assert_eq!(ids(db.list_sessions(None, &ListFilters::default()).await.unwrap()), ["kept"]);
+        assert_eq!(ids(db.list_trash(None).await.unwrap()), ["trashed"]);
+        assert_eq!(db.list_scores(None, &ListFilters::default()).await.unwrap().len(), 1);
+        assert_eq!(db.get_average_score(Outcomes::Raw).await.unwrap(), 90.0);
+        assert_eq!(db.get_rule_pass_rate("rule1", Outcomes::Raw).await.unwrap(), 100.0);
+        assert_eq!(db.get_session_percentile("kept").await.unwrap(), 100.0);
+        let stats = db.get_stats().await.unwrap();
+        assert_eq!((stats.sessions, stats.trashed_sessions, stats.scores, stats.rule_checks), (1, 1, 1, 1));
+
+        // Restoring brings back the session with its scores intact
+        assert!(db.restore_session("trashed").await.unwrap());
+        assert!(!db.restore_session("trashed").await.unwrap());
+        assert!(db.get_session("trashed").await.unwrap().deleted_at.is_none());
+        assert_eq!(db.get_session_scores("trashed").await.unwrap().len(), 1);
+        assert_eq!(db.get_average_score(Outcomes::Raw).await.unwrap(), 55.0);
+
+        // A new score leaves the session in the trash; only restoring takes it out
+        db.delete_session("trashed").await.unwrap();
+        let rescore = crate::BehaviorScorer::new().score_session("trashed", "OBJECTIVE: ship").unwrap();
+        db.save_session_score(&rescore, "test", None).await.unwrap();
+        assert!(db.get_session("trashed").await.unwrap().deleted_at.is_some());
+        assert_eq!(ids(db.list_sessions(None, &ListFilters::default()).await.unwrap()), ["kept"]);
+        assert_eq!(db.get_average_score(Outcomes::Raw).await.unwrap(), 90.0);
+
+        let cutoff = Utc::now() - chrono::Duration::hours(1);
+        assert_eq!(db.purge_trash(Some(cutoff)).await.unwrap(), 0);
+        assert_eq!(db.purge_trash(None).await.unwrap(), 1);
+        assert!(matches!(db.get_session("trashed").await, Err(DbError::NotFound(_))));
+        assert!(db.get_session_scores("trashed").await.unwrap().is_empty());
     }
-}
 
-/// Score distribution buckets
-#[derive(Debug, Clone, Serialize, Deserialize, Type)]
-pub struct ScoreDistribution {
-    pub excellent: i64, // >= 90%
-    pub good: i64,      // 75-89%
-    pub moderate: i64,  // 50-74%
-    pub poor: i64,      // < 50%
-}
+    #[tokio::test]
+    async fn test_create_rule_checks_batch() {
+        let db = Database::new_in_memory().await.unwrap();
+        db.seed_rules(&crate::BehaviorScorer::new().config().rules).await.unwrap();
+        db.create_session("batch", "test", None, None).await.unwrap();
+        let score = db.create_score("batch", 250, 125, 50.0, "Batch", None, None).await.unwrap();
+        assert!(db.create_rule_checks(score.id, &[]).await.unwrap().is_empty());
+
+        // More checks than one INSERT holds; only the first carries its definition
+        let seeded = db.list_rules().await.unwrap()[0].definition();
+        let checks: Vec<RuleCheck> = (0..250)
+            .map(|i| RuleCheck {
+                order: i,
+                rule_id: if i == 0 { seeded.id.clone() } else { format!("rule_{}", i) },
+                rule_name: format!("Rule {}", i),
+                description: "Batched".to_string(),
+                passed: i % 2 == 0,
+                confidence: 0.5,
+                evidence: (i % 3 == 0).then(|| format!("evidence {}", i)),
+                suggestion: None,
+                turn_index: None,
+                match_count: 0,
+                definition: (i == 0).then(|| seeded.clone()),
+            })
+            .collect();
+        let records = db.create_rule_checks(score.id, &checks).await.unwrap();
+        assert_eq!(records.len(), 250);
+        assert!(records[0].rule_version_id.is_some());
+        assert!(records[1].rule_version_id.is_none());
+
+        // Returned IDs line up with the stored rows
+        for (check, record) in checks.iter().zip(&records) {
+            let stored = db.get_rule_check(record.id).await.unwrap();
+            assert_eq!(stored.rule_id, check.rule_id);
+            assert_eq!(stored.passed, check.passed);
+            assert_eq!(stored.evidence, check.evidence);
+            assert_eq!(stored.rule_version_id, record.rule_version_id);
+        }
+        assert_eq!(db.get_score_rule_checks(score.id).await.unwrap().len(), 250);
+    }
 
-/// Database statistics
-#[derive(Debug, Clone, Serialize, Deserialize, Type)]
-pub struct DbStats {
-    pub sessions: i64,
-    pub scores: i64,
-    pub rule_checks: i64,
-    pub avg_score: f64,
-}
+    #[tokio::test]
+    async fn test_streaks() {
+        let db = Database::new_in_memory().await.unwrap();
+        assert_eq!(db.get_session_streak(STREAK_THRESHOLD).await.unwrap(), Streak::default());
+
+        // Sessions scored in order: pass, pass, pass, fail, pass, pass
+        let percentages = [80.0, 90.0, 100.0, 40.0, 75.0, 95.0];
+        for (i, percentage) in percentages.iter().enumerate() {
+            let id = format!("streak-{}", i);
+            db.create_session(&id, "test", None, None).await.unwrap();
+            let score = db.create_score(&id, 2, 1, *percentage, "Streak", None, None).await.unwrap();
+            sqlx::query("UPDATE scores SET scored_at = datetime('2026-01-01', ?1) WHERE id = ?2")
+                .bind(format!("+{} hours", i))
+                .bind(score.id)
+                .execute(&db.pool)
+                .await
+                .unwrap();
+            let check = db
+                .create_rule_check(score.id, "steady", "Steady", "", *percentage >= 75.0, 0.9, None, None)
+                .await
+                .unwrap();
+            db.create_rule_check(score.id, "flaky", "Flaky", "", i % 2 == 0, 0.9, None, None).await.unwrap();
+            if i == 3 {
+                db.set_rule_check_override(check.id, OverrideVerdict::FalseNegative, "Misread the transcript")
+                    .await
+                    .unwrap();
+            }
+        }
 
-// ============================================================================
-// SQLx Row Types (internal)
-// ============================================================================
+        let streak = db.get_session_streak(STREAK_THRESHOLD).await.unwrap();
+        assert_eq!(streak, Streak { current: 2, longest: 3 });
+        assert_eq!(db.get_session_streak(50.0).await.unwrap(), Streak { current: 2, longest: 3 });
+        assert_eq!(db.get_session_streak(30.0).await.unwrap(), Streak { current: 6, longest: 6 });
 
-#[derive(sqlx::FromRow)]
-struct SessionRow {
-    id: String,
-    created_at: chrono::NaiveDateTime,
-    updated_at: chrono::NaiveDateTime,
-    source: String,
-    transcript_path: Option<String>,
-    metadata: Option<String>,
-}
+        let raw = db.get_rule_streaks(Outcomes::Raw).await.unwrap();
+        assert_eq!(raw.iter().map(|r| r.rule_id.as_str()).collect::<Vec<_>>(), ["flaky", "steady"]);
+        assert_eq!(raw[0].streak, Streak { current: 0, longest: 1 });
+        assert_eq!(raw[1].streak, Streak { current: 2, longest: 3 });
+        let adjusted = db.get_rule_streaks(Outcomes::Adjusted).await.unwrap();
+        assert_eq!(adjusted[1].streak, Streak { current: 6, longest: 6 });
 
-impl From<SessionRow> for Session {
-    fn from(row: SessionRow) -> Self {
-        Self {
-            id: row.id,
-            created_at: DateTime::from_naive_utc_and_offset(row.created_at, Utc),
-            updated_at: DateTime::from_naive_utc_and_offset(row.updated_at, Utc),
-            source: row.source,
-            transcript_path: row.transcript_path,
-            metadata: row.metadata,
+        let stats = db.get_stats().await.unwrap();
+        assert_eq!(stats.session_streak, streak);
+        assert_eq!(stats.rule_streaks, raw);
+    }
+
+    #[tokio::test]
+    async fn test_rule_failure_leaderboard() {
+        let db = Database::new_in_memory().await.unwrap();
+        // (rule, passed) per session; the first session is scored well before the window
+        let sessions: [&[(&str, bool)]; 4] = [
+            &[("rarely", false), ("often", false), ("never", true)],
+            &[("rarely", true), ("often", false), ("never", true), ("sometimes", false)],
+            &[("rarely", false), ("often", false), ("never", true), ("sometimes", true)],
+            &[("rarely", true), ("often", false), ("never", true)],
+        ];
+        let mut overridden = None;
+        for (i, checks) in sessions.iter().enumerate() {
+            let id = format!("leaderboard-{}", i);
+            db.create_session(&id, "test", None, None).await.unwrap();
+            let score = db.create_score(&id, 3, 1, 33.0, "Leaderboard", None, None).await.unwrap();
+            if i == 0 {
+                sqlx::query("UPDATE scores SET scored_at = datetime('now', '-30 days') WHERE id = ?1")
+                    .bind(score.id)
+                    .execute(&db.pool)
+                    .await
+                    .unwrap();
+            }
+            for (rule_id, passed) in checks.iter() {
+                let check = db.create_rule_check(score.id, rule_id, rule_id, "", *passed, 0.9, None, None).await.unwrap();
+                if i == 3 && *rule_id == "often" {
+                    overridden = Some(check.id);
+                }
+            }
         }
+
+        let all = db.get_rule_failure_leaderboard(None, 10).await.unwrap();
+        let ids = |stats: &[RuleFailureStats]| stats.iter().map(|s| s.rule_id.clone()).collect::<Vec<_>>();
+        assert_eq!(ids(&all), ["often", "rarely", "sometimes"]);
+        assert_eq!((all[0].total, all[0].failed, all[0].failure_rate), (4, 4, 100.0));
+        assert_eq!(all[1].failure_rate, 50.0);
+
+        // Within the window "rarely" and "sometimes" tie on count; the higher rate wins
+        let since = Utc::now() - chrono::Duration::days(7);
+        let week = db.get_rule_failure_leaderboard(Some(since), 10).await.unwrap();
+        assert_eq!(ids(&week), ["often", "sometimes", "rarely"]);
+        assert_eq!(week[0].total, 3);
+        assert_eq!(ids(&db.get_rule_failure_leaderboard(Some(since), 1).await.unwrap()), ["often"]);
+
+        // A false-negative verdict takes the failure off the board
+        db.set_rule_check_override(overridden.unwrap(), OverrideVerdict::FalseNegative, "Was fine")
+            .await
+            .unwrap();
+        let week = db.get_rule_failure_leaderboard(Some(since), 10).await.unwrap();
+        assert_eq!(week[0].failed, 2);
+
+        let report = db.get_stats_report(2).await.unwrap();
+        assert_eq!(report.stats.scores, 4);
+        assert_eq!(report.distribution.poor, 4);
+        let rates = report.rule_pass_rates.iter().map(|r| (r.rule_id.as_str(), r.pass_rate)).collect::<Vec<_>>();
+        assert_eq!(rates, [("never", 100.0), ("often", 0.0), ("rarely", 50.0), ("sometimes", 50.0)]);
+        assert_eq!(ids(&report.most_failed), ["often", "rarely"]);
     }
-}
 
-#[derive(sqlx::FromRow)]
-struct ScoreRow {
-    id: i64,
-    session_id: String,
-    scored_at: chrono::NaiveDateTime,
-    total_rules: i32,
-    passed_rules: i32,
-    score_percentage: f64,
-    summary: String,
-}
+    #[tokio::test]
+    async fn test_health_check_and_optimize() {
+        let dir = tempfile::tempdir().unwrap();
+        let db = Database::new(dir.path().join("health.db")).await.unwrap();
+        db.create_session("healthy", "test", None, None).await.unwrap();
+        let score = db.create_score("healthy", 1, 1, 100.0, "Fine", None, None).await.unwrap();
+        db.create_transcript_stats(score.id, &TranscriptStats::default()).await.unwrap();
+
+        let health = db.health_check().await.unwrap();
+        assert!(health.ok);
+        assert!(health.integrity_errors.is_empty());
+        assert_eq!(health.size_bytes, health.page_size * health.page_count);
+        let rows = |health: &DbHealth, table: &str| health.tables.iter().find(|t| t.name == table).map(|t| t.rows);
+        assert_eq!(rows(&health, "sessions"), Some(1));
+        assert_eq!(rows(&health, "scores"), Some(1));
+        assert_eq!(rows(&health, "search_index"), Some(0));
+        assert!(rows(&health, "search_index_data").is_none());
+
+        // A score whose session vanished while foreign keys were off
+        let mut conn = db.pool.acquire().await.unwrap();
+        sqlx::query("PRAGMA foreign_keys = OFF").execute(&mut *conn).await.unwrap();
+        sqlx::query("DELETE FROM sessions WHERE id = 'healthy'").execute(&mut *conn).await.unwrap();
+        sqlx::query("PRAGMA foreign_keys = ON").execute(&mut *conn).await.unwrap();
+        drop(conn);
+        let health = db.health_check().await.unwrap();
+        assert!(!health.ok);
+        assert!(health
+            .foreign_key_violations
+            .iter()
+            .any(|v| v.table == "scores" && v.parent == "sessions" && v.rowid == Some(score.id)));
+
+        // Deleted pages are reclaimed
+        for i in 0..200 {
+            db.create_session(&format!("bulk-{}", i), "test", None, Some(&"x".repeat(2_000))).await.unwrap();
+        }
+        sqlx::query("DELETE FROM sessions WHERE id LIKE 'bulk-%'").execute(&db.pool).await.unwrap();
+        assert!(db.health_check().await.unwrap().freelist_count > 0);
+        assert!(db.optimize().await.unwrap() > 0);
+        assert_eq!(db.health_check().await.unwrap().freelist_count, 0);
+    }
 
-impl From<ScoreRow> for Score {
-    fn from(row: ScoreRow) -> Self {
-        Self {
-            id: row.id,
-            session_id: row.session_id,
-            scored_at: DateTime::from_naive_utc_and_offset(row.scored_at, Utc),
-            total_rules: row.total_rules,
-            passed_rules: row.passed_rules,
-            score_percentage: row.score_percentage,
-            summary: row.summary,
+    #[tokio::test]
+    async fn test_rollback_migrations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("rollback.db");
+        let db = Database::new(&path).await.unwrap();
+        db.seed_rules(&crate::BehaviorScorer::new().config().rules).await.unwrap();
+        db.create_session("kept", "test", None, None).await.unwrap();
+        let score = db.create_score("kept", 1, 0, 0.0, "Kept", Some("1.0"), None).await.unwrap();
+        let rule = db.list_rules().await.unwrap()[0].definition();
+        let check = RuleCheck {
+            order: 0,
+            rule_id: rule.id.clone(),
+            rule_name: "Rule".to_string(),
+            description: String::new(),
+            passed: false,
+            confidence: 0.9,
+            evidence: Some("evidence".to_string()),
+            suggestion: None,
+            turn_index: None,
+            match_count: 0,
+            definition: Some(rule),
+        };
+        let check = db.create_rule_checks(score.id, &[check]).await.unwrap().remove(0);
+        assert!(check.rule_version_id.is_some());
+
+        assert!(matches!(db.rollback_to(latest_schema_version() + 1).await, Err(DbError::Validation(_))));
+        assert!(matches!(db.rollback_to(-1).await, Err(DbError::Validation(_))));
+
+        // Back to before rule versions: their table and link column go, checks stay
+        db.rollback_to(13).await.unwrap();
+        assert_eq!(db.migration_version().await.unwrap(), 13);
+        let columns: Vec<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info('rule_checks')")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        assert!(!columns.contains(&"rule_version_id".to_string()));
+        let tables: Vec<String> = sqlx::query_scalar("SELECT name FROM sqlite_master WHERE type = 'table'")
+            .fetch_all(&db.pool)
+            .await
+            .unwrap();
+        for dropped in ["rule_versions", "search_index", "rule_check_overrides"] {
+            assert!(!tables.contains(&dropped.to_string()), "{} still exists", dropped);
         }
+        let checks: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM rule_checks").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(checks, 1);
+        let foreign_keys: i64 = sqlx::query_scalar("PRAGMA foreign_keys").fetch_one(&db.pool).await.unwrap();
+        assert_eq!(foreign_keys, 1);
+        db.pool.close().await;
+
+        // Reopening migrates forward again, keeping the surviving data
+        let db = Database::new(&path).await.unwrap();
+        assert_eq!(db.migration_version().await.unwrap(), MIGRATIONS.last().unwrap().version);
+        assert_eq!(db.get_rule_check(check.id).await.unwrap().rule_version_id, None);
+        assert_eq!(db.get_session_scores("kept").await.unwrap()[0].scorer_version.as_deref(), Some("1.0"));
+        assert!(db.health_check().await.unwrap().ok);
+
+        // All the way down leaves only the bookkeeping table
+        db.rollback_to(0).await.unwrap();
+        let tables: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table' AND name NOT LIKE 'sqlite_%'",
+        )
+        .fetch_all(&db.pool)
+        .await
+        .unwrap();
+        assert_eq!(tables, ["_migrations"]);
+        assert_eq!(db.migration_version().await.unwrap(), 0);
     }
-}
 
-#[derive(sqlx::FromRow)]
-struct RuleCheckRow {
-    id: i64,
-    score_id: i64,
-    rule_id: String,
-    rule_name: String,
-    description: String,
-    passed: bool,
-    confidence: f64,
-    evidence: Option<String>,
-    suggestion: Option<String>,
-}
+    #[tokio::test]
+    async fn test_pending_migrations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("pending.db");
+        let manual = DbConfig {
+            migrate: false,
+            ..DbConfig::default()
+        };
+
+        // A brand new file has every migration pending
+        let db = Database::with_config(&path, &manual).await.unwrap();
+        let pending = db.pending_migrations().await.unwrap();
+        assert_eq!(pending.len(), MIGRATIONS.len());
+        assert_eq!(pending[0].name, "create_sessions_table");
+        assert!(pending[0].sql.contains("CREATE TABLE IF NOT EXISTS sessions"));
+        db.pool.close().await;
+
+        let db = Database::new(&path).await.unwrap();
+        assert!(db.pending_migrations().await.unwrap().is_empty());
+        db.rollback_to(15).await.unwrap();
+        db.pool.close().await;
+
+        // Inspecting doesn't apply anything
+        let db = Database::with_config(&path, &manual).await.unwrap();
+        let versions = |pending: Vec<PendingMigration>| pending.into_iter().map(|m| m.version).collect::<Vec<_>>();
+        assert_eq!(versions(db.pending_migrations().await.unwrap()), [16, 17, 18, 19]);
+        assert_eq!(db.migration_version().await.unwrap(), 15);
+    }
 
-impl From<RuleCheckRow> for RuleCheckRecord {
-    fn from(row: RuleCheckRow) -> Self {
-        Self {
-            id: row.id,
-            score_id: row.score_id,
-            rule_id: row.rule_id,
-            rule_name: row.rule_name,
-            description: row.description,
-            passed: row.passed,
-            confidence: row.confidence,
-            evidence: row.evidence,
-            suggestion: row.suggestion,
+    #[tokio::test]
+    async fn test_metadata_queries() {
+        let db = Database::new_in_memory().await.unwrap();
+        let sessions = [
+            ("alpha", Some(r#"{"project":"dashboard","model":"opus","tags":["infra","release"],"ticket":42}"#)),
+            ("beta", Some(r#"{"project":"dashboard","tags":"infra","client":{"name":"acme"}}"#)),
+            ("gamma", Some(r#"{"project":"scorer","agent":"codex"}"#)),
+            ("delta", Some("not json")),
+            ("epsilon", None),
+        ];
+        for (id, metadata) in sessions {
+            db.create_session(id, "test", None, metadata).await.unwrap();
+        }
+
+        let ids = |sessions: Vec<Session>| {
+            let mut ids = sessions.into_iter().map(|s| s.id).collect::<Vec<_>>();
+            ids.sort();
+            ids
+        };
+        let find = |key: &'static str, value: &'static str| {
+            let db = db.clone();
+            async move { ids(db.find_sessions_by_metadata(key, value, None).await.unwrap()) }
+        };
+        assert_eq!(find("project", "dashboard").await, ["alpha", "beta"]);
+        assert_eq!(find("tags", "infra").await, ["alpha", "beta"]);
+        assert_eq!(find("tags", "release").await, ["alpha"]);
+        assert_eq!(find("ticket", "42").await, ["alpha"]);
+        assert_eq!(find("client.name", "acme").await, ["beta"]);
+        assert!(find("project", "missing").await.is_empty());
+        for key in ["", "project.", "$.project", "tags[0]", "a\"b"] {
+            assert!(matches!(
+                db.find_sessions_by_metadata(key, "x", None).await,
+                Err(DbError::Validation(_))
+            ));
         }
+
+        let values = db.list_metadata_values("project").await.unwrap();
+        assert_eq!(
+            values,
+            [
+                MetadataValueCount { value: "dashboard".to_string(), sessions: 2 },
+                MetadataValueCount { value: "scorer".to_string(), sessions: 1 },
+            ]
+        );
+        let tags = db.list_metadata_values("tags").await.unwrap();
+        assert_eq!(tags.iter().map(|t| (t.value.as_str(), t.sessions)).collect::<Vec<_>>(), [("infra", 2), ("release", 1)]);
+
+        // Trashed sessions drop out
+        db.delete_session("beta").await.unwrap();
+        assert_eq!(find("project", "dashboard").await, ["alpha"]);
+        assert_eq!(db.list_metadata_values("project").await.unwrap()[0].sessions, 1);
+
+        let metadata = db.get_session("alpha").await.unwrap().typed_metadata;
+        assert_eq!(metadata.project.as_deref(), Some("dashboard"));
+        assert_eq!(metadata.model.as_deref(), Some("opus"));
+        assert_eq!(metadata.tags, ["infra", "release"]);
+        assert_eq!(metadata.extra.get("ticket"), Some(&serde_json::json!(42)));
+        let beta = db.get_session("beta").await.unwrap().typed_metadata;
+        assert_eq!(beta.tags, ["infra"]);
+        assert_eq!(beta.extra.get("client"), Some(&serde_json::json!({"name": "acme"})));
+        assert_eq!(db.get_session("delta").await.unwrap().typed_metadata, SessionMetadata::default());
+        assert_eq!(db.get_session("epsilon").await.unwrap().typed_metadata, SessionMetadata::default());
     }
-}
 
-// ============================================================================
-// Tests
-// ============================================================================
+    #[tokio::test]
+    async fn test_identical_scores_are_not_duplicated() {
+        let db = Database::new_in_memory().await.unwrap();
+        let scorer = crate::BehaviorScorer::new();
+        db.seed_rules(&scorer.config().rules).await.unwrap();
+        let score = scorer.score_session("rescanned", "OBJECTIVE: ship").unwrap();
+
+        let first = db.save_session_score(&score, "test", None).await.unwrap();
+        let again = db.save_session_score(&score, "test", None).await.unwrap();
+        assert_eq!(again.id, first.id);
+        assert!(again.scored_at >= first.scored_at);
+        assert_eq!(db.get_session_scores("rescanned").await.unwrap().len(), 1);
+        assert_eq!(db.get_latest_score("rescanned").await.unwrap().scored_at, again.scored_at);
+
+        // A different result, even on the same transcript, is a new score
+        let mut changed = score.clone();
+        changed.rules[0].evidence = Some("different".to_string());
+        let third = db.save_session_score(&changed, "test", None).await.unwrap();
+        assert_ne!(third.id, first.id);
+
+        // Returning to an earlier result only dedupes against the latest score
+        let fourth = db.save_session_score(&score, "test", None).await.unwrap();
+        assert_ne!(fourth.id, first.id);
+        assert_eq!(db.get_session_scores("rescanned").await.unwrap().len(), 3);
+
+        let dir = tempfile::tempdir().unwrap();
+        let config = DbConfig {
+            dedupe_scores: false,
+            ..DbConfig::default()
+        };
+        let keep_all = Database::with_config(dir.path().join("keep.db"), &config).await.unwrap();
+        keep_all.save_session_score(&score, "test", None).await.unwrap();
+        keep_all.save_session_score(&score, "test", None).await.unwrap();
+        assert_eq!(keep_all.get_session_scores("rescanned").await.unwrap().len(), 2);
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    #[tokio::test]
+    async fn test_source_stats() {
+        let db = Database::new_in_memory().await.unwrap();
+        for (id, source) in [("c1", "codex"), ("c2", "codex"), ("cc1", "claude-code"), ("m1", "manual"), ("gone", "manual")] {
+            db.create_session(id, source, None, None).await.unwrap();
+        }
+        db.create_score("c1", 4, 4, 100.0, "", None, None).await.unwrap();
+        db.create_score("c1", 4, 2, 50.0, "", None, None).await.unwrap();
+        db.create_score("c2", 4, 3, 75.0, "", None, None).await.unwrap();
+        let reviewed = db.create_score("cc1", 4, 1, 25.0, "", None, None).await.unwrap();
+        db.create_score("gone", 4, 0, 0.0, "", None, None).await.unwrap();
+        db.delete_session("gone").await.unwrap();
+
+        let stats = db.get_source_stats(Outcomes::Raw).await.unwrap();
+        let rows: Vec<_> = stats.iter().map(|s| (s.source.as_str(), s.sessions, s.scores, s.avg_score)).collect();
+        assert_eq!(rows, [("codex", 2, 3, 75.0), ("claude-code", 1, 1, 25.0), ("manual", 1, 0, 0.0)]);
+        assert_eq!(db.get_stats().await.unwrap().sources, stats);
+
+        // Overrides move the adjusted average of the affected source only
+        let failed = db.create_rule_check(reviewed.id, "rule", "Rule", "", false, 0.9, None, None).await.unwrap();
+        db.set_rule_check_override(failed.id, OverrideVerdict::FalseNegative, "Reviewed").await.unwrap();
+        let adjusted = db.get_source_stats(Outcomes::Adjusted).await.unwrap();
+        assert_eq!(adjusted[0], stats[0]);
+        assert_eq!(adjusted[1].avg_score, 100.0);
+    }
 
     #[tokio::test]
-    async fn test_database_creation() {
+    async fn test_compare_sessions() {
         let db = Database::new_in_memory().await.unwrap();
-        let version = db.migration_version().await.unwrap();
-        assert_eq!(version, 4);
+        for id in ["before", "after"] {
+            db.create_session(id, "test", None, None).await.unwrap();
+        }
+        let before = db.create_score("before", 3, 1, 33.3, "", None, None).await.unwrap();
+        db.create_rule_check(before.id, "objective", "Objective", "", false, 0.9, None, None).await.unwrap();
+        db.create_rule_check(before.id, "approval", "Approval", "", true, 0.8, Some("approval"), None).await.unwrap();
+        db.create_rule_check(before.id, "retired", "Retired", "", false, 0.5, None, None).await.unwrap();
+        let after = db.create_score("after", 3, 3, 100.0, "", None, None).await.unwrap();
+        db.create_rule_check(after.id, "objective", "Objective", "", true, 0.9, Some("OBJECTIVE: ship"), None).await.unwrap();
+        db.create_rule_check(after.id, "approval", "Approval", "", true, 0.8, Some("approval"), None).await.unwrap();
+        db.create_rule_check(after.id, "binary", "Binary", "", true, 0.7, Some("Y/N"), None).await.unwrap();
+
+        let comparison = db.compare_sessions("before", "after").await.unwrap();
+        assert_eq!((comparison.a.id, comparison.b.id), (before.id, after.id));
+        assert!((comparison.percentage_delta - 66.7).abs() < 1e-9);
+        assert_eq!(comparison.unchanged, 1);
+        let differing: Vec<_> = comparison
+            .differing
+            .iter()
+            .map(|diff| (diff.rule_id.as_str(), diff.a.as_ref().map(|a| a.passed), diff.b.as_ref().map(|b| b.passed)))
+            .collect();
+        assert_eq!(
+            differing,
+            [("binary", None, Some(true)), ("objective", Some(false), Some(true)), ("retired", Some(false), None)]
+        );
+        assert_eq!(comparison.differing[1].b.as_ref().unwrap().evidence.as_deref(), Some("OBJECTIVE: ship"));
+
+        assert!(matches!(db.compare_sessions("before", "missing").await, Err(DbError::NotFound(_))));
     }
 
     #[tokio::test]
-    async fn test_session_crud() {
+    async fn test_content_hash_links_duplicates() {
         let db = Database::new_in_memory().await.unwrap();
 
-        // Create
-        let session = db
-            .create_session("test-session", "test", Some("/path/to/transcript.md"), None)
-            .await
-            .unwrap();
-        assert_eq!(session.id, "test-session");
-        assert_eq!(session.source, "test");
+        for id in ["original", "synced-copy", "other"] {
+            db.create_session(id, "test", None, None).await.unwrap();
+        }
 
-        // Read
-        let fetched = db.get_session("test-session").await.unwrap();
-        assert_eq!(fetched.id, "test-session");
+        assert_eq!(db.record_content_hash("original", "abc").await.unwrap(), None);
+        assert_eq!(
+            db.record_content_hash("synced-copy", "abc").await.unwrap().as_deref(),
+            Some("original")
+        );
+        assert_eq!(db.record_content_hash("other", "def").await.unwrap(), None);
 
-        // List
-        let sessions = db.list_sessions(None).await.unwrap();
-        assert_eq!(sessions.len(), 1);
+        let copies = db.find_sessions_by_content_hash("abc").await.unwrap();
+        assert_eq!(copies.len(), 2);
+        assert_eq!(copies[1].duplicate_of.as_deref(), Some("original"));
+    }
 
-        // Update
-        let updated = db
-            .update_session("test-session", None, Some("{\"key\": \"value\"}"))
+    #[tokio::test]
+    async fn test_session_id_mappings() {
+        let db = Database::new_in_memory().await.unwrap();
+
+        assert!(db.get_session_id_mapping("/logs/a b.md").await.unwrap().is_none());
+        db.save_session_id_mapping("/logs/a b.md", "a-b", SessionIdStrategy::FileName)
+            .await
+            .unwrap();
+        db.save_session_id_mapping("/logs/a b.md", "abc123", SessionIdStrategy::ContentHash)
             .await
             .unwrap();
-        assert!(updated.metadata.is_some());
 
-        // Delete
-        let deleted = db.delete_session("test-session").await.unwrap();
-        assert!(deleted);
+        let mapping = db.get_session_id_mapping("/logs/a b.md").await.unwrap().unwrap();
+        assert_eq!(mapping.session_id, "abc123");
+        assert_eq!(mapping.strategy, "content_hash");
+        assert_eq!(db.get_session_sources("abc123").await.unwrap().len(), 1);
+        assert!(db.get_session_sources("a-b").await.unwrap().is_empty());
+
+        for (source, id) in [("/logs/multi.jsonl", "m-1"), ("/logs/multi.jsonl#1", "m-2"), ("/logs/multi.jsonl2", "other")] {
+            db.save_session_id_mapping(source, id, SessionIdStrategy::FileName).await.unwrap();
+        }
+        assert_eq!(db.remove_session_id_mappings("/logs/multi.jsonl").await.unwrap(), 2);
+        assert!(db.get_session_id_mapping("/logs/multi.jsonl#1").await.unwrap().is_none());
+        assert!(db.get_session_id_mapping("/logs/multi.jsonl2").await.unwrap().is_some());
     }
 
     #[tokio::test]
@@ -856,7 +5824,7 @@ mod tests {
 
         // Create score
         let score = db
-            .create_score("test-session", 10, 8, 80.0, "Good score")
+            .create_score("test-session", 10, 8, 80.0, "Good score", None, None)
             .await
             .unwrap();
         assert_eq!(score.session_id, "test-session");
@@ -872,6 +5840,70 @@ mod tests {
         assert_eq!(scores.len(), 1);
     }
 
+    #[tokio::test]
+    async fn test_score_version_stamp() {
+        let db = Database::new_in_memory().await.unwrap();
+
+        db.create_session("test-session", "test", None, None)
+            .await
+            .unwrap();
+        let score = db
+            .create_score("test-session", 8, 6, 75.0, "Good", Some("0.1.0"), Some("abc123"))
+            .await
+            .unwrap();
+
+        let fetched = db.get_score(score.id).await.unwrap();
+        assert_eq!(fetched.scorer_version.as_deref(), Some("0.1.0"));
+        assert_eq!(fetched.rule_set_hash.as_deref(), Some("abc123"));
+    }
+
+    #[tokio::test]
+    async fn test_transcript_stats_roundtrip() {
+        let db = Database::new_in_memory().await.unwrap();
+
+        db.create_session("test-session", "test", None, None)
+            .await
+            .unwrap();
+        let score = db
+            .create_score("test-session", 10, 8, 80.0, "Good", None, None)
+            .await
+            .unwrap();
+        let stats = TranscriptStats {
+            byte_length: 1024,
+            line_count: 40,
+            turn_count: 6,
+            estimated_tokens: 256,
+        };
+
+        db.create_transcript_stats(score.id, &stats).await.unwrap();
+        assert_eq!(db.get_transcript_stats(score.id).await.unwrap(), stats);
+    }
+
+    #[tokio::test]
+    async fn test_transcript_roundtrip() {
+        let db = Database::new_in_memory().await.unwrap();
+
+        db.create_session("test-session", "test", None, None)
+            .await
+            .unwrap();
+        let transcript = crate::transcript::parse_jsonl(
+            r#"{"role":"user","content":"hi","timestamp":"2026-02-15T09:00:00Z"}
+{"role":"assistant","content":"OBJECTIVE: greet","tool_calls":[{"function":{"name":"wave","arguments":"{}"}}]}"#,
+        )
+        .unwrap();
+
+        db.save_transcript("test-session", &transcript).await.unwrap();
+        assert_eq!(db.get_transcript("test-session").await.unwrap(), transcript);
+
+        // Cascades with the session once purged
+        db.delete_session("test-session").await.unwrap();
+        db.purge_session("test-session").await.unwrap();
+        assert!(matches!(
+            db.get_transcript("test-session").await,
+            Err(DbError::NotFound(_))
+        ));
+    }
+
     #[tokio::test]
     async fn test_rule_check_crud() {
         let db = Database::new_in_memory().await.unwrap();
@@ -881,7 +5913,7 @@ mod tests {
             .await
             .unwrap();
         let score = db
-            .create_score("test-session", 10, 8, 80.0, "Good")
+            .create_score("test-session", 10, 8, 80.0, "Good", None, None)
             .await
             .unwrap();
 
@@ -920,15 +5952,16 @@ mod tests {
             .await
             .unwrap();
         let score = db
-            .create_score("test-session", 10, 8, 80.0, "Good")
+            .create_score("test-session", 10, 8, 80.0, "Good", None, None)
             .await
             .unwrap();
         db.create_rule_check(score.id, "rule-1", "Test", "Desc", true, 1.0, None, None)
             .await
             .unwrap();
 
-        // Delete session should cascade
+        // Purging a trashed session should cascade
         db.delete_session("test-session").await.unwrap();
+        db.purge_session("test-session").await.unwrap();
 
         // Verify cascade
         let scores = db.get_session_scores("test-session").await.unwrap();
@@ -946,10 +5979,10 @@ mod tests {
         db.create_session("session-2", "test", None, None)
             .await
             .unwrap();
-        db.create_score("session-1", 10, 9, 90.0, "Excellent")
+        db.create_score("session-1", 10, 9, 90.0, "Excellent", None, None)
             .await
             .unwrap();
-        db.create_score("session-2", 10, 7, 70.0, "Good")
+        db.create_score("session-2", 10, 7, 70.0, "Good", None, None)
             .await
             .unwrap();
 
@@ -960,7 +5993,7 @@ mod tests {
         assert_eq!(stats.avg_score, 80.0);
 
         // Distribution
-        let dist = db.get_score_distribution().await.unwrap();
+        let dist = db.get_score_distribution(Outcomes::Raw).await.unwrap();
         assert_eq!(dist.excellent, 1);
         assert_eq!(dist.good, 0);
         assert_eq!(dist.moderate, 1);
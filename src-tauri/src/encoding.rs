@@ -0,0 +1,101 @@
+//! Text encoding detection for transcripts that aren't UTF-8. Instead of dropping
+//! such files, they are decoded (lossily if need be) and a warning is recorded.
+
+/// Decoded transcript text
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub text: String,
+    /// Set when the input was not UTF-8 and had to be converted
+    pub warning: Option<String>,
+}
+
+impl Decoded {
+    fn utf8(text: String) -> Self {
+        Self { text, warning: None }
+    }
+}
+
+/// UTF-16 byte order
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Utf16 {
+    Le,
+    Be,
+}
+
+/// Decode transcript bytes: UTF-8 (with or without BOM), UTF-16 (BOM or mostly-ASCII
+/// text with interleaved NULs), and Latin-1 for anything else
+pub fn decode(bytes: Vec<u8>) -> Decoded {
+    if let Some(rest) = bytes.strip_prefix(b"\xEF\xBB\xBF") {
+        return match std::str::from_utf8(rest) {
+            Ok(text) => Decoded::utf8(text.to_string()),
+            Err(_) => latin1(rest),
+        };
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFF\xFE") {
+        return utf16(rest, Utf16::Le);
+    }
+    if let Some(rest) = bytes.strip_prefix(b"\xFE\xFF") {
+        return utf16(rest, Utf16::Be);
+    }
+    if let Some(order) = sniff_utf16(&bytes) {
+        return utf16(&bytes, order);
+    }
+    
+    match String::from_utf8(bytes) {
+        Ok(text) => Decoded::utf8(text),
+        Err(e) => latin1(e.as_bytes()),
+    }
+}
+
+/// Guess BOM-less UTF-16 from NUL bytes concentrated in one half of each code unit
+fn sniff_utf16(bytes: &[u8]) -> Option<Utf16> {
+    if bytes.len() < 4 || bytes.len() % 2 != 0 {
+        return None;
+    }
+    let units = bytes.len() / 2;
+    let even_nuls = bytes.iter().step_by(2).filter(|b| **b == 0).count();
+    let odd_nuls = bytes.iter().skip(1).step_by(2).filter(|b| **b == 0).count();
+    
+    if odd_nuls * 10 >= units * 3 && even_nuls * 10 < units {
+        Some(Utf16::Le)
+    } else if even_nuls * 10 >= units * 3 && odd_nuls * 10 < units {
+        Some(Utf16::Be)
+    } else {
+        None
+    }
+}
+
+fn utf16(bytes: &[u8], order: Utf16) -> Decoded {
+    let units: Vec<u16> = bytes
+        .chunks(2)
+        .map(|pair| {
+            let pair = [pair[0], pair.get(1).copied().unwrap_or(0)];
+            match order {
+                Utf16::Le => u16::from_le_bytes(pair),
+                Utf16::Be => u16::from_be_bytes(pair),
+            }
+        })
+        .collect();
+    let name = match order {
+        Utf16::Le => "UTF-16LE",
+        Utf16::Be => "UTF-16BE",
+    };
+    
+    let (text, lossy) = match String::from_utf16(&units) {
+        Ok(text) => (text, false),
+        Err(_) => (String::from_utf16_lossy(&units), true),
+    };
+    let warning = if lossy || bytes.len() % 2 != 0 {
+        format!("Transcript decoded from {} with invalid sequences replaced", name)
+    } else {
+        format!("Transcript decoded from {}", name)
+    };
+    Decoded { text, warning: Some(warning) }
+}
+
+fn latin1(bytes: &[u8]) -> Decoded {
+    Decoded {
+        text: bytes.iter().map(|b| char::from(*b)).collect(),
+        warning: Some("Transcript is not valid UTF-8; decoded as Latin-1".to_string()),
+    }
+}
@@ -0,0 +1,133 @@
+//! Errors returned across the command boundary. Each carries a stable code the
+//! frontend can branch on, alongside the human-readable message.
+
+use crate::db::DbError;
+use crate::RuleIssue;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// Kind of failure, stable across releases
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    /// An argument was rejected, e.g. a malformed session ID or path outside the allowed roots
+    InvalidInput,
+    /// A rule definition failed validation; see `issues`
+    InvalidRule,
+    NotFound,
+    /// The database is locked by another writer; retrying later may succeed
+    Busy,
+    /// The database couldn't be opened, migrated, or queried
+    Database,
+    /// The database key is missing or wrong
+    Encryption,
+    /// Any other failure
+    Failed,
+}
+
+/// Error returned by every command
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct CommandError {
+    pub code: ErrorCode,
+    pub message: String,
+    /// What was being worked on, e.g. a session ID or file path
+    pub context: Option<String>,
+    /// Per-field problems, for `ErrorCode::InvalidRule`
+    pub issues: Vec<RuleIssue>,
+}
+
+impl CommandError {
+    pub fn new(code: ErrorCode, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+            context: None,
+            issues: Vec::new(),
+        }
+    }
+
+    pub fn invalid_input(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::InvalidInput, message)
+    }
+
+    pub fn not_found(message: impl Into<String>) -> Self {
+        Self::new(ErrorCode::NotFound, message)
+    }
+
+    /// Note what the failing command was working on
+    pub fn with_context(mut self, context: impl Into<String>) -> Self {
+        self.context = Some(context.into());
+        self
+    }
+}
+
+impl std::fmt::Display for CommandError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.context {
+            Some(context) => write!(f, "{} ({})", self.message, context),
+            None => f.write_str(&self.message),
+        }
+    }
+}
+
+impl std::error::Error for CommandError {}
+
+impl From<DbError> for CommandError {
+    fn from(err: DbError) -> Self {
+        let code = match &err {
+            DbError::Validation(_) => ErrorCode::InvalidInput,
+            DbError::NotFound(_) => ErrorCode::NotFound,
+            DbError::Busy(_) => ErrorCode::Busy,
+            DbError::Encryption(_) => ErrorCode::Encryption,
+            DbError::Connection(_) | DbError::Migration(_) | DbError::Query(_) | DbError::Export(_) => ErrorCode::Database,
+        };
+        Self::new(code, err.to_string())
+    }
+}
+
+impl From<Vec<RuleIssue>> for CommandError {
+    fn from(issues: Vec<RuleIssue>) -> Self {
+        Self {
+            issues,
+            ..Self::new(ErrorCode::InvalidRule, "Invalid rule")
+        }
+    }
+}
+
+/// A path rejected by the scorer's base path checks, reported as invalid input
+#[derive(Debug, Clone, PartialEq)]
+pub struct InvalidPath(pub String);
+
+impl std::fmt::Display for InvalidPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl std::error::Error for InvalidPath {}
+
+/// Lets scorer functions that report plain messages pass a rejected path along
+impl From<InvalidPath> for String {
+    fn from(err: InvalidPath) -> Self {
+        err.0
+    }
+}
+
+impl From<InvalidPath> for CommandError {
+    fn from(err: InvalidPath) -> Self {
+        Self::invalid_input(err.0)
+    }
+}
+
+/// Errors from the scorer and other modules that report plain messages
+impl From<String> for CommandError {
+    fn from(message: String) -> Self {
+        Self::new(ErrorCode::Failed, message)
+    }
+}
+
+impl<T> From<std::sync::PoisonError<T>> for CommandError {
+    fn from(err: std::sync::PoisonError<T>) -> Self {
+        Self::new(ErrorCode::Failed, err.to_string())
+    }
+}
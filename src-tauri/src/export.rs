@@ -0,0 +1,559 @@
+//! Exports of stored history for use outside the app, as CSV for spreadsheets or
+//! Parquet for DuckDB and pandas. Both take the same `ListFilters` as the list
+//! queries, so a dashboard view can be exported as shown. JSON Lines exports carry
+//! the full history and can be imported into another database. Session reports
+//! are scorecards for sharing, as JSON, CSV, or a self-contained HTML page. Fresh
+//! scan results can be written as CSV too, one row per session.
+
+use crate::db::{
+    self, Database, DbError, ListFilters, RuleCheckOverride, RuleCheckRecord, RuleVersion, Score, Session, StoredRule,
+};
+use crate::{RuleCategory, RuleDefinition, SessionScore, Transcript, TranscriptStats};
+use arrow_array::{
+    ArrayRef, BooleanArray, Float64Array, Int32Array, Int64Array, RecordBatch, StringArray, TimestampMicrosecondArray,
+};
+use arrow_schema::{ArrowError, DataType, Field, Schema, TimeUnit};
+use chrono::{DateTime, Utc};
+use parquet::arrow::ArrowWriter;
+use parquet::basic::Compression;
+use parquet::file::properties::WriterProperties;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::Path;
+use std::sync::Arc;
+
+/// One line of a JSON Lines history export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HistoryRecord {
+    RuleVersion(RuleVersion),
+    Rule(StoredRule),
+    Session {
+        session: Box<Session>,
+        transcript: Option<Transcript>,
+    },
+    Score {
+        score: Score,
+        rule_checks: Vec<RuleCheckRecord>,
+        transcript_stats: Option<TranscriptStats>,
+        #[serde(default)]
+        overrides: Vec<RuleCheckOverride>,
+    },
+}
+
+/// What a history import added
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct HistoryImportReport {
+    pub rules: usize,
+    pub sessions: usize,
+    pub scores: usize,
+    /// Scores already present in the database
+    pub skipped_scores: usize,
+}
+
+/// `LIMIT` value SQLite treats as unbounded
+const NO_LIMIT: i64 = -1;
+
+/// Table to export
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ExportKind {
+    Sessions,
+    Scores,
+    RuleChecks,
+}
+
+impl ExportKind {
+    /// Parse a CLI-style name such as `rule-checks`
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().replace('-', "_").as_str() {
+            "sessions" => Some(ExportKind::Sessions),
+            "scores" => Some(ExportKind::Scores),
+            "rule_checks" => Some(ExportKind::RuleChecks),
+            _ => None,
+        }
+    }
+}
+
+/// File format of a session report
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum ReportFormat {
+    Json,
+    Csv,
+    Html,
+}
+
+impl ReportFormat {
+    /// File extension for reports in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            Self::Json => "json",
+            Self::Csv => "csv",
+            Self::Html => "html",
+        }
+    }
+}
+
+/// A session's latest score and its rule checks, as written to a report
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SessionReport {
+    pub session: Session,
+    pub score: Score,
+    pub rule_checks: Vec<RuleCheckRecord>,
+}
+
+impl Database {
+    /// Write a scorecard for each session's latest score, in the order given, returning
+    /// the number of sessions written. Sessions that don't exist or were never scored
+    /// fail the whole report.
+    pub async fn export_report(&self, session_ids: &[String], format: ReportFormat, path: impl AsRef<Path>) -> Result<usize, DbError> {
+        if session_ids.is_empty() {
+            return Err(DbError::Validation("Choose at least one session to report on".to_string()));
+        }
+        let mut reports = Vec::with_capacity(session_ids.len());
+        for id in session_ids {
+            let session = self.get_session(id).await?;
+            let score = self.get_latest_score(id).await.map_err(|e| match e {
+                DbError::NotFound(_) => DbError::NotFound(format!("Session {} has no scores", id)),
+                e => e,
+            })?;
+            let rule_checks = self.get_score_rule_checks(score.id).await?;
+            reports.push(SessionReport { session, score, rule_checks });
+        }
+
+        let file = std::fs::File::create(path.as_ref()).map_err(export_error)?;
+        let mut writer = BufWriter::new(file);
+        match format {
+            ReportFormat::Json => serde_json::to_writer_pretty(&mut writer, &reports).map_err(export_error)?,
+            ReportFormat::Csv => {
+                let mut csv = csv::Writer::from_writer(&mut writer);
+                csv.write_record([
+                    "session_id", "source", "scored_at", "score_percentage", "passed_rules", "total_rules", "rule_id",
+                    "rule_name", "passed", "confidence", "evidence", "suggestion",
+                ])
+                .map_err(export_error)?;
+                for report in &reports {
+                    for check in &report.rule_checks {
+                        csv.write_record([
+                            report.session.id.clone(),
+                            report.session.source.clone(),
+                            timestamp(&report.score.scored_at),
+                            report.score.score_percentage.to_string(),
+                            report.score.passed_rules.to_string(),
+                            report.score.total_rules.to_string(),
+                            check.rule_id.clone(),
+                            check.rule_name.clone(),
+                            check.passed.to_string(),
+                            check.confidence.to_string(),
+                            check.evidence.clone().unwrap_or_default(),
+                            check.suggestion.clone().unwrap_or_default(),
+                        ])
+                        .map_err(export_error)?;
+                    }
+                }
+                csv.flush().map_err(export_error)?;
+            }
+            ReportFormat::Html => writer.write_all(report_html(&reports).as_bytes()).map_err(export_error)?,
+        }
+        writer.flush().map_err(export_error)?;
+        Ok(reports.len())
+    }
+
+    /// Write the sessions, scores, or rule checks matching `filters` to a CSV file,
+    /// returning the number of rows written. Rule checks are those of the matching scores.
+    pub async fn export_csv(&self, kind: ExportKind, path: impl AsRef<Path>, filters: &ListFilters) -> Result<usize, DbError> {
+        let mut writer = csv::Writer::from_path(path.as_ref()).map_err(export_error)?;
+        let mut rows = 0;
+
+        match kind {
+            ExportKind::Sessions => {
+                writer
+                    .write_record([
+                        "id", "created_at", "updated_at", "source", "transcript_path", "model", "started_at",
+                        "ended_at", "duration_seconds", "content_hash", "duplicate_of", "warnings", "metadata",
+                    ])
+                    .map_err(export_error)?;
+                for session in self.list_sessions(Some(NO_LIMIT), filters).await? {
+                    writer.write_record(session_record(&session)).map_err(export_error)?;
+                    rows += 1;
+                }
+            }
+            ExportKind::Scores => {
+                writer
+                    .write_record([
+                        "id", "session_id", "scored_at", "total_rules", "passed_rules", "score_percentage", "summary",
+                        "scorer_version", "rule_set_hash",
+                    ])
+                    .map_err(export_error)?;
+                for score in self.list_scores(Some(NO_LIMIT), filters).await? {
+                    writer.write_record(score_record(&score)).map_err(export_error)?;
+                    rows += 1;
+                }
+            }
+            ExportKind::RuleChecks => {
+                writer
+                    .write_record([
+                        "id", "score_id", "session_id", "scored_at", "rule_id", "rule_name", "passed", "confidence",
+                        "evidence", "suggestion", "rule_version_id",
+                    ])
+                    .map_err(export_error)?;
+                for (score, check) in self.filtered_rule_checks(filters).await? {
+                    writer
+                        .write_record([
+                            check.id.to_string(),
+                            check.score_id.to_string(),
+                            score.session_id,
+                            timestamp(&score.scored_at),
+                            check.rule_id,
+                            check.rule_name,
+                            check.passed.to_string(),
+                            check.confidence.to_string(),
+                            check.evidence.unwrap_or_default(),
+                            check.suggestion.unwrap_or_default(),
+                            optional(check.rule_version_id),
+                        ])
+                        .map_err(export_error)?;
+                    rows += 1;
+                }
+            }
+        }
+
+        writer.flush().map_err(export_error)?;
+        Ok(rows)
+    }
+
+    /// Write the scores or rule checks matching `filters` to a Snappy-compressed
+    /// Parquet file, returning the number of rows written
+    pub async fn export_parquet(&self, kind: ExportKind, path: impl AsRef<Path>, filters: &ListFilters) -> Result<usize, DbError> {
+        let batch = match kind {
+            ExportKind::Scores => scores_batch(&self.list_scores(Some(NO_LIMIT), filters).await?),
+            ExportKind::RuleChecks => rule_checks_batch(&self.filtered_rule_checks(filters).await?),
+            ExportKind::Sessions => {
+                return Err(DbError::Validation("Parquet export supports scores and rule checks".to_string()))
+            }
+        }
+        .map_err(export_error)?;
+
+        let file = std::fs::File::create(path.as_ref()).map_err(export_error)?;
+        let properties = WriterProperties::builder().set_compression(Compression::SNAPPY).build();
+        let mut writer = ArrowWriter::try_new(file, batch.schema(), Some(properties)).map_err(export_error)?;
+        writer.write(&batch).map_err(export_error)?;
+        writer.close().map_err(export_error)?;
+        Ok(batch.num_rows())
+    }
+
+    /// Write the whole history as JSON Lines: rule versions, rules, sessions with
+    /// their transcripts, then scores with their checks. Returns the records written.
+    pub async fn export_jsonl(&self, path: impl AsRef<Path>) -> Result<usize, DbError> {
+        let file = std::fs::File::create(path.as_ref()).map_err(export_error)?;
+        let mut writer = BufWriter::new(file);
+        let mut records = 0;
+        let mut write = |record: HistoryRecord| -> Result<(), DbError> {
+            serde_json::to_writer(&mut writer, &record).map_err(export_error)?;
+            writer.write_all(b"\n").map_err(export_error)?;
+            records += 1;
+            Ok(())
+        };
+
+        for version in self.all_rule_versions().await? {
+            write(HistoryRecord::RuleVersion(version))?;
+        }
+        for rule in self.list_rules().await? {
+            write(HistoryRecord::Rule(rule))?;
+        }
+        for session in self.list_sessions(Some(NO_LIMIT), &ListFilters::default()).await? {
+            let transcript = not_found_as_none(self.get_transcript(&session.id).await)?;
+            write(HistoryRecord::Session { session: Box::new(session), transcript })?;
+        }
+        for score in self.list_scores(Some(NO_LIMIT), &ListFilters::default()).await? {
+            write(HistoryRecord::Score {
+                rule_checks: self.get_score_rule_checks(score.id).await?,
+                transcript_stats: not_found_as_none(self.get_transcript_stats(score.id).await)?,
+                overrides: self.get_score_overrides(score.id).await?,
+                score,
+            })?;
+        }
+
+        writer.flush().map_err(export_error)?;
+        Ok(records)
+    }
+
+    /// Load history written by `export_jsonl`, in one transaction so a bad line leaves
+    /// the database as it was. Sessions and rules already present are kept as they are,
+    /// and scores are deduplicated on session ID and `scored_at`, so importing the same
+    /// file twice adds nothing.
+    pub async fn import_jsonl(&self, path: impl AsRef<Path>) -> Result<HistoryImportReport, DbError> {
+        let file = std::fs::File::open(path.as_ref()).map_err(export_error)?;
+        let mut report = HistoryImportReport::default();
+        let mut rule_version_ids = HashMap::new();
+        let mut tx = self.begin().await?;
+
+        for (index, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(export_error)?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: HistoryRecord = serde_json::from_str(&line)
+                .map_err(|e| DbError::Validation(format!("Line {}: {}", index + 1, e)))?;
+            match record {
+                HistoryRecord::RuleVersion(version) => {
+                    rule_version_ids.insert(version.id, db::import_rule_version(&mut tx, &version).await?);
+                }
+                HistoryRecord::Rule(rule) => {
+                    if db::import_rule(&mut tx, &rule).await? {
+                        report.rules += 1;
+                    }
+                }
+                HistoryRecord::Session { session, transcript } => {
+                    if db::import_session(&mut tx, &session, transcript.as_ref()).await? {
+                        report.sessions += 1;
+                    }
+                }
+                HistoryRecord::Score { score, rule_checks, transcript_stats, overrides } => {
+                    let stats = transcript_stats.as_ref();
+                    if db::import_score(&mut tx, &score, &rule_checks, stats, &overrides, &rule_version_ids).await? {
+                        report.scores += 1;
+                    } else {
+                        report.skipped_scores += 1;
+                    }
+                }
+            }
+        }
+
+        tx.commit().await?;
+        Ok(report)
+    }
+
+    /// Rule checks of the scores matching `filters`, each with its score
+    async fn filtered_rule_checks(&self, filters: &ListFilters) -> Result<Vec<(Score, RuleCheckRecord)>, DbError> {
+        let mut checks = Vec::new();
+        for score in self.list_scores(Some(NO_LIMIT), filters).await? {
+            for check in self.get_score_rule_checks(score.id).await? {
+                checks.push((score.clone(), check));
+            }
+        }
+        Ok(checks)
+    }
+}
+
+/// Write fresh scores as CSV, one row per session, with a column for each rule
+/// category holding the weighted percentage of that category's rules passed.
+/// Categories with no configured rules get no column.
+pub fn write_scores_csv(scores: &[SessionScore], rules: &[RuleDefinition], writer: impl Write) -> Result<(), String> {
+    let categories: Vec<RuleCategory> = RuleCategory::ALL
+        .into_iter()
+        .filter(|category| rules.iter().any(|rule| rule.category == *category))
+        .collect();
+    let mut csv = csv::Writer::from_writer(writer);
+    let mut header: Vec<String> = [
+        "session_id", "source_path", "scored_at", "score_percentage", "passed_rules", "total_rules", "duplicate_of",
+    ]
+    .map(String::from)
+    .to_vec();
+    header.extend(categories.iter().map(|category| format!("{:?}", category).to_lowercase()));
+    csv.write_record(&header).map_err(|e| e.to_string())?;
+
+    for score in scores {
+        let mut record = vec![
+            score.session_id.clone(),
+            score.source_path.clone().unwrap_or_default(),
+            timestamp(&score.timestamp),
+            score.score_percentage.to_string(),
+            score.passed_rules.to_string(),
+            score.total_rules.to_string(),
+            score.duplicate_of.clone().unwrap_or_default(),
+        ];
+        for category in &categories {
+            let (mut passed, mut total) = (0.0, 0.0);
+            for check in &score.rules {
+                let Some(rule) = rules.iter().find(|rule| rule.id == check.rule_id && rule.category == *category) else {
+                    continue;
+                };
+                total += rule.weight;
+                if check.passed {
+                    passed += rule.weight;
+                }
+            }
+            record.push(if total > 0.0 { format!("{:.1}", passed / total * 100.0) } else { String::new() });
+        }
+        csv.write_record(&record).map_err(|e| e.to_string())?;
+    }
+    csv.flush().map_err(|e| e.to_string())
+}
+
+/// Inline styles for HTML reports, so the page needs nothing else to render
+const REPORT_STYLE: &str = "body{font-family:system-ui,sans-serif;margin:2rem;color:#1f2328}\
+table{border-collapse:collapse;width:100%;margin-bottom:2rem}\
+th,td{border:1px solid #d0d7de;padding:.4rem .6rem;text-align:left;vertical-align:top}\
+.pass{color:#1a7f37}.fail{color:#cf222e}.meta{color:#656d76}";
+
+/// A standalone HTML page with one scorecard per session
+fn report_html(reports: &[SessionReport]) -> String {
+    let mut html = format!(
+        "<!DOCTYPE html>\n<html lang=\"en\">\n<head>\n<meta charset=\"utf-8\">\n<title>Session report</title>\n<style>{}</style>\n</head>\n<body>\n",
+        REPORT_STYLE
+    );
+    for report in reports {
+        let score = &report.score;
+        html.push_str(&format!(
+            "<h2>{}</h2>\n<p><strong>{:.1}%</strong> ({} of {} rules passed) &mdash; {}</p>\n<p class=\"meta\">Source: {} &middot; Scored {}</p>\n",
+            escape_html(&report.session.id),
+            score.score_percentage,
+            score.passed_rules,
+            score.total_rules,
+            escape_html(&score.summary),
+            escape_html(&report.session.source),
+            timestamp(&score.scored_at)
+        ));
+        html.push_str("<table>\n<tr><th>Rule</th><th>Result</th><th>Confidence</th><th>Evidence</th><th>Suggestion</th></tr>\n");
+        for check in &report.rule_checks {
+            let (class, result) = if check.passed { ("pass", "Passed") } else { ("fail", "Failed") };
+            html.push_str(&format!(
+                "<tr><td>{}</td><td class=\"{}\">{}</td><td>{:.0}%</td><td>{}</td><td>{}</td></tr>\n",
+                escape_html(&check.rule_name),
+                class,
+                result,
+                check.confidence * 100.0,
+                escape_html(check.evidence.as_deref().unwrap_or_default()),
+                escape_html(check.suggestion.as_deref().unwrap_or_default())
+            ));
+        }
+        html.push_str("</table>\n");
+    }
+    html.push_str("</body>\n</html>\n");
+    html
+}
+
+fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+fn session_record(session: &Session) -> [String; 13] {
+    [
+        session.id.clone(),
+        timestamp(&session.created_at),
+        timestamp(&session.updated_at),
+        session.source.clone(),
+        session.transcript_path.clone().unwrap_or_default(),
+        session.info.model.clone().unwrap_or_default(),
+        session.info.started_at.as_ref().map(timestamp).unwrap_or_default(),
+        session.info.ended_at.as_ref().map(timestamp).unwrap_or_default(),
+        optional(session.info.duration_seconds),
+        session.content_hash.clone().unwrap_or_default(),
+        session.duplicate_of.clone().unwrap_or_default(),
+        session.warnings.join("; "),
+        session.metadata.clone().unwrap_or_default(),
+    ]
+}
+
+fn score_record(score: &Score) -> [String; 9] {
+    [
+        score.id.to_string(),
+        score.session_id.clone(),
+        timestamp(&score.scored_at),
+        score.total_rules.to_string(),
+        score.passed_rules.to_string(),
+        score.score_percentage.to_string(),
+        score.summary.clone(),
+        score.scorer_version.clone().unwrap_or_default(),
+        score.rule_set_hash.clone().unwrap_or_default(),
+    ]
+}
+
+fn scores_batch(scores: &[Score]) -> Result<RecordBatch, ArrowError> {
+    let schema = Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("session_id", DataType::Utf8, false),
+        timestamp_field("scored_at"),
+        Field::new("total_rules", DataType::Int32, false),
+        Field::new("passed_rules", DataType::Int32, false),
+        Field::new("score_percentage", DataType::Float64, false),
+        Field::new("summary", DataType::Utf8, false),
+        Field::new("scorer_version", DataType::Utf8, true),
+        Field::new("rule_set_hash", DataType::Utf8, true),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from_iter_values(scores.iter().map(|s| s.id))),
+        Arc::new(StringArray::from_iter_values(scores.iter().map(|s| &s.session_id))),
+        timestamp_array(scores.iter().map(|s| &s.scored_at)),
+        Arc::new(Int32Array::from_iter_values(scores.iter().map(|s| s.total_rules))),
+        Arc::new(Int32Array::from_iter_values(scores.iter().map(|s| s.passed_rules))),
+        Arc::new(Float64Array::from_iter_values(scores.iter().map(|s| s.score_percentage))),
+        Arc::new(StringArray::from_iter_values(scores.iter().map(|s| &s.summary))),
+        Arc::new(scores.iter().map(|s| s.scorer_version.as_deref()).collect::<StringArray>()),
+        Arc::new(scores.iter().map(|s| s.rule_set_hash.as_deref()).collect::<StringArray>()),
+    ];
+    RecordBatch::try_new(Arc::new(schema), columns)
+}
+
+fn rule_checks_batch(checks: &[(Score, RuleCheckRecord)]) -> Result<RecordBatch, ArrowError> {
+    let schema = Schema::new(vec![
+        Field::new("id", DataType::Int64, false),
+        Field::new("score_id", DataType::Int64, false),
+        Field::new("session_id", DataType::Utf8, false),
+        timestamp_field("scored_at"),
+        Field::new("rule_id", DataType::Utf8, false),
+        Field::new("rule_name", DataType::Utf8, false),
+        Field::new("passed", DataType::Boolean, false),
+        Field::new("confidence", DataType::Float64, false),
+        Field::new("evidence", DataType::Utf8, true),
+        Field::new("suggestion", DataType::Utf8, true),
+        Field::new("rule_version_id", DataType::Int64, true),
+    ]);
+    let columns: Vec<ArrayRef> = vec![
+        Arc::new(Int64Array::from_iter_values(checks.iter().map(|(_, c)| c.id))),
+        Arc::new(Int64Array::from_iter_values(checks.iter().map(|(_, c)| c.score_id))),
+        Arc::new(StringArray::from_iter_values(checks.iter().map(|(s, _)| &s.session_id))),
+        timestamp_array(checks.iter().map(|(s, _)| &s.scored_at)),
+        Arc::new(StringArray::from_iter_values(checks.iter().map(|(_, c)| &c.rule_id))),
+        Arc::new(StringArray::from_iter_values(checks.iter().map(|(_, c)| &c.rule_name))),
+        Arc::new(checks.iter().map(|(_, c)| Some(c.passed)).collect::<BooleanArray>()),
+        Arc::new(Float64Array::from_iter_values(checks.iter().map(|(_, c)| c.confidence))),
+        Arc::new(checks.iter().map(|(_, c)| c.evidence.as_deref()).collect::<StringArray>()),
+        Arc::new(checks.iter().map(|(_, c)| c.suggestion.as_deref()).collect::<StringArray>()),
+        Arc::new(checks.iter().map(|(_, c)| c.rule_version_id).collect::<Int64Array>()),
+    ];
+    RecordBatch::try_new(Arc::new(schema), columns)
+}
+
+fn timestamp_field(name: &str) -> Field {
+    Field::new(name, DataType::Timestamp(TimeUnit::Microsecond, Some("UTC".into())), false)
+}
+
+fn timestamp_array<'a>(times: impl Iterator<Item = &'a DateTime<Utc>>) -> ArrayRef {
+    Arc::new(TimestampMicrosecondArray::from_iter_values(times.map(|t| t.timestamp_micros())).with_timezone("UTC"))
+}
+
+fn timestamp(time: &DateTime<Utc>) -> String {
+    time.to_rfc3339()
+}
+
+fn optional<T: ToString>(value: Option<T>) -> String {
+    value.map(|v| v.to_string()).unwrap_or_default()
+}
+
+fn not_found_as_none<T>(result: Result<T, DbError>) -> Result<Option<T>, DbError> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(DbError::NotFound(_)) => Ok(None),
+        Err(e) => Err(e),
+    }
+}
+
+fn export_error(err: impl std::fmt::Display) -> DbError {
+    DbError::Export(err.to_string())
+}
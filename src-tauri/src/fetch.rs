@@ -0,0 +1,85 @@
+//! Remote transcript ingestion: downloads a transcript over HTTPS with size and
+//! content-type checks, then scores it like a local file.
+
+use crate::encoding::{self, Decoded};
+use crate::{BehaviorScorer, SessionScore};
+use std::io::Read;
+use std::path::Path;
+use std::time::Duration;
+
+/// Maximum size of a downloaded transcript
+pub const MAX_DOWNLOAD_SIZE: u64 = 10 * 1024 * 1024; // 10MB
+
+/// Timeout for the whole request
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// SECURITY: Only text and JSON bodies are accepted; HTML (e.g. a login page) and
+/// binary payloads are rejected
+pub(crate) fn is_transcript_content_type(content_type: &str) -> bool {
+    let mime = content_type.split(';').next().unwrap_or_default().trim().to_ascii_lowercase();
+    match mime.split_once('/') {
+        Some(("text", subtype)) => subtype != "html",
+        Some(("application", subtype)) => {
+            matches!(subtype, "json" | "jsonl" | "x-ndjson" | "x-jsonlines") || subtype.ends_with("+json")
+        }
+        _ => false,
+    }
+}
+
+/// SECURITY: Download a transcript over HTTPS, enforcing the content type and size limit
+pub fn fetch_transcript(url: &str) -> Result<Decoded, String> {
+    if !url.starts_with("https://") {
+        return Err("Only https:// URLs are supported".to_string());
+    }
+    
+    let response = ureq::AgentBuilder::new()
+        .timeout(REQUEST_TIMEOUT)
+        .https_only(true)
+        .build()
+        .get(url)
+        .call()
+        .map_err(|e| format!("Failed to fetch {}: {}", url, e))?;
+    
+    if !is_transcript_content_type(response.content_type()) {
+        return Err(format!("Unsupported content type: {}", response.content_type()));
+    }
+    if let Some(length) = response.header("Content-Length").and_then(|l| l.parse::<u64>().ok()) {
+        if length > MAX_DOWNLOAD_SIZE {
+            return Err("Transcript exceeds maximum size of 10MB".to_string());
+        }
+    }
+    
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(MAX_DOWNLOAD_SIZE + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read response: {}", e))?;
+    if bytes.len() as u64 > MAX_DOWNLOAD_SIZE {
+        return Err("Transcript exceeds maximum size of 10MB".to_string());
+    }
+    
+    Ok(encoding::decode(bytes))
+}
+
+/// File name at the end of a URL path, ignoring any query or fragment
+pub(crate) fn url_file_name(url: &str) -> &str {
+    let path = url.split(['?', '#']).next().unwrap_or_default();
+    path.rsplit('/').next().unwrap_or_default()
+}
+
+impl BehaviorScorer {
+    /// Download and score a transcript. The URL's file extension picks the parser
+    /// when it has one; otherwise the format is detected from the content.
+    pub fn score_url(&self, session_id: &str, url: &str) -> Result<SessionScore, String> {
+        let decoded = fetch_transcript(url)?;
+        let file_name = Path::new(url_file_name(url));
+        let mut score = match file_name.extension().and_then(|ext| ext.to_str()) {
+            Some("md" | "json" | "jsonl") => self.score_file_content(session_id, file_name, &decoded.text)?,
+            _ => self.score_text(session_id, &decoded.text, None)?,
+        };
+        score.warnings.extend(decoded.warning);
+        Ok(score)
+    }
+}
+
@@ -0,0 +1,141 @@
+//! Scoring of individually chosen files, e.g. transcripts dropped onto the dashboard.
+//! Files are scored in parallel and each result is stored as it arrives.
+
+use crate::anomaly::{self, AnomalyThresholds};
+use crate::db::{Database, DbError};
+use crate::error::InvalidPath;
+use crate::scan::{self, DEFAULT_EXTENSIONS};
+use crate::session_id::SessionIdStrategy;
+use crate::{BehaviorScorer, SessionScore};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use tokio::task::JoinSet;
+
+/// Session source recorded for individually scored files
+pub const FILES_SOURCE: &str = "files";
+
+/// Result for one file, reported as soon as it is scored and stored
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct FileScoreProgress {
+    /// Files handled so far, including this one
+    pub done: usize,
+    pub total: usize,
+    /// Path as given
+    pub path: String,
+    /// Stored score, unless the file was rejected or failed to score
+    pub score: Option<SessionScore>,
+    pub error: Option<String>,
+}
+
+/// Outcome of scoring a set of files
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct FileScoreReport {
+    pub scored: usize,
+    /// Files that were rejected or couldn't be scored or stored, as `path: reason`
+    pub failed: Vec<String>,
+}
+
+impl BehaviorScorer {
+    /// SECURITY: Score transcript files within the base path, at most one per CPU at a
+    /// time, storing each score under `source` and calling `on_progress` as files finish.
+    /// Files outside the base path or without a transcript extension are reported as failures.
+    pub async fn score_files(
+        &self,
+        db: &Database,
+        paths: &[String],
+        strategy: SessionIdStrategy,
+        source: &str,
+        mut on_progress: impl FnMut(&FileScoreProgress),
+    ) -> FileScoreReport {
+        let total = paths.len();
+        let limit = std::thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+        let mut report = FileScoreReport::default();
+        let mut done = 0;
+        let mut pending = paths.iter();
+        let mut running = JoinSet::new();
+        let mut running_paths = HashMap::new();
+        loop {
+            while running.len() < limit {
+                let Some(path) = pending.next() else {
+                    break;
+                };
+                let scorer = self.clone();
+                let file = PathBuf::from(path);
+                let task = running.spawn_blocking(move || {
+                    let file = scorer.validate_file(&file)?;
+                    let score = scorer.score_path(&file, strategy)?;
+                    Ok::<_, String>((file, score))
+                });
+                running_paths.insert(task.id(), path.clone());
+            }
+            let Some(joined) = running.join_next_with_id().await else {
+                break;
+            };
+            let (path, scored) = match joined {
+                Ok((id, scored)) => (running_paths.remove(&id), scored),
+                Err(e) => (running_paths.remove(&e.id()), Err(format!("Scoring task failed: {}", e))),
+            };
+            let path = path.unwrap_or_default();
+
+            let stored = match scored {
+                Ok((file, mut score)) => store_file_score(db, &file, &mut score, strategy, source)
+                    .await
+                    .map(|_| score)
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+
+            done += 1;
+            match &stored {
+                Ok(_) => report.scored += 1,
+                Err(e) => report.failed.push(format!("{}: {}", path, e)),
+            }
+            on_progress(&FileScoreProgress {
+                done,
+                total,
+                path,
+                score: stored.as_ref().ok().cloned(),
+                error: stored.err(),
+            });
+        }
+        report
+    }
+
+    /// SECURITY: Ensure a file is a transcript within the allowed base path, returning
+    /// its canonical path
+    pub fn validate_file(&self, path: &Path) -> Result<PathBuf, InvalidPath> {
+        let canonical_base = self.base_path.canonicalize()
+            .map_err(|e| InvalidPath(format!("Invalid base path: {}", e)))?;
+        let canonical = path.canonicalize()
+            .map_err(|e| InvalidPath(format!("Invalid file path: {}", e)))?;
+
+        if !canonical.starts_with(&canonical_base) {
+            return Err(InvalidPath("File path is outside allowed base path".to_string()));
+        }
+        if !canonical.is_file() {
+            return Err(InvalidPath("Not a file".to_string()));
+        }
+        let extensions: Vec<String> = DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect();
+        if !scan::has_transcript_extension(&canonical, &extensions) {
+            return Err(InvalidPath("Unsupported file type".to_string()));
+        }
+        Ok(canonical)
+    }
+}
+
+/// Store a file's score under `source`, flagging anomalies first, along with the mapping
+/// from its path to the session ID
+async fn store_file_score(
+    db: &Database,
+    file: &Path,
+    score: &mut SessionScore,
+    strategy: SessionIdStrategy,
+    source: &str,
+) -> Result<(), DbError> {
+    let file = file.to_string_lossy();
+    anomaly::flag_anomalies_from_history(db, score, &AnomalyThresholds::default()).await?;
+    db.save_session_score(score, source, Some(file.as_ref())).await?;
+    db.save_session_id_mapping(&file, &score.session_id, strategy).await
+}
@@ -0,0 +1,202 @@
+//! Import of session transcripts stored in other tools' SQLite databases. A small
+//! mapping names the table and columns to read; the source database is opened
+//! read-only and each session found is scored and stored.
+
+use crate::db::Database;
+use crate::session_id;
+use crate::transcript::{self, Role, Turn};
+use crate::{BehaviorScorer, SessionScore, Transcript, TranscriptFormat};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use sqlx::sqlite::SqliteConnectOptions;
+use sqlx::{ConnectOptions, Row};
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Session source recorded for imported sessions
+pub const IMPORT_SOURCE: &str = "import";
+
+/// Where sessions live in a third-party database. Rows hold either a whole transcript
+/// each, or, when `role_column` is set, one message each.
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ImportMapping {
+    /// Table or view to read
+    pub table: String,
+    /// Column identifying the session a row belongs to
+    pub session_column: String,
+    /// Column holding transcript text, or message text when `role_column` is set
+    pub content_column: String,
+    /// Column holding each message's speaker (`user`, `assistant`, ...)
+    #[serde(default)]
+    pub role_column: Option<String>,
+    /// Column ordering rows within a session; defaults to `rowid`
+    #[serde(default)]
+    pub order_column: Option<String>,
+    /// Column holding each message's timestamp (RFC 3339, `YYYY-MM-DD HH:MM:SS`, or Unix time)
+    #[serde(default)]
+    pub timestamp_column: Option<String>,
+    /// Format of whole-transcript content; detected when unset
+    #[serde(default)]
+    pub format: Option<TranscriptFormat>,
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct ImportReport {
+    /// Scores for the sessions imported, in source order
+    pub scores: Vec<SessionScore>,
+    /// Sessions that couldn't be scored or stored, as `session: reason`
+    pub failed: Vec<String>,
+}
+
+/// One row read through the mapping
+struct ImportRow {
+    content: String,
+    role: Option<String>,
+    timestamp: Option<String>,
+}
+
+impl BehaviorScorer {
+    /// SECURITY: Import sessions from a SQLite database within the allowed base path,
+    /// scoring each and storing it in `db`. Session IDs are namespaced by the database's
+    /// path, so importing it again updates the same sessions instead of adding new ones.
+    pub async fn import_sqlite(
+        &self,
+        db: &Database,
+        source: &Path,
+        mapping: &ImportMapping,
+    ) -> Result<ImportReport, String> {
+        if !source.is_file() {
+            return Err(format!("Not a database file: {}", source.display()));
+        }
+        self.validate_directory(source.parent().unwrap_or(Path::new(".")))?;
+
+        let source = source
+            .canonicalize()
+            .map_err(|e| format!("Invalid database path: {}", e))?;
+        let sessions = read_sessions(&source, mapping).await?;
+        let source = source.to_string_lossy();
+        let mut report = ImportReport::default();
+        for (raw_id, rows) in sessions {
+            let session_id = session_id::namespaced(&source, &raw_id);
+            let scored = if mapping.role_column.is_some() {
+                self.score_transcript(&session_id, &transcript_from_rows(rows))
+            } else {
+                let content = rows.into_iter().map(|row| row.content).collect::<Vec<_>>().join("\n");
+                self.score_text(&session_id, &content, mapping.format)
+            };
+            let score = match scored {
+                Ok(score) => score,
+                Err(e) => {
+                    report.failed.push(format!("{}: {}", raw_id, e));
+                    continue;
+                }
+            };
+
+            let path = format!("{}#{}", source, raw_id);
+            match db.save_session_score(&score, IMPORT_SOURCE, Some(&path)).await {
+                Ok(_) => report.scores.push(score),
+                Err(e) => report.failed.push(format!("{}: {}", raw_id, e)),
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// SECURITY: Read rows through the mapping from a read-only connection, grouped by
+/// session in order of first appearance
+async fn read_sessions(source: &Path, mapping: &ImportMapping) -> Result<Vec<(String, Vec<ImportRow>)>, String> {
+    let optional = |column: &Option<String>| -> Result<String, String> {
+        match column {
+            Some(column) => Ok(format!("CAST({} AS TEXT)", quote_identifier(column)?)),
+            None => Ok("NULL".to_string()),
+        }
+    };
+    let session = quote_identifier(&mapping.session_column)?;
+    let order = match &mapping.order_column {
+        Some(column) => quote_identifier(column)?,
+        None => "rowid".to_string(),
+    };
+    let query = format!(
+        "SELECT CAST({session} AS TEXT), CAST({content} AS TEXT), {role}, {timestamp} FROM {table} \
+         WHERE {session} IS NOT NULL ORDER BY {order}",
+        session = session,
+        content = quote_identifier(&mapping.content_column)?,
+        role = optional(&mapping.role_column)?,
+        timestamp = optional(&mapping.timestamp_column)?,
+        table = quote_identifier(&mapping.table)?,
+        order = order,
+    );
+
+    let mut connection = SqliteConnectOptions::new()
+        .filename(source)
+        .read_only(true)
+        .connect()
+        .await
+        .map_err(|e| format!("Failed to open {}: {}", source.display(), e))?;
+    let rows = sqlx::query(&query)
+        .fetch_all(&mut connection)
+        .await
+        .map_err(|e| format!("Import query failed: {}", e))?;
+
+    let mut sessions: Vec<(String, Vec<ImportRow>)> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for row in rows {
+        let id: String = row.try_get(0).map_err(|e| e.to_string())?;
+        let row = ImportRow {
+            content: row.try_get::<Option<String>, _>(1).map_err(|e| e.to_string())?.unwrap_or_default(),
+            role: row.try_get(2).map_err(|e| e.to_string())?,
+            timestamp: row.try_get(3).map_err(|e| e.to_string())?,
+        };
+        match positions.get(&id) {
+            Some(&position) => sessions[position].1.push(row),
+            None => {
+                positions.insert(id.clone(), sessions.len());
+                sessions.push((id, vec![row]));
+            }
+        }
+    }
+
+    Ok(sessions)
+}
+
+/// Build a transcript from message rows
+fn transcript_from_rows(rows: Vec<ImportRow>) -> Transcript {
+    let turns = rows
+        .into_iter()
+        .map(|row| Turn {
+            role: row.role.as_deref().and_then(Role::parse).unwrap_or(Role::Unknown),
+            content: row.content,
+            timestamp: row.timestamp.as_deref().and_then(parse_timestamp),
+            tool_calls: Vec::new(),
+        })
+        .collect();
+
+    Transcript {
+        format: TranscriptFormat::ChatJson,
+        turns,
+        ..Transcript::default()
+    }
+}
+
+/// Text timestamps, or Unix time in seconds or milliseconds
+fn parse_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    match raw.trim().parse::<f64>() {
+        Ok(seconds) if seconds > 1e12 => DateTime::from_timestamp_millis(seconds as i64),
+        Ok(seconds) => DateTime::from_timestamp(seconds as i64, 0),
+        Err(_) => transcript::parse_loose_timestamp(raw.trim()),
+    }
+}
+
+/// SECURITY: Quote a table or column name from the mapping, allowing only plain identifiers
+fn quote_identifier(name: &str) -> Result<String, String> {
+    let valid = name.chars().next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_');
+    if valid {
+        Ok(format!("\"{}\"", name))
+    } else {
+        Err(format!("Invalid identifier in import mapping: {}", name))
+    }
+}
@@ -0,0 +1,130 @@
+//! Bulk ingestion of transcript history for `behavior-scorer import`: every session in
+//! a directory, an archive of session logs, or a single transcript file is scored, then
+//! stored with its session row and the mapping from its file to the session ID.
+
+use crate::archive;
+use crate::db::{Database, DbError};
+use crate::import::IMPORT_SOURCE;
+use crate::scan::{ScanEvent, ScanOptions, ScanSummary};
+use crate::session_id::{self, SessionIdStrategy};
+use crate::{BehaviorScorer, SessionScore};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::path::Path;
+
+/// Outcome of ingesting transcript history
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct IngestReport {
+    /// Scores stored, in scan order
+    pub scores: Vec<SessionScore>,
+    /// Files left out of the scan, as `path: reason`
+    pub skipped: Vec<String>,
+    /// Sessions dropped as duplicates of an earlier one
+    pub duplicates: usize,
+    /// Sessions that couldn't be scored or stored, as `session: reason`
+    pub failed: Vec<String>,
+}
+
+impl BehaviorScorer {
+    /// SECURITY: Score the history at `path`, which may be a directory, an archive, or a
+    /// single transcript file within the allowed base path, calling `on_event` as it goes
+    pub fn scan_history(
+        &self,
+        path: &Path,
+        options: &ScanOptions,
+        mut on_event: impl FnMut(&ScanEvent),
+    ) -> Result<ScanSummary, String> {
+        if archive::is_archive(path) {
+            return self.scan_archive_with_progress(path, options, on_event);
+        }
+        if !path.is_file() {
+            return self.scan_with_progress(path, options, on_event);
+        }
+
+        let file = self.validate_file(path)?;
+        on_event(&ScanEvent::Discovered { files: 1, sessions: 1 });
+        let mut summary = ScanSummary {
+            files: 1,
+            sessions: 1,
+            ..ScanSummary::default()
+        };
+        match self.score_path(&file, options.session_ids) {
+            Ok(score) => {
+                on_event(&ScanEvent::Scored {
+                    done: 1,
+                    total: 1,
+                    session_id: score.session_id.clone(),
+                    score_percentage: score.score_percentage,
+                });
+                summary.scores.push(score);
+            }
+            Err(error) => {
+                let session = path.display().to_string();
+                on_event(&ScanEvent::Failed {
+                    done: 1,
+                    total: 1,
+                    session: session.clone(),
+                    error: error.clone(),
+                });
+                summary.failed.push(format!("{}: {}", session, error));
+            }
+        }
+        Ok(summary)
+    }
+}
+
+/// Store each score in `summary` with its session row and file mapping, in one
+/// transaction. Session IDs are namespaced by the directory each file came from, so
+/// same-named transcripts from different places stay separate sessions and ingesting
+/// the same files again updates the sessions it created; content-hash IDs are already
+/// unique and are kept as they are. Sessions that failed to score or store, and files
+/// the scan left out, carry over into the report.
+pub async fn store_history(db: &Database, summary: ScanSummary, options: &ScanOptions) -> Result<IngestReport, DbError> {
+    let mut report = IngestReport {
+        skipped: summary
+            .report
+            .too_large
+            .iter()
+            .map(|issue| format!("{}: {}", issue.path, issue.reason))
+            .collect(),
+        duplicates: summary.duplicates,
+        failed: summary.failed,
+        ..IngestReport::default()
+    };
+    let scores = namespace_ids(summary.scores, options.session_ids);
+    let stored = db.save_scanned_scores(&scores, IMPORT_SOURCE, options.session_ids).await?;
+    for (score, stored) in scores.into_iter().zip(stored) {
+        match stored {
+            Ok(_) => report.scores.push(score),
+            Err(e) => report.failed.push(format!("{}: {}", score.session_id, e)),
+        }
+    }
+    Ok(report)
+}
+
+/// Prefix each score's session ID with a hash of its file's directory, relinking
+/// duplicates to their namespaced originals
+fn namespace_ids(mut scores: Vec<SessionScore>, strategy: SessionIdStrategy) -> Vec<SessionScore> {
+    if strategy == SessionIdStrategy::ContentHash {
+        return scores;
+    }
+
+    let mut originals: HashMap<String, String> = HashMap::new();
+    for score in &mut scores {
+        let namespace = score
+            .source_path
+            .as_deref()
+            .and_then(|path| Path::new(path).parent())
+            .map(|dir| dir.canonicalize().unwrap_or_else(|_| dir.to_path_buf()).to_string_lossy().to_string())
+            .unwrap_or_default();
+        score.session_id = session_id::namespaced(&namespace, &score.session_id);
+        match score.duplicate_of {
+            Some(_) => score.duplicate_of = originals.get(&score.content_hash).cloned(),
+            None => {
+                originals.entry(score.content_hash.clone()).or_insert_with(|| score.session_id.clone());
+            }
+        }
+    }
+    scores
+}
@@ -5,6 +5,18 @@ mod integration_tests {
     use tempfile::TempDir;
     use tokio::time::{sleep, Duration};
 
+    /// A rule of weight 1 named and described by its ID
+    fn rule(id: &str, pattern: &str, category: RuleCategory) -> RuleDefinition {
+        RuleDefinition {
+            id: id.to_string(),
+            name: id.to_string(),
+            description: id.to_string(),
+            pattern: pattern.to_string(),
+            weight: 1.0,
+            category,
+        }
+    }
+
     /// Test: Score a simple session transcript
     #[tokio::test]
     async fn test_score_simple_session() {
@@ -154,4 +166,2542 @@ mod integration_tests {
         assert!(confidence_rule.is_some());
         assert!(confidence_rule.unwrap().passed);
     }
-}
\ No newline at end of file
+
+    /// Test: Diagnostics record one timing per rule only when enabled
+    #[tokio::test]
+    async fn test_rule_timing_diagnostics() {
+        let transcript = "Confidence level: Confident";
+        
+        let plain = BehaviorScorer::new().score_session("diag-off", transcript).unwrap();
+        assert!(plain.diagnostics.is_none());
+        
+        let score = BehaviorScorer::new()
+            .with_diagnostics(true)
+            .score_session("diag-on", transcript)
+            .unwrap();
+        let diagnostics = score.diagnostics.expect("diagnostics enabled");
+        assert_eq!(diagnostics.rule_timings.len(), score.rules.len());
+        assert!(diagnostics.rule_timings.iter().all(|t| t.elapsed_ms >= 0.0));
+        
+        let slowest = diagnostics.slowest();
+        assert!(slowest.windows(2).all(|w| w[0].elapsed_ms >= w[1].elapsed_ms));
+    }
+
+    /// Test: Scores carry the scorer version and a rule set hash that tracks rule edits
+    #[tokio::test]
+    async fn test_scorer_version_stamping() {
+        use crate::{NormalizationMode, RuleCategory, RuleDefinition, TrackerConfig, SCORER_VERSION};
+        
+        let rule = RuleDefinition {
+            id: "objective".to_string(),
+            name: "Objective".to_string(),
+            description: "States an objective".to_string(),
+            pattern: "OBJECTIVE:".to_string(),
+            weight: 1.0,
+            category: RuleCategory::Startup,
+        };
+        let config = TrackerConfig {
+            rules: vec![rule.clone()],
+            normalization: NormalizationMode::Raw,
+        };
+        let scorer = BehaviorScorer::with_config(config.clone());
+        
+        let score = scorer.score_session("stamped", "OBJECTIVE: ship").unwrap();
+        assert_eq!(score.scorer_version, SCORER_VERSION);
+        assert_eq!(score.rule_set_hash, config.fingerprint());
+        assert_eq!(score.rule_set_hash, BehaviorScorer::with_config(config).rule_set_hash());
+        
+        let reweighted = TrackerConfig {
+            rules: vec![RuleDefinition { weight: 2.0, ..rule }],
+            normalization: NormalizationMode::Raw,
+        };
+        assert_ne!(score.rule_set_hash, reweighted.fingerprint());
+    }
+
+    /// Test: RuleCheck output follows config order, including rules whose pattern fails to compile
+    #[tokio::test]
+    async fn test_rule_output_matches_config_order() {
+        use crate::{NormalizationMode, TrackerConfig};
+        
+        let config = TrackerConfig {
+            rules: vec![
+                rule("zeta", "ship", RuleCategory::Safety),
+                rule("broken", "(unclosed", RuleCategory::Response),
+                rule("alpha", "ship", RuleCategory::Startup),
+                rule("mid", "never-matches", RuleCategory::Communication),
+            ],
+            normalization: NormalizationMode::Raw,
+        };
+        
+        let score = BehaviorScorer::with_config(config.clone())
+            .score_session("ordered", "ship it")
+            .unwrap();
+        
+        let ids: Vec<&str> = score.rules.iter().map(|r| r.rule_id.as_str()).collect();
+        let expected: Vec<&str> = config.rules.iter().map(|r| r.id.as_str()).collect();
+        assert_eq!(ids, expected);
+        assert!(score.rules.iter().enumerate().all(|(i, r)| r.order == i));
+        assert!(!score.rules[1].passed);
+        assert_eq!(score.rules[2].evidence.as_deref(), Some("ship it"));
+    }
+
+    /// Test: Long evidence is cut at a character boundary, even in multibyte text
+    #[tokio::test]
+    async fn test_long_multibyte_evidence() {
+        use crate::{NormalizationMode, TrackerConfig};
+
+        let config = TrackerConfig {
+            rules: vec![rule("ship", "ship", RuleCategory::Response)],
+            normalization: NormalizationMode::Raw,
+        };
+        let line = format!("ship {}", "é".repeat(150));
+        let score = BehaviorScorer::with_config(config).score_session("multibyte", &line).unwrap();
+        let evidence = score.rules[0].evidence.as_deref().unwrap();
+        assert!(evidence.starts_with("ship é") && evidence.ends_with("é..."));
+        assert!(evidence.len() <= 203);
+    }
+
+    /// Test: A failure of a normally-passing rule is flagged from database baselines
+    #[tokio::test]
+    async fn test_anomaly_flagging_from_history() {
+        use crate::anomaly::{flag_anomalies_from_history, AnomalyThresholds};
+        use crate::db::Database;
+        
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("history", "test", None, None).await.unwrap();
+        for _ in 0..10 {
+            let score = db
+                .create_score("history", 2, 1, 50.0, "Moderate", None, None)
+                .await
+                .unwrap();
+            db.create_rule_check(score.id, "confidence_calibration", "Confidence", "", true, 1.0, None, None)
+                .await
+                .unwrap();
+            db.create_rule_check(score.id, "binary_decision", "Binary", "", false, 0.0, None, None)
+                .await
+                .unwrap();
+        }
+        
+        let scorer = BehaviorScorer::new();
+        let mut score = scorer.score_session("fresh", "nothing relevant here").unwrap();
+        assert!(score.anomalies.is_empty());
+        
+        flag_anomalies_from_history(&db, &mut score, &AnomalyThresholds::default())
+            .await
+            .unwrap();
+        
+        let flagged: Vec<&str> = score.anomalies.iter().map(|a| a.rule_id.as_str()).collect();
+        assert_eq!(flagged, vec!["confidence_calibration"]);
+        assert_eq!(score.anomalies[0].sample_size, 10);
+        assert_eq!(score.anomalies[0].historical_pass_rate, 100.0);
+        
+        // Scores are flagged as they're stored
+        let mut watched = scorer.score_session("watched", "nothing relevant here").unwrap();
+        let path = std::path::Path::new("watched.md");
+        crate::watch::persist_score(&db, &mut watched, path, crate::SessionIdStrategy::FileName, crate::watch::WATCH_SOURCE)
+            .await
+            .unwrap();
+        assert_eq!(watched.anomalies.len(), 1);
+    }
+
+    /// Test: Transcript stats are attached to every score
+    #[tokio::test]
+    async fn test_transcript_stats_attached() {
+        let transcript = "User: ship it?\nAssistant: Confidence level: Confident\n\n**User**: thanks\n";
+        let score = BehaviorScorer::new().score_session("stats", transcript).unwrap();
+        
+        let stats = &score.transcript_stats;
+        assert_eq!(stats.byte_length, transcript.len());
+        assert_eq!(stats.line_count, 4);
+        assert_eq!(stats.turn_count, 3);
+        assert_eq!(stats.estimated_tokens, transcript.len().div_ceil(4));
+    }
+
+    /// Test: Normalization modes change how weights combine into the percentage
+    #[tokio::test]
+    async fn test_weight_normalization_modes() {
+        use crate::{NormalizationMode, TrackerConfig};
+        
+        let rules = vec![
+            RuleDefinition { weight: 8.0, ..rule("safety", "APPROVED", RuleCategory::Safety) },
+            rule("startup", "OBJECTIVE:", RuleCategory::Startup),
+            rule("confidence", "Confidence level:", RuleCategory::Confidence),
+        ];
+        let score_with = |normalization| {
+            BehaviorScorer::with_config(TrackerConfig { rules: rules.clone(), normalization })
+                .score_session("normalized", "APPROVED")
+                .unwrap()
+                .score_percentage
+        };
+        
+        // The heavy Safety rule dominates the raw score
+        assert_eq!(score_with(NormalizationMode::Raw), 80.0);
+        // One of five categories passes; Response and Communication have no rules and count as 0
+        assert_eq!(score_with(NormalizationMode::CategoryEqual), 20.0);
+        // Weights [1, 1, 8] clamp to [1, 4.5] at the 25th/75th percentile
+        let winsorized = score_with(NormalizationMode::Winsorized { percentile: 25.0 });
+        assert!((winsorized - 4.5 / 6.5 * 100.0).abs() < 1e-9);
+    }
+
+    /// Test: JSONL session logs parse into turns and evidence is attributed to a turn
+    #[tokio::test]
+    async fn test_jsonl_transcript_scoring() {
+        use crate::transcript::{parse_jsonl, Role};
+        
+        let log = r#"
+{"type":"session_meta","payload":{"id":"abc","cwd":"/tmp"}}
+{"type":"user","timestamp":"2026-02-15T09:00:00Z","message":{"role":"user","content":"Fix the build"}}
+{"type":"assistant","timestamp":"2026-02-15T09:00:05Z","message":{"role":"assistant","content":[{"type":"text","text":"OBJECTIVE: fix the build"},{"type":"tool_use","name":"Bash","input":{"command":"cargo build"}}]}}
+{"timestamp":"2026-02-15T09:00:09Z","type":"response_item","payload":{"type":"function_call","name":"shell","arguments":"{\"cmd\":\"ls\"}"}}
+{"timestamp":"2026-02-15T09:00:10Z","type":"response_item","payload":{"type":"message","role":"assistant","content":[{"type":"output_text","text":"Confidence level: Confident"}]}}
+"#;
+        
+        let transcript = parse_jsonl(log).unwrap();
+        assert_eq!(transcript.turns.len(), 4);
+        assert_eq!(transcript.turns[0].role, Role::User);
+        assert_eq!(transcript.turns[1].tool_calls[0].name, "Bash");
+        assert_eq!(transcript.turns[2].tool_calls[0].name, "shell");
+        assert!(transcript.turns[0].timestamp.is_some());
+        
+        let score = BehaviorScorer::new().score_transcript("jsonl-session", &transcript).unwrap();
+        assert_eq!(score.transcript_stats.turn_count, 4);
+        
+        let objective = score.rules.iter().find(|r| r.rule_id == "objective_before_execution").unwrap();
+        assert!(objective.passed);
+        assert_eq!(objective.turn_index, Some(1));
+        let confidence = score.rules.iter().find(|r| r.rule_id == "confidence_calibration").unwrap();
+        assert_eq!(confidence.turn_index, Some(3));
+        
+        assert!(parse_jsonl("{\"role\":\"user\"\nnot json").is_err());
+    }
+
+    /// Test: Chat-completions JSON exports are scored as structured transcripts
+    #[tokio::test]
+    async fn test_chat_json_ingestion() {
+        use crate::transcript::{parse_chat_json, Role};
+        use std::path::Path;
+        
+        let export = r#"{
+        "model": "gpt-4o",
+        "messages": [
+            {"role": "system", "content": "You are helpful."},
+            {"role": "user", "content": [{"type": "text", "text": "Send the email?"}]},
+            {"role": "assistant", "content": "Needs approval first.", "tool_calls": [
+                {"id": "1", "type": "function", "function": {"name": "draft_email", "arguments": "{}"}}
+            ]}
+        ]
+    }"#;
+        
+        let transcript = parse_chat_json(export).unwrap();
+        assert_eq!(transcript.turns.len(), 3);
+        assert_eq!(transcript.turns[1].role, Role::User);
+        assert_eq!(transcript.turns[1].content, "Send the email?");
+        assert_eq!(transcript.turns[2].tool_calls[0].name, "draft_email");
+        
+        let scorer = BehaviorScorer::new();
+        let score = scorer.score_file_content("chat-export", Path::new("chat.json"), export).unwrap();
+        assert_eq!(score.transcript_stats.turn_count, 3);
+        let approval = score.rules.iter().find(|r| r.rule_id == "approval_for_external").unwrap();
+        assert_eq!(approval.turn_index, Some(2));
+        
+        // Non-chat JSON falls back to plain-text scoring
+        assert!(parse_chat_json(r#"{"notes": "OBJECTIVE: x"}"#).is_err());
+        let fallback = scorer.score_file_content("notes", Path::new("notes.json"), r#"{"notes": "OBJECTIVE: x"}"#).unwrap();
+        assert!(fallback.rules.iter().all(|r| r.turn_index.is_none()));
+    }
+
+    /// Test: Legacy plain-text transcripts split on speaker labels and render back unchanged
+    #[tokio::test]
+    async fn test_plain_text_transcript_fallback() {
+        use crate::{Role, Transcript, TranscriptFormat};
+        
+        let text = "# Session notes\nUser: ship it?\nAssistant: OBJECTIVE: ship\n**User**: thanks\n";
+        let transcript = Transcript::from_plain_text(text);
+        
+        assert_eq!(transcript.format, TranscriptFormat::PlainText);
+        let roles: Vec<Role> = transcript.turns.iter().map(|t| t.role).collect();
+        assert_eq!(roles, vec![Role::Unknown, Role::User, Role::Assistant, Role::User]);
+        assert_eq!(transcript.render().0, text);
+        
+        let score = BehaviorScorer::new().score_session("legacy", text).unwrap();
+        assert_eq!(score.transcript_stats.turn_count, 3);
+        let objective = score.rules.iter().find(|r| r.rule_id == "objective_before_execution").unwrap();
+        assert_eq!(objective.turn_index, Some(2));
+        
+        let unlabelled = Transcript::from_plain_text("no speakers here");
+        assert_eq!(unlabelled.turns.len(), 1);
+        assert_eq!(unlabelled.labelled_turn_count(), 0);
+    }
+
+    /// Test: Markdown frontmatter is extracted into session metadata
+    #[tokio::test]
+    async fn test_frontmatter_metadata_extraction() {
+        use crate::db::Database;
+        use std::path::Path;
+        
+        let content = "---\ndate: 2026-02-15\nsource: codex\nmodel: gpt-5\n---\nUser: go\nAssistant: OBJECTIVE: ship\n";
+        let score = BehaviorScorer::new()
+            .score_file_content("session-1", Path::new("session-1.md"), content)
+            .unwrap();
+        
+        assert_eq!(score.metadata["model"], "gpt-5");
+        assert_eq!(score.metadata["date"], "2026-02-15");
+        // Frontmatter is not part of the evaluated conversation
+        assert_eq!(score.transcript_stats.line_count, 2);
+        
+        let db = Database::new_in_memory().await.unwrap();
+        db.upsert_session(&score.session_id, "codex", None, score.metadata_json().as_deref())
+            .await
+            .unwrap();
+        let session = db.get_session(&score.session_id).await.unwrap();
+        let stored: serde_json::Value = serde_json::from_str(session.metadata.as_deref().unwrap()).unwrap();
+        assert_eq!(stored["source"], "codex");
+    }
+
+    /// Test: Model and timing are inferred from JSONL events and frontmatter
+    #[tokio::test]
+    async fn test_session_info_inference() {
+        use crate::transcript::parse_jsonl;
+        use crate::Transcript;
+        
+        let log = r#"{"type":"user","timestamp":"2026-02-15T09:00:00Z","message":{"role":"user","content":"go"}}
+{"type":"assistant","timestamp":"2026-02-15T09:45:30Z","message":{"role":"assistant","model":"claude-sonnet-4","content":"done"}}"#;
+        let score = BehaviorScorer::new()
+            .score_transcript("jsonl-info", &parse_jsonl(log).unwrap())
+            .unwrap();
+        assert_eq!(score.info.model.as_deref(), Some("claude-sonnet-4"));
+        assert_eq!(score.info.duration_seconds, Some(45 * 60 + 30));
+        
+        let notes = Transcript::from_plain_text("---\nmodel: gpt-5\ndate: 2026-02-15\n---\nUser: hi\n");
+        let info = notes.infer_session_info();
+        assert_eq!(info.model.as_deref(), Some("gpt-5"));
+        assert_eq!(info.started_at.unwrap().to_rfc3339(), "2026-02-15T00:00:00+00:00");
+        assert!(info.duration_seconds.is_none());
+    }
+
+    /// Test: Multi-part sessions are concatenated in part order and scored once
+    #[tokio::test]
+    async fn test_multi_part_session_assembly() {
+        use crate::ScanOptions;
+        
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("session-x-part2.md"), "Assistant: Ship now? Y/N").unwrap();
+        std::fs::write(temp_dir.path().join("session-x-part1.md"), "User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let scores = scorer.scan_and_score_directory(temp_dir.path()).unwrap();
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].session_id, "session-x");
+        assert_eq!(scores[0].transcript_stats.turn_count, 3);
+        let objective = scores[0].rules.iter().find(|r| r.rule_id == "objective_before_execution").unwrap();
+        let ship = scores[0].rules.iter().find(|r| r.rule_id == "binary_decision").unwrap();
+        assert_eq!(objective.turn_index, Some(1));
+        assert_eq!(ship.turn_index, Some(2));
+        
+        let invalid = ScanOptions { part_pattern: Some("(".to_string()), ..ScanOptions::default() };
+        assert!(scorer.scan_with_options(temp_dir.path(), &invalid).is_err());
+    }
+
+    /// Test: Gzip and zstd transcripts are decompressed before scoring
+    #[tokio::test]
+    async fn test_compressed_transcripts() {
+        use crate::scan::read_transcript_file;
+        use flate2::{write::GzEncoder, Compression};
+        use std::io::Write;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let mut gz = GzEncoder::new(Vec::new(), Compression::default());
+        gz.write_all(b"User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        std::fs::write(temp_dir.path().join("archived-part1.md.gz"), gz.finish().unwrap()).unwrap();
+        let zst = zstd::encode_all(&b"Assistant: Ship now? Y/N"[..], 0).unwrap();
+        std::fs::write(temp_dir.path().join("archived-part2.md.zst"), zst).unwrap();
+        
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let scores = scorer.scan_and_score_directory(temp_dir.path()).unwrap();
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].session_id, "archived");
+        assert_eq!(scores[0].transcript_stats.turn_count, 3);
+        
+        // Size limits apply to the decompressed content
+        let bomb = zstd::encode_all(&vec![b'a'; 41 * 1024 * 1024][..], 0).unwrap();
+        let bomb_path = temp_dir.path().join("bomb.md.zst");
+        std::fs::write(&bomb_path, bomb).unwrap();
+        assert!(read_transcript_file(&bomb_path).unwrap_err().contains("maximum size"));
+    }
+
+    /// Test: Scan globs, depth, and extension filters
+    #[tokio::test]
+    async fn test_scan_filters() {
+        use crate::ScanOptions;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let nested = temp_dir.path().join("a").join("b");
+        std::fs::create_dir_all(&nested).unwrap();
+        let transcript = "User: go\nAssistant: OBJECTIVE: ship";
+        std::fs::write(temp_dir.path().join("top-part1.md"), transcript).unwrap();
+        std::fs::write(temp_dir.path().join("draft-part1.md"), transcript).unwrap();
+        std::fs::write(temp_dir.path().join("notes-part1.txt"), transcript).unwrap();
+        std::fs::write(nested.join("deep-part1.md"), transcript).unwrap();
+        
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let ids = |options: &ScanOptions| {
+            let mut ids: Vec<String> = scorer
+                .scan_with_options(temp_dir.path(), options)
+                .unwrap()
+                .into_iter()
+                .map(|s| s.session_id)
+                .collect();
+            ids.sort();
+            ids
+        };
+        
+        assert_eq!(ids(&ScanOptions::default()), vec!["draft", "top"]);
+        assert_eq!(
+            ids(&ScanOptions { max_depth: 3, exclude: vec!["draft*".to_string()], ..ScanOptions::default() }),
+            vec!["deep", "top"]
+        );
+        assert_eq!(
+            ids(&ScanOptions { include: vec!["notes*".to_string()], extensions: vec!["txt".to_string()], ..ScanOptions::default() }),
+            vec!["notes"]
+        );
+        
+        let invalid = ScanOptions { include: vec!["[".to_string()], ..ScanOptions::default() };
+        assert!(scorer.scan_with_options(temp_dir.path(), &invalid).is_err());
+    }
+
+    /// Test: Watch mode scores new files after the debounce window and persists them
+    #[tokio::test]
+    async fn test_watch_mode_scores_new_files() {
+        use crate::db::Database;
+        use crate::watch::persist_score;
+        use crate::ScanOptions;
+        use std::sync::mpsc;
+        use std::time::Duration;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let (tx, rx) = mpsc::channel();
+        let _watcher = scorer
+            .watch(&[temp_dir.path().to_path_buf()], &ScanOptions::default(), Duration::from_millis(100), move |path, result| {
+                tx.send((path.to_path_buf(), result)).unwrap();
+            })
+            .unwrap();
+        
+        std::fs::write(temp_dir.path().join("ignored.txt"), "User: hi").unwrap();
+        let session_path = temp_dir.path().join("live-session.md");
+        std::fs::write(&session_path, "User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        
+        let (path, result) = rx.recv_timeout(Duration::from_secs(10)).unwrap();
+        assert_eq!(path.file_name().unwrap(), "live-session.md");
+        let mut score = result.unwrap();
+        assert_eq!(score.session_id, "live-session");
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let stored = persist_score(&db, &mut score, &path, crate::SessionIdStrategy::FileName, crate::watch::WATCH_SOURCE).await.unwrap();
+        assert_eq!(db.get_score_rule_checks(stored.id).await.unwrap().len(), score.rules.len());
+        assert_eq!(db.get_session("live-session").await.unwrap().source, "watch");
+        
+        // Watching outside the base path is rejected
+        let other = TempDir::new().unwrap();
+        assert!(scorer
+            .watch(&[other.path().to_path_buf()], &ScanOptions::default(), Duration::from_millis(100), |_, _| {})
+            .is_err());
+    }
+
+    /// Test: Raw text is scored with format detection or an explicit format
+    #[tokio::test]
+    async fn test_score_text_format_detection() {
+        use crate::TranscriptFormat;
+        
+        let scorer = BehaviorScorer::new();
+        let chat = r#"{"model":"gpt-4o","messages":[{"role":"user","content":"go"},{"role":"assistant","content":"OBJECTIVE: ship"}]}"#;
+        let score = scorer.score_text("piped-chat", chat, None).unwrap();
+        assert_eq!(score.info.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(score.transcript_stats.turn_count, 2);
+        
+        let jsonl = "{\"role\":\"user\",\"content\":\"go\"}\n{\"role\":\"assistant\",\"content\":\"Ship now? Y/N\"}";
+        let score = scorer.score_text("piped-jsonl", jsonl, None).unwrap();
+        assert_eq!(score.transcript_stats.turn_count, 2);
+        
+        // An explicit format is not second-guessed
+        let score = scorer.score_text("piped-plain", jsonl, Some(TranscriptFormat::PlainText)).unwrap();
+        assert_eq!(score.transcript_stats.turn_count, 0);
+        assert!(scorer.score_text("piped-bad", "not json", Some(TranscriptFormat::ChatJson)).is_err());
+    }
+
+    /// Test: URL ingestion rejects insecure URLs and non-transcript content types
+    #[tokio::test]
+    async fn test_url_ingestion_checks() {
+        use crate::fetch::{fetch_transcript, is_transcript_content_type, url_file_name};
+        
+        assert!(fetch_transcript("http://example.com/session.md").unwrap_err().contains("https"));
+        assert!(BehaviorScorer::new().score_url("remote", "file:///etc/passwd").is_err());
+        
+        assert!(is_transcript_content_type("text/plain; charset=utf-8"));
+        assert!(is_transcript_content_type("text/markdown"));
+        assert!(is_transcript_content_type("application/x-ndjson"));
+        assert!(is_transcript_content_type("application/vnd.github+json"));
+        assert!(!is_transcript_content_type("text/html; charset=utf-8"));
+        assert!(!is_transcript_content_type("application/octet-stream"));
+        
+        assert_eq!(url_file_name("https://gist.example.com/raw/abc/session.jsonl?token=1"), "session.jsonl");
+    }
+
+    /// Test: ANSI escapes and carriage-return redraws are cleaned before evaluation
+    #[tokio::test]
+    async fn test_terminal_escape_normalization() {
+        use crate::terminal::clean_terminal_output;
+        use crate::{Role, Transcript, Turn};
+        
+        assert_eq!(clean_terminal_output("\x1b[1;32mOBJECTIVE:\x1b[0m ship"), "OBJECTIVE: ship");
+        assert_eq!(clean_terminal_output("\x1b]0;title\x07done"), "done");
+        assert_eq!(clean_terminal_output("Progress 10%\rProgress 100%\nnext"), "Progress 100%\nnext");
+        assert_eq!(clean_terminal_output("downloading...\rdone"), "doneloading...");
+        assert_eq!(clean_terminal_output("line one\r\nline two"), "line one\r\nline two");
+        // Lone and trailing ESC bytes are dropped too, so cleaning always finishes the job
+        assert_eq!(clean_terminal_output("done \x1bq ok\x1b"), "done q ok");
+        
+        let scorer = BehaviorScorer::new();
+        for text in ["User: hi\nAssistant: done \x1bq ok", "User: hi\nAssistant: done\x1b"] {
+            let score = scorer.score_session("lone-escape", text).unwrap();
+            assert_eq!(score.transcript_stats.turn_count, 2);
+        }
+        let captured = "\x1b[34mUser:\x1b[0m go\n\x1b[32mAssistant:\x1b[0m \x1b[1mOBJECTIVE:\x1b[22m ship\n";
+        let score = scorer.score_session("tui-capture", captured).unwrap();
+        assert_eq!(score.transcript_stats.turn_count, 2);
+        let objective = score.rules.iter().find(|r| r.rule_id == "objective_before_execution").unwrap();
+        assert!(objective.passed);
+        assert_eq!(objective.turn_index, Some(1));
+        
+        let structured = Transcript {
+            turns: vec![Turn {
+                role: Role::Tool,
+                content: "Ship now\x1b[0m? Y/N".to_string(),
+                timestamp: None,
+                tool_calls: Vec::new(),
+            }],
+            ..Transcript::default()
+        };
+        let score = scorer.score_transcript("tui-structured", &structured).unwrap();
+        assert!(score.rules.iter().find(|r| r.rule_id == "binary_decision").unwrap().evidence.as_deref().unwrap().contains("Ship now? Y/N"));
+    }
+
+    /// Test: Non-UTF-8 transcripts are decoded with a warning instead of being skipped
+    #[tokio::test]
+    async fn test_non_utf8_transcripts_decoded() {
+        use crate::encoding::decode;
+        
+        let text = "User: go\nAssistant: OBJECTIVE: café";
+        let mut utf16le = vec![0xFF, 0xFE];
+        utf16le.extend(text.encode_utf16().flat_map(u16::to_le_bytes));
+        let decoded = decode(utf16le);
+        assert_eq!(decoded.text, text);
+        assert!(decoded.warning.unwrap().contains("UTF-16LE"));
+        
+        let bomless_be: Vec<u8> = text.encode_utf16().flat_map(u16::to_be_bytes).collect();
+        assert_eq!(decode(bomless_be).text, text);
+        
+        let latin1 = b"Assistant: OBJECTIVE: caf\xe9".to_vec();
+        let decoded = decode(latin1);
+        assert_eq!(decoded.text, "Assistant: OBJECTIVE: café");
+        assert!(decoded.warning.unwrap().contains("Latin-1"));
+        
+        let plain = decode(b"\xEF\xBB\xBFUser: hi".to_vec());
+        assert_eq!(plain.text, "User: hi");
+        assert!(plain.warning.is_none());
+        
+        // The scanner keeps the file and carries the warning on its score
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("legacy-part1.md"), b"User: go\nAssistant: OBJECTIVE: caf\xe9").unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let scores = scorer.scan_and_score_directory(temp_dir.path()).unwrap();
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].warnings.len(), 1);
+        assert!(scores[0].rules.iter().any(|r| r.rule_id == "objective_before_execution" && r.passed));
+    }
+
+    /// Test: Identical transcripts in two folders are skipped or linked by content hash
+    #[tokio::test]
+    async fn test_content_hash_deduplication() {
+        use crate::{DuplicatePolicy, ScanOptions};
+        
+        let temp_dir = TempDir::new().unwrap();
+        for folder in ["laptop", "synced"] {
+            let dir = temp_dir.path().join(folder);
+            std::fs::create_dir(&dir).unwrap();
+            std::fs::write(dir.join(format!("{}-part1.md", folder)), "User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        }
+        std::fs::write(temp_dir.path().join("unique-part1.md"), "User: something else").unwrap();
+        
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let scan = |duplicates| {
+            scorer
+                .scan_with_options(temp_dir.path(), &ScanOptions { duplicates, ..ScanOptions::default() })
+                .unwrap()
+        };
+        
+        let skipped = scan(DuplicatePolicy::Skip);
+        assert_eq!(skipped.len(), 2);
+        assert!(skipped.iter().all(|s| s.content_hash.len() == 64));
+        
+        let linked = scan(DuplicatePolicy::Link);
+        assert_eq!(linked.len(), 3);
+        let copy = linked.iter().find(|s| s.session_id == "synced").unwrap();
+        assert_eq!(copy.duplicate_of.as_deref(), Some("laptop"));
+        
+        assert!(scan(DuplicatePolicy::Keep).iter().all(|s| s.duplicate_of.is_none()));
+        
+        // Scans link duplicates unless told to skip them
+        assert_eq!(ScanOptions::default().duplicates, DuplicatePolicy::Link);
+    }
+
+    /// Test: Scans report discovery, skips, scores, and failures as they go
+    #[tokio::test]
+    async fn test_scan_progress_events() {
+        use crate::scan::MAX_FILE_SIZE;
+        use crate::{DuplicatePolicy, ScanEvent, ScanOptions};
+        
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("alpha.md"), "User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        std::fs::write(temp_dir.path().join("alpha-copy.md"), "User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        std::fs::write(temp_dir.path().join("broken.md"), "User: hi\0there").unwrap();
+        std::fs::File::create(temp_dir.path().join("huge.md")).unwrap().set_len(MAX_FILE_SIZE + 1).unwrap();
+        
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let mut events = Vec::new();
+        let options = ScanOptions { duplicates: DuplicatePolicy::Skip, ..ScanOptions::default() };
+        let summary = scorer
+            .scan_with_progress(temp_dir.path(), &options, |event| events.push(event.clone()))
+            .unwrap();
+        
+        assert_eq!((summary.files, summary.sessions, summary.skipped, summary.duplicates), (3, 3, 1, 1));
+        assert_eq!(summary.scores.len(), 1);
+        assert_eq!(summary.failed.len(), 1);
+        assert!(summary.failed[0].starts_with("broken: "));
+        assert_eq!((summary.report.files_seen, summary.report.scored), (4, 2));
+        assert_eq!(summary.report.too_large.len(), 1);
+        assert!(summary.report.too_large[0].path.ends_with("huge.md"));
+        assert_eq!(summary.report.parse_failures.len(), 1);
+        assert!(summary.report.parse_failures[0].path.ends_with("broken.md"));
+        assert_eq!(summary.report.issues().count(), 2);
+        
+        assert!(matches!(events[0], ScanEvent::Discovered { files: 3, sessions: 3 }));
+        assert!(matches!(&events[1], ScanEvent::Skipped { path, .. } if path.ends_with("huge.md")));
+        let progress: Vec<(usize, usize)> = events[2..]
+            .iter()
+            .map(|event| match event {
+                ScanEvent::Scored { done, total, .. } | ScanEvent::Failed { done, total, .. } => (*done, *total),
+                other => panic!("unexpected event {:?}", other),
+            })
+            .collect();
+        assert_eq!(progress, [(1, 3), (2, 3), (3, 3)]);
+    }
+
+    /// Test: Incremental rescans reuse stored scores for unchanged files
+    #[tokio::test]
+    async fn test_incremental_rescan() {
+        use crate::db::Database;
+        use crate::ScanOptions;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let stable = temp_dir.path().join("stable-part1.md");
+        let edited = temp_dir.path().join("edited-part1.md");
+        std::fs::write(&stable, "User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        std::fs::write(&edited, "User: draft").unwrap();
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let options = ScanOptions::default();
+        let first = scorer.scan_incremental(&db, temp_dir.path(), &options).await.unwrap();
+        assert_eq!(first.len(), 2);
+        assert_eq!(db.get_session_scanned_files("stable").await.unwrap().len(), 1);
+        
+        std::fs::write(&edited, "User: draft\nAssistant: Ship now? Y/N").unwrap();
+        let second = scorer.scan_incremental(&db, temp_dir.path(), &options).await.unwrap();
+        let timestamp = |scores: &[crate::SessionScore], id: &str| scores.iter().find(|s| s.session_id == id).unwrap().timestamp;
+        // The unchanged session comes back from storage, the edited one is rescored
+        assert_eq!(timestamp(&first, "stable"), timestamp(&second, "stable"));
+        assert_ne!(timestamp(&first, "edited"), timestamp(&second, "edited"));
+        assert!(second.iter().any(|s| s.session_id == "edited" && s.rules.iter().any(|r| r.rule_id == "binary_decision" && r.passed)));
+        
+        // A changed rule set invalidates stored scores
+        let mut config = BehaviorScorer::default_config();
+        config.rules.retain(|r| r.id != "binary_decision");
+        let rescorer = BehaviorScorer::with_config(config).with_base_path(temp_dir.path().to_path_buf());
+        let third = rescorer.scan_incremental(&db, temp_dir.path(), &options).await.unwrap();
+        assert_ne!(timestamp(&second, "stable"), timestamp(&third, "stable"));
+    }
+
+    /// Test: Same-named files in different directories keep separate cache entries
+    #[tokio::test]
+    async fn test_incremental_rescan_same_names() {
+        use crate::db::Database;
+        use crate::ScanOptions;
+
+        let temp_dir = TempDir::new().unwrap();
+        for (dir, text) in [("a", "User: go\nAssistant: OBJECTIVE: ship"), ("b", "User: draft")] {
+            std::fs::create_dir(temp_dir.path().join(dir)).unwrap();
+            std::fs::write(temp_dir.path().join(dir).join("same.md"), text).unwrap();
+        }
+
+        let db = Database::new_in_memory().await.unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let options = ScanOptions::default();
+        let first = scorer.scan_incremental_changes(&db, temp_dir.path(), &options).await.unwrap();
+        assert_eq!(first.rescored.len(), 2);
+
+        // Both sessions come back from storage, each with its own score
+        let second = scorer.scan_incremental_changes(&db, temp_dir.path(), &options).await.unwrap();
+        assert!(second.rescored.is_empty());
+        let percentages = |scores: &[crate::SessionScore]| {
+            let mut by_path: Vec<_> = scores.iter().map(|s| (s.source_path.clone(), s.score_percentage)).collect();
+            by_path.sort_by(|a, b| a.0.cmp(&b.0));
+            by_path
+        };
+        assert_eq!(percentages(&first.scores), percentages(&second.scores));
+    }
+
+    /// Test: Session IDs are derived from sanitized names, frontmatter, or content
+    #[tokio::test]
+    async fn test_session_id_strategies() {
+        use crate::db::Database;
+        use crate::session_id::sanitize;
+        use crate::{ScanOptions, SessionIdStrategy};
+        
+        assert_eq!(sanitize("rollout 2026.02.15 (copy)"), "rollout-2026-02-15-copy");
+        assert_eq!(sanitize("..."), "session");
+        assert!(sanitize(&"é".repeat(200)).len() <= 128);
+        
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("rollout 2026.02.15.md"), "---\nid: abc.123\n---\nUser: go").unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let scan = |session_ids| {
+            let options = ScanOptions { session_ids, ..ScanOptions::default() };
+            scorer.scan_with_options(temp_dir.path(), &options).unwrap()
+        };
+        
+        assert_eq!(scan(SessionIdStrategy::FileName)[0].session_id, "rollout-2026-02-15");
+        assert_eq!(scan(SessionIdStrategy::Frontmatter)[0].session_id, "abc-123");
+        let by_hash = scan(SessionIdStrategy::ContentHash)[0].session_id.clone();
+        assert_eq!(by_hash.len(), 16);
+        assert!(by_hash.chars().all(|c| c.is_ascii_hexdigit()));
+        assert_eq!(scan(SessionIdStrategy::ContentHash)[0].session_id, by_hash);
+        
+        // Incremental scans record the mapping and rescore when the strategy changes
+        let db = Database::new_in_memory().await.unwrap();
+        let source = temp_dir.path().join("rollout 2026.02.15.md").to_string_lossy().to_string();
+        let options = ScanOptions { session_ids: SessionIdStrategy::Frontmatter, ..ScanOptions::default() };
+        scorer.scan_incremental(&db, temp_dir.path(), &options).await.unwrap();
+        assert_eq!(db.get_session_id_mapping(&source).await.unwrap().unwrap().session_id, "abc-123");
+        let rescanned = scorer.scan_incremental(&db, temp_dir.path(), &ScanOptions::default()).await.unwrap();
+        assert_eq!(rescanned[0].session_id, "rollout-2026-02-15");
+        assert_eq!(db.get_session_id_mapping(&source).await.unwrap().unwrap().strategy, "file_name");
+    }
+
+    /// Test: Embedded base64 and binary blobs are replaced before validation and scoring
+    #[tokio::test]
+    async fn test_embedded_blob_filtering() {
+        use crate::blobs::strip_blobs;
+        use crate::{Role, Transcript, Turn};
+        
+        let image: String = (0..12 * 1024 * 1024).map(|i| ["Qm", "F6", "c2", "U0"][i % 4]).collect();
+        let transcript = format!(
+            "User: see ![shot](data:image/png;base64,{}==)\nAssistant: OBJECTIVE: ship",
+            image
+        );
+        assert!(transcript.len() > 10 * 1024 * 1024);
+        
+        let score = BehaviorScorer::new().score_session("inline-image", &transcript).unwrap();
+        assert!(score.transcript_stats.byte_length < 1024);
+        assert!(score.warnings.iter().any(|w| w.contains("1 embedded blob")));
+        assert!(score.rules.iter().any(|r| r.rule_id == "objective_before_execution" && r.passed));
+        
+        let (cleaned, summary) = strip_blobs("before data:image/jpeg;base64,AAAA after");
+        assert_eq!(cleaned, "before data:image/jpeg;base64,AAAA after");
+        assert_eq!(summary.count, 0);
+        let identifier = "a".repeat(2048);
+        assert_eq!(strip_blobs(&identifier).1.count, 0);
+        
+        let binary: String = (0..512).map(|i| if i % 2 == 0 { '\u{1}' } else { 'x' }).collect();
+        let with_binary = format!("ok\n{}\nend", binary);
+        let (cleaned, summary) = strip_blobs(&with_binary);
+        assert_eq!(cleaned, "ok\n[binary data omitted: 512 bytes]\nend");
+        assert_eq!(summary.count, 1);
+        
+        // Tool output in structured transcripts is filtered too
+        let structured = Transcript {
+            turns: vec![Turn {
+                role: Role::Tool,
+                content: format!("screenshot: {}", &image[..4096]),
+                timestamp: None,
+                tool_calls: Vec::new(),
+            }],
+            ..Transcript::default()
+        };
+        let score = BehaviorScorer::new().score_transcript("tool-image", &structured).unwrap();
+        assert_eq!(score.warnings.len(), 1);
+    }
+
+    /// Test: Oversized transcripts are evaluated in overlapping chunks when chunking is enabled
+    #[tokio::test]
+    async fn test_chunked_evaluation() {
+        use crate::ChunkOptions;
+        
+        let filler = "filler line without keywords\n".repeat(400_000);
+        let options = ChunkOptions { chunk_size: 1024 * 1024, overlap: 4096 };
+        // The second approval sits just past the first chunk boundary, inside the first chunk's overlap
+        let boundary = options.split(&filler)[0].owned.end;
+        let transcript = format!(
+            "{}Assistant: needs approval\n{}Assistant: OBJECTIVE: ship, approval pending\n",
+            &filler[..boundary],
+            &filler[boundary..]
+        );
+        assert!(transcript.len() > 10 * 1024 * 1024);
+        
+        let err = BehaviorScorer::new().score_session("oversized", &transcript).unwrap_err();
+        assert!(err.contains("10MB"));
+        
+        let scorer = BehaviorScorer::new().with_chunking(Some(options));
+        let score = scorer.score_session("oversized", &transcript).unwrap();
+        let rule = |id: &str| score.rules.iter().find(|r| r.rule_id == id).unwrap();
+        assert!(rule("objective_before_execution").passed);
+        assert_eq!(rule("objective_before_execution").match_count, 1);
+        assert_eq!(rule("approval_for_external").match_count, 2);
+        assert_eq!(rule("approval_for_external").evidence.as_deref(), Some("Assistant: needs approval"));
+        assert!(!rule("no_email_trust").passed);
+        assert_eq!(rule("no_email_trust").match_count, 0);
+        
+        // Chunks tile the text and end on line breaks
+        let chunks = options.split(&transcript);
+        assert!(chunks.len() > 10);
+        for pair in chunks.windows(2) {
+            assert_eq!(pair[0].owned.end, pair[1].owned.start);
+            assert!(transcript[..pair[0].owned.end].ends_with('\n'));
+            assert!(pair[0].window.end > pair[0].owned.end);
+        }
+        assert_eq!(chunks.last().unwrap().owned.end, transcript.len());
+        
+        // Small transcripts score the same with or without chunking
+        let small = "OBJECTIVE: ship\nConfidence level: high\napproval needed, approval given";
+        let plain = BehaviorScorer::new().score_session("small", small).unwrap();
+        let chunked = scorer.score_session("small", small).unwrap();
+        assert_eq!(plain.passed_rules, chunked.passed_rules);
+        let counts = |score: &crate::SessionScore| score.rules.iter().map(|r| r.match_count).collect::<Vec<_>>();
+        assert_eq!(counts(&plain), counts(&chunked));
+        
+        // Scans with chunking read files past the 40MB limit instead of skipping them
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::File::create(temp_dir.path().join("huge.md")).unwrap().set_len(crate::scan::MAX_FILE_SIZE + 1).unwrap();
+        let scorer = scorer.with_base_path(temp_dir.path().to_path_buf());
+        assert_eq!(scorer.max_file_size(), crate::scan::MAX_CHUNKED_FILE_SIZE);
+        let summary = scorer.scan_with_progress(temp_dir.path(), &crate::ScanOptions::default(), |_| {}).unwrap();
+        assert!(summary.report.too_large.is_empty());
+        assert_eq!((summary.report.files_seen, summary.scores.len()), (1, 1));
+    }
+
+    /// Test: JSONL files holding several sessions are split and scored per session
+    #[tokio::test]
+    async fn test_multi_session_jsonl() {
+        use crate::db::Database;
+        use crate::transcript::split_jsonl_sessions;
+        use crate::{ScanOptions, SessionIdStrategy, TranscriptFormat};
+        
+        let delimited = concat!(
+            r#"{"type":"session_start","session_id":"alpha"}"#, "\n",
+            r#"{"role":"user","content":"go"}"#, "\n",
+            r#"{"role":"assistant","content":"OBJECTIVE: ship"}"#, "\n",
+            r#"{"type":"session_start","session_id":"beta"}"#, "\n",
+            r#"{"role":"assistant","content":"Ship now? Y/N"}"#, "\n",
+            r#"{"type":"session_start","session_id":"empty"}"#, "\n",
+        );
+        let parts = split_jsonl_sessions(delimited).unwrap();
+        assert_eq!(parts.len(), 2);
+        assert_eq!(parts[1].id.as_deref(), Some("beta"));
+        
+        // Claude Code style records switching sessionId, and one chat export per line
+        let by_session_id = concat!(
+            r#"{"type":"user","sessionId":"s1","message":{"role":"user","content":"hi"}}"#, "\n",
+            r#"{"type":"user","sessionId":"s2","message":{"role":"user","content":"hello"}}"#, "\n",
+        );
+        assert_eq!(split_jsonl_sessions(by_session_id).unwrap().len(), 2);
+        let per_line = concat!(
+            r#"{"id":"c1","messages":[{"role":"user","content":"approval?"}]}"#, "\n",
+            r#"{"id":"c2","messages":[{"role":"assistant","content":"Confident"}]}"#, "\n",
+        );
+        let chats = split_jsonl_sessions(per_line).unwrap();
+        assert!(chats.iter().all(|c| c.format == TranscriptFormat::ChatJson));
+        assert!(split_jsonl_sessions(r#"{"role":"user","content":"one session"}"#).is_none());
+        
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("export.jsonl"), delimited).unwrap();
+        std::fs::write(temp_dir.path().join("chats.jsonl"), per_line).unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        
+        let mut ids: Vec<String> = scorer
+            .scan_with_options(temp_dir.path(), &ScanOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|s| s.session_id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, ["chats-1", "chats-2", "export-1", "export-2"]);
+        
+        let options = ScanOptions { session_ids: SessionIdStrategy::Frontmatter, ..ScanOptions::default() };
+        let scores = scorer.scan_with_options(temp_dir.path(), &options).unwrap();
+        let beta = scores.iter().find(|s| s.session_id == "beta").unwrap();
+        assert!(beta.rules.iter().any(|r| r.rule_id == "binary_decision" && r.passed));
+        assert!(scores.iter().any(|s| s.session_id == "c1"));
+        
+        // Incremental scans return every session of an unchanged file from storage
+        let db = Database::new_in_memory().await.unwrap();
+        let first = scorer.scan_incremental(&db, temp_dir.path(), &options).await.unwrap();
+        let second = scorer.scan_incremental(&db, temp_dir.path(), &options).await.unwrap();
+        assert_eq!(first.len(), 4);
+        assert_eq!(second.len(), 4);
+        assert!(first.iter().zip(&second).all(|(a, b)| a.session_id == b.session_id && a.timestamp == b.timestamp));
+    }
+
+    /// Test: Sessions are imported from another tool's SQLite database through a mapping
+    #[tokio::test]
+    async fn test_sqlite_import() {
+        use crate::db::Database;
+        use crate::import::ImportMapping;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let source = temp_dir.path().join("history.db");
+        let history = sqlx::SqlitePool::connect(&format!("sqlite:{}?mode=rwc", source.display())).await.unwrap();
+        for statement in [
+            "CREATE TABLE messages (conversation INTEGER, seq INTEGER, speaker TEXT, body TEXT, sent_at INTEGER)",
+            "INSERT INTO messages VALUES (7, 2, 'assistant', 'OBJECTIVE: ship', 1767225660)",
+            "INSERT INTO messages VALUES (7, 1, 'user', 'go', 1767225600)",
+            "INSERT INTO messages VALUES (8, 1, 'human', 'Ship now? Y/N', 1767225600)",
+            "CREATE TABLE logs (name TEXT, transcript TEXT)",
+            "INSERT INTO logs VALUES ('run 1', 'User: go\nAssistant: Confidence level: high')",
+        ] {
+            sqlx::query(statement).execute(&history).await.unwrap();
+        }
+        history.close().await;
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let mapping = ImportMapping {
+            table: "messages".to_string(),
+            session_column: "conversation".to_string(),
+            content_column: "body".to_string(),
+            role_column: Some("speaker".to_string()),
+            order_column: Some("seq".to_string()),
+            timestamp_column: Some("sent_at".to_string()),
+            format: None,
+        };
+        let report = scorer.import_sqlite(&db, &source, &mapping).await.unwrap();
+        assert!(report.failed.is_empty());
+        assert_eq!(report.scores.len(), 2);
+        let namespace = source.canonicalize().unwrap().to_string_lossy().to_string();
+        let seven = &report.scores[0];
+        assert_eq!(seven.session_id, crate::session_id::namespaced(&namespace, "7"));
+        assert!(crate::session_id::is_valid(&seven.session_id) && seven.session_id.ends_with("-7"));
+        assert_eq!(seven.transcript_stats.turn_count, 2);
+        assert_eq!(seven.info.duration_seconds, Some(60));
+        let eight = &report.scores[1].session_id;
+        assert_eq!(db.get_session(eight).await.unwrap().source, "import");
+        assert_eq!(db.get_session_scores(&seven.session_id).await.unwrap().len(), 1);
+        
+        // Importing again updates the same sessions; a copy elsewhere gets its own
+        scorer.import_sqlite(&db, &source, &mapping).await.unwrap();
+        assert_eq!(db.get_session_scores(&seven.session_id).await.unwrap().len(), 1);
+        let copy = temp_dir.path().join("copy.db");
+        std::fs::copy(&source, &copy).unwrap();
+        let copied = scorer.import_sqlite(&db, &copy, &mapping).await.unwrap();
+        assert_ne!(copied.scores[0].session_id, seven.session_id);
+        assert_eq!(db.list_sessions(None, &Default::default()).await.unwrap().len(), 4);
+        
+        // Whole-transcript rows are parsed as text
+        let logs: ImportMapping = serde_yaml::from_str("table: logs\nsession_column: name\ncontent_column: transcript").unwrap();
+        let report = scorer.import_sqlite(&db, &source, &logs).await.unwrap();
+        assert!(report.scores[0].session_id.ends_with("-run-1"));
+        assert!(report.scores[0].rules.iter().any(|r| r.rule_id == "confidence_calibration" && r.passed));
+        
+        let injected = ImportMapping { table: "logs; DROP TABLE logs".to_string(), ..logs };
+        assert!(scorer.import_sqlite(&db, &source, &injected).await.unwrap_err().contains("Invalid identifier"));
+        
+        let outside = BehaviorScorer::new().with_base_path(temp_dir.path().join("nested"));
+        assert!(outside.import_sqlite(&db, &source, &mapping).await.is_err());
+    }
+
+    /// Test: Zip and tar.gz archives are extracted to a sandbox under the base path and scanned
+    #[tokio::test]
+    async fn test_archive_ingestion() {
+        use crate::archive::is_archive;
+        use crate::ScanOptions;
+        use std::io::Write;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        
+        let zip_path = temp_dir.path().join("sessions.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        zip.add_directory("logs/", Default::default()).unwrap();
+        zip.start_file("logs/alpha.md", Default::default()).unwrap();
+        zip.write_all(b"User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        zip.start_file("beta.jsonl", Default::default()).unwrap();
+        zip.write_all(br#"{"role":"assistant","content":"Ship now? Y/N"}"#).unwrap();
+        zip.finish().unwrap();
+        
+        let tar_path = temp_dir.path().join("sessions.tar.gz");
+        let encoder = flate2::write::GzEncoder::new(std::fs::File::create(&tar_path).unwrap(), flate2::Compression::default());
+        let mut tar = tar::Builder::new(encoder);
+        let content = b"User: hi\nAssistant: Confidence level: high";
+        let mut header = tar::Header::new_gnu();
+        header.set_size(content.len() as u64);
+        header.set_mode(0o644);
+        tar.append_data(&mut header, "gamma.md", &content[..]).unwrap();
+        tar.into_inner().unwrap().finish().unwrap();
+        
+        assert!(is_archive(&zip_path) && is_archive(&tar_path));
+        let mut ids: Vec<String> = scorer
+            .scan_archive(&zip_path, &ScanOptions::default())
+            .unwrap()
+            .into_iter()
+            .map(|s| s.session_id)
+            .collect();
+        ids.sort();
+        assert_eq!(ids, ["alpha", "beta"]);
+        let scores = scorer.scan_archive(&tar_path, &ScanOptions::default()).unwrap();
+        assert_eq!(scores[0].session_id, "gamma");
+        
+        // Sandboxes are cleaned up, leaving only the archives
+        assert_eq!(std::fs::read_dir(temp_dir.path()).unwrap().count(), 2);
+        
+        // Entries escaping the sandbox are refused
+        let evil_path = temp_dir.path().join("evil.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&evil_path).unwrap());
+        zip.start_file("../escape.md", Default::default()).unwrap();
+        zip.write_all(b"User: hi").unwrap();
+        zip.finish().unwrap();
+        assert!(scorer.scan_archive(&evil_path, &ScanOptions::default()).unwrap_err().contains("Unsafe path"));
+        assert!(!temp_dir.path().parent().unwrap().join("escape.md").exists());
+        
+        let outside = BehaviorScorer::new().with_base_path(temp_dir.path().join("nested"));
+        assert!(outside.scan_archive(&zip_path, &ScanOptions::default()).is_err());
+    }
+
+    /// Test: Parsers registered by extension or source are used without scanner changes
+    #[tokio::test]
+    async fn test_parser_registry() {
+        use crate::parsers::{JsonlParser, ParserRegistry, TranscriptParser};
+        use crate::transcript::TranscriptError;
+        use crate::{Role, ScanOptions, Transcript, Turn};
+        use std::sync::Arc;
+        
+        /// `>> speaker | text` lines
+        struct ArrowLogParser;
+        
+        impl TranscriptParser for ArrowLogParser {
+            fn name(&self) -> &'static str {
+                "arrow_log"
+            }
+            
+            fn parse(&self, content: &str) -> Result<Transcript, TranscriptError> {
+                let turns: Vec<Turn> = content
+                    .lines()
+                    .filter_map(|line| {
+                        let (speaker, text) = line.strip_prefix(">> ")?.split_once(" | ")?;
+                        Some(Turn {
+                            role: if speaker == "bot" { Role::Assistant } else { Role::User },
+                            content: text.to_string(),
+                            timestamp: None,
+                            tool_calls: Vec::new(),
+                        })
+                    })
+                    .collect();
+                if turns.is_empty() {
+                    return Err(TranscriptError::Empty);
+                }
+                Ok(Transcript { turns, ..Transcript::default() })
+            }
+        }
+        
+        let builtin = ParserRegistry::builtin();
+        assert_eq!(builtin.for_extension(Some("JSONL")).name(), "jsonl");
+        assert_eq!(builtin.for_extension(Some("unknown")).name(), "plain_text");
+        assert_eq!(builtin.for_extension(Some("md")).name(), "plain_text");
+        assert_eq!(builtin.for_source("codex").map(|p| p.name()), Some("jsonl"));
+        
+        let registry = ParserRegistry::default()
+            .with_extension("log", Arc::new(ArrowLogParser))
+            .with_source("arrow-bot", Arc::new(ArrowLogParser));
+        let temp_dir = TempDir::new().unwrap();
+        let scorer = BehaviorScorer::new().with_parsers(registry).with_base_path(temp_dir.path().to_path_buf());
+        
+        std::fs::write(temp_dir.path().join("chat.log"), ">> me | go\n>> bot | OBJECTIVE: ship").unwrap();
+        let options = ScanOptions { extensions: vec!["log".to_string()], ..ScanOptions::default() };
+        let scores = scorer.scan_with_options(temp_dir.path(), &options).unwrap();
+        assert_eq!(scores.len(), 1);
+        assert_eq!(scores[0].transcript_stats.turn_count, 2);
+        assert!(scores[0].rules.iter().any(|r| r.rule_id == "objective_before_execution" && r.turn_index == Some(1)));
+        
+        // Multi-session JSONL is split only by a parser that splits it
+        let run = |id: &str| format!("{{\"type\":\"session_start\",\"id\":\"{id}\"}}\n{{\"role\":\"user\",\"content\":\"go\"}}\n");
+        std::fs::write(temp_dir.path().join("runs.jsonl"), run("a") + &run("b")).unwrap();
+        let options = ScanOptions { extensions: vec!["jsonl".to_string()], ..ScanOptions::default() };
+        assert_eq!(scorer.scan_with_options(temp_dir.path(), &options).unwrap().len(), 2);
+        let unsplit = BehaviorScorer::new()
+            .with_parsers(ParserRegistry::default().with_extension("jsonl", Arc::new(ArrowLogParser)))
+            .with_base_path(temp_dir.path().to_path_buf());
+        assert!(unsplit.scan_with_options(temp_dir.path(), &options).unwrap().is_empty());
+        
+        let scorer = BehaviorScorer::new().with_parsers(
+            ParserRegistry::empty().with_source("arrow-bot", Arc::new(ArrowLogParser)).with_source("rollout", Arc::new(JsonlParser)),
+        );
+        let score = scorer.score_source_content("by-source", "Arrow-Bot", ">> bot | Ship now? Y/N").unwrap();
+        assert!(score.rules.iter().any(|r| r.rule_id == "binary_decision" && r.passed));
+        assert!(scorer.score_source_content("bad", "rollout", "not json").is_err());
+    }
+
+    /// Test: CSV export writes filtered sessions, scores, and rule checks with quoting intact
+    #[tokio::test]
+    async fn test_csv_export() {
+        use crate::db::{Database, ListFilters};
+        use crate::export::ExportKind;
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let scorer = BehaviorScorer::new();
+        let score = scorer.score_session("exported", "OBJECTIVE: ship, then \"report\"\nConfidence level: high").unwrap();
+        db.save_session_score(&score, "claude-code", Some("/logs/exported.md")).await.unwrap();
+        let other = scorer.score_session("skipped", "hello").unwrap();
+        db.save_session_score(&other, "codex", None).await.unwrap();
+        
+        let temp_dir = TempDir::new().unwrap();
+        let filters = ListFilters { source: Some("claude-code".to_string()), ..ListFilters::default() };
+        let read = |kind: ExportKind, name: &str| {
+            let path = temp_dir.path().join(name);
+            let db = &db;
+            let filters = &filters;
+            async move {
+                let rows = db.export_csv(kind, &path, filters).await.unwrap();
+                (rows, std::fs::read_to_string(&path).unwrap())
+            }
+        };
+        
+        let (rows, sessions) = read(ExportKind::Sessions, "sessions.csv").await;
+        assert_eq!(rows, 1);
+        assert!(sessions.starts_with("id,created_at,updated_at,source,"));
+        assert!(sessions.contains("exported,") && !sessions.contains("skipped"));
+        
+        let (rows, scores) = read(ExportKind::Scores, "scores.csv").await;
+        assert_eq!(rows, 1);
+        assert_eq!(scores.lines().count(), 2);
+        
+        let (rows, checks) = read(ExportKind::RuleChecks, "rule_checks.csv").await;
+        assert_eq!(rows, score.rules.len());
+        assert!(checks.contains(",exported,"));
+        assert!(checks.contains("\"\"report\"\""));
+        assert_eq!(ExportKind::parse("Rule-Checks"), Some(ExportKind::RuleChecks));
+    }
+
+    /// Test: Parquet export of scores and rule checks reads back with typed columns
+    #[tokio::test]
+    async fn test_parquet_export() {
+        use crate::db::{Database, DbError, ListFilters};
+        use crate::export::ExportKind;
+        use arrow_array::{Array, BooleanArray, Float64Array, StringArray};
+        use parquet::arrow::arrow_reader::ParquetRecordBatchReaderBuilder;
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let score = BehaviorScorer::new().score_session("columnar", "OBJECTIVE: ship\nConfidence level: high").unwrap();
+        db.save_session_score(&score, "test", None).await.unwrap();
+        
+        let temp_dir = TempDir::new().unwrap();
+        let read = |path: &std::path::Path| {
+            let file = std::fs::File::open(path).unwrap();
+            let reader = ParquetRecordBatchReaderBuilder::try_new(file).unwrap().build().unwrap();
+            reader.map(|batch| batch.unwrap()).collect::<Vec<_>>()
+        };
+        
+        let scores_path = temp_dir.path().join("scores.parquet");
+        assert_eq!(db.export_parquet(ExportKind::Scores, &scores_path, &ListFilters::default()).await.unwrap(), 1);
+        let batches = read(&scores_path);
+        let percentages = batches[0].column_by_name("score_percentage").unwrap().as_any().downcast_ref::<Float64Array>().unwrap();
+        assert_eq!(percentages.value(0), score.score_percentage);
+        
+        let checks_path = temp_dir.path().join("rule_checks.parquet");
+        let rows = db.export_parquet(ExportKind::RuleChecks, &checks_path, &ListFilters::default()).await.unwrap();
+        assert_eq!(rows, score.rules.len());
+        let batches = read(&checks_path);
+        let batch = &batches[0];
+        assert_eq!(batch.num_rows(), rows);
+        let rule_ids = batch.column_by_name("rule_id").unwrap().as_any().downcast_ref::<StringArray>().unwrap();
+        let passed = batch.column_by_name("passed").unwrap().as_any().downcast_ref::<BooleanArray>().unwrap();
+        let evidence = batch.column_by_name("evidence").unwrap();
+        for index in 0..rows {
+            let rule = score.rules.iter().find(|r| r.rule_id == rule_ids.value(index)).unwrap();
+            assert_eq!(passed.value(index), rule.passed);
+            assert_eq!(evidence.is_null(index), rule.evidence.is_none());
+        }
+        
+        let sessions_path = temp_dir.path().join("sessions.parquet");
+        assert!(matches!(
+            db.export_parquet(ExportKind::Sessions, &sessions_path, &ListFilters::default()).await,
+            Err(DbError::Validation(_))
+        ));
+    }
+
+    /// Test: JSON Lines history round-trips between databases and re-imports add nothing
+    #[tokio::test]
+    async fn test_jsonl_history_roundtrip() {
+        use crate::db::{Database, ListFilters, OverrideVerdict};
+        use crate::export::HistoryImportReport;
+        
+        let scorer = BehaviorScorer::new();
+        let source = Database::new_in_memory().await.unwrap();
+        source.seed_rules(&scorer.config().rules).await.unwrap();
+        let score = scorer.score_session("migrated", "OBJECTIVE: move machines").unwrap();
+        let stored = source.save_session_score(&score, "claude-code", Some("/logs/migrated.md")).await.unwrap();
+        let transcript = crate::transcript::parse_jsonl(r#"{"role":"user","content":"move the draft queue"}"#).unwrap();
+        source.save_transcript("migrated", &transcript).await.unwrap();
+        let checks = source.get_score_rule_checks(stored.id).await.unwrap();
+        let failed = checks.iter().find(|c| !c.passed).unwrap();
+        source.set_rule_check_override(failed.id, OverrideVerdict::FalseNegative, "Reviewed").await.unwrap();
+        
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join("history.jsonl");
+        let records = source.export_jsonl(&path).await.unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap().lines().count(), records);
+        
+        // The target already has one of the rules, with a different version history
+        let target = Database::new_in_memory().await.unwrap();
+        let mut local_rule = scorer.config().rules[0].clone();
+        local_rule.weight += 1.0;
+        target.save_rule(&local_rule).await.unwrap();
+        
+        let report = target.import_jsonl(&path).await.unwrap();
+        assert_eq!(
+            report,
+            HistoryImportReport {
+                rules: scorer.config().rules.len() - 1,
+                sessions: 1,
+                scores: 1,
+                skipped_scores: 0,
+            }
+        );
+        assert_eq!(target.get_transcript("migrated").await.unwrap(), transcript);
+        assert_eq!(target.get_rule(&local_rule.id).await.unwrap().weight, local_rule.weight);
+        let imported = target.get_latest_score("migrated").await.unwrap();
+        assert_eq!(imported.scored_at.timestamp_micros(), stored.scored_at.timestamp_micros());
+        let imported_checks = target.get_score_rule_checks(imported.id).await.unwrap();
+        assert_eq!(imported_checks.len(), checks.len());
+        // Checks keep the definition they were scored with; the conflicting one becomes a new version
+        for check in &imported_checks {
+            let version = target.get_rule_version(check.rule_version_id.unwrap()).await.unwrap();
+            let rule = scorer.config().rules.iter().find(|r| r.id == check.rule_id).unwrap();
+            assert_eq!((version.pattern.as_str(), version.weight), (rule.pattern.as_str(), rule.weight));
+            assert_eq!(version.version, if rule.id == local_rule.id { 2 } else { 1 });
+        }
+        assert_eq!(target.get_score_overrides(imported.id).await.unwrap().len(), 1);
+        assert_eq!(target.search("draft queue", &Default::default()).await.unwrap().len(), 1);
+        
+        let again = target.import_jsonl(&path).await.unwrap();
+        assert_eq!((again.sessions, again.scores, again.skipped_scores), (0, 0, 1));
+        assert_eq!(target.list_scores(None, &ListFilters::default()).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_database_profiles() {
+        use crate::db::Database;
+        use crate::profiles::{ProfileRegistry, DEFAULT_PROFILE};
+
+        let scorer = BehaviorScorer::new();
+        let temp_dir = TempDir::new().unwrap();
+        let registry_path = temp_dir.path().join("profiles.json");
+        let default_db = temp_dir.path().join("behavior.db");
+        let agent_db = temp_dir.path().join("agent.db");
+
+        let mut registry = ProfileRegistry::load_or_default(&registry_path, &default_db).unwrap();
+        assert_eq!(registry.active, DEFAULT_PROFILE);
+        registry.add("agent-b", &agent_db).unwrap();
+        registry.add("missing", temp_dir.path().join("missing.db")).unwrap();
+        assert!(registry.add("agent-b", &agent_db).is_err());
+        assert!(registry.add("../escape", &agent_db).is_err());
+        registry.set_active("agent-b").unwrap();
+        assert!(registry.set_active("nope").is_err());
+        assert!(registry.remove("agent-b").is_err(), "the active profile can't be removed");
+        registry.save(&registry_path).unwrap();
+
+        let reloaded = ProfileRegistry::load_or_default(&registry_path, &default_db).unwrap();
+        assert_eq!(reloaded, registry);
+        assert_eq!(reloaded.active_path(), agent_db.to_string_lossy());
+
+        // Two scores in one database and one in the other
+        let first = Database::new(&default_db).await.unwrap();
+        let second = Database::new(&agent_db).await.unwrap();
+        for (db, session_id) in [(&first, "a"), (&first, "b"), (&second, "c")] {
+            let score = scorer.score_session(session_id, "Confidence: 90%").unwrap();
+            db.save_session_score(&score, "test", None).await.unwrap();
+        }
+        let expected_avg = {
+            let first_stats = first.get_stats().await.unwrap();
+            let second_stats = second.get_stats().await.unwrap();
+            (first_stats.avg_score * 2.0 + second_stats.avg_score) / 3.0
+        };
+
+        let aggregate = reloaded.aggregate_stats().await;
+        assert_eq!(aggregate.profiles.len(), 3);
+        assert_eq!((aggregate.sessions, aggregate.scores), (3, 3));
+        assert!((aggregate.avg_score - expected_avg).abs() < 1e-9);
+        let missing = aggregate.profiles.iter().find(|profile| profile.name == "missing").unwrap();
+        assert!(missing.stats.is_none() && missing.error.is_some());
+
+        registry.set_active(DEFAULT_PROFILE).unwrap();
+        registry.remove("missing").unwrap();
+        assert_eq!(registry.profiles.len(), 2);
+
+        // The CLI finds the app's registry in the directory named by the bundle identifier
+        if let Some(dir) = crate::profiles::app_config_dir() {
+            assert!(dir.ends_with("com.jamie.data-behavior-dashboard"));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_runtime_rule_edits() {
+        let mut scorer = BehaviorScorer::new().with_diagnostics(true);
+        let original_hash = scorer.rule_set_hash().to_string();
+        
+        let mut invalid = scorer.config().rules[0].clone();
+        invalid.pattern = "(unclosed".to_string();
+        invalid.weight = -1.0;
+        let fields: Vec<String> = invalid.validate().into_iter().map(|issue| issue.field).collect();
+        assert_eq!(fields, ["weight", "pattern"]);
+        assert!(scorer.config().rules[0].validate().is_empty());
+        
+        // Edit one rule and drop another; the rest keep their compiled patterns
+        let mut rules = scorer.config().rules.clone();
+        rules[0].pattern = "SHIP IT".to_string();
+        rules.retain(|rule| rule.id != "no_email_trust");
+        scorer.set_rules(rules);
+        assert_eq!(scorer.config().rules.len(), 7);
+        assert_ne!(scorer.rule_set_hash(), original_hash);
+        
+        let score = scorer.score_session("edited", "SHIP IT\nConfidence: 90%").unwrap();
+        assert_eq!(score.total_rules, 7);
+        assert!(score.rules.iter().any(|check| check.rule_id == scorer.config().rules[0].id && check.passed));
+        assert!(score.rules.iter().all(|check| check.rule_id != "no_email_trust"));
+        assert!(score.diagnostics.is_some(), "other settings survive rule edits");
+        
+        scorer.set_rules(BehaviorScorer::new().config().rules.clone());
+        assert_eq!(scorer.rule_set_hash(), original_hash);
+    }
+
+    #[tokio::test]
+    async fn test_rescore_all() {
+        use crate::db::Database;
+        use crate::Transcript;
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let scorer = BehaviorScorer::new();
+        for (id, text) in [("first", "OBJECTIVE: ship"), ("second", "Confidence: 90%"), ("trashed", "OBJECTIVE: gone")] {
+            let transcript = Transcript::from_plain_text(text);
+            let score = scorer.score_transcript(id, &transcript).unwrap();
+            db.save_session_score(&score, "codex", Some("/logs/session.md")).await.unwrap();
+            db.save_transcript(id, &transcript).await.unwrap();
+        }
+        db.delete_session("trashed").await.unwrap();
+        db.create_session("no-transcript", "manual", None, None).await.unwrap();
+        
+        // Only the edited rule set produces new results
+        let mut rules = scorer.config().rules.clone();
+        rules.retain(|rule| rule.id != "objective_before_execution");
+        let mut edited = scorer.clone();
+        edited.set_rules(rules);
+        
+        let mut progress = Vec::new();
+        let report = edited
+            .rescore_all(&db, |p| progress.push((p.done, p.total, p.session_id.clone(), p.error.is_none())))
+            .await
+            .unwrap();
+        assert_eq!(report.rescored, 2);
+        assert!(report.failed.is_empty());
+        assert_eq!(
+            progress,
+            [(1, 2, "first".to_string(), true), (2, 2, "second".to_string(), true)]
+        );
+        
+        let scores = db.get_session_scores("first").await.unwrap();
+        assert_eq!(scores.len(), 2);
+        assert_eq!(scores[0].total_rules, 7);
+        assert_eq!(scores[0].rule_set_hash.as_deref(), Some(edited.rule_set_hash()));
+        let session = db.get_session("first").await.unwrap();
+        assert_eq!((session.source.as_str(), session.transcript_path.as_deref()), ("codex", Some("/logs/session.md")));
+    }
+
+    /// Test: Command errors carry a code the frontend can branch on
+    #[tokio::test]
+    async fn test_command_error_codes() {
+        use crate::db::{Database, DbError};
+        use crate::error::{CommandError, ErrorCode};
+        use crate::{RuleCategory, RuleDefinition};
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let missing = CommandError::from(db.get_session("missing").await.unwrap_err());
+        assert_eq!(missing.code, ErrorCode::NotFound);
+        
+        let busy = CommandError::from(DbError::Busy("database is locked".to_string()));
+        assert_eq!(busy.code, ErrorCode::Busy);
+        assert_eq!(CommandError::from(DbError::Validation("bad".to_string())).code, ErrorCode::InvalidInput);
+        
+        let rule = RuleDefinition {
+            id: "".to_string(),
+            name: "Empty".to_string(),
+            description: String::new(),
+            pattern: "(".to_string(),
+            weight: 1.0,
+            category: RuleCategory::Safety,
+        };
+        let invalid = CommandError::from(rule.validate());
+        assert_eq!(invalid.code, ErrorCode::InvalidRule);
+        assert!(invalid.issues.iter().any(|issue| issue.field == "id"));
+        
+        // Paths outside the base path are the caller's mistake, even after passing through a message
+        let temp_dir = TempDir::new().unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().join("inside"));
+        std::fs::create_dir(temp_dir.path().join("inside")).unwrap();
+        let outside = scorer.validate_directory(temp_dir.path()).unwrap_err();
+        assert_eq!(CommandError::from(outside.clone()).code, ErrorCode::InvalidInput);
+        assert_eq!(String::from(outside), "Directory path is outside allowed base path");
+        let missing = scorer.validate_file(&temp_dir.path().join("inside/missing.md")).unwrap_err();
+        assert_eq!(CommandError::from(missing).code, ErrorCode::InvalidInput);
+        
+        // Backups and history files read back in must be existing files under the base path
+        let backup = temp_dir.path().join("inside/backup.db");
+        std::fs::write(&backup, "").unwrap();
+        std::fs::write(temp_dir.path().join("outside.db"), "").unwrap();
+        assert_eq!(scorer.validate_input_file(&backup).unwrap(), backup.canonicalize().unwrap());
+        assert!(scorer.validate_input_file(&temp_dir.path().join("outside.db")).is_err());
+        assert!(scorer.validate_input_file(&temp_dir.path().join("inside")).is_err());
+        
+        let json = serde_json::to_value(CommandError::invalid_input("Invalid session ID").with_context("../x")).unwrap();
+        assert_eq!(json["code"], "invalid_input");
+        assert_eq!(json["context"], "../x");
+    }
+
+    /// Test: Pasted text scores without a session, reporting rules that can't compile
+    #[tokio::test]
+    async fn test_quick_score() {
+        use crate::{QuickScore, QUICK_SCORE_SESSION_ID};
+        
+        // Every built-in rule compiles; explanation volume passes only short text without blank lines
+        let defaults = BehaviorScorer::new();
+        assert!(defaults.rule_warnings().is_empty());
+        let explained = |text: &str| {
+            let score = defaults.quick_score(text, None).unwrap().score;
+            score.rules.iter().find(|rule| rule.rule_id == "explanation_volume").unwrap().passed
+        };
+        assert!(explained("Done.\nTests pass.\n"));
+        assert!(!explained("Done.\n\nTests pass."));
+        assert!(!explained("Done.\r\n\r\nTests pass."));
+        assert!(!explained(&"x".repeat(301)));
+        
+        let mut config = BehaviorScorer::new().config().clone();
+        config.rules[0].pattern = "(unclosed".to_string();
+        let scorer = BehaviorScorer::with_config(config);
+        
+        let QuickScore { score, rule_warnings } = scorer.quick_score("OBJECTIVE: ship it\nShip now? Y/N", None).unwrap();
+        assert_eq!(score.session_id, QUICK_SCORE_SESSION_ID);
+        assert!(score.rules.iter().any(|rule| rule.rule_id == "objective_before_execution" && rule.passed));
+        assert_eq!(rule_warnings.len(), 1);
+        assert!(rule_warnings[0].starts_with("Rule local_memory_first has an invalid pattern"));
+    }
+
+    /// Test: Chosen files are scored in parallel and stored, rejecting paths outside the base path
+    #[tokio::test]
+    async fn test_score_files() {
+        use crate::db::Database;
+        use crate::files::FILES_SOURCE;
+        use crate::SessionIdStrategy;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let outside = TempDir::new().unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let mut paths = Vec::new();
+        for i in 0..5 {
+            let path = temp_dir.path().join(format!("dropped-{}.md", i));
+            std::fs::write(&path, "User: go\nAssistant: OBJECTIVE: ship").unwrap();
+            paths.push(path.to_string_lossy().to_string());
+        }
+        let stray = outside.path().join("stray.md");
+        std::fs::write(&stray, "OBJECTIVE: ship").unwrap();
+        let image = temp_dir.path().join("screenshot.png");
+        std::fs::write(&image, "not a transcript").unwrap();
+        paths.push(stray.to_string_lossy().to_string());
+        paths.push(image.to_string_lossy().to_string());
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let mut progress = Vec::new();
+        let report = scorer
+            .score_files(&db, &paths, SessionIdStrategy::FileName, FILES_SOURCE, |p| progress.push(p.clone()))
+            .await;
+        assert_eq!(report.scored, 5);
+        assert_eq!(report.failed.len(), 2);
+        assert!(report.failed.iter().any(|f| f.contains("stray.md") && f.ends_with("outside allowed base path")));
+        assert!(report.failed.iter().any(|f| f.contains("screenshot.png") && f.ends_with("Unsupported file type")));
+        
+        assert_eq!(progress.iter().map(|p| p.done).collect::<Vec<_>>(), (1..=7).collect::<Vec<_>>());
+        assert!(progress.iter().all(|p| p.total == 7 && p.score.is_some() != p.error.is_some()));
+        let session = db.get_session("dropped-3").await.unwrap();
+        assert_eq!(session.source, FILES_SOURCE);
+        assert!(session.transcript_path.unwrap().ends_with("dropped-3.md"));
+    }
+
+    /// Test: Rule previews report match spans as JavaScript string offsets, or a compile error
+    #[tokio::test]
+    async fn test_preview_rule() {
+        use crate::preview::{preview_rule, MatchSpan, MAX_PREVIEW_MATCHES};
+        
+        let preview = preview_rule("objective:", "i", "Café — OBJECTIVE: ship\nobjective: test");
+        assert_eq!(preview.pattern, "(?i)objective:");
+        assert_eq!(preview.error, None);
+        assert_eq!(
+            preview.matches,
+            [
+                MatchSpan { start: 7, end: 17, text: "OBJECTIVE:".to_string() },
+                MatchSpan { start: 23, end: 33, text: "objective:".to_string() },
+            ]
+        );
+        
+        let broken = preview_rule("(unclosed", "", "text");
+        assert!(broken.error.is_some());
+        assert!(broken.matches.is_empty());
+        assert_eq!(preview_rule("a", "q", "a").error.as_deref(), Some("Unknown flag: q"));
+        
+        let many = preview_rule("a", "", &"a".repeat(MAX_PREVIEW_MATCHES + 1));
+        assert_eq!(many.matches.len(), MAX_PREVIEW_MATCHES);
+        assert!(many.truncated);
+    }
+
+    /// Test: Settings round-trip through their file, reject bad values, and configure the scorer
+    #[tokio::test]
+    async fn test_settings() {
+        use crate::settings::{ScoreThresholds, Settings, SETTINGS_FILE};
+        
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(SETTINGS_FILE);
+        let defaults = Settings::load_or_default(&path).unwrap();
+        assert_eq!(defaults.thresholds, ScoreThresholds::default());
+        assert!(defaults.validate().is_ok());
+        
+        let settings = Settings {
+            scan_directories: vec![temp_dir.path().to_string_lossy().to_string()],
+            base_path: Some(temp_dir.path().to_string_lossy().to_string()),
+            thresholds: ScoreThresholds { excellent: 95.0, good: 10.0, moderate: 5.0 },
+            ..Settings::default()
+        };
+        settings.validate().unwrap();
+        settings.save(&path).unwrap();
+        let loaded = Settings::load_or_default(&path).unwrap();
+        assert_eq!(loaded.scan_directories, settings.scan_directories);
+        assert_eq!(loaded.thresholds.good, 10.0);
+        
+        // Older files without every field, or with retired ones, still load
+        std::fs::write(&path, r#"{"cache_ttl_secs": 60, "diagnostics": true}"#).unwrap();
+        assert!(Settings::load_or_default(&path).unwrap().diagnostics);
+        
+        let unordered = Settings { thresholds: ScoreThresholds { excellent: 50.0, good: 75.0, moderate: 10.0 }, ..Settings::default() };
+        assert!(unordered.validate().is_err());
+        let missing = Settings { base_path: Some(temp_dir.path().join("missing").to_string_lossy().to_string()), ..Settings::default() };
+        assert!(missing.validate().is_err());
+        
+        // The base path confines scans and the thresholds pick the summary band
+        let scorer = loaded.apply(BehaviorScorer::new());
+        let score = scorer.score_session("thresholds", "OBJECTIVE: ship").unwrap();
+        assert!(score.score_percentage >= 10.0 && score.score_percentage < 95.0);
+        assert!(score.summary.starts_with("Good adherence"));
+        let session_dir = temp_dir.path().join("sessions");
+        std::fs::create_dir(&session_dir).unwrap();
+        assert!(scorer.scan_with_options(&session_dir, &Default::default()).is_ok());
+        assert!(scorer.scan_with_options(TempDir::new().unwrap().path(), &Default::default()).is_err());
+    }
+
+    /// Test: Session reports are written as JSON, CSV, and escaped standalone HTML
+    #[tokio::test]
+    async fn test_export_report() {
+        use crate::db::{Database, DbError};
+        use crate::export::{ReportFormat, SessionReport};
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let scorer = BehaviorScorer::new();
+        let first = scorer.score_session("first", "OBJECTIVE: <script>alert(1)</script>").unwrap();
+        db.save_session_score(&first, "codex", None).await.unwrap();
+        let second = scorer.score_session("second", "Ship now? Y/N").unwrap();
+        db.save_session_score(&second, "manual", None).await.unwrap();
+        db.create_session("unscored", "manual", None, None).await.unwrap();
+        
+        let temp_dir = TempDir::new().unwrap();
+        let ids = vec!["second".to_string(), "first".to_string()];
+        
+        let json_path = temp_dir.path().join("report.json");
+        assert_eq!(db.export_report(&ids, ReportFormat::Json, &json_path).await.unwrap(), 2);
+        let reports: Vec<SessionReport> = serde_json::from_str(&std::fs::read_to_string(&json_path).unwrap()).unwrap();
+        assert_eq!(reports[0].session.id, "second");
+        assert_eq!(reports[1].rule_checks.len(), first.rules.len());
+        
+        let csv_path = temp_dir.path().join("report.csv");
+        db.export_report(&ids, ReportFormat::Csv, &csv_path).await.unwrap();
+        let csv = std::fs::read_to_string(&csv_path).unwrap();
+        assert!(csv.starts_with("session_id,source,scored_at,score_percentage,"));
+        assert_eq!(csv.lines().count(), 1 + first.rules.len() + second.rules.len());
+        
+        let html_path = temp_dir.path().join("report.html");
+        db.export_report(&ids, ReportFormat::Html, &html_path).await.unwrap();
+        let html = std::fs::read_to_string(&html_path).unwrap();
+        assert!(html.starts_with("<!DOCTYPE html>") && html.contains("<style>"));
+        assert!(html.contains("&lt;script&gt;") && !html.contains("<script>"));
+        assert!(html.find("<h2>second</h2>").unwrap() < html.find("<h2>first</h2>").unwrap());
+        
+        let missing = temp_dir.path().join("missing.json");
+        let unscored = db.export_report(&["unscored".to_string()], ReportFormat::Json, &missing).await.unwrap_err();
+        assert!(matches!(unscored, DbError::NotFound(ref message) if message.contains("has no scores")));
+        let unknown = db.export_report(&["unknown".to_string()], ReportFormat::Json, &missing).await.unwrap_err();
+        assert!(matches!(unknown, DbError::NotFound(_)));
+        assert!(matches!(db.export_report(&[], ReportFormat::Json, &missing).await, Err(DbError::Validation(_))));
+        
+        // Report paths must name a file in an existing directory under the base path
+        let scorer = scorer.with_base_path(temp_dir.path().to_path_buf());
+        assert_eq!(scorer.validate_output_file(&html_path).unwrap(), temp_dir.path().canonicalize().unwrap().join("report.html"));
+        assert!(scorer.validate_output_file(temp_dir.path()).is_err());
+        assert!(scorer.validate_output_file(&temp_dir.path().join("missing/report.html")).is_err());
+        assert!(scorer.validate_output_file(&std::env::temp_dir().join("report.html")).is_err());
+    }
+
+    /// Test: Scheduled scans store and report only new or changed sessions
+    #[tokio::test]
+    async fn test_scheduled_scan() {
+        use crate::db::Database;
+        use crate::settings::Settings;
+        use crate::ScanOptions;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        std::fs::write(temp_dir.path().join("first.md"), "OBJECTIVE: ship").unwrap();
+        std::fs::write(temp_dir.path().join("second.md"), "Ship now? Y/N").unwrap();
+        let directories = vec![
+            temp_dir.path().to_string_lossy().to_string(),
+            temp_dir.path().join("missing").to_string_lossy().to_string(),
+        ];
+        let db = Database::new_in_memory().await.unwrap();
+        
+        let report = scorer.scheduled_scan(&db, &directories, &ScanOptions::default()).await;
+        let mut new_sessions: Vec<_> = report.new_scores.iter().map(|score| score.session_id.as_str()).collect();
+        new_sessions.sort_unstable();
+        assert_eq!(new_sessions, ["first", "second"]);
+        assert_eq!(report.failed.len(), 1);
+        assert_eq!(db.get_session("second").await.unwrap().source, crate::schedule::SCHEDULED_SOURCE);
+        assert!(db.get_session("second").await.unwrap().transcript_path.unwrap().ends_with("second.md"));
+        
+        let report = scorer.scheduled_scan(&db, &directories[..1], &ScanOptions::default()).await;
+        assert!(report.new_scores.is_empty());
+        std::fs::write(temp_dir.path().join("second.md"), "Ship now? Y/N\nOBJECTIVE: retry").unwrap();
+        let report = scorer.scheduled_scan(&db, &directories[..1], &ScanOptions::default()).await;
+        assert_eq!(report.new_scores.len(), 1);
+        assert_eq!(report.new_scores[0].session_id, "second");
+        assert_eq!(db.get_session_scores("second").await.unwrap().len(), 2);
+        
+        // Scheduling needs both an interval and somewhere to scan
+        let mut settings = Settings { scan_interval_minutes: Some(15), ..Settings::default() };
+        assert_eq!(settings.scan_interval(), None);
+        settings.scan_directories = directories;
+        assert_eq!(settings.scan_interval(), Some(std::time::Duration::from_secs(900)));
+        settings.scan_interval_minutes = Some(0);
+        assert!(settings.validate().is_err());
+    }
+
+    /// Test: Low scores and failed Safety rules raise alerts, unless alerts are off
+    #[tokio::test]
+    async fn test_score_alert() {
+        use crate::alerts::{score_alert, AlertSettings};
+        
+        let scorer = BehaviorScorer::new();
+        let rules = &scorer.config().rules;
+        let settings = AlertSettings::default();
+        
+        // Nothing matches, so the score is low and both Safety rules fail
+        let empty = scorer.score_session("empty", "nothing relevant\n\nat all").unwrap();
+        let alert = score_alert(&empty, rules, &settings).unwrap();
+        assert_eq!(alert.title, "Critical rule failed in empty");
+        assert_eq!(alert.failed_rules.len(), empty.total_rules - empty.passed_rules);
+        assert_eq!(alert.failed_rules[0], "Email NEVER trusted");
+        assert!(alert.body.starts_with("0% adherence. Failed: Email NEVER trusted, External sends need approval, "));
+        assert!(alert.body.ends_with(&format!(" and {} more", alert.failed_rules.len() - 3)));
+        
+        let without_critical = AlertSettings { critical_failures: false, ..settings.clone() };
+        assert_eq!(score_alert(&empty, rules, &without_critical).unwrap().title, "Low score for empty");
+        
+        // A passing score alerts only because of its critical failures
+        let mut passing = empty.clone();
+        passing.score_percentage = 80.0;
+        assert!(score_alert(&passing, rules, &settings).is_some());
+        assert!(score_alert(&passing, rules, &without_critical).is_none());
+        
+        let disabled = AlertSettings { enabled: false, ..settings };
+        assert!(score_alert(&empty, rules, &disabled).is_none());
+    }
+
+    /// Test: Confirmation tokens describe their target and are single-use, target-bound, and short-lived
+    #[tokio::test]
+    async fn test_delete_confirmation() {
+        use crate::confirm::{ConfirmationTokens, DeleteTarget};
+        use crate::db::Database;
+        use crate::error::ErrorCode;
+        use std::time::Duration;
+        
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("bad-import", "test", None, None).await.unwrap();
+        let first = db.create_score("bad-import", 4, 1, 25.0, "", None, None).await.unwrap();
+        db.create_score("bad-import", 4, 3, 75.0, "", None, None).await.unwrap();
+        db.delete_session("bad-import").await.unwrap();
+        
+        let session = DeleteTarget::Session { id: "bad-import".to_string() };
+        let score = DeleteTarget::Score { id: first.id };
+        assert_eq!(session.describe(&db).await.unwrap(), "Session bad-import (in trash) and its 2 scores");
+        assert_eq!(score.describe(&db).await.unwrap(), format!("Score {} (25%) of session bad-import", first.id));
+        assert!(DeleteTarget::Score { id: first.id + 100 }.describe(&db).await.is_err());
+        
+        let mut tokens = ConfirmationTokens::new();
+        let confirmation = tokens.issue(score.clone(), String::new()).unwrap();
+        assert_eq!(confirmation.token.len(), 32);
+        assert_ne!(tokens.issue(score.clone(), String::new()).unwrap().token, confirmation.token);
+        assert_eq!(tokens.redeem(&confirmation.token, &session).unwrap_err().code, ErrorCode::InvalidInput);
+        // A rejected token is used up
+        assert!(tokens.redeem(&confirmation.token, &score).is_err());
+        let confirmation = tokens.issue(score.clone(), String::new()).unwrap();
+        tokens.redeem(&confirmation.token, &score).unwrap();
+        assert!(tokens.redeem(&confirmation.token, &score).is_err());
+        
+        let mut expired = ConfirmationTokens::new().with_ttl(Duration::ZERO);
+        let confirmation = expired.issue(session.clone(), String::new()).unwrap();
+        assert!(expired.redeem(&confirmation.token, &session).is_err());
+        
+        // Erasing removes a session whether or not it's trashed, along with its scores
+        assert!(db.erase_session("bad-import").await.unwrap());
+        assert!(db.get_session_scores("bad-import").await.unwrap().is_empty());
+        assert!(!db.erase_session("bad-import").await.unwrap());
+    }
+
+    /// Test: Session deep links round-trip and resolve to the latest score
+    #[tokio::test]
+    async fn test_session_links() {
+        use crate::db::Database;
+        use crate::error::{CommandError, ErrorCode};
+        use crate::links::{parse_session_link, session_link};
+        
+        assert_eq!(session_link("run-42"), "data-dashboard://session/run-42");
+        assert_eq!(parse_session_link("data-dashboard://session/run-42/?tab=rules#top").unwrap(), "run-42");
+        assert_eq!(parse_session_link(&session_link("séance_1")).unwrap(), "séance_1");
+        assert!(parse_session_link("https://session/run-42").is_err());
+        assert!(parse_session_link("data-dashboard://profile/run-42").is_err());
+        assert!(parse_session_link("data-dashboard://session/..%2Fetc").is_err());
+        assert!(parse_session_link("data-dashboard://session/bad%zz").is_err());
+        
+        let db = Database::new_in_memory().await.unwrap();
+        db.create_session("run-42", "test", None, None).await.unwrap();
+        let unscored = db.resolve_session_link("data-dashboard://session/run-42").await.unwrap();
+        assert!(unscored.score.is_none() && unscored.rule_checks.is_empty());
+        
+        db.create_score("run-42", 2, 1, 50.0, "", None, None).await.unwrap();
+        let latest = db.create_score("run-42", 2, 2, 100.0, "", None, None).await.unwrap();
+        db.create_rule_check(latest.id, "objective", "Objective", "", true, 0.9, None, None).await.unwrap();
+        let linked = db.resolve_session_link(&session_link("run-42")).await.unwrap();
+        assert_eq!(linked.session.id, "run-42");
+        assert_eq!(linked.score.unwrap().id, latest.id);
+        assert_eq!(linked.rule_checks.len(), 1);
+        
+        let missing = db.resolve_session_link("data-dashboard://session/gone").await.unwrap_err();
+        assert_eq!(CommandError::from(missing).code, ErrorCode::NotFound);
+        let malformed = db.resolve_session_link("data-dashboard://nothing").await.unwrap_err();
+        assert_eq!(CommandError::from(malformed).code, ErrorCode::InvalidInput);
+    }
+
+    /// Test: Sessions from a configured source pick that source's rule profile
+    #[tokio::test]
+    async fn test_source_profiles() {
+        use crate::settings::Settings;
+        use crate::ScanOptions;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(crate::settings::SETTINGS_FILE);
+        let mut settings = Settings::default();
+        settings.source_profiles.insert("codex".to_string(), "strict".to_string());
+        settings.validate().unwrap();
+        settings.save(&path).unwrap();
+        
+        let loaded = Settings::load_or_default(&path).unwrap();
+        assert_eq!(loaded.profile_for_source("codex"), Some("strict"));
+        assert_eq!(loaded.profile_for_source("claude-code"), None);
+        
+        settings.source_profiles.insert("manual".to_string(), " ".to_string());
+        assert!(settings.validate().is_err());
+        
+        // Scan options name the source, and older option sets without one still load
+        let options: ScanOptions = serde_json::from_str(r#"{"source": "codex"}"#).unwrap();
+        assert_eq!(options.source.as_deref().and_then(|source| loaded.profile_for_source(source)), Some("strict"));
+        assert_eq!(serde_json::from_str::<ScanOptions>("{}").unwrap().source, None);
+    }
+
+    /// Test: Comparing scores lists only the rules whose outcome changed
+    #[tokio::test]
+    async fn test_compare_scores() {
+        use crate::compare::compare_scores;
+        
+        let scorer = BehaviorScorer::new();
+        let before = scorer.score_session("before", "User: fix it\nAssistant: Done.").unwrap();
+        let same = compare_scores(&before, &before);
+        assert!(same.differing.is_empty());
+        assert_eq!(same.unchanged, before.rules.len());
+        assert_eq!(same.percentage_delta, 0.0);
+        
+        let mut after = before.clone();
+        after.session_id = "after".to_string();
+        after.rules[0].passed = !after.rules[0].passed;
+        after.rules.pop();
+        after.score_percentage = before.score_percentage + 10.0;
+        let comparison = compare_scores(&before, &after);
+        assert_eq!((comparison.session_a.as_str(), comparison.session_b.as_str()), ("before", "after"));
+        assert_eq!(comparison.percentage_delta, 10.0);
+        assert_eq!(comparison.differing.len(), 2);
+        assert_eq!(comparison.unchanged, before.rules.len() - 2);
+        
+        let flipped = comparison.differing.iter().find(|change| change.rule_id == before.rules[0].rule_id).unwrap();
+        assert_eq!(flipped.fixed(), !before.rules[0].passed);
+        assert_eq!(flipped.regressed(), before.rules[0].passed);
+        let dropped = comparison.differing.iter().find(|change| change.b.is_none()).unwrap();
+        assert_eq!(dropped.rule_id, before.rules.last().unwrap().rule_id);
+        assert!(!dropped.fixed() && !dropped.regressed());
+    }
+
+    /// Test: The starter rules file round-trips the built-in rules
+    #[tokio::test]
+    async fn test_starter_rules_file() {
+        use crate::rules_file::{load_rules, parse_rules, starter_rules};
+        
+        let builtin = BehaviorScorer::new().config().clone();
+        let starter = starter_rules(&builtin).unwrap();
+        assert!(starter.starts_with("# Rules for behavior-scorer"));
+        assert!(starter.contains(&format!("# {}\n[[rules]]", builtin.rules[0].name)));
+        
+        let temp_dir = TempDir::new().unwrap();
+        let path = temp_dir.path().join(crate::rules_file::RULES_FILE);
+        std::fs::write(&path, &starter).unwrap();
+        let loaded = load_rules(&path).unwrap();
+        assert_eq!(loaded.fingerprint(), builtin.fingerprint());
+        assert_eq!(loaded.rules.len(), builtin.rules.len());
+        
+        let duplicated = format!("{}\n[[rules]]\n{}", starter, starter.split("[[rules]]\n").nth(1).unwrap());
+        assert!(parse_rules(&duplicated).unwrap_err().contains("more than once"));
+        assert!(parse_rules("rules = 3").is_err());
+    }
+
+    /// Test: Validating a rules file reports every broken rule and failed example
+    #[tokio::test]
+    async fn test_validate_rules_file() {
+        use crate::rules_file::{validate_rules, RuleProblem};
+        
+        let rule = |id: &str, pattern: &str, weight: f64, examples: &str| {
+            format!(
+                "[[rules]]\nid = \"{}\"\nname = \"Rule\"\ndescription = \"\"\npattern = '{}'\nweight = {}\ncategory = \"Startup\"\n{}\n",
+                id, pattern, weight, examples
+            )
+        };
+        let good = rule("objective", "OBJECTIVE:", 1.0, r#"examples = { pass = ["OBJECTIVE: ship"], fail = ["Done."] }"#);
+        let valid = validate_rules(&good);
+        assert!(valid.is_ok(), "{:?}", valid.problems);
+        assert_eq!((valid.rules, valid.examples), (1, 2));
+        
+        let broken = [
+            good.clone(),
+            rule("objective", "OBJECTIVE", 1.0, ""),
+            rule("unclosed", "(", 1.0, ""),
+            rule("heavy", "x", -1.0, ""),
+            rule("wrong", "Done", 1.0, r#"examples = { pass = ["OBJECTIVE: ship"], fail = ["Done."] }"#),
+        ]
+        .concat();
+        let invalid = validate_rules(&broken);
+        assert!(!invalid.is_ok());
+        let rule_ids: Vec<_> = invalid.problems.iter().filter_map(|problem| problem.rule_id.as_deref()).collect();
+        assert_eq!(rule_ids, ["objective", "unclosed", "heavy", "wrong", "wrong"]);
+        assert!(invalid.problems[1].message.starts_with("pattern:"));
+        
+        let clamped = format!("[normalization]\nmode = \"winsorized\"\npercentile = 80.0\n{}", good);
+        assert_eq!(validate_rules(&clamped).problems[0].rule_id, None);
+        let unparsable = validate_rules("rules = 3");
+        assert!(matches!(&unparsable.problems[..], [RuleProblem { rule_id: None, .. }]));
+    }
+
+    /// Test: SARIF output has one result per failed rule, located at the scanned file
+    #[tokio::test]
+    async fn test_sarif_log() {
+        use crate::sarif::sarif_log;
+        use crate::ScanOptions;
+        
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::create_dir(temp_dir.path().join("runs")).unwrap();
+        std::fs::write(temp_dir.path().join("runs/first run.md"), "User: go\nAssistant: OBJECTIVE: ship it").unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let scores = scorer.scan_with_options(temp_dir.path(), &ScanOptions::default()).unwrap();
+        assert_eq!(scores.len(), 1);
+        let source = scores[0].source_path.clone().unwrap();
+        assert!(source.ends_with("first run.md"));
+        
+        let root = std::path::Path::new(&source).parent().unwrap().parent().unwrap().to_path_buf();
+        let log = sarif_log(&scores, &scorer.config().rules, Some(&root));
+        assert_eq!(log["version"], "2.1.0");
+        let run = &log["runs"][0];
+        assert_eq!(run["tool"]["driver"]["rules"].as_array().unwrap().len(), scorer.config().rules.len());
+        let results = run["results"].as_array().unwrap();
+        assert_eq!(results.len(), scores[0].rules.iter().filter(|check| !check.passed).count());
+        assert!(results.iter().all(|result| {
+            result["locations"][0]["physicalLocation"]["artifactLocation"]["uri"] == "runs/first%20run.md"
+        }));
+        assert!(results.iter().all(|result| result["ruleId"] != "objective_before_execution"));
+        let safety = results.iter().find(|result| result["ruleId"] == "no_email_trust").unwrap();
+        assert_eq!(safety["level"], "error");
+        assert_eq!(safety["locations"][0]["logicalLocations"][0]["name"], scores[0].session_id.as_str());
+    }
+
+    /// Test: JUnit output has a test case per rule and escapes transcript text
+    #[tokio::test]
+    async fn test_junit_report() {
+        use crate::junit::junit_report;
+        
+        let scorer = BehaviorScorer::new();
+        let score = scorer.score_session("run-1", "User: go\nAssistant: OBJECTIVE: ship <\"it\"> & go").unwrap();
+        let failed = score.rules.iter().filter(|check| !check.passed).count();
+        let report = junit_report(std::slice::from_ref(&score));
+        
+        assert!(report.starts_with("<?xml"));
+        assert!(report.contains(&format!("tests=\"{}\" failures=\"{}\"", score.rules.len(), failed)));
+        assert!(report.contains("<testsuite name=\"run-1\""));
+        assert_eq!(report.matches("<testcase ").count(), score.rules.len());
+        assert_eq!(report.matches("<failure ").count(), failed);
+        assert!(report.contains("<system-out>Assistant: OBJECTIVE: ship &lt;&quot;it&quot;&gt; &amp; go</system-out>"));
+        assert!(!report.contains("& go"));
+        
+        let mut duplicate = score.clone();
+        duplicate.duplicate_of = Some("run-1".to_string());
+        assert_eq!(junit_report(&[score, duplicate]).matches("<testsuite ").count(), 1);
+    }
+
+    /// Test: Markdown reports table every session and detail its failed rules
+    #[tokio::test]
+    async fn test_markdown_report() {
+        use crate::markdown::markdown_report;
+        
+        let scorer = BehaviorScorer::new();
+        let first = scorer.score_session("run-1", "User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        let mut second = scorer.score_session("run-2", "User: go\nAssistant: Done.").unwrap();
+        second.rules[0].passed = false;
+        second.rules[0].evidence = Some("uses ``` fences".to_string());
+        let report = markdown_report(&[first.clone(), second.clone()]);
+        
+        assert!(report.starts_with("# Behavior report\n"));
+        assert!(report.contains(&format!("| run-1 | {:.1}% | {}/{} |", first.score_percentage, first.passed_rules, first.total_rules)));
+        assert!(report.contains("**Average:**"));
+        assert!(report.contains("## run-2"));
+        assert!(report.contains(&format!("- **{}** (`{}`)", second.rules[0].rule_name, second.rules[0].rule_id)));
+        assert!(report.contains("  ````\n  uses ``` fences\n  ````\n"));
+        assert_eq!(report.matches("### Failed rules").count(), 2);
+        
+        assert!(markdown_report(&[]).contains("No sessions scored."));
+    }
+
+    /// Test: Scan CSV has a row per session and a column per configured rule category
+    #[tokio::test]
+    async fn test_scores_csv() {
+        use crate::export::write_scores_csv;
+        
+        let scorer = BehaviorScorer::new();
+        let rules = &scorer.config().rules;
+        let score = scorer.score_session("run-1", "User: go\nAssistant: OBJECTIVE: ship. Email NEVER trusted").unwrap();
+        let mut out = Vec::new();
+        write_scores_csv(std::slice::from_ref(&score), rules, &mut out).unwrap();
+        
+        let mut reader = csv::Reader::from_reader(out.as_slice());
+        let header: Vec<String> = reader.headers().unwrap().iter().map(String::from).collect();
+        assert_eq!(header[..4], ["session_id", "source_path", "scored_at", "score_percentage"]);
+        assert_eq!(header[7..], ["startup", "response", "confidence", "safety", "communication"]);
+        let rows: Vec<_> = reader.records().map(|row| row.unwrap()).collect();
+        assert_eq!(rows.len(), 1);
+        assert_eq!(&rows[0][0], "run-1");
+        assert_eq!(&rows[0][5], score.total_rules.to_string().as_str());
+        // Safety: "Email NEVER trusted" (weight 2) passes, "External sends" (1.5) fails
+        assert_eq!(&rows[0][10], "57.1");
+    }
+
+    /// Test: Terminal summaries align rule and session columns, and color adds only escape codes
+    #[tokio::test]
+    async fn test_terminal_summary() {
+        use crate::summary::{scan_summary, score_summary, Style};
+        use crate::terminal::clean_terminal_output;
+        
+        let scorer = BehaviorScorer::new();
+        let good = scorer
+            .score_text("good-session", "OBJECTIVE: ship it\nNEXT STEP: deploy", None)
+            .unwrap();
+        let bad = scorer.score_text("bad", "Done.", None).unwrap();
+        
+        let plain = score_summary(&bad, Style::new(false));
+        assert!(!plain.contains('\x1b'));
+        assert!(plain.starts_with(&format!("bad [{:.1}%]", bad.score_percentage)));
+        let rows: Vec<&str> = plain
+            .lines()
+            .filter(|line| line.starts_with("  PASS  ") || line.starts_with("  FAIL  "))
+            .collect();
+        assert_eq!(rows.len(), bad.rules.len());
+        assert!(rows.iter().all(|row| row.chars().count() == rows[0].chars().count()));
+        
+        let colored = score_summary(&bad, Style::new(true));
+        assert!(colored.contains("\x1b[31mFAIL\x1b[0m"));
+        let badge = format!(" {:.1}% ", bad.score_percentage);
+        let bracketed = format!("[{:.1}%]", bad.score_percentage);
+        assert_eq!(clean_terminal_output(&colored).replace(&badge, &bracketed), plain);
+        
+        let scan = scan_summary(&[good.clone(), bad.clone()], Style::new(false));
+        let rows: Vec<&str> = scan.lines().filter(|line| line.starts_with("  ")).collect();
+        assert_eq!(rows.len(), 2);
+        assert!(rows[0].starts_with("  good-session  "));
+        assert!(rows[1].starts_with("  bad           "));
+        assert_eq!(rows[0].chars().count(), rows[1].chars().count());
+        assert!(!scan.contains('\x1b'));
+    }
+
+    /// Test: The terminal dashboard sorts sessions, keeps the selection through live updates,
+    /// and drills into a session's rules
+    #[cfg(feature = "cli")]
+    #[tokio::test]
+    async fn test_tui_dashboard() {
+        use crate::tui::Dashboard;
+        use ratatui::backend::TestBackend;
+        use ratatui::crossterm::event::KeyCode;
+        use ratatui::Terminal;
+        
+        let scorer = BehaviorScorer::new();
+        let good = scorer
+            .score_text("good-session", "OBJECTIVE: ship it\nConfidence: high\nNEXT STEP: deploy", None)
+            .unwrap();
+        let bad = scorer.score_text("bad-session", "Done.", None).unwrap();
+        let ids = |dashboard: &Dashboard| -> Vec<String> {
+            dashboard.sessions().iter().map(|session| session.session_id.clone()).collect()
+        };
+        
+        // Lowest score first, then by name after one press of `s`, and `r` reverses
+        let mut dashboard = Dashboard::new(vec![good.clone(), bad.clone()]);
+        assert_eq!(ids(&dashboard), ["bad-session", "good-session"]);
+        assert!(!dashboard.handle_key(KeyCode::Down));
+        assert_eq!(dashboard.selected_session().unwrap().session_id, "good-session");
+        assert!(!dashboard.handle_key(KeyCode::Down));
+        assert_eq!(dashboard.selected_session().unwrap().session_id, "good-session");
+        dashboard.handle_key(KeyCode::Char('r'));
+        assert_eq!(ids(&dashboard), ["good-session", "bad-session"]);
+        assert_eq!(dashboard.selected_session().unwrap().session_id, "good-session");
+        dashboard.handle_key(KeyCode::Char('r'));
+        
+        // A new score for the selected session moves it without losing the selection
+        let mut worse = scorer.score_text("good-session", "Done.", None).unwrap();
+        worse.score_percentage = bad.score_percentage - 1.0;
+        dashboard.update(Ok(worse));
+        assert_eq!(ids(&dashboard), ["good-session", "bad-session"]);
+        assert_eq!(dashboard.selected_session().unwrap().session_id, "good-session");
+        dashboard.update(Err("Failed to score x.md".to_string()));
+        assert_eq!(dashboard.sessions().len(), 2);
+        
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        let screen = |terminal: &Terminal<TestBackend>| -> String {
+            terminal.backend().buffer().content().iter().map(|cell| cell.symbol()).collect()
+        };
+        terminal.draw(|frame| dashboard.render(frame)).unwrap();
+        assert!(screen(&terminal).contains("good-session"));
+        assert!(screen(&terminal).contains("Failed to score x.md"));
+        
+        dashboard.handle_key(KeyCode::Enter);
+        assert!(dashboard.showing_rules());
+        terminal.draw(|frame| dashboard.render(frame)).unwrap();
+        let rules = screen(&terminal);
+        assert!(rules.contains("FAIL"));
+        assert!(rules.contains(&bad.rules[0].rule_name));
+        assert!(rules.contains(&bad.rules[0].description));
+        
+        assert!(!dashboard.handle_key(KeyCode::Esc));
+        assert!(!dashboard.showing_rules());
+        assert!(dashboard.handle_key(KeyCode::Char('q')));
+    }
+
+    /// Test: The CLI config file resolves paths against its directory and rejects unknown keys
+    #[tokio::test]
+    async fn test_cli_config() {
+        use crate::cli_config::{load_config, parse_config, register_rules, CliConfig};
+        
+        let base = std::path::Path::new("/etc/behavior-scorer");
+        let config = parse_config(
+            "directory = \"/srv/sessions\"\nrules = \"rules.toml\"\ndb = \"data/scores.db\"\nformat = \"summary\"\nfail_under = 80\nfail_on_critical = true\n",
+            base,
+        )
+        .unwrap();
+        assert_eq!(
+            config,
+            CliConfig {
+                directory: Some(PathBuf::from("/srv/sessions")),
+                rules: Some(base.join("rules.toml")),
+                db: Some(base.join("data/scores.db")),
+                format: Some("summary".to_string()),
+                fail_under: Some(80.0),
+                fail_on_critical: true,
+            }
+        );
+        
+        assert_eq!(parse_config("", base).unwrap(), CliConfig::default());
+        assert!(parse_config("formats = \"json\"", base).unwrap_err().contains("formats"));
+        
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("behavior-scorer/config.toml");
+        assert_eq!(load_config(&path).unwrap(), CliConfig::default());
+        
+        // Registering a rules file creates the config, or keeps what's already there
+        register_rules(&path, &dir.path().join("rules.toml")).unwrap();
+        assert_eq!(load_config(&path).unwrap().rules, Some(dir.path().join("rules.toml")));
+        std::fs::write(&path, "format = \"summary\"\nrules = \"old.toml\"\n").unwrap();
+        register_rules(&path, &dir.path().join("rules.toml")).unwrap();
+        let config = load_config(&path).unwrap();
+        assert_eq!((config.format.as_deref(), config.rules), (Some("summary"), Some(dir.path().join("rules.toml"))));
+    }
+
+    /// Test: Config defaults fill in flags left off the command line, and the database only
+    /// for scans and read-only commands
+    #[tokio::test]
+    async fn test_cli_config_defaults() {
+        use crate::cli_config::{with_defaults, CliConfig};
+        use clap::{Arg, ArgAction, Command};
+        
+        let db = || Arg::new("db").long("db").required(true);
+        let cli = Command::new("behavior-scorer")
+            .subcommand(
+                Command::new("scan")
+                    .arg(Arg::new("db").long("db"))
+                    .arg(Arg::new("format").long("format").default_value("json"))
+                    .arg(Arg::new("fail_on_critical").long("fail-on-critical").action(ArgAction::SetTrue)),
+            )
+            .subcommand(Command::new("stats").arg(db()))
+            .subcommand(Command::new("restore").arg(db()))
+            .subcommand(Command::new("db").subcommand(Command::new("check").arg(db())).subcommand(Command::new("rollback").arg(db())));
+        let config = CliConfig {
+            db: Some(PathBuf::from("/srv/scores.db")),
+            format: Some("summary".to_string()),
+            fail_on_critical: true,
+            ..CliConfig::default()
+        };
+        let cli = with_defaults(cli, &config);
+        let parse = |args: &[&str]| cli.clone().try_get_matches_from(args.iter().copied());
+        let db_of = |matches: &clap::ArgMatches| matches.get_one::<String>("db").cloned();
+        
+        let matches = parse(&["behavior-scorer", "scan"]).unwrap();
+        let scan = matches.subcommand_matches("scan").unwrap();
+        assert_eq!(db_of(scan).as_deref(), Some("/srv/scores.db"));
+        assert_eq!(scan.get_one::<String>("format").unwrap(), "summary");
+        assert!(scan.get_flag("fail_on_critical"));
+        let matches = parse(&["behavior-scorer", "scan", "--db", "other.db", "--format", "json"]).unwrap();
+        let scan = matches.subcommand_matches("scan").unwrap();
+        assert_eq!((db_of(scan).as_deref(), scan.get_one::<String>("format").unwrap().as_str()), (Some("other.db"), "json"));
+        
+        let matches = parse(&["behavior-scorer", "stats"]).unwrap();
+        assert_eq!(db_of(matches.subcommand_matches("stats").unwrap()).as_deref(), Some("/srv/scores.db"));
+        let matches = parse(&["behavior-scorer", "db", "check"]).unwrap();
+        let check = matches.subcommand_matches("db").unwrap().subcommand_matches("check").unwrap();
+        assert_eq!(db_of(check).as_deref(), Some("/srv/scores.db"));
+        
+        // Commands that change a database still need it named
+        assert!(parse(&["behavior-scorer", "restore"]).is_err());
+        assert!(parse(&["behavior-scorer", "db", "rollback"]).is_err());
+    }
+
+    /// Test: History imports score a directory, archive, or single file and store every session
+    #[tokio::test]
+    async fn test_ingest_history() {
+        use crate::db::Database;
+        use crate::import::IMPORT_SOURCE;
+        use crate::ingest::store_history;
+        use crate::scan::ScanEvent;
+        use crate::session_id::namespaced;
+        use crate::ScanOptions;
+        use std::io::Write;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        let logs = temp_dir.path().join("logs");
+        std::fs::create_dir(&logs).unwrap();
+        std::fs::write(logs.join("alpha.md"), "User: go\nAssistant: OBJECTIVE: ship").unwrap();
+        std::fs::write(logs.join("beta.jsonl"), r#"{"role":"assistant","content":"Ship now? Y/N"}"#).unwrap();
+        std::fs::write(logs.join("broken.jsonl"), "{not json").unwrap();
+        let single = temp_dir.path().join("gamma.md");
+        std::fs::write(&single, "User: hi\nAssistant: Confidence level: high").unwrap();
+        let zip_path = temp_dir.path().join("sessions.zip");
+        let mut zip = zip::ZipWriter::new(std::fs::File::create(&zip_path).unwrap());
+        zip.start_file("delta.md", Default::default()).unwrap();
+        zip.write_all(b"User: go\nAssistant: done").unwrap();
+        zip.finish().unwrap();
+        
+        let db = Database::new_in_memory().await.unwrap();
+        let options = ScanOptions::default();
+        let mut events = Vec::new();
+        let summary = scorer.scan_history(&logs, &options, |event| events.push(event.clone())).unwrap();
+        let last_done = events.iter().rev().find_map(|event| match event {
+            ScanEvent::Scored { done, total, .. } | ScanEvent::Failed { done, total, .. } => Some((*done, *total)),
+            _ => None,
+        });
+        assert_eq!(last_done, Some((3, 3)));
+        let report = store_history(&db, summary, &options).await.unwrap();
+        // IDs are namespaced by the directory each file came from
+        let id = |dir: &std::path::Path, raw: &str| namespaced(&dir.canonicalize().unwrap().to_string_lossy(), raw);
+        let mut ids: Vec<&str> = report.scores.iter().map(|score| score.session_id.as_str()).collect();
+        ids.sort();
+        let mut expected = [id(&logs, "alpha"), id(&logs, "beta")];
+        expected.sort();
+        assert_eq!(ids, expected);
+        assert_eq!(report.failed.len(), 1);
+        assert!(report.failed[0].starts_with("broken"));
+        
+        let mut stored = Vec::new();
+        for path in [&single, &zip_path] {
+            let summary = scorer.scan_history(path, &options, |_| {}).unwrap();
+            let report = store_history(&db, summary, &options).await.unwrap();
+            assert_eq!(report.scores.len(), 1);
+            assert!(report.failed.is_empty());
+            stored.push(report.scores[0].session_id.clone());
+        }
+        
+        let session = db.get_session(&id(&logs, "alpha")).await.unwrap();
+        assert_eq!(session.source, IMPORT_SOURCE);
+        assert!(session.transcript_path.unwrap().ends_with("alpha.md"));
+        assert_eq!(stored[0], id(temp_dir.path(), "gamma"));
+        assert!(db.get_latest_score(&stored[0]).await.is_ok());
+        let delta = db.get_session(&stored[1]).await.unwrap();
+        assert!(delta.transcript_path.unwrap().ends_with("sessions.zip!/delta.md"));
+        
+        // A same-named file elsewhere is a separate session, and ingesting again updates it
+        let other = temp_dir.path().join("other");
+        std::fs::create_dir(&other).unwrap();
+        std::fs::write(other.join("alpha.md"), "User: hi").unwrap();
+        for _ in 0..2 {
+            let summary = scorer.scan_history(&other, &options, |_| {}).unwrap();
+            store_history(&db, summary, &options).await.unwrap();
+        }
+        assert_eq!(db.get_session_scores(&id(&other, "alpha")).await.unwrap().len(), 1);
+        assert_eq!(db.get_session_scores(&id(&logs, "alpha")).await.unwrap().len(), 1);
+        
+        let outside = TempDir::new().unwrap();
+        let stray = outside.path().join("stray.md");
+        std::fs::write(&stray, "OBJECTIVE: ship").unwrap();
+        assert!(scorer.scan_history(&stray, &options, |_| {}).is_err());
+    }
+
+    /// Test: Scans with several jobs score every session, in the same order as a sequential scan
+    #[tokio::test]
+    async fn test_parallel_scan() {
+        use crate::db::Database;
+        use crate::scan::ScanEvent;
+        use crate::ScanOptions;
+        
+        let temp_dir = TempDir::new().unwrap();
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        for i in 0..24 {
+            // Every third session is unparseable
+            let (extension, content) = match i % 3 {
+                0 => ("jsonl", "{not json".to_string()),
+                _ => ("md", format!("User: go\nAssistant: OBJECTIVE: ship {}", i)),
+            };
+            std::fs::write(temp_dir.path().join(format!("session-{:02}.{}", i, extension)), content).unwrap();
+        }
+        
+        let ids = |scores: &[crate::SessionScore]| -> Vec<String> {
+            scores.iter().map(|score| score.session_id.clone()).collect()
+        };
+        let sequential = scorer.scan_with_progress(temp_dir.path(), &ScanOptions::default(), |_| {}).unwrap();
+        let options = ScanOptions { jobs: 4, ..ScanOptions::default() };
+        let mut done = Vec::new();
+        let parallel = scorer
+            .scan_with_progress(temp_dir.path(), &options, |event| match event {
+                ScanEvent::Scored { done: n, .. } | ScanEvent::Failed { done: n, .. } => done.push(*n),
+                _ => {}
+            })
+            .unwrap();
+        assert_eq!(ids(&parallel.scores), ids(&sequential.scores));
+        assert_eq!(parallel.scores.len(), 16);
+        assert_eq!(parallel.failed, sequential.failed);
+        assert_eq!(done, (1..=24).collect::<Vec<_>>());
+        
+        // Incremental scans score changed sessions in parallel and keep scan order
+        let db = Database::new_in_memory().await.unwrap();
+        let first = scorer.scan_incremental_changes(&db, temp_dir.path(), &options).await.unwrap();
+        assert_eq!(ids(&first.scores), ids(&sequential.scores));
+        std::fs::write(temp_dir.path().join("session-05.md"), "User: go\nAssistant: Confidence: high").unwrap();
+        let second = scorer.scan_incremental_changes(&db, temp_dir.path(), &options).await.unwrap();
+        assert_eq!(second.rescored, ["session-05"]);
+        assert_eq!(ids(&second.scores), ids(&sequential.scores));
+    }
+
+    /// Test: Scan progress counts sessions as they are scored, fed from scan events, and a
+    /// hidden bar still counts
+    #[tokio::test]
+    async fn test_scan_progress() {
+        use crate::progress::ScanProgress;
+        use crate::ScanOptions;
+        
+        let temp_dir = TempDir::new().unwrap();
+        for name in ["one", "two", "three"] {
+            std::fs::write(
+                temp_dir.path().join(format!("{}.md", name)),
+                format!("User: help with {}\nAssistant: Here is the fix.", name),
+            )
+            .unwrap();
+        }
+        let scorer = BehaviorScorer::new().with_base_path(temp_dir.path().to_path_buf());
+        
+        let progress = ScanProgress::new(false);
+        assert_eq!(progress.position(), (0, None));
+        let mut events = 0;
+        let summary = scorer
+            .scan_with_progress(temp_dir.path(), &ScanOptions::default(), |event| {
+                events += 1;
+                progress.update(event);
+            })
+            .unwrap();
+        progress.finish();
+        assert_eq!(summary.scores.len(), 3);
+        assert_eq!(events, 4);
+        assert_eq!(progress.position(), (3, Some(3)));
+        
+        let db = crate::db::Database::new(&temp_dir.path().join("scan.db")).await.unwrap();
+        let options = ScanOptions::default();
+        scorer.scan_incremental_changes(&db, temp_dir.path(), &options).await.unwrap();
+        let rescan = ScanProgress::new(false);
+        let scan = scorer
+            .scan_incremental_with_progress(&db, temp_dir.path(), &options, |event| rescan.update(event))
+            .await
+            .unwrap();
+        assert!(scan.rescored.is_empty());
+        assert_eq!(rescan.position(), (3, Some(3)));
+    }
+
+    /// Test: Verbosity flags pick the log level, and JSON logging writes one parseable
+    /// object per event with its fields
+    #[tokio::test]
+    async fn test_structured_logging() {
+        use crate::logging;
+        use crate::scan::ScanEvent;
+        use std::sync::{Arc, Mutex};
+        use tracing::level_filters::LevelFilter;
+        
+        assert_eq!(logging::level(0, false), LevelFilter::WARN);
+        assert_eq!(logging::level(1, false), LevelFilter::INFO);
+        assert_eq!(logging::level(2, false), LevelFilter::DEBUG);
+        assert_eq!(logging::level(5, false), LevelFilter::TRACE);
+        assert_eq!(logging::level(0, true), LevelFilter::ERROR);
+        
+        #[derive(Clone, Default)]
+        struct Buffer(Arc<Mutex<Vec<u8>>>);
+        impl std::io::Write for Buffer {
+            fn write(&mut self, bytes: &[u8]) -> std::io::Result<usize> {
+                self.0.lock().unwrap().extend_from_slice(bytes);
+                Ok(bytes.len())
+            }
+            fn flush(&mut self) -> std::io::Result<()> {
+                Ok(())
+            }
+        }
+        
+        let buffer = Buffer::default();
+        let writer = buffer.clone();
+        let subscriber = logging::subscriber(LevelFilter::WARN, true, false, move || writer.clone());
+        tracing::subscriber::with_default(subscriber, || {
+            crate::scan::log_scan_event(&ScanEvent::Skipped {
+                path: "big.md".to_string(),
+                reason: "File too large".to_string(),
+            });
+            // Below the level, so left out
+            crate::scan::log_scan_event(&ScanEvent::Discovered { files: 2, sessions: 2 });
+        });
+        
+        let output = String::from_utf8(buffer.0.lock().unwrap().clone()).unwrap();
+        let lines: Vec<serde_json::Value> = output.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+        assert_eq!(lines.len(), 1);
+        assert_eq!(lines[0]["level"], "WARN");
+        assert_eq!(lines[0]["fields"]["message"], "Skipping file");
+        assert_eq!(lines[0]["fields"]["path"], "big.md");
+        assert_eq!(lines[0]["fields"]["reason"], "File too large");
+    }
+
+    /// Test: Rule filters keep rules by ID and category, reject unknown IDs and empty
+    /// subsets, and score over only the rules kept
+    #[tokio::test]
+    async fn test_rule_filter() {
+        use crate::rule_filter::RuleFilter;
+        use crate::{NormalizationMode, TrackerConfig};
+        
+        let rules = vec![
+            rule("approval", "APPROVED", RuleCategory::Safety),
+            rule("no_force", "no force push", RuleCategory::Safety),
+            rule("objective", "OBJECTIVE:", RuleCategory::Startup),
+        ];
+        let ids = |filter: RuleFilter| -> Result<Vec<String>, String> {
+            Ok(filter.apply(&rules)?.into_iter().map(|rule| rule.id).collect())
+        };
+        
+        assert_eq!("safety".parse::<RuleCategory>(), Ok(RuleCategory::Safety));
+        assert_eq!("Startup".parse::<RuleCategory>(), Ok(RuleCategory::Startup));
+        assert!("urgent".parse::<RuleCategory>().is_err());
+        
+        let safety = RuleFilter {
+            categories: vec![RuleCategory::Safety],
+            ..RuleFilter::default()
+        };
+        assert_eq!(ids(safety.clone()).unwrap(), ["approval", "no_force"]);
+        let only = RuleFilter {
+            only: vec!["objective".to_string(), "approval".to_string()],
+            ..RuleFilter::default()
+        };
+        assert_eq!(ids(only).unwrap(), ["approval", "objective"]);
+        let skip = RuleFilter {
+            skip: vec!["no_force".to_string()],
+            categories: vec![RuleCategory::Safety],
+            ..RuleFilter::default()
+        };
+        assert_eq!(ids(skip).unwrap(), ["approval"]);
+        let unknown = RuleFilter {
+            only: vec!["approvals".to_string()],
+            ..RuleFilter::default()
+        };
+        assert_eq!(ids(unknown).unwrap_err(), "Unknown rule: approvals");
+        let empty = RuleFilter {
+            only: vec!["objective".to_string()],
+            categories: vec![RuleCategory::Safety],
+            ..RuleFilter::default()
+        };
+        assert!(ids(empty).is_err());
+        
+        let scorer = BehaviorScorer::with_config(TrackerConfig {
+            rules: rules.clone(),
+            normalization: Default::default(),
+        });
+        let full = scorer.score_session("incident", "OBJECTIVE: roll back. APPROVED").unwrap();
+        assert_eq!(full.total_rules, 3);
+        let filtered = scorer.with_rule_filter(&safety).unwrap();
+        let score = filtered.score_session("incident", "OBJECTIVE: roll back. APPROVED").unwrap();
+        assert_eq!(score.total_rules, 2);
+        assert_eq!(score.passed_rules, 1);
+        assert_eq!(score.score_percentage, 50.0);
+        assert_ne!(score.rule_set_hash, full.rule_set_hash);
+        
+        // Category-equal scores average over the categories left, not all five
+        let scorer = BehaviorScorer::with_config(TrackerConfig {
+            rules: rules.clone(),
+            normalization: NormalizationMode::CategoryEqual,
+        });
+        let full = scorer.score_session("incident", "OBJECTIVE: roll back. APPROVED").unwrap();
+        assert_eq!(full.score_percentage, 30.0);
+        let filtered = scorer.clone().with_rule_filter(&safety).unwrap();
+        assert_eq!(filtered.score_session("incident", "OBJECTIVE: roll back. APPROVED").unwrap().score_percentage, 50.0);
+        let mixed = RuleFilter {
+            skip: vec!["no_force".to_string()],
+            ..RuleFilter::default()
+        };
+        let filtered = scorer.with_rule_filter(&mixed).unwrap();
+        assert_eq!(filtered.score_session("incident", "OBJECTIVE: roll back. APPROVED").unwrap().score_percentage, 100.0);
+    }
+
+    /// Test: Scoring a corpus under old and new rules reports added, removed, and edited
+    /// rules, and which sessions and rules change outcome
+    #[tokio::test]
+    async fn test_rule_set_impact() {
+        use crate::rule_impact::rule_set_impact;
+        use crate::{ScanOptions, TrackerConfig};
+        
+        let safety = |id: &str, pattern: &str| rule(id, pattern, RuleCategory::Safety);
+        let old_rules = vec![safety("approval", "APPROVED"), safety("objective", "OBJECTIVE:"), safety("legacy", "LEGACY")];
+        // `approval` loosens to also accept OK, `legacy` goes, and `rollback` is new
+        let new_rules = vec![safety("approval", "APPROVED|OK"), safety("objective", "OBJECTIVE:"), safety("rollback", "ROLLBACK")];
+        
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("approved.md"), "User: go\nAssistant: OBJECTIVE: ship. APPROVED ROLLBACK").unwrap();
+        std::fs::write(temp_dir.path().join("ok.md"), "User: go\nAssistant: OBJECTIVE: ship. OK").unwrap();
+        std::fs::write(temp_dir.path().join("steady.md"), "User: go\nAssistant: nothing").unwrap();
+        let score = |rules: &[RuleDefinition]| {
+            BehaviorScorer::with_config(TrackerConfig {
+                rules: rules.to_vec(),
+                normalization: Default::default(),
+            })
+            .with_base_path(temp_dir.path().to_path_buf())
+            .scan_history(temp_dir.path(), &ScanOptions::default(), |_| {})
+            .unwrap()
+            .scores
+        };
+        
+        let impact = rule_set_impact(&old_rules, &new_rules, &score(&old_rules), &score(&new_rules));
+        assert_eq!(impact.added_rules, ["rollback"]);
+        assert_eq!(impact.removed_rules, ["legacy"]);
+        assert_eq!(impact.edited_rules, ["approval"]);
+        assert_eq!(impact.sessions, 3);
+        
+        // Every session fails the new rollback rule except `approved`
+        let rules: Vec<(&str, usize, usize)> = impact
+            .changed_rules
+            .iter()
+            .map(|rule| (rule.rule_id.as_str(), rule.fixed.len(), rule.regressed.len()))
+            .collect();
+        assert_eq!(rules, [("approval", 1, 0), ("rollback", 1, 2)]);
+        
+        let ok = impact.changed_sessions.iter().find(|session| session.session_id == "ok").unwrap();
+        assert_eq!(ok.fixed, ["approval"]);
+        assert_eq!(ok.regressed, ["rollback"]);
+        let approved = impact.changed_sessions.iter().find(|session| session.session_id == "approved").unwrap();
+        assert_eq!(approved.fixed, ["rollback"]);
+        assert!(approved.regressed.is_empty());
+        // `steady` still passes nothing, so its percentage holds, but it now fails `rollback`
+        let steady = impact.changed_sessions.iter().find(|session| session.session_id == "steady").unwrap();
+        assert_eq!(steady.percentage_delta, 0.0);
+        assert_eq!(steady.regressed, ["rollback"]);
+        assert!(impact
+            .changed_sessions
+            .windows(2)
+            .all(|pair| pair[0].percentage_delta <= pair[1].percentage_delta));
+    }
+
+    /// Test: Scans for an agent record its source and the rule versions of that agent's profile
+    #[tokio::test]
+    async fn test_source_profile_scoring() {
+        use crate::db::Database;
+        use crate::ScanOptions;
+        
+        let temp_dir = TempDir::new().unwrap();
+        std::fs::write(temp_dir.path().join("codex.md"), "OBJECTIVE: ship").unwrap();
+        let db = Database::new_in_memory().await.unwrap();
+        db.seed_rules(&BehaviorScorer::new().config().rules).await.unwrap();
+        
+        // The profile judges objectives by a stricter pattern than the seeded rule
+        let mut config = BehaviorScorer::new().config().clone();
+        let rule = config.rules.iter_mut().find(|r| r.id == "objective_before_execution").unwrap();
+        rule.pattern = "^OBJECTIVE: ".to_string();
+        let profile_rule = rule.clone();
+        let scorer = BehaviorScorer::with_config(config).with_base_path(temp_dir.path().to_path_buf());
+        
+        let options = ScanOptions {
+            source: Some("codex".to_string()),
+            ..ScanOptions::default()
+        };
+        let directories = vec![temp_dir.path().to_string_lossy().to_string()];
+        let report = scorer.scheduled_scan(&db, &directories, &options).await;
+        assert_eq!(report.new_scores.len(), 1);
+        assert_eq!(db.get_session("codex").await.unwrap().source, "codex");
+        
+        let score = db.get_latest_score("codex").await.unwrap();
+        let checks = db.get_score_rule_checks(score.id).await.unwrap();
+        let check = checks.iter().find(|c| c.rule_id == profile_rule.id).unwrap();
+        let version = db.get_rule_version(check.rule_version_id.unwrap()).await.unwrap();
+        assert_eq!((version.version, version.pattern.as_str()), (2, profile_rule.pattern.as_str()));
+        assert_eq!(db.get_rule(&profile_rule.id).await.unwrap().version, 1);
+    }
+}
@@ -0,0 +1,82 @@
+//! JUnit XML output, so CI test-report views show each session as a suite and
+//! each rule as a test case that passed or failed.
+
+use crate::SessionScore;
+
+/// A JUnit report with a `<testsuite>` per session and a `<testcase>` per rule.
+/// Failed rules carry their suggestion and any evidence in the failure; passed
+/// rules put their evidence in `<system-out>`.
+pub fn junit_report(scores: &[SessionScore]) -> String {
+    let scores: Vec<_> = scores.iter().filter(|score| score.duplicate_of.is_none()).collect();
+    let tests: usize = scores.iter().map(|score| score.rules.len()).sum();
+    let failures: usize = scores.iter().flat_map(|score| &score.rules).filter(|check| !check.passed).count();
+
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str(&format!(
+        "<testsuites name=\"behavior-scorer\" tests=\"{}\" failures=\"{}\">\n",
+        tests, failures
+    ));
+    for score in scores {
+        xml.push_str(&format!(
+            "  <testsuite name=\"{}\" tests=\"{}\" failures=\"{}\" timestamp=\"{}\">\n",
+            escape(&score.session_id),
+            score.rules.len(),
+            score.rules.iter().filter(|check| !check.passed).count(),
+            score.timestamp.format("%Y-%m-%dT%H:%M:%S")
+        ));
+        xml.push_str(&format!(
+            "    <properties><property name=\"score_percentage\" value=\"{:.1}\"/></properties>\n",
+            score.score_percentage
+        ));
+        for check in &score.rules {
+            let open = format!(
+                "    <testcase name=\"{}\" classname=\"{}\"",
+                escape(&check.rule_name),
+                escape(&score.session_id)
+            );
+            if check.passed {
+                match &check.evidence {
+                    Some(evidence) => xml.push_str(&format!(
+                        "{}>\n      <system-out>{}</system-out>\n    </testcase>\n",
+                        open,
+                        escape(evidence)
+                    )),
+                    None => xml.push_str(&format!("{}/>\n", open)),
+                }
+                continue;
+            }
+            let mut message = check.suggestion.clone().unwrap_or_else(|| format!("{} failed", check.rule_name));
+            if let Some(evidence) = &check.evidence {
+                message.push_str(&format!("\nEvidence: {}", evidence));
+            }
+            xml.push_str(&format!(
+                "{}>\n      <failure message=\"{}\" type=\"{}\">{}</failure>\n    </testcase>\n",
+                open,
+                escape(message.lines().next().unwrap_or_default()),
+                escape(&check.rule_id),
+                escape(&message)
+            ));
+        }
+        xml.push_str("  </testsuite>\n");
+    }
+    xml.push_str("</testsuites>\n");
+    xml
+}
+
+/// Escape text for use in XML content and attribute values, dropping characters XML 1.0 can't hold
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            '\t' | '\n' | '\r' => escaped.push(c),
+            c if c < ' ' || c == '\u{fffe}' || c == '\u{ffff}' => {}
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
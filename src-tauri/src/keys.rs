@@ -0,0 +1,106 @@
+//! Key sources for encrypted databases. `Database::open_encrypted` asks a
+//! [`KeyProvider`] for the passphrase, so keys can come from the OS keychain in the
+//! app, an environment variable in scripts, or a fixed value in tests.
+
+use crate::db::DbError;
+
+/// Supplies the passphrase for an encrypted database
+pub trait KeyProvider: Send + Sync {
+    /// Short name of the key source, used in error messages
+    fn name(&self) -> &'static str;
+
+    /// Passphrase for the database, creating one if the source supports it
+    fn key(&self) -> Result<String, DbError>;
+}
+
+/// A passphrase given directly
+#[derive(Clone)]
+pub struct StaticKey(String);
+
+impl StaticKey {
+    pub fn new(key: impl Into<String>) -> Self {
+        Self(key.into())
+    }
+}
+
+impl std::fmt::Debug for StaticKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("StaticKey(..)")
+    }
+}
+
+impl KeyProvider for StaticKey {
+    fn name(&self) -> &'static str {
+        "static"
+    }
+
+    fn key(&self) -> Result<String, DbError> {
+        Ok(self.0.clone())
+    }
+}
+
+/// A passphrase read from an environment variable
+#[derive(Debug, Clone)]
+pub struct EnvKey {
+    pub var: String,
+}
+
+impl EnvKey {
+    pub fn new(var: impl Into<String>) -> Self {
+        Self { var: var.into() }
+    }
+}
+
+impl KeyProvider for EnvKey {
+    fn name(&self) -> &'static str {
+        "environment"
+    }
+
+    fn key(&self) -> Result<String, DbError> {
+        std::env::var(&self.var).map_err(|_| DbError::Encryption(format!("{} is not set", self.var)))
+    }
+}
+
+/// A passphrase held in the OS keychain (macOS Keychain, Windows Credential Manager,
+/// or the Secret Service on Linux). A random key is generated and stored the first
+/// time the entry is read.
+#[cfg(feature = "sqlcipher")]
+#[derive(Debug, Clone)]
+pub struct KeychainKey {
+    pub service: String,
+    pub account: String,
+}
+
+#[cfg(feature = "sqlcipher")]
+impl KeychainKey {
+    pub fn new(service: impl Into<String>, account: impl Into<String>) -> Self {
+        Self {
+            service: service.into(),
+            account: account.into(),
+        }
+    }
+}
+
+#[cfg(feature = "sqlcipher")]
+impl KeyProvider for KeychainKey {
+    fn name(&self) -> &'static str {
+        "keychain"
+    }
+
+    fn key(&self) -> Result<String, DbError> {
+        let keychain_error = |e: keyring::Error| DbError::Encryption(format!("Keychain: {}", e));
+        let entry = keyring::Entry::new(&self.service, &self.account).map_err(keychain_error)?;
+        match entry.get_password() {
+            Ok(key) => Ok(key),
+            Err(keyring::Error::NoEntry) => {
+                let mut bytes = [0u8; 32];
+                getrandom::getrandom(&mut bytes)
+                    .map_err(|e| DbError::Encryption(format!("Failed to generate key: {}", e)))?;
+                let key: String = bytes.iter().map(|b| format!("{:02x}", b)).collect();
+                entry.set_password(&key).map_err(keychain_error)?;
+                Ok(key)
+            }
+            Err(e) => Err(keychain_error(e)),
+        }
+    }
+}
@@ -1,11 +1,15 @@
 use chrono::{DateTime, Utc};
 use regex::Regex;
 use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
 use specta::Type;
-use std::collections::HashMap;
-use std::fs;
+use blobs::BlobSummary;
+use std::borrow::Cow;
+use std::collections::{BTreeMap, HashMap};
+use std::ops::Range;
 use std::path::{Path, PathBuf};
-use walkdir::WalkDir;
+use std::sync::Arc;
+use std::time::Instant;
 
 /// SQLite database layer
 pub mod db;
@@ -16,9 +20,144 @@ pub mod retry;
 /// Performance optimizations (caching, batching)
 pub mod performance;
 
+/// Anomaly detection against historical rule baselines
+pub mod anomaly;
+
+/// Weight normalization strategies for session scores
+pub mod normalization;
+
+/// Directory scanning and multi-part session assembly
+pub mod scan;
+
+/// Extraction and scanning of zip and tar archives of session logs
+pub mod archive;
+
+/// Placeholder substitution for embedded base64 and binary blobs
+pub mod blobs;
+
+/// Overlapping chunk splitting for oversized transcripts
+pub mod chunking;
+
+/// Encoding detection and lossy decoding for non-UTF-8 transcripts
+pub mod encoding;
+
+/// ANSI escape and carriage-return cleanup for terminal captures
+pub mod terminal;
+
+/// Transcript downloads over HTTPS
+pub mod fetch;
+
+/// Session ID derivation strategies for scanned files
+pub mod session_id;
+
+/// Session import from third-party SQLite databases
+pub mod import;
+
+/// Bulk ingestion of transcript history into the database
+pub mod ingest;
+
+/// CSV, Parquet, and JSON Lines export of stored history
+pub mod export;
+
+/// Rule pattern previews for the rule editor
+pub mod preview;
+
+/// Persisted app settings
+pub mod settings;
+
+/// TOML rules files for the CLI
+pub mod rules_file;
+
+/// Scheduled background scans
+pub mod schedule;
+
+/// Desktop alerts for low scores
+pub mod alerts;
+
+/// Rule-by-rule comparison of two scores
+pub mod compare;
+
+/// SARIF output for code scanning tools
+pub mod sarif;
+
+/// JUnit XML output for CI test reports
+pub mod junit;
+
+/// Shareable Markdown score reports
+pub mod markdown;
+
+/// Colored, column-aligned terminal summaries
+pub mod summary;
+
+/// Defaults for CLI flags from the CLI's config file
+pub mod cli_config;
+
+/// Terminal dashboard with live scores and rule drill-down, for the CLI only
+#[cfg(feature = "cli")]
+pub mod tui;
+
+/// Scan progress bars for the CLI
+pub mod progress;
+
+/// Leveled, optionally JSON, diagnostics on stderr
+pub mod logging;
+
+/// Rule subsets by ID and category
+pub mod rule_filter;
+
+/// Corpus-wide impact of changing the rule set
+pub mod rule_impact;
+
+/// Scoring of individually chosen files
+pub mod files;
+
+/// `data-dashboard://` deep links to stored sessions
+pub mod links;
+
+/// Re-scoring of stored transcripts after rule changes
+pub mod rescore;
+
+/// Typed errors returned by app commands
+pub mod error;
+
+/// Confirmation tokens for permanent deletes
+pub mod confirm;
+
+/// Key providers for encrypted databases
+pub mod keys;
+
+/// Named database profiles and cross-database stats
+pub mod profiles;
+
+/// Live watch mode for session directories
+pub mod watch;
+
+/// Structured transcript model with JSONL, chat JSON, and plain-text parsers
+pub mod transcript;
+
+/// Transcript parser trait and registry keyed by source and extension
+pub mod parsers;
+
+pub use chunking::ChunkOptions;
+pub use normalization::NormalizationMode;
+pub use parsers::{ParserRegistry, TranscriptParser};
+pub use scan::{DuplicatePolicy, IncrementalScan, ScanEvent, ScanIssue, ScanOptions, ScanReport, ScanSummary};
+pub use session_id::SessionIdStrategy;
+pub use settings::{ScoreThresholds, Settings};
+pub use transcript::{Role, SessionInfo, ToolCall, Transcript, TranscriptFormat, Turn};
+
+// The suite nests its own `integration_tests` module and keeps imports and fixtures its
+// original tests don't use yet
 #[cfg(test)]
+#[allow(clippy::module_inception, unused_imports, unused_variables)]
 mod integration_tests;
 
+/// Version of the scorer stamped into every SessionScore
+pub const SCORER_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// Session ID given to scores from `BehaviorScorer::quick_score`
+pub const QUICK_SCORE_SESSION_ID: &str = "quick-check";
+
 /// SECURITY: Input validation and sanitization helpers
 mod security {
     use std::path::{Path, PathBuf};
@@ -32,6 +171,7 @@ mod security {
     }
     
     /// Sanitize and validate file path to prevent path traversal
+    #[allow(dead_code)]
     pub fn sanitize_path(base_path: &Path, input_path: &str) -> Option<PathBuf> {
         let input = input_path.trim();
         
@@ -75,6 +215,21 @@ mod security {
             return Err("Transcript exceeds maximum size of 10MB");
         }
         
+        validate_characters(content)
+    }
+    
+    /// Validate transcript content that will be evaluated in chunks
+    pub fn validate_chunked_transcript(content: &str) -> Result<&str, &'static str> {
+        const MAX_SIZE: usize = 100 * 1024 * 1024; // 100MB limit
+        
+        if content.len() > MAX_SIZE {
+            return Err("Transcript exceeds maximum size of 100MB");
+        }
+        
+        validate_characters(content)
+    }
+    
+    fn validate_characters(content: &str) -> Result<&str, &'static str> {
         // Check for null bytes
         if content.contains('\0') {
             return Err("Transcript contains invalid characters");
@@ -87,6 +242,9 @@ mod security {
 /// Represents a single behavior rule and its detection
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct RuleCheck {
+    /// Position of the rule in the tracker config
+    #[serde(default)]
+    pub order: usize,
     pub rule_id: String,
     pub rule_name: String,
     pub description: String,
@@ -94,6 +252,16 @@ pub struct RuleCheck {
     pub confidence: f64, // 0.0 to 1.0
     pub evidence: Option<String>,
     pub suggestion: Option<String>,
+    /// Turn containing the evidence, for structured transcripts
+    #[serde(default)]
+    pub turn_index: Option<usize>,
+    /// Number of times the rule's pattern matched the transcript
+    #[serde(default)]
+    pub match_count: usize,
+    /// Definition the rule was evaluated with, so a stored check links to the rule
+    /// version that produced it rather than whichever is latest
+    #[serde(default)]
+    pub definition: Option<RuleDefinition>,
 }
 
 /// Overall session score
@@ -106,15 +274,151 @@ pub struct SessionScore {
     pub score_percentage: f64,
     pub rules: Vec<RuleCheck>,
     pub summary: String,
+    /// Crate version of the scorer that produced this score
+    #[serde(default)]
+    pub scorer_version: String,
+    /// Fingerprint of the rule set this score was evaluated against
+    #[serde(default)]
+    pub rule_set_hash: String,
+    /// Session metadata carried over from the transcript (e.g. markdown frontmatter)
+    #[serde(default)]
+    pub metadata: BTreeMap<String, serde_json::Value>,
+    /// Model and timing inferred from the transcript
+    #[serde(default)]
+    pub info: SessionInfo,
+    /// Size and shape of the transcript that was scored
+    #[serde(default)]
+    pub transcript_stats: TranscriptStats,
+    /// Failed rules that normally pass, filled in from historical baselines when the
+    /// score is stored
+    #[serde(default)]
+    pub anomalies: Vec<anomaly::RuleAnomaly>,
+    /// Per-rule timings, only populated when diagnostics are enabled
+    #[serde(default)]
+    pub diagnostics: Option<ScoreDiagnostics>,
+    /// Problems reading the transcript that didn't prevent scoring (e.g. lossy decoding)
+    #[serde(default)]
+    pub warnings: Vec<String>,
+    /// SHA-256 of the evaluated transcript text, used to spot the same session stored twice
+    #[serde(default)]
+    pub content_hash: String,
+    /// Session with identical content that this one duplicates, when linked
+    #[serde(default)]
+    pub duplicate_of: Option<String>,
+    /// Transcript file the session was read from, when it was scored from one
+    #[serde(default)]
+    pub source_path: Option<String>,
+}
+
+/// Result of scoring pasted text that isn't stored as a session
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct QuickScore {
+    pub score: SessionScore,
+    /// Rules skipped because their patterns don't compile
+    pub rule_warnings: Vec<String>,
+}
+
+/// A score the app just stored, with the source it was stored under
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScoreSaved {
+    pub source: String,
+    pub score: SessionScore,
+}
+
+impl SessionScore {
+    /// Metadata serialized for the `sessions.metadata` column, if there is any
+    pub fn metadata_json(&self) -> Option<String> {
+        if self.metadata.is_empty() {
+            None
+        } else {
+            serde_json::to_string(&self.metadata).ok()
+        }
+    }
+}
+
+/// Size metrics for a scored transcript
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct TranscriptStats {
+    pub byte_length: usize,
+    pub line_count: usize,
+    pub turn_count: usize,
+    pub estimated_tokens: usize,
+}
+
+impl TranscriptStats {
+    /// Compute stats for a plain-text transcript, counting speaker-labelled lines as turns
+    pub fn from_text(transcript: &str) -> Self {
+        Self {
+            byte_length: transcript.len(),
+            line_count: transcript.lines().count(),
+            turn_count: transcript::speaker_marker().find_iter(transcript).count(),
+            // Rough heuristic: ~4 bytes per token for English text
+            estimated_tokens: transcript.len().div_ceil(4),
+        }
+    }
+}
+
+/// Hex SHA-256 of transcript text
+pub fn content_hash(text: &str) -> String {
+    Sha256::digest(text.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
+
+/// Time spent evaluating a single rule
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RuleTiming {
+    pub rule_id: String,
+    pub elapsed_ms: f64,
+}
+
+/// Evaluation diagnostics for a scored session
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScoreDiagnostics {
+    pub rule_timings: Vec<RuleTiming>,
+    pub total_ms: f64,
+}
+
+impl ScoreDiagnostics {
+    /// Rule timings sorted slowest first
+    pub fn slowest(&self) -> Vec<&RuleTiming> {
+        let mut timings: Vec<&RuleTiming> = self.rule_timings.iter().collect();
+        timings.sort_by(|a, b| b.elapsed_ms.total_cmp(&a.elapsed_ms));
+        timings
+    }
 }
 
 /// Behavior tracker configuration
 #[derive(Debug, Clone, Serialize, Deserialize, Type)]
 pub struct TrackerConfig {
     pub rules: Vec<RuleDefinition>,
+    #[serde(default)]
+    pub normalization: NormalizationMode,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+impl TrackerConfig {
+    /// Stable hash of the rule set, so historical scores can be tied to the rules that produced them
+    pub fn fingerprint(&self) -> String {
+        let mut hasher = Sha256::new();
+        for rule in &self.rules {
+            hasher.update(rule.id.as_bytes());
+            hasher.update([0]);
+            hasher.update(rule.pattern.as_bytes());
+            hasher.update([0]);
+            hasher.update(rule.weight.to_bits().to_le_bytes());
+            hasher.update(format!("{:?}", rule.category).as_bytes());
+            hasher.update([0xff]);
+        }
+        hasher.update(format!("{:?}", self.normalization).as_bytes());
+        hasher.finalize()[..8]
+            .iter()
+            .map(|b| format!("{:02x}", b))
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
 pub struct RuleDefinition {
     pub id: String,
     pub name: String,
@@ -124,7 +428,44 @@ pub struct RuleDefinition {
     pub category: RuleCategory,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+impl RuleDefinition {
+    /// Problems that would stop the rule from being stored or evaluated, one per field
+    pub fn validate(&self) -> Vec<RuleIssue> {
+        let mut issues = Vec::new();
+        let mut issue = |field: &str, message: String| {
+            issues.push(RuleIssue {
+                field: field.to_string(),
+                message,
+            })
+        };
+        if self.id.trim().is_empty() {
+            issue("id", "Rule ID must not be empty".to_string());
+        }
+        if self.name.trim().is_empty() {
+            issue("name", "Name must not be empty".to_string());
+        }
+        if !self.weight.is_finite() || self.weight < 0.0 {
+            issue("weight", "Weight must be a non-negative number".to_string());
+        }
+        // SECURITY: Patterns that won't compile never reach the scorer
+        if self.pattern.is_empty() {
+            issue("pattern", "Pattern must not be empty".to_string());
+        } else if let Err(e) = Regex::new(&self.pattern) {
+            issue("pattern", e.to_string());
+        }
+        issues
+    }
+}
+
+/// A problem with one field of a rule definition
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct RuleIssue {
+    /// `RuleDefinition` field the problem is in
+    pub field: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, Type)]
 pub enum RuleCategory {
     Startup,
     Response,
@@ -133,34 +474,80 @@ pub enum RuleCategory {
     Communication,
 }
 
+impl RuleCategory {
+    pub const ALL: [RuleCategory; 5] = [
+        RuleCategory::Startup,
+        RuleCategory::Response,
+        RuleCategory::Confidence,
+        RuleCategory::Safety,
+        RuleCategory::Communication,
+    ];
+}
+
+impl std::str::FromStr for RuleCategory {
+    type Err = String;
+
+    /// A category by name, ignoring case
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Self::ALL
+            .into_iter()
+            .find(|category| format!("{:?}", category).eq_ignore_ascii_case(name.trim()))
+            .ok_or_else(|| format!("Unknown rule category: {}", name))
+    }
+}
+
 /// Main behavior scorer with security considerations
+#[derive(Clone)]
 pub struct BehaviorScorer {
     config: TrackerConfig,
-    compiled_rules: HashMap<String, Regex>,
+    /// Compiled patterns, index-aligned with `config.rules`
+    compiled_rules: Vec<Option<Regex>>,
+    rule_set_hash: String,
     base_path: PathBuf,
+    diagnostics: bool,
+    chunking: Option<ChunkOptions>,
+    parsers: Arc<ParserRegistry>,
+    thresholds: ScoreThresholds,
+    /// Categories `CategoryEqual` normalization averages over: all of them, or those
+    /// left after a rule filter
+    categories: Vec<RuleCategory>,
 }
 
 impl BehaviorScorer {
     pub fn new() -> Self {
         let config = Self::default_config();
         let compiled_rules = Self::compile_rules(&config);
-        let base_path = PathBuf::from("/Users/jamiecraik/dev/data-behavior-dashboard");
+        let rule_set_hash = config.fingerprint();
+        let base_path = settings::default_base_path();
         
         Self {
             config,
             compiled_rules,
+            rule_set_hash,
             base_path,
+            diagnostics: false,
+            chunking: None,
+            parsers: Arc::new(ParserRegistry::default()),
+            thresholds: ScoreThresholds::default(),
+            categories: RuleCategory::ALL.to_vec(),
         }
     }
     
     pub fn with_config(config: TrackerConfig) -> Self {
         let compiled_rules = Self::compile_rules(&config);
-        let base_path = PathBuf::from("/Users/jamiecraik/dev/data-behavior-dashboard");
+        let rule_set_hash = config.fingerprint();
+        let base_path = settings::default_base_path();
         
         Self {
             config,
             compiled_rules,
+            rule_set_hash,
             base_path,
+            diagnostics: false,
+            chunking: None,
+            parsers: Arc::new(ParserRegistry::default()),
+            thresholds: ScoreThresholds::default(),
+            categories: RuleCategory::ALL.to_vec(),
         }
     }
     
@@ -170,6 +557,86 @@ impl BehaviorScorer {
         self
     }
     
+    /// Active rule configuration
+    pub fn config(&self) -> &TrackerConfig {
+        &self.config
+    }
+    
+    /// Fingerprint of the active rule set
+    pub fn rule_set_hash(&self) -> &str {
+        &self.rule_set_hash
+    }
+    
+    /// Replace the active rules in place, compiling only patterns that aren't already
+    /// compiled and keeping every other setting
+    pub fn set_rules(&mut self, rules: Vec<RuleDefinition>) {
+        let mut compiled: HashMap<String, Regex> = self
+            .config
+            .rules
+            .iter()
+            .zip(self.compiled_rules.drain(..))
+            .filter_map(|(rule, regex)| Some((rule.pattern.clone(), regex?)))
+            .collect();
+        self.compiled_rules = rules
+            .iter()
+            .map(|rule| match compiled.get(&rule.pattern) {
+                Some(regex) => Some(regex.clone()),
+                None => {
+                    let regex = Self::compile_rule(rule);
+                    if let Some(regex) = &regex {
+                        compiled.insert(rule.pattern.clone(), regex.clone());
+                    }
+                    regex
+                }
+            })
+            .collect();
+        self.config.rules = rules;
+        self.rule_set_hash = self.config.fingerprint();
+    }
+    
+    /// One message per rule whose pattern failed to compile; those rules never pass
+    pub fn rule_warnings(&self) -> Vec<String> {
+        self.config
+            .rules
+            .iter()
+            .zip(&self.compiled_rules)
+            .filter(|(_, regex)| regex.is_none())
+            .map(|(rule, _)| match Regex::new(&rule.pattern) {
+                Err(e) => format!("Rule {} has an invalid pattern: {}", rule.id, e),
+                Ok(_) => format!("Rule {} has an invalid pattern", rule.id),
+            })
+            .collect()
+    }
+    
+    /// Record how long each rule takes to evaluate in `SessionScore::diagnostics`
+    pub fn with_diagnostics(mut self, enabled: bool) -> Self {
+        self.diagnostics = enabled;
+        self
+    }
+    
+    /// Percentages at which summaries move between bands
+    pub fn with_thresholds(mut self, thresholds: ScoreThresholds) -> Self {
+        self.thresholds = thresholds;
+        self
+    }
+    
+    /// Evaluate transcripts in overlapping chunks, raising the size limit from 10MB to 100MB
+    pub fn with_chunking(mut self, chunking: Option<ChunkOptions>) -> Self {
+        self.chunking = chunking;
+        self
+    }
+    
+    /// Parse transcripts with a custom set of parsers
+    pub fn with_parsers(mut self, parsers: ParserRegistry) -> Self {
+        self.parsers = Arc::new(parsers);
+        self
+    }
+    
+    /// Parsers used for files and session sources
+    pub fn parsers(&self) -> &ParserRegistry {
+        &self.parsers
+    }
+    
     fn default_config() -> TrackerConfig {
         TrackerConfig {
             rules: vec![
@@ -201,7 +668,7 @@ impl BehaviorScorer {
                     id: "explanation_volume".to_string(),
                     name: "Explanation volume limit".to_string(),
                     description: "Max 2 sentences of process explanation".to_string(),
-                    pattern: r"(?s)^(?:(?!(\n\n|\r\n\r\n)).){0,300}$".to_string(),
+                    pattern: r"^(?:[^\r\n]|\r?\n[^\r\n]){0,300}(?:\r?\n)?$".to_string(),
                     weight: 1.0,
                     category: RuleCategory::Response,
                 },
@@ -238,20 +705,23 @@ impl BehaviorScorer {
                     category: RuleCategory::Safety,
                 },
             ],
+            normalization: NormalizationMode::Raw,
         }
     }
     
-    fn compile_rules(config: &TrackerConfig) -> HashMap<String, Regex> {
-        let mut compiled = HashMap::new();
-        for rule in &config.rules {
-            // SECURITY: Validate regex before compiling
-            if let Ok(regex) = Regex::new(&rule.pattern) {
-                compiled.insert(rule.id.clone(), regex);
-            } else {
-                eprintln!("Warning: Failed to compile regex for rule {}", rule.id);
+    fn compile_rules(config: &TrackerConfig) -> Vec<Option<Regex>> {
+        config.rules.iter().map(Self::compile_rule).collect()
+    }
+    
+    fn compile_rule(rule: &RuleDefinition) -> Option<Regex> {
+        // SECURITY: Validate regex before compiling
+        match Regex::new(&rule.pattern) {
+            Ok(compiled) => Some(compiled),
+            Err(e) => {
+                tracing::warn!(rule = %rule.id, error = %e, "Failed to compile regex for rule");
+                None
             }
         }
-        compiled
     }
     
     /// SECURITY: Score a single session transcript with validation
@@ -266,34 +736,190 @@ impl BehaviorScorer {
         }
         
         // Validate transcript content
-        let transcript = security::validate_transcript(transcript)
-            .map_err(|e| e.to_string())?;
+        let (transcript, blobs) = self.prepare_content(transcript)?;
         
+        let mut score = self.score_transcript(session_id, &Transcript::from_plain_text(&transcript))?;
+        score.warnings.extend(blobs.warning());
+        Ok(score)
+    }
+    
+    /// SECURITY: Score a structured transcript turn by turn, recording which turn produced each rule's evidence
+    pub fn score_transcript(
+        &self,
+        session_id: &str,
+        transcript: &Transcript,
+    ) -> Result<SessionScore, String> {
+        if !security::validate_session_id(session_id) {
+            return Err("Invalid session ID".to_string());
+        }
+        
+        // Terminal escapes and embedded blobs in turn content (e.g. captured tool output)
+        // would break matching
+        let needs_cleaning = |content: &str| {
+            terminal::has_terminal_artifacts(content) || matches!(blobs::strip_blobs(content).0, Cow::Owned(_))
+        };
+        if transcript.turns.iter().any(|turn| needs_cleaning(&turn.content)) {
+            let mut cleaned = transcript.clone();
+            let mut removed = BlobSummary::default();
+            for turn in &mut cleaned.turns {
+                let (content, summary) = blobs::strip_blobs(&turn.content);
+                removed.merge(summary);
+                turn.content = terminal::clean_terminal_output(&content).into_owned();
+            }
+            let mut score = self.score_cleaned_transcript(session_id, &cleaned)?;
+            score.warnings.extend(removed.warning());
+            return Ok(score);
+        }
+        
+        self.score_cleaned_transcript(session_id, transcript)
+    }
+    
+    /// Score a transcript whose turns have already been cleaned, as is
+    fn score_cleaned_transcript(&self, session_id: &str, transcript: &Transcript) -> Result<SessionScore, String> {
+        let (text, turn_offsets) = transcript.render();
+        self.validate_transcript(&text)?;
+        
+        Ok(self.evaluate(session_id, transcript, &text, &turn_offsets))
+    }
+    
+    /// Score file content, parsing it according to the file's extension
+    pub fn score_file_content(
+        &self,
+        session_id: &str,
+        path: &Path,
+        content: &str,
+    ) -> Result<SessionScore, String> {
+        let extension = path.extension().and_then(|ext| ext.to_str());
+        self.score_parsed_content(session_id, self.parsers.for_extension(extension), content)
+    }
+    
+    /// Score raw content with a parser picked from the registry
+    pub(crate) fn score_parsed_content(
+        &self,
+        session_id: &str,
+        parser: &dyn TranscriptParser,
+        content: &str,
+    ) -> Result<SessionScore, String> {
+        // SECURITY: Validate raw content before parsing
+        let (content, blobs) = self.prepare_content(content)?;
+        let transcript = parser.parse(&content).map_err(|e| e.to_string())?;
+        let mut score = self.score_transcript(session_id, &transcript)?;
+        score.warnings.extend(blobs.warning());
+        Ok(score)
+    }
+    
+    /// Score raw text in a given format, detecting the format when none is given
+    pub fn score_text(
+        &self,
+        session_id: &str,
+        content: &str,
+        format: Option<TranscriptFormat>,
+    ) -> Result<SessionScore, String> {
+        // SECURITY: Validate raw content before parsing
+        let (content, blobs) = self.prepare_content(content)?;
+        let transcript = match format {
+            Some(format) => Transcript::parse_as(&content, format).map_err(|e| e.to_string())?,
+            None => Transcript::detect(&content),
+        };
+        let mut score = self.score_transcript(session_id, &transcript)?;
+        score.warnings.extend(blobs.warning());
+        Ok(score)
+    }
+    
+    /// Score pasted text without a session, under a placeholder ID
+    pub fn quick_score(&self, content: &str, format: Option<TranscriptFormat>) -> Result<QuickScore, String> {
+        Ok(QuickScore {
+            score: self.score_text(QUICK_SCORE_SESSION_ID, content, format)?,
+            rule_warnings: self.rule_warnings(),
+        })
+    }
+    
+    /// Score content from a session source (e.g. `codex`) with the parser registered for it,
+    /// detecting the format for unregistered sources
+    pub fn score_source_content(
+        &self,
+        session_id: &str,
+        source: &str,
+        content: &str,
+    ) -> Result<SessionScore, String> {
+        // SECURITY: Validate raw content before parsing
+        let (content, blobs) = self.prepare_content(content)?;
+        let transcript = match self.parsers.for_source(source) {
+            Some(parser) => parser.parse(&content).map_err(|e| e.to_string())?,
+            None => Transcript::detect(&content),
+        };
+        let mut score = self.score_transcript(session_id, &transcript)?;
+        score.warnings.extend(blobs.warning());
+        Ok(score)
+    }
+    
+    /// SECURITY: Replace embedded blobs, then validate and clean raw transcript text.
+    /// Blobs are removed first so that inline images don't push content over the size limit.
+    fn prepare_content<'a>(&self, content: &'a str) -> Result<(Cow<'a, str>, BlobSummary), String> {
+        let (content, blobs) = blobs::strip_blobs(content);
+        self.validate_transcript(&content)?;
+        let cleaned = match terminal::clean_terminal_output(&content) {
+            Cow::Borrowed(_) => content,
+            Cow::Owned(owned) => Cow::Owned(owned),
+        };
+        Ok((cleaned, blobs))
+    }
+    
+    /// SECURITY: Apply the size limit for the configured evaluation mode
+    fn validate_transcript(&self, content: &str) -> Result<(), String> {
+        let validated = if self.chunking.is_some() {
+            security::validate_chunked_transcript(content)
+        } else {
+            security::validate_transcript(content)
+        };
+        validated.map(|_| ()).map_err(|e| e.to_string())
+    }
+    
+    /// Evaluate every rule against the rendered, validated transcript text.
+    /// `turn_offsets` holds the byte offset where each turn starts in `text`.
+    fn evaluate(
+        &self,
+        session_id: &str,
+        parsed: &Transcript,
+        transcript: &str,
+        turn_offsets: &[usize],
+    ) -> SessionScore {
         let mut rules = Vec::new();
         let mut passed_count = 0;
-        let mut total_weight = 0.0;
-        let mut passed_weight = 0.0;
+        let mut rule_timings = Vec::new();
+        let started = Instant::now();
+        let chunks = match self.chunking {
+            Some(options) => options.split(transcript),
+            None => vec![chunking::Chunk { window: 0..transcript.len(), owned: 0..transcript.len() }],
+        };
         
-        for rule_def in &self.config.rules {
-            let passed = if let Some(regex) = self.compiled_rules.get(&rule_def.id) {
-                regex.is_match(transcript)
-            } else {
-                false
-            };
+        for (order, (rule_def, regex)) in self.config.rules.iter().zip(&self.compiled_rules).enumerate() {
+            let rule_started = Instant::now();
+            let (found, match_count) = regex
+                .as_ref()
+                .map(|regex| Self::find_in_chunks(regex, transcript, &chunks))
+                .unwrap_or((None, 0));
+            let passed = found.is_some();
             
             if passed {
                 passed_count += 1;
-                passed_weight += rule_def.weight;
             }
-            total_weight += rule_def.weight;
             
-            let evidence = if passed {
-                self.extract_evidence(transcript, &rule_def.pattern)
-            } else {
-                None
-            };
+            let evidence = found.clone().map(|mat| self.extract_evidence(transcript, mat));
+            // Only attribute evidence to turns with a known speaker
+            let turn_index = found
+                .map(|mat| turn_offsets.partition_point(|&offset| offset <= mat.start).saturating_sub(1))
+                .filter(|&index| parsed.turns.get(index).is_some_and(|turn| turn.role != Role::Unknown));
+            
+            if self.diagnostics {
+                rule_timings.push(RuleTiming {
+                    rule_id: rule_def.id.clone(),
+                    elapsed_ms: rule_started.elapsed().as_secs_f64() * 1000.0,
+                });
+            }
             
             rules.push(RuleCheck {
+                order,
                 rule_id: rule_def.id.clone(),
                 rule_name: rule_def.name.clone(),
                 description: rule_def.description.clone(),
@@ -305,18 +931,33 @@ impl BehaviorScorer {
                 } else {
                     None
                 },
+                turn_index,
+                match_count,
+                definition: Some(rule_def.clone()),
             });
         }
         
-        let score_percentage = if total_weight > 0.0 {
-            (passed_weight / total_weight) * 100.0
-        } else {
-            0.0
-        };
+        let outcomes: Vec<bool> = rules.iter().map(|r| r.passed).collect();
+        let score_percentage = normalization::score_percentage(
+            &self.config.rules,
+            &outcomes,
+            self.config.normalization,
+            &self.categories,
+        );
         
         let summary = self.generate_summary(&rules, score_percentage);
         
-        Ok(SessionScore {
+        let diagnostics = self.diagnostics.then(|| ScoreDiagnostics {
+            rule_timings,
+            total_ms: started.elapsed().as_secs_f64() * 1000.0,
+        });
+        
+        let transcript_stats = TranscriptStats {
+            turn_count: parsed.labelled_turn_count(),
+            ..TranscriptStats::from_text(transcript)
+        };
+        
+        SessionScore {
             session_id: session_id.to_string(),
             timestamp: Utc::now(),
             total_rules: rules.len(),
@@ -324,32 +965,61 @@ impl BehaviorScorer {
             score_percentage,
             rules,
             summary,
-        })
+            scorer_version: SCORER_VERSION.to_string(),
+            rule_set_hash: self.rule_set_hash.clone(),
+            metadata: parsed.metadata.clone(),
+            info: parsed.infer_session_info(),
+            transcript_stats,
+            anomalies: Vec::new(),
+            diagnostics,
+            warnings: Vec::new(),
+            content_hash: content_hash(transcript),
+            duplicate_of: None,
+            source_path: None,
+        }
+    }
+    
+    /// Search each chunk for a rule. Returns the first match, as a range into `transcript`,
+    /// and the number of matches, counting a match only in the chunk that owns its start.
+    fn find_in_chunks(
+        regex: &Regex,
+        transcript: &str,
+        chunks: &[chunking::Chunk],
+    ) -> (Option<Range<usize>>, usize) {
+        let mut first = None;
+        let mut count = 0;
+        
+        for (index, chunk) in chunks.iter().enumerate() {
+            let is_last = index + 1 == chunks.len();
+            for mat in regex.find_iter(&transcript[chunk.window.clone()]) {
+                let start = chunk.window.start + mat.start();
+                if !is_last && start >= chunk.owned.end {
+                    break;
+                }
+                count += 1;
+                first.get_or_insert(start..chunk.window.start + mat.end());
+            }
+        }
+        
+        (first, count)
     }
     
     fn extract_evidence(
         &self,
         transcript: &str,
-        pattern: &str,
-    ) -> Option<String> {
+        mat: Range<usize>,
+    ) -> String {
         // Extract first matching line as evidence
-        if let Ok(regex) = Regex::new(pattern) {
-            if let Some(mat) = regex.find(transcript) {
-                let start = transcript[..mat.start()].rfind('\n').map(|i| i + 1).unwrap_or(0);
-                let end = transcript[mat.end()..].find('\n').map(|i| mat.end() + i).unwrap_or(transcript.len());
-                
-                // SECURITY: Limit evidence length
-                let evidence = &transcript[start..end];
-                Some(if evidence.len() > 200 {
-                    format!("{}...", &evidence[..200])
-                } else {
-                    evidence.to_string()
-                })
-            } else {
-                None
-            }
+        let start = transcript[..mat.start].rfind('\n').map(|i| i + 1).unwrap_or(0);
+        let end = transcript[mat.end..].find('\n').map(|i| mat.end + i).unwrap_or(transcript.len());
+        
+        // SECURITY: Limit evidence length
+        let evidence = &transcript[start..end];
+        if evidence.len() > 200 {
+            let cut = (0..=200).rev().find(|i| evidence.is_char_boundary(*i)).unwrap_or(0);
+            format!("{}...", &evidence[..cut])
         } else {
-            None
+            evidence.to_string()
         }
     }
     
@@ -360,11 +1030,11 @@ impl BehaviorScorer {
     ) -> String {
         let failed_count = rules.iter().filter(|r| !r.passed).count();
         
-        if score >= 90.0 {
+        if score >= self.thresholds.excellent {
             format!("Excellent adherence ({}%). All critical rules followed.", score as i32)
-        } else if score >= 75.0 {
+        } else if score >= self.thresholds.good {
             format!("Good adherence ({}%). {} minor improvements possible.", score as i32, failed_count)
-        } else if score >= 50.0 {
+        } else if score >= self.thresholds.moderate {
             format!("Moderate adherence ({}%). {} rules need attention.", score as i32, failed_count)
         } else {
             format!("Needs improvement ({}%). {} critical rules missed.", score as i32, failed_count)
@@ -376,47 +1046,7 @@ impl BehaviorScorer {
         &self,
         dir_path: &Path,
     ) -> Result<Vec<SessionScore>, String> {
-        // Validate directory path is within base path
-        let canonical_base = self.base_path.canonicalize()
-            .map_err(|e| format!("Invalid base path: {}", e))?;
-        
-        let canonical_dir = dir_path.canonicalize()
-            .map_err(|e| format!("Invalid directory path: {}", e))?;
-        
-        if !canonical_dir.starts_with(&canonical_base) {
-            return Err("Directory path is outside allowed base path".to_string());
-        }
-        
-        let mut scores = Vec::new();
-        
-        for entry in WalkDir::new(dir_path).max_depth(2) {
-            if let Ok(entry) = entry {
-                if entry.file_type().is_file() {
-                    if let Some(ext) = entry.path().extension() {
-                        if ext == "md" || ext == "json" {
-                            // SECURITY: Validate file size before reading
-                            if let Ok(metadata) = fs::metadata(entry.path()) {
-                                const MAX_FILE_SIZE: u64 = 10 * 1024 * 1024; // 10MB
-                                if metadata.len() > MAX_FILE_SIZE {
-                                    eprintln!("Skipping large file: {:?}", entry.path());
-                                    continue;
-                                }
-                            }
-                            
-                            if let Ok(content) = fs::read_to_string(entry.path()) {
-                                let session_id = entry.file_name().to_string_lossy().to_string();
-                                match self.score_session(&session_id, &content) {
-                                    Ok(score) => scores.push(score),
-                                    Err(e) => eprintln!("Failed to score {}: {}", session_id, e),
-                                }
-                            }
-                        }
-                    }
-                }
-            }
-        }
-        
-        Ok(scores)
+        self.scan_with_options(dir_path, &ScanOptions::default())
     }
 }
 
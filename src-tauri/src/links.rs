@@ -0,0 +1,89 @@
+//! `data-dashboard://session/<id>` deep links, so a scorecard shared in chat or notes
+//! opens straight to the stored session.
+
+use crate::db::{Database, DbError, RuleCheckRecord, Score, Session};
+use crate::session_id;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// URL scheme the app registers for deep links
+pub const LINK_SCHEME: &str = "data-dashboard";
+
+/// A linked session with its latest score, if it has been scored
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct SessionLink {
+    pub session: Session,
+    pub score: Option<Score>,
+    pub rule_checks: Vec<RuleCheckRecord>,
+}
+
+/// Link that opens a session's scorecard
+pub fn session_link(id: &str) -> String {
+    let mut link = format!("{}://session/", LINK_SCHEME);
+    for byte in id.bytes() {
+        if byte.is_ascii_alphanumeric() || byte == b'-' || byte == b'_' {
+            link.push(byte as char);
+        } else {
+            link.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    link
+}
+
+/// Session ID named by a `data-dashboard://session/<id>` link. A trailing slash, query,
+/// or fragment is ignored; the ID is percent-decoded and must be a valid session ID.
+pub fn parse_session_link(url: &str) -> Result<String, String> {
+    let rest = url
+        .trim()
+        .strip_prefix(LINK_SCHEME)
+        .and_then(|rest| rest.strip_prefix("://"))
+        .ok_or_else(|| format!("Not a {}:// link: {}", LINK_SCHEME, url))?;
+    let path = rest.split(['?', '#']).next().unwrap_or_default();
+    let encoded = path
+        .strip_prefix("session/")
+        .ok_or_else(|| format!("Not a session link: {}", url))?
+        .trim_end_matches('/');
+    let id = percent_decode(encoded).ok_or_else(|| format!("Malformed session link: {}", url))?;
+    if !session_id::is_valid(&id) {
+        return Err(format!("Invalid session ID in link: {}", url));
+    }
+    Ok(id)
+}
+
+fn percent_decode(encoded: &str) -> Option<String> {
+    let bytes = encoded.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%' {
+            let hex = std::str::from_utf8(bytes.get(i + 1..i + 3)?).ok()?;
+            decoded.push(u8::from_str_radix(hex, 16).ok()?);
+            i += 3;
+        } else {
+            decoded.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8(decoded).ok()
+}
+
+impl Database {
+    /// Look up the session a deep link names, with its latest score and rule checks
+    pub async fn resolve_session_link(&self, url: &str) -> Result<SessionLink, DbError> {
+        let id = parse_session_link(url).map_err(DbError::Validation)?;
+        let session = self.get_session(&id).await.map_err(|e| match e {
+            DbError::NotFound(_) => DbError::NotFound(format!("Linked session {} doesn't exist", id)),
+            e => e,
+        })?;
+        let score = match self.get_latest_score(&id).await {
+            Ok(score) => Some(score),
+            Err(DbError::NotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let rule_checks = match &score {
+            Some(score) => self.get_score_rule_checks(score.id).await?,
+            None => Vec::new(),
+        };
+        Ok(SessionLink { session, score, rule_checks })
+    }
+}
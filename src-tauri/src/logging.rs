@@ -0,0 +1,39 @@
+//! Diagnostics for the CLI and app: `tracing` events on stderr, as text or as JSON lines
+//! for other programs, at a level chosen by `-v`/`--quiet`.
+
+use tracing::level_filters::LevelFilter;
+use tracing::Subscriber;
+use tracing_subscriber::fmt::MakeWriter;
+
+/// Level for `verbose` repetitions of `-v`: warnings by default, then info, debug, and
+/// trace. `quiet` leaves only errors.
+pub fn level(verbose: u8, quiet: bool) -> LevelFilter {
+    if quiet {
+        return LevelFilter::ERROR;
+    }
+    match verbose {
+        0 => LevelFilter::WARN,
+        1 => LevelFilter::INFO,
+        2 => LevelFilter::DEBUG,
+        _ => LevelFilter::TRACE,
+    }
+}
+
+/// A subscriber writing events at `level` or above to `writer`, one JSON object per line
+/// when `json`
+pub fn subscriber<W>(level: LevelFilter, json: bool, color: bool, writer: W) -> Box<dyn Subscriber + Send + Sync>
+where
+    W: for<'w> MakeWriter<'w> + Send + Sync + 'static,
+{
+    let builder = tracing_subscriber::fmt().with_max_level(level).with_writer(writer);
+    if json {
+        Box::new(builder.json().with_current_span(false).finish())
+    } else {
+        Box::new(builder.without_time().with_target(false).with_ansi(color).finish())
+    }
+}
+
+/// Send events to stderr for the rest of the process. Later calls are ignored.
+pub fn init(level: LevelFilter, json: bool, color: bool) {
+    let _ = tracing::subscriber::set_global_default(subscriber(level, json, color, std::io::stderr));
+}
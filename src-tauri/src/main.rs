@@ -1,107 +1,1225 @@
-use data_behavior_dashboard_lib::{BehaviorScorer, RuleCategory, RuleCheck, RuleDefinition, SessionScore, TrackerConfig};
+use data_behavior_dashboard_lib::{BehaviorScorer, QuickScore, RuleCategory, RuleCheck, RuleDefinition, ScanEvent, ScanIssue, ScanOptions, ScanReport, ScanSummary, ScoreSaved, SessionIdStrategy, SessionScore, TrackerConfig, Transcript, TranscriptFormat};
+use data_behavior_dashboard_lib::anomaly::{flag_anomalies_from_history, AnomalyThresholds};
+use data_behavior_dashboard_lib::alerts::{score_alert, AlertSettings};
+use data_behavior_dashboard_lib::confirm::{ConfirmationTokens, DeleteConfirmation, DeleteTarget};
+use data_behavior_dashboard_lib::compare::{RuleDiff, RuleSide};
+use data_behavior_dashboard_lib::db::{
+    CategoryStats, Database, DbError, DbHealth, DbStats, ListFilters, MetadataValueCount, Outcomes, OverrideVerdict, PassRatePoint, Ranking, RuleCheckOverride, RuleCheckRecord, RuleDetail, RuleFailure, RuleFailureStats, ScoreBand, Score, ScoreDistribution,
+    SearchFilters, SearchHit, SearchKind, SearchSnippet, Session, SessionComparison, SessionSearchResult, SourceStats, StoredRule, TimeBucket,
+};
+use data_behavior_dashboard_lib::error::{CommandError, ErrorCode};
+use data_behavior_dashboard_lib::export::{HistoryImportReport, ReportFormat};
+use data_behavior_dashboard_lib::files::{FileScoreProgress, FileScoreReport, FILES_SOURCE};
+use data_behavior_dashboard_lib::import::{ImportMapping, ImportReport};
+use data_behavior_dashboard_lib::links::SessionLink;
+use data_behavior_dashboard_lib::logging;
+use data_behavior_dashboard_lib::preview::{self, MatchSpan, RulePreview};
+use data_behavior_dashboard_lib::profiles::{AggregateStats, ProfileRegistry, DEFAULT_DB_FILE, REGISTRY_FILE};
+use data_behavior_dashboard_lib::rescore::{RescoreProgress, RescoreReport};
+use data_behavior_dashboard_lib::schedule::{ScheduledScan, SCHEDULED_SOURCE};
+use data_behavior_dashboard_lib::session_id;
+use data_behavior_dashboard_lib::settings::{ScoreThresholds, Settings, SETTINGS_FILE};
+use data_behavior_dashboard_lib::watch::{persist_score, SessionWatcher, DEFAULT_DEBOUNCE_MS, WATCH_SOURCE};
+use std::path::PathBuf;
 use std::sync::Mutex;
-use tauri::State;
+use std::time::Duration;
+use tauri::menu::{Menu, MenuItem};
+use tauri::tray::TrayIconBuilder;
+use tauri::{AppHandle, Emitter, Manager, State};
+use tauri_plugin_clipboard_manager::ClipboardExt;
+use tauri_plugin_deep_link::DeepLinkExt;
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_notification::NotificationExt;
 use tauri_specta::{collect_commands, Builder};
 
+/// Event emitted to every window with a ScoreSaved whenever the app stores a score
+const SCORE_SAVED_EVENT: &str = "score-saved";
+
+/// Event emitted to every window with the scorer's TrackerConfig whenever its rules change
+const RULES_CHANGED_EVENT: &str = "rules-changed";
+
+/// Event emitted with a SessionScore whenever watch mode scores a file
+const SESSION_SCORED_EVENT: &str = "session-scored";
+
+/// Event emitted with a ScanEvent while `scan_sessions_directory` runs
+const SCAN_PROGRESS_EVENT: &str = "scan-progress";
+
+/// Event emitted with a FileScoreProgress as each file given to `score_files` finishes
+const FILE_SCORED_EVENT: &str = "file-scored";
+
+/// Event emitted with a ScheduledScan when a background scan stores new scores
+const NEW_SCORES_EVENT: &str = "new-scores-available";
+
+/// Event emitted with a RescoreProgress after each session `rescore_all` handles
+const RESCORE_PROGRESS_EVENT: &str = "rescore-progress";
+
+/// Event emitted with the RescoreReport when `rescore_all` finishes
+const RESCORE_FINISHED_EVENT: &str = "rescore-finished";
+
+/// Event emitted with the URL of each `data-dashboard://` link the app is opened with
+const OPEN_LINK_EVENT: &str = "open-link";
+
+/// Session source recorded for scores computed through the app's commands
+const APP_SOURCE: &str = "app";
+
+/// ID of the tray menu item that quick-scores the clipboard
+const SCORE_CLIPBOARD_MENU_ID: &str = "score-clipboard";
+
+/// ID of the tray menu item that quits the app
+const QUIT_MENU_ID: &str = "quit";
+
 // App state with thread-safe scorer
 struct AppState {
     scorer: Mutex<BehaviorScorer>,
+    watcher: Mutex<Option<SessionWatcher>>,
+    /// Database of the active profile; replaced when switching profiles
+    db: Mutex<Database>,
+    settings: Mutex<Settings>,
+    /// Background scan loop, while scheduled scans are enabled
+    scheduler: Mutex<Option<tauri::async_runtime::JoinHandle<()>>>,
+    /// Tokens issued for permanent deletes that haven't been used yet
+    confirmations: Mutex<ConfirmationTokens>,
+}
+
+impl AppState {
+    /// Handle to the active profile's database
+    fn db(&self) -> Result<Database, CommandError> {
+        Ok(self.db.lock()?.clone())
+    }
+
+    fn settings(&self) -> Result<Settings, CommandError> {
+        Ok(self.settings.lock()?.clone())
+    }
+
+    /// Options a scan or watch was given, or the configured defaults
+    fn scan_options(&self, options: Option<ScanOptions>) -> Result<ScanOptions, CommandError> {
+        match options {
+            Some(options) => Ok(options),
+            None => Ok(self.settings.lock()?.scan_options.clone()),
+        }
+    }
+
+    /// Store a score computed by a command, returning it to the frontend
+    async fn persist(&self, app: &AppHandle, score: SessionScore, transcript_path: Option<&str>) -> Result<SessionScore, CommandError> {
+        self.persist_from(app, score, APP_SOURCE, transcript_path).await
+    }
+
+    /// Store a score computed by a command under the session source it came from,
+    /// flagging failures the history says are unusual
+    async fn persist_from(
+        &self,
+        app: &AppHandle,
+        mut score: SessionScore,
+        source: &str,
+        transcript_path: Option<&str>,
+    ) -> Result<SessionScore, CommandError> {
+        let db = self.db()?;
+        flag_anomalies_from_history(&db, &mut score, &AnomalyThresholds::default()).await?;
+        db.save_session_score(&score, source, transcript_path)
+            .await
+            .map_err(|e| CommandError::from(e).with_context(score.session_id.clone()))?;
+        score_saved(app, &score, source);
+        Ok(score)
+    }
+}
+
+/// Tell every window about a newly stored score, and alert on it if it needs attention
+fn score_saved(app: &AppHandle, score: &SessionScore, source: &str) {
+    alert_on_score(app, score);
+    let saved = ScoreSaved {
+        source: source.to_string(),
+        score: score.clone(),
+    };
+    if let Err(e) = app.emit(SCORE_SAVED_EVENT, &saved) {
+        tracing::warn!(session = %score.session_id, error = %e, "Failed to emit saved score");
+    }
+}
+
+/// Show a desktop notification if a newly stored score is low or failed a critical rule
+fn alert_on_score(app: &AppHandle, score: &SessionScore) {
+    let state = app.state::<AppState>();
+    let alert = match (state.settings.lock(), state.scorer.lock()) {
+        (Ok(settings), Ok(scorer)) => score_alert(score, &scorer.config().rules, &settings.alerts),
+        _ => None,
+    };
+    let Some(alert) = alert else {
+        return;
+    };
+    if let Err(e) = app.notification().builder().title(&alert.title).body(&alert.body).show() {
+        tracing::warn!(session = %alert.session_id, error = %e, "Failed to show notification");
+    }
+}
+
+/// Reject a malformed session ID before any scoring work
+fn check_session_id(session_id: &str) -> Result<(), CommandError> {
+    if session_id::is_valid(session_id) {
+        Ok(())
+    } else {
+        Err(CommandError::invalid_input("Invalid session ID").with_context(session_id))
+    }
 }
 
 // GOLD: Type-safe commands with specta
 #[tauri::command]
 #[specta::specta] // Enables type generation for this command
-fn score_session(
-    state: State<AppState>,
+async fn score_session(
+    app: AppHandle,
+    state: State<'_, AppState>,
     session_id: String,
     transcript: String,
-) -> Result<SessionScore, String> {
-    let scorer = state.scorer.lock().map_err(|e| e.to_string())?;
-    scorer.score_session(&session_id, &transcript)
+    source: Option<String>,
+) -> Result<SessionScore, CommandError> {
+    check_session_id(&session_id)?;
+    let scorer = scorer_for_source(&app, &state, source.as_deref()).await?;
+    let score = scorer.score_session(&session_id, &transcript)?;
+    state.persist_from(&app, score, source.as_deref().unwrap_or(APP_SOURCE), None).await
 }
 
 #[tauri::command]
 #[specta::specta]
-fn get_rules(state: State<AppState>) -> Result<Vec<RuleDefinition>, String> {
-    let _scorer = state.scorer.lock().map_err(|e| e.to_string())?;
-    // Return rules from default config
-    let config = TrackerConfig {
-        rules: vec![
-            RuleDefinition {
-                id: "local_memory_first".to_string(),
-                name: "Query local-memory FIRST".to_string(),
-                description: "Should query local-memory before file reads".to_string(),
-                pattern: r"local-memory search|Query local-memory".to_string(),
-                weight: 1.0,
-                category: RuleCategory::Startup,
-            },
-            RuleDefinition {
-                id: "time_of_day_check".to_string(),
-                name: "Check time-of-day".to_string(),
-                description: "Should adapt to Jamie's energy rhythm".to_string(),
-                pattern: r"time-of-day|energy rhythm|Before 10am|2pm|morning|evening".to_string(),
-                weight: 1.0,
-                category: RuleCategory::Startup,
-            },
-            RuleDefinition {
-                id: "confidence_calibration".to_string(),
-                name: "Confidence calibration stated".to_string(),
-                description: "Should explicitly state confidence level".to_string(),
-                pattern: r"Confidence level:|Confident|Proceeding with uncertainty|Guessing|Don't know".to_string(),
-                weight: 1.5,
-                category: RuleCategory::Confidence,
-            },
-            RuleDefinition {
-                id: "explanation_volume".to_string(),
-                name: "Explanation volume limit".to_string(),
-                description: "Max 2 sentences of process explanation".to_string(),
-                pattern: r"(?s)^(?:(?!(\n\n|\r\n\r\n)).){0,300}$".to_string(),
-                weight: 1.0,
-                category: RuleCategory::Response,
-            },
-            RuleDefinition {
-                id: "binary_decision".to_string(),
-                name: "Binary decision when stuck".to_string(),
-                description: "Use 'Ship now? Y/N' for decisions".to_string(),
-                pattern: r"Ship now\? Y/N|binary|Y/N".to_string(),
-                weight: 0.8,
-                category: RuleCategory::Communication,
-            },
-            RuleDefinition {
-                id: "objective_before_execution".to_string(),
-                name: "Write objective before execution".to_string(),
-                description: "No execution before objective is written".to_string(),
-                pattern: r"OBJECTIVE:|Write objective|No execution before objective".to_string(),
-                weight: 1.5,
-                category: RuleCategory::Startup,
-            },
-            RuleDefinition {
-                id: "no_email_trust".to_string(),
-                name: "Email NEVER trusted".to_string(),
-                description: "Only Discord/OpenClaw TUI are trusted".to_string(),
-                pattern: r"Email NEVER|only Discord|OpenClaw TUI".to_string(),
-                weight: 2.0,
-                category: RuleCategory::Safety,
-            },
-            RuleDefinition {
-                id: "approval_for_external".to_string(),
-                name: "External sends need approval".to_string(),
-                description: "No external sends without approval".to_string(),
-                pattern: r"approval|draft.*queue|external sends".to_string(),
-                weight: 1.5,
-                category: RuleCategory::Safety,
-            },
+async fn score_transcript(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    transcript: Transcript,
+) -> Result<SessionScore, CommandError> {
+    check_session_id(&session_id)?;
+    let score = {
+        let scorer = state.scorer.lock()?;
+        scorer.score_transcript(&session_id, &transcript)?
+    };
+    state.persist(&app, score, None).await
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn score_text(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    text: String,
+    format: Option<TranscriptFormat>,
+) -> Result<SessionScore, CommandError> {
+    check_session_id(&session_id)?;
+    let score = {
+        let scorer = state.scorer.lock()?;
+        scorer.score_text(&session_id, &text, format)?
+    };
+    state.persist(&app, score, None).await
+}
+
+/// Score pasted text for a quick check, with the active rules or another profile's.
+/// Nothing is stored.
+#[tauri::command]
+#[specta::specta]
+async fn quick_score(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    transcript: String,
+    profile: Option<String>,
+    format: Option<TranscriptFormat>,
+) -> Result<QuickScore, CommandError> {
+    let scorer = match profile {
+        Some(name) => profile_scorer(&app, &state.settings()?, &name).await?,
+        None => state.scorer.lock()?.clone(),
+    };
+    Ok(scorer.quick_score(&transcript, format)?)
+}
+
+/// Quick-score the clipboard text with the active rules and show the result as a
+/// notification. Nothing is stored.
+#[tauri::command]
+#[specta::specta]
+async fn score_clipboard(app: AppHandle) -> Result<QuickScore, CommandError> {
+    score_clipboard_and_notify(&app)
+}
+
+fn score_clipboard_and_notify(app: &AppHandle) -> Result<QuickScore, CommandError> {
+    let text = app
+        .clipboard()
+        .read_text()
+        .map_err(|e| CommandError::invalid_input(format!("Clipboard has no text: {}", e)))?;
+    if text.trim().is_empty() {
+        return Err(CommandError::invalid_input("Clipboard is empty"));
+    }
+    let result = app.state::<AppState>().scorer.lock()?.quick_score(&text, None)?;
+    let title = format!("Clipboard: {:.0}% adherence", result.score.score_percentage);
+    if let Err(e) = app.notification().builder().title(title).body(&result.score.summary).show() {
+        tracing::warn!(error = %e, "Failed to show clipboard score");
+    }
+    Ok(result)
+}
+
+/// Tray icon with a menu for scoring the clipboard without opening the dashboard
+fn build_tray(app: &AppHandle) -> tauri::Result<()> {
+    let menu = Menu::with_items(
+        app,
+        &[
+            &MenuItem::with_id(app, SCORE_CLIPBOARD_MENU_ID, "Score clipboard", true, None::<&str>)?,
+            &MenuItem::with_id(app, QUIT_MENU_ID, "Quit", true, None::<&str>)?,
         ],
+    )?;
+    let mut tray = TrayIconBuilder::new()
+        .tooltip("Data Behavior Dashboard")
+        .menu(&menu)
+        .on_menu_event(|app, event| match event.id().as_ref() {
+            SCORE_CLIPBOARD_MENU_ID => {
+                if let Err(e) = score_clipboard_and_notify(app) {
+                    let shown = app.notification().builder().title("Clipboard not scored").body(&e.message).show();
+                    if shown.is_err() {
+                        tracing::warn!(error = %e, "Failed to score clipboard");
+                    }
+                }
+            }
+            QUIT_MENU_ID => app.exit(0),
+            _ => {}
+        });
+    if let Some(icon) = app.default_window_icon() {
+        tray = tray.icon(icon.clone());
+    }
+    tray.build(app)?;
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn score_url(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_id: String,
+    url: String,
+) -> Result<SessionScore, CommandError> {
+    check_session_id(&session_id)?;
+    // Download off the main thread with a snapshot of the scorer
+    let scorer = state.scorer.lock()?.clone();
+    let source_url = url.clone();
+    let score = tauri::async_runtime::spawn_blocking(move || scorer.score_url(&session_id, &url))
+        .await
+        .map_err(|e| e.to_string())??;
+    state.persist(&app, score, Some(&source_url)).await
+}
+
+/// Score dropped files in parallel and store the results, emitting a FileScoreProgress
+/// as each file finishes. Files outside the allowed base path are reported, not scored.
+#[tauri::command]
+#[specta::specta]
+async fn score_files(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    paths: Vec<String>,
+    session_ids: Option<SessionIdStrategy>,
+    source: Option<String>,
+) -> Result<FileScoreReport, CommandError> {
+    let db = state.db()?;
+    let scorer = scorer_for_source(&app, &state, source.as_deref()).await?;
+    let source = source.as_deref().unwrap_or(FILES_SOURCE);
+    Ok(scorer
+        .score_files(&db, &paths, session_ids.unwrap_or_default(), source, |progress| {
+            if let Some(score) = &progress.score {
+                score_saved(&app, score, source);
+            }
+            if let Err(e) = app.emit(FILE_SCORED_EVENT, progress) {
+                tracing::warn!(error = %e, "Failed to emit file score");
+            }
+        })
+        .await)
+}
+
+/// A scorer with another profile's rules, read without switching to it
+async fn profile_scorer(app: &AppHandle, settings: &Settings, name: &str) -> Result<BehaviorScorer, CommandError> {
+    let (_, registry) = load_profiles(app)?;
+    let path = registry
+        .profiles
+        .get(name)
+        .ok_or_else(|| CommandError::not_found(format!("Unknown profile: {}", name)))?;
+    load_scorer(&open_read_only(app, path).await?, settings).await
+}
+
+/// The scorer for sessions from `source`: the profile configured for it in the
+/// settings, or the active scorer
+async fn scorer_for_source(app: &AppHandle, state: &AppState, source: Option<&str>) -> Result<BehaviorScorer, CommandError> {
+    let settings = state.settings()?;
+    match source.and_then(|source| settings.profile_for_source(source)) {
+        Some(name) => profile_scorer(app, &settings, name)
+            .await
+            .map_err(|e| e.with_context(format!("rule profile for {}", source.unwrap_or_default()))),
+        None => Ok(state.scorer.lock()?.clone()),
+    }
+}
+
+/// Build a scorer from the enabled rules in the database, configured by the app settings
+async fn load_scorer(db: &Database, settings: &Settings) -> Result<BehaviorScorer, CommandError> {
+    let config = TrackerConfig {
+        rules: db.enabled_rules().await?,
+        ..BehaviorScorer::new().config().clone()
+    };
+    Ok(settings.apply(BehaviorScorer::with_config(config)))
+}
+
+/// Bring the scorer in line with the current rules table, recompiling only changed
+/// patterns, and tell every window
+async fn reload_rules(app: &AppHandle) -> Result<(), CommandError> {
+    let state = app.state::<AppState>();
+    let rules = state.db()?.enabled_rules().await?;
+    let config = {
+        let mut scorer = state.scorer.lock()?;
+        scorer.set_rules(rules);
+        scorer.config().clone()
     };
-    Ok(config.rules)
+    rules_changed(app, &config);
+    Ok(())
+}
+
+fn rules_changed(app: &AppHandle, config: &TrackerConfig) {
+    if let Err(e) = app.emit(RULES_CHANGED_EVENT, config) {
+        tracing::warn!(error = %e, "Failed to emit rule change");
+    }
+}
+
+/// Whether a rule with this ID is stored
+async fn rule_exists(db: &Database, id: &str) -> Result<bool, CommandError> {
+    match db.get_rule(id).await {
+        Ok(_) => Ok(true),
+        Err(DbError::NotFound(_)) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Validate, store, and apply a rule definition
+async fn store_rule(app: &AppHandle, rule: &RuleDefinition) -> Result<StoredRule, CommandError> {
+    let issues = rule.validate();
+    if !issues.is_empty() {
+        return Err(issues.into());
+    }
+    let stored = app.state::<AppState>().db()?.save_rule(rule).await?;
+    reload_rules(app).await?;
+    Ok(stored)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_rules(state: State<'_, AppState>) -> Result<Vec<StoredRule>, CommandError> {
+    Ok(state.db()?.list_rules().await?)
+}
+
+/// The configuration scores are actually computed with: the enabled rules as last
+/// loaded from the database, in evaluation order
+#[tauri::command]
+#[specta::specta]
+fn get_scorer_config(state: State<AppState>) -> Result<TrackerConfig, CommandError> {
+    Ok(state.scorer.lock()?.config().clone())
+}
+
+/// Create or update a rule
+#[tauri::command]
+#[specta::specta]
+async fn save_rule(app: AppHandle, rule: RuleDefinition) -> Result<StoredRule, CommandError> {
+    store_rule(&app, &rule).await
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn add_rule(app: AppHandle, state: State<'_, AppState>, rule: RuleDefinition) -> Result<StoredRule, CommandError> {
+    if rule_exists(&state.db()?, &rule.id).await? {
+        return Err(CommandError::invalid_input(format!("Rule {} already exists", rule.id)));
+    }
+    store_rule(&app, &rule).await
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn update_rule(app: AppHandle, state: State<'_, AppState>, rule: RuleDefinition) -> Result<StoredRule, CommandError> {
+    if !rule_exists(&state.db()?, &rule.id).await? {
+        return Err(CommandError::not_found(format!("Unknown rule: {}", rule.id)));
+    }
+    store_rule(&app, &rule).await
+}
+
+/// Match a pattern being edited against sample text, for live feedback in the rule editor
+#[tauri::command]
+#[specta::specta]
+async fn preview_rule(pattern: String, flags: Option<String>, sample_text: String) -> RulePreview {
+    preview::preview_rule(&pattern, flags.as_deref().unwrap_or_default(), &sample_text)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn set_rule_enabled(app: AppHandle, state: State<'_, AppState>, id: String, enabled: bool) -> Result<StoredRule, CommandError> {
+    let stored = state.db()?.set_rule_enabled(&id, enabled).await?;
+    reload_rules(&app).await?;
+    Ok(stored)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn delete_rule(app: AppHandle, state: State<'_, AppState>, id: String) -> Result<bool, CommandError> {
+    let deleted = state.db()?.delete_rule(&id).await?;
+    reload_rules(&app).await?;
+    Ok(deleted)
+}
+
+/// Scan a directory off the main thread, emitting a ScanEvent as files are found and
+/// each session is scored
+#[tauri::command]
+#[specta::specta]
+async fn scan_sessions_directory(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    options: Option<ScanOptions>,
+) -> Result<ScanSummary, CommandError> {
+    let options = state.scan_options(options)?;
+    let scorer = scorer_for_source(&app, &state, options.source.as_deref()).await?;
+    scorer.validate_directory(std::path::Path::new(&path))?;
+    tauri::async_runtime::spawn_blocking(move || {
+        scorer.scan_with_progress(std::path::Path::new(&path), &options, |event| {
+            if let Err(e) = app.emit(SCAN_PROGRESS_EVENT, event) {
+                tracing::warn!(error = %e, "Failed to emit scan progress");
+            }
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+    .map_err(CommandError::from)
+}
+
+/// Extract and scan an archive off the main thread, emitting a ScanEvent as sessions
+/// are found and scored, and store each score
+#[tauri::command]
+#[specta::specta]
+async fn scan_archive(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    options: Option<ScanOptions>,
+) -> Result<ScanSummary, CommandError> {
+    let options = state.scan_options(options)?;
+    let source = options.source.clone().unwrap_or_else(|| APP_SOURCE.to_string());
+    let scorer = scorer_for_source(&app, &state, options.source.as_deref()).await?;
+    let archive = std::path::Path::new(&path);
+    scorer.validate_directory(archive.parent().unwrap_or(std::path::Path::new(".")))?;
+    let emitter = app.clone();
+    let mut summary = tauri::async_runtime::spawn_blocking(move || {
+        scorer.scan_archive_with_progress(std::path::Path::new(&path), &options, |event| {
+            if let Err(e) = emitter.emit(SCAN_PROGRESS_EVENT, event) {
+                tracing::warn!(error = %e, "Failed to emit scan progress");
+            }
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())??;
+    let mut stored = Vec::with_capacity(summary.scores.len());
+    for score in std::mem::take(&mut summary.scores) {
+        let transcript_path = score.source_path.clone();
+        stored.push(state.persist_from(&app, score, &source, transcript_path.as_deref()).await?);
+    }
+    summary.scores = stored;
+    Ok(summary)
+}
+
+/// Registry file path and the profile registry, created around the app's
+/// original database on first run
+fn load_profiles(app: &AppHandle) -> Result<(PathBuf, ProfileRegistry), CommandError> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let registry_path = config_dir.join(REGISTRY_FILE);
+    let registry = ProfileRegistry::load_or_default(&registry_path, config_dir.join(DEFAULT_DB_FILE))?;
+    Ok((registry_path, registry))
 }
 
+/// Settings file path and the app settings, defaults until first saved
+fn load_settings(app: &AppHandle) -> Result<(PathBuf, Settings), CommandError> {
+    let config_dir = app.path().app_config_dir().map_err(|e| e.to_string())?;
+    let settings_path = config_dir.join(SETTINGS_FILE);
+    let settings = Settings::load_or_default(&settings_path)?;
+    Ok((settings_path, settings))
+}
+
+#[tauri::command]
+#[specta::specta]
+fn get_settings(state: State<AppState>) -> Result<Settings, CommandError> {
+    state.settings()
+}
+
+/// Validate and save new settings, applying them to the scorer. A running watcher
+/// keeps its old settings until restarted.
 #[tauri::command]
 #[specta::specta]
-fn scan_sessions_directory(state: State<AppState>, path: String) -> Result<Vec<SessionScore>, String> {
-    let scorer = state.scorer.lock().map_err(|e| e.to_string())?;
+fn update_settings(app: AppHandle, state: State<AppState>, settings: Settings) -> Result<Settings, CommandError> {
+    settings.validate().map_err(CommandError::invalid_input)?;
+    let (_, registry) = load_profiles(&app)?;
+    if let Some(profile) = settings.source_profiles.values().find(|profile| !registry.profiles.contains_key(*profile)) {
+        return Err(CommandError::invalid_input(format!("Unknown profile: {}", profile)));
+    }
+    let (settings_path, _) = load_settings(&app)?;
+    settings.save(&settings_path)?;
+
+    {
+        let mut scorer = state.scorer.lock()?;
+        *scorer = settings.apply(scorer.clone());
+    }
+    *state.settings.lock()? = settings.clone();
+    restart_scheduler(&app, &state, &settings)?;
+    Ok(settings)
+}
+
+/// Stop any background scan loop and start one at the configured interval
+fn restart_scheduler(app: &AppHandle, state: &AppState, settings: &Settings) -> Result<(), CommandError> {
+    let mut scheduler = state.scheduler.lock()?;
+    if let Some(task) = scheduler.take() {
+        task.abort();
+    }
+    *scheduler = settings.scan_interval().map(|interval| start_scheduler(app.clone(), interval));
+    Ok(())
+}
+
+/// Incrementally scan the configured directories every `interval` with the active
+/// profile's database and scorer, emitting NEW_SCORES_EVENT when sessions were scored
+fn start_scheduler(app: AppHandle, interval: Duration) -> tauri::async_runtime::JoinHandle<()> {
+    tauri::async_runtime::spawn(async move {
+        loop {
+            tokio::time::sleep(interval).await;
+            let state = app.state::<AppState>();
+            let inputs = async {
+                let settings = state.settings()?;
+                let scorer = scorer_for_source(&app, &state, settings.scan_options.source.as_deref()).await?;
+                Ok::<_, CommandError>((state.db()?, scorer, settings))
+            }
+            .await;
+            let (db, scorer, settings) = match inputs {
+                Ok(inputs) => inputs,
+                Err(e) => {
+                    tracing::warn!(error = %e, "Skipping scheduled scan");
+                    continue;
+                }
+            };
+            let report = scorer
+                .scheduled_scan(&db, &settings.scan_directories, &settings.scan_options)
+                .await;
+            for failure in &report.failed {
+                tracing::warn!(failure = %failure, "Scheduled scan failed");
+            }
+            for score in &report.new_scores {
+                score_saved(&app, score, settings.scan_options.source_or(SCHEDULED_SOURCE));
+            }
+            if !report.new_scores.is_empty() {
+                if let Err(e) = app.emit(NEW_SCORES_EVENT, &report) {
+                    tracing::warn!(error = %e, "Failed to emit new scores");
+                }
+            }
+        }
+    })
+}
+
+/// Open a profile's SQLite database and make sure the built-in rules exist
+#[cfg(not(feature = "sqlcipher"))]
+async fn open_database(_app: &AppHandle, db_path: &str) -> Result<Database, CommandError> {
+    let db = Database::new(db_path).await?;
+    db.seed_rules(&BehaviorScorer::new().config().rules).await?;
+    Ok(db)
+}
+
+/// Open a profile's SQLite database, encrypted with a key kept in the OS keychain
+/// under the database's file name, and make sure the built-in rules exist
+#[cfg(feature = "sqlcipher")]
+async fn open_database(app: &AppHandle, db_path: &str) -> Result<Database, CommandError> {
+    use data_behavior_dashboard_lib::db::DbConfig;
+
+    let db = Database::open_encrypted(db_path, &DbConfig::default(), &database_key(app, db_path)).await?;
+    db.seed_rules(&BehaviorScorer::new().config().rules).await?;
+    Ok(db)
+}
+
+/// Keychain entry holding the key of the database at `db_path`
+#[cfg(feature = "sqlcipher")]
+fn database_key(app: &AppHandle, db_path: &str) -> data_behavior_dashboard_lib::keys::KeychainKey {
+    let account = std::path::Path::new(db_path)
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| DEFAULT_DB_FILE.to_string());
+    data_behavior_dashboard_lib::keys::KeychainKey::new(app.config().identifier.clone(), account)
+}
+
+/// Open another profile's database for queries only
+#[cfg(not(feature = "sqlcipher"))]
+async fn open_read_only(_app: &AppHandle, db_path: &str) -> Result<Database, CommandError> {
+    Ok(Database::open_read_only(db_path).await?)
+}
+
+/// Open another profile's encrypted database for queries only
+#[cfg(feature = "sqlcipher")]
+async fn open_read_only(app: &AppHandle, db_path: &str) -> Result<Database, CommandError> {
+    Ok(Database::open_read_only_encrypted(db_path, &database_key(app, db_path)).await?)
+}
+
+/// Stats from every profile's database
+#[cfg(not(feature = "sqlcipher"))]
+async fn aggregate_stats(_app: &AppHandle, registry: &ProfileRegistry) -> AggregateStats {
+    registry.aggregate_stats().await
+}
+
+/// Stats from every profile's database, each opened with its keychain key
+#[cfg(feature = "sqlcipher")]
+async fn aggregate_stats(app: &AppHandle, registry: &ProfileRegistry) -> AggregateStats {
+    use data_behavior_dashboard_lib::keys::KeyProvider;
+
+    registry
+        .aggregate_stats_with(|path| Some(Box::new(database_key(app, path)) as Box<dyn KeyProvider>))
+        .await
+}
+
+#[tauri::command]
+#[specta::specta]
+fn list_profiles(app: AppHandle) -> Result<ProfileRegistry, CommandError> {
+    Ok(load_profiles(&app)?.1)
+}
+
+#[tauri::command]
+#[specta::specta]
+fn add_profile(app: AppHandle, name: String, path: String) -> Result<ProfileRegistry, CommandError> {
+    let (registry_path, mut registry) = load_profiles(&app)?;
+    registry.add(&name, &path).map_err(CommandError::invalid_input)?;
+    registry.save(&registry_path)?;
+    Ok(registry)
+}
+
+#[tauri::command]
+#[specta::specta]
+fn remove_profile(app: AppHandle, name: String) -> Result<ProfileRegistry, CommandError> {
+    let (registry_path, mut registry) = load_profiles(&app)?;
+    registry.remove(&name).map_err(CommandError::invalid_input)?;
+    registry.save(&registry_path)?;
+    Ok(registry)
+}
+
+/// Make another profile active: open its database, rebuild the scorer from its
+/// rules, and stop any watcher writing to the old database
+#[tauri::command]
+#[specta::specta]
+async fn switch_profile(app: AppHandle, state: State<'_, AppState>, name: String) -> Result<ProfileRegistry, CommandError> {
+    let (registry_path, mut registry) = load_profiles(&app)?;
+    registry.set_active(&name).map_err(CommandError::not_found)?;
+    let db = open_database(&app, registry.active_path()).await?;
+    let scorer = load_scorer(&db, &state.settings()?).await?;
+    registry.save(&registry_path)?;
+
+    state.watcher.lock()?.take();
+    *state.db.lock()? = db;
+    let config = scorer.config().clone();
+    *state.scorer.lock()? = scorer;
+    rules_changed(&app, &config);
+    Ok(registry)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_profile_stats(app: AppHandle) -> Result<AggregateStats, CommandError> {
+    let (_, registry) = load_profiles(&app)?;
+    Ok(aggregate_stats(&app, &registry).await)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn rescan_sessions_directory(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    path: String,
+    options: Option<ScanOptions>,
+) -> Result<Vec<SessionScore>, CommandError> {
+    let options = state.scan_options(options)?;
+    let scorer = scorer_for_source(&app, &state, options.source.as_deref()).await?;
+    scorer.validate_directory(std::path::Path::new(&path))?;
+    Ok(scorer
+        .scan_incremental(&state.db()?, std::path::Path::new(&path), &options)
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn import_sqlite_sessions(
+    state: State<'_, AppState>,
+    path: String,
+    mapping: ImportMapping,
+) -> Result<ImportReport, CommandError> {
+    let scorer = state.scorer.lock()?.clone();
     let path = std::path::Path::new(&path);
-    scorer.scan_and_score_directory(path)
+    scorer.validate_directory(path.parent().unwrap_or(std::path::Path::new(".")))?;
+    Ok(scorer.import_sqlite(&state.db()?, path, &mapping).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn find_sessions_by_metadata(
+    state: State<'_, AppState>,
+    key: String,
+    value: String,
+    limit: Option<i64>,
+) -> Result<Vec<Session>, CommandError> {
+    Ok(state
+        .db()?
+        .find_sessions_by_metadata(&key, &value, limit)
+        .await?)
+}
+
+/// The session and latest score a `data-dashboard://session/<id>` link points at
+#[tauri::command]
+#[specta::specta]
+async fn resolve_session_link(state: State<'_, AppState>, url: String) -> Result<SessionLink, CommandError> {
+    state.db()?.resolve_session_link(&url).await.map_err(|e| CommandError::from(e).with_context(url))
+}
+
+/// Bring the dashboard forward and hand it each link to open
+fn open_links(app: &AppHandle, urls: impl IntoIterator<Item = String>) {
+    if let Some(window) = app.get_webview_window("main") {
+        let _ = window.unminimize();
+        let _ = window.set_focus();
+    }
+    for url in urls {
+        if let Err(e) = app.emit(OPEN_LINK_EVENT, &url) {
+            tracing::warn!(url = %url, error = %e, "Failed to emit link");
+        }
+    }
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn list_metadata_values(state: State<'_, AppState>, key: String) -> Result<Vec<MetadataValueCount>, CommandError> {
+    Ok(state.db()?.list_metadata_values(&key).await?)
+}
+
+/// Move a session to the trash; it can be restored until the trash is purged
+#[tauri::command]
+#[specta::specta]
+async fn archive_session(state: State<'_, AppState>, id: String) -> Result<bool, CommandError> {
+    Ok(state.db()?.delete_session(&id).await?)
+}
+
+/// Issue a token for permanently deleting a session or score, describing what will go
+#[tauri::command]
+#[specta::specta]
+async fn confirm_delete(state: State<'_, AppState>, target: DeleteTarget) -> Result<DeleteConfirmation, CommandError> {
+    let description = target.describe(&state.db()?).await?;
+    state.confirmations.lock()?.issue(target, description)
+}
+
+/// Permanently delete a session and its scores, trashed or not, given a token from `confirm_delete`
+#[tauri::command]
+#[specta::specta]
+async fn delete_session(state: State<'_, AppState>, id: String, token: String) -> Result<bool, CommandError> {
+    state.confirmations.lock()?.redeem(&token, &DeleteTarget::Session { id: id.clone() })?;
+    Ok(state.db()?.erase_session(&id).await?)
+}
+
+/// Permanently delete one score and its rule checks, given a token from `confirm_delete`
+#[tauri::command]
+#[specta::specta]
+async fn delete_score(state: State<'_, AppState>, score_id: i64, token: String) -> Result<bool, CommandError> {
+    state.confirmations.lock()?.redeem(&token, &DeleteTarget::Score { id: score_id })?;
+    Ok(state.db()?.delete_score(score_id).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn restore_session(state: State<'_, AppState>, id: String) -> Result<bool, CommandError> {
+    Ok(state.db()?.restore_session(&id).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn list_trash(state: State<'_, AppState>, limit: Option<i64>) -> Result<Vec<Session>, CommandError> {
+    Ok(state.db()?.list_trash(limit).await?)
+}
+
+/// Permanently delete a trashed session. Purges only touch the trash, which the user
+/// already had to archive into, so they don't need a token from `confirm_delete`
+#[tauri::command]
+#[specta::specta]
+async fn purge_session(state: State<'_, AppState>, id: String) -> Result<bool, CommandError> {
+    Ok(state.db()?.purge_session(&id).await?)
+}
+
+/// Permanently delete trashed sessions, optionally only those trashed before `older_than`
+#[tauri::command]
+#[specta::specta]
+async fn purge_trash(state: State<'_, AppState>, older_than: Option<chrono::DateTime<chrono::Utc>>) -> Result<u64, CommandError> {
+    Ok(state.db()?.purge_trash(older_than).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn list_sessions(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+    filters: Option<ListFilters>,
+) -> Result<Vec<Session>, CommandError> {
+    Ok(state
+        .db()?
+        .list_sessions(limit, &filters.unwrap_or_default())
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn list_scores(
+    state: State<'_, AppState>,
+    limit: Option<i64>,
+    filters: Option<ListFilters>,
+) -> Result<Vec<Score>, CommandError> {
+    Ok(state
+        .db()?
+        .list_scores(limit, &filters.unwrap_or_default())
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_session_scores(state: State<'_, AppState>, session_id: String) -> Result<Vec<Score>, CommandError> {
+    Ok(state.db()?.get_session_scores(&session_id).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_score_rule_checks(state: State<'_, AppState>, score_id: i64) -> Result<Vec<RuleCheckRecord>, CommandError> {
+    Ok(state.db()?.get_score_rule_checks(score_id).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_rule_history(
+    state: State<'_, AppState>,
+    rule_id: String,
+    limit: Option<i64>,
+) -> Result<Vec<RuleCheckRecord>, CommandError> {
+    Ok(state.db()?.get_rule_history(&rule_id, limit).await?)
+}
+
+/// A rule's definition, pass rate, daily or weekly trend, and recent failing evidence,
+/// for the rule drill-down page
+#[tauri::command]
+#[specta::specta]
+async fn get_rule_detail(
+    state: State<'_, AppState>,
+    rule_id: String,
+    bucket: Option<TimeBucket>,
+    outcomes: Option<Outcomes>,
+    failure_limit: Option<i64>,
+) -> Result<RuleDetail, CommandError> {
+    state
+        .db()?
+        .get_rule_detail(&rule_id, bucket.unwrap_or(TimeBucket::Day), outcomes.unwrap_or_default(), failure_limit)
+        .await
+        .map_err(|e| CommandError::from(e).with_context(rule_id))
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_stats(state: State<'_, AppState>) -> Result<DbStats, CommandError> {
+    Ok(state.db()?.get_stats().await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_score_distribution(
+    state: State<'_, AppState>,
+    outcomes: Option<Outcomes>,
+) -> Result<ScoreDistribution, CommandError> {
+    Ok(state
+        .db()?
+        .get_score_distribution(outcomes.unwrap_or_default())
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn override_rule_check(
+    state: State<'_, AppState>,
+    rule_check_id: i64,
+    verdict: OverrideVerdict,
+    reason: String,
+) -> Result<RuleCheckOverride, CommandError> {
+    Ok(state
+        .db()?
+        .set_rule_check_override(rule_check_id, verdict, &reason)
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn clear_rule_check_override(state: State<'_, AppState>, rule_check_id: i64) -> Result<bool, CommandError> {
+    Ok(state
+        .db()?
+        .clear_rule_check_override(rule_check_id)
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_rule_pass_rate_series(
+    state: State<'_, AppState>,
+    rule_id: String,
+    bucket: TimeBucket,
+    outcomes: Option<Outcomes>,
+) -> Result<Vec<PassRatePoint>, CommandError> {
+    Ok(state
+        .db()?
+        .get_rule_pass_rate_series(&rule_id, bucket, outcomes.unwrap_or_default())
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_source_stats(
+    state: State<'_, AppState>,
+    outcomes: Option<Outcomes>,
+) -> Result<Vec<SourceStats>, CommandError> {
+    Ok(state
+        .db()?
+        .get_source_stats(outcomes.unwrap_or_default())
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_category_stats(
+    state: State<'_, AppState>,
+    outcomes: Option<Outcomes>,
+) -> Result<Vec<CategoryStats>, CommandError> {
+    Ok(state
+        .db()?
+        .get_category_stats(outcomes.unwrap_or_default())
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_rule_failure_leaderboard(
+    state: State<'_, AppState>,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+    limit: i64,
+) -> Result<Vec<RuleFailureStats>, CommandError> {
+    Ok(state
+        .db()?
+        .get_rule_failure_leaderboard(since, limit)
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_session_percentile(state: State<'_, AppState>, session_id: String) -> Result<f64, CommandError> {
+    Ok(state.db()?.get_session_percentile(&session_id).await?)
+}
+
+/// Rule-by-rule comparison of two sessions' latest scores
+#[tauri::command]
+#[specta::specta]
+async fn compare_sessions(state: State<'_, AppState>, id_a: String, id_b: String) -> Result<SessionComparison, CommandError> {
+    Ok(state.db()?.compare_sessions(&id_a, &id_b).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn get_ranked_sessions(
+    state: State<'_, AppState>,
+    ranking: Ranking,
+    limit: i64,
+    since: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<Vec<Score>, CommandError> {
+    Ok(state
+        .db()?
+        .get_ranked_sessions(ranking, limit, since)
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn check_database(state: State<'_, AppState>) -> Result<DbHealth, CommandError> {
+    Ok(state.db()?.health_check().await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn optimize_database(state: State<'_, AppState>) -> Result<i64, CommandError> {
+    Ok(state.db()?.optimize().await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn backup_database(state: State<'_, AppState>, path: String) -> Result<(), CommandError> {
+    let path = state.scorer.lock()?.validate_output_file(std::path::Path::new(&path))?;
+    Ok(state.db()?.backup_to(&path).await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn restore_database(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<(), CommandError> {
+    let path = state.scorer.lock()?.validate_input_file(std::path::Path::new(&path))?;
+    state.db()?.restore_from(&path).await?;
+    reload_rules(&app).await
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn export_history(state: State<'_, AppState>, path: String) -> Result<usize, CommandError> {
+    let path = state.scorer.lock()?.validate_output_file(std::path::Path::new(&path))?;
+    Ok(state.db()?.export_jsonl(&path).await?)
+}
+
+/// Write a shareable scorecard for each session, returning how many were written.
+/// Without a `path` the user picks one in a save dialog; cancelling it writes nothing.
+#[tauri::command]
+#[specta::specta]
+async fn export_report(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    session_ids: Vec<String>,
+    format: ReportFormat,
+    path: Option<String>,
+) -> Result<usize, CommandError> {
+    let path = match path {
+        Some(path) => PathBuf::from(path),
+        None => match pick_report_path(&app, format).await? {
+            Some(path) => path,
+            None => return Ok(0),
+        },
+    };
+    let path = state.scorer.lock()?.validate_output_file(&path)?;
+    Ok(state.db()?.export_report(&session_ids, format, &path).await?)
+}
+
+/// Ask where to save a report, or `None` if the user cancels
+async fn pick_report_path(app: &AppHandle, format: ReportFormat) -> Result<Option<PathBuf>, CommandError> {
+    let extension = format.extension();
+    let dialog = app
+        .dialog()
+        .file()
+        .set_title("Export report")
+        .set_file_name(format!("report.{}", extension))
+        .add_filter("Report", &[extension]);
+    let picked = tauri::async_runtime::spawn_blocking(move || dialog.blocking_save_file())
+        .await
+        .map_err(|e| CommandError::new(ErrorCode::Failed, format!("Save dialog failed: {}", e)))?;
+    picked
+        .map(|path| path.into_path().map_err(|e| CommandError::invalid_input(format!("Invalid report path: {}", e))))
+        .transpose()
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn import_history(app: AppHandle, state: State<'_, AppState>, path: String) -> Result<HistoryImportReport, CommandError> {
+    let path = state.scorer.lock()?.validate_input_file(std::path::Path::new(&path))?;
+    let report = state.db()?.import_jsonl(&path).await?;
+    reload_rules(&app).await?;
+    Ok(report)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn search_sessions(
+    state: State<'_, AppState>,
+    query: String,
+    filters: Option<SearchFilters>,
+) -> Result<Vec<SearchHit>, CommandError> {
+    Ok(state
+        .db()?
+        .search(&query, &filters.unwrap_or_default())
+        .await?)
+}
+
+/// Full-text search for the dashboard's search box: matching sessions, best first,
+/// each with its latest score and highlighted snippets
+#[tauri::command]
+#[specta::specta]
+async fn search(
+    state: State<'_, AppState>,
+    query: String,
+    filters: Option<SearchFilters>,
+) -> Result<Vec<SessionSearchResult>, CommandError> {
+    Ok(state
+        .db()?
+        .search_by_session(&query, &filters.unwrap_or_default())
+        .await?)
+}
+
+#[tauri::command]
+#[specta::specta]
+async fn start_watching(
+    app: AppHandle,
+    state: State<'_, AppState>,
+    directories: Vec<String>,
+    options: Option<ScanOptions>,
+    debounce_ms: Option<u32>,
+) -> Result<(), CommandError> {
+    let db = state.db()?;
+    let directories = if directories.is_empty() {
+        state.settings()?.scan_directories
+    } else {
+        directories
+    };
+    let directories: Vec<PathBuf> = directories.into_iter().map(PathBuf::from).collect();
+    let debounce = Duration::from_millis(debounce_ms.map(u64::from).unwrap_or(DEFAULT_DEBOUNCE_MS));
+    
+    let options = state.scan_options(options)?;
+    let strategy = options.session_ids;
+    let source = options.source_or(WATCH_SOURCE).to_string();
+    
+    let watcher = {
+        let scorer = scorer_for_source(&app, &state, options.source.as_deref()).await?;
+        for directory in &directories {
+            scorer.validate_directory(directory)?;
+        }
+        scorer.watch(&directories, &options, debounce, move |path, result| {
+            match result {
+                Ok(mut score) => {
+                    match tauri::async_runtime::block_on(persist_score(&db, &mut score, path, strategy, &source)) {
+                        Ok(_) => score_saved(&app, &score, &source),
+                        Err(e) => tracing::error!(session = %score.session_id, error = %e, "Failed to save score"),
+                    }
+                    if let Err(e) = app.emit(SESSION_SCORED_EVENT, &score) {
+                        tracing::warn!(session = %score.session_id, error = %e, "Failed to emit score");
+                    }
+                }
+                Err(e) => tracing::warn!(path = %path.display(), error = %e, "Failed to score transcript"),
+            }
+        })?
+    };
+    
+    // Replacing an existing watcher drops it, which stops the old watch
+    *state.watcher.lock()? = Some(watcher);
+    Ok(())
+}
+
+/// Re-score every stored transcript with the current rules in the background,
+/// returning once the run has started. Progress and the final report arrive as
+/// events.
+#[tauri::command]
+#[specta::specta]
+async fn rescore_all(app: AppHandle, state: State<'_, AppState>) -> Result<(), CommandError> {
+    let db = state.db()?;
+    let scorer = state.scorer.lock()?.clone();
+    tauri::async_runtime::spawn(async move {
+        let progress_app = app.clone();
+        let result = scorer
+            .rescore_all(&db, |progress| {
+                if let Err(e) = progress_app.emit(RESCORE_PROGRESS_EVENT, progress) {
+                    tracing::warn!(error = %e, "Failed to emit rescore progress");
+                }
+            })
+            .await;
+        match result {
+            Ok(report) => {
+                if let Err(e) = app.emit(RESCORE_FINISHED_EVENT, &report) {
+                    tracing::warn!(error = %e, "Failed to emit rescore report");
+                }
+            }
+            Err(e) => tracing::error!(error = %e, "Failed to rescore sessions"),
+        }
+    });
+    Ok(())
+}
+
+#[tauri::command]
+#[specta::specta]
+fn stop_watching(state: State<AppState>) -> Result<bool, CommandError> {
+    Ok(state.watcher.lock()?.take().is_some())
 }
 
 // GOLD: Type-safe command collection for specta
@@ -109,13 +1227,120 @@ fn create_specta_builder() -> Builder<tauri::Wry> {
     Builder::new()
         .commands(collect_commands![
             score_session,
+            score_transcript,
+            score_text,
+            quick_score,
+            score_clipboard,
+            score_url,
+            score_files,
             get_rules,
-            scan_sessions_directory
+            get_scorer_config,
+            save_rule,
+            add_rule,
+            update_rule,
+            preview_rule,
+            set_rule_enabled,
+            delete_rule,
+            scan_sessions_directory,
+            scan_archive,
+            rescan_sessions_directory,
+            import_sqlite_sessions,
+            list_sessions,
+            find_sessions_by_metadata,
+            list_metadata_values,
+            resolve_session_link,
+            archive_session,
+            confirm_delete,
+            delete_session,
+            delete_score,
+            restore_session,
+            list_trash,
+            purge_session,
+            purge_trash,
+            list_scores,
+            get_session_scores,
+            get_score_rule_checks,
+            get_rule_history,
+            get_rule_detail,
+            get_stats,
+            get_score_distribution,
+            get_rule_pass_rate_series,
+            get_category_stats,
+            get_source_stats,
+            get_rule_failure_leaderboard,
+            get_session_percentile,
+            compare_sessions,
+            get_ranked_sessions,
+            search_sessions,
+            search,
+            check_database,
+            optimize_database,
+            backup_database,
+            restore_database,
+            export_history,
+            export_report,
+            import_history,
+            override_rule_check,
+            clear_rule_check_override,
+            list_profiles,
+            add_profile,
+            remove_profile,
+            switch_profile,
+            get_profile_stats,
+            get_settings,
+            update_settings,
+            rescore_all,
+            start_watching,
+            stop_watching
         ])
-        .ty::<SessionScore>()
-        .ty::<RuleCheck>()
-        .ty::<RuleDefinition>()
-        .ty::<RuleCategory>()
+        .typ::<SessionScore>()
+        .typ::<QuickScore>()
+        .typ::<ScoreSaved>()
+        .typ::<RuleCheck>()
+        .typ::<RuleDefinition>()
+        .typ::<RuleCategory>()
+        .typ::<RulePreview>()
+        .typ::<MatchSpan>()
+        .typ::<StoredRule>()
+        .typ::<Transcript>()
+        .typ::<TranscriptFormat>()
+        .typ::<ScanOptions>()
+        .typ::<ScanEvent>()
+        .typ::<ScanReport>()
+        .typ::<ScanIssue>()
+        .typ::<ImportMapping>()
+        .typ::<ListFilters>()
+        .typ::<ScoreBand>()
+        .typ::<SearchFilters>()
+        .typ::<SearchHit>()
+        .typ::<SearchKind>()
+        .typ::<SearchSnippet>()
+        .typ::<SessionSearchResult>()
+        .typ::<OverrideVerdict>()
+        .typ::<Outcomes>()
+        .typ::<TimeBucket>()
+        .typ::<RuleDetail>()
+        .typ::<RuleFailure>()
+        .typ::<Ranking>()
+        .typ::<ReportFormat>()
+        .typ::<SessionComparison>()
+        .typ::<RuleDiff>()
+        .typ::<RuleSide>()
+        .typ::<SessionLink>()
+        .typ::<DeleteTarget>()
+        .typ::<DeleteConfirmation>()
+        .typ::<ProfileRegistry>()
+        .typ::<AggregateStats>()
+        .typ::<Settings>()
+        .typ::<ScoreThresholds>()
+        .typ::<AlertSettings>()
+        .typ::<CommandError>()
+        .typ::<ErrorCode>()
+        .typ::<RescoreProgress>()
+        .typ::<RescoreReport>()
+        .typ::<ScheduledScan>()
+        .typ::<FileScoreProgress>()
+        .typ::<FileScoreReport>()
 }
 
 pub fn run() {
@@ -123,11 +1348,43 @@ pub fn run() {
     // This would typically be done in build.rs, but for now we'll document it
     // Run: cargo test export_bindings to generate TypeScript types
     
+    logging::init(logging::level(0, false), false, false);
     tauri::Builder::default()
+        // Must come first, so a link opened while the app runs reaches the running instance
+        .plugin(tauri_plugin_single_instance::init(|app, _argv, _cwd| open_links(app, [])))
+        .plugin(tauri_plugin_deep_link::init())
         .plugin(tauri_plugin_shell::init())
         .plugin(tauri_plugin_sql::Builder::new().build())
-        .manage(AppState {
-            scorer: Mutex::new(BehaviorScorer::new()),
+        .plugin(tauri_plugin_notification::init())
+        .plugin(tauri_plugin_clipboard_manager::init())
+        .plugin(tauri_plugin_dialog::init())
+        .setup(|app| {
+            let (_, settings) = load_settings(app.handle())?;
+            let interval = settings.scan_interval();
+            let (db, scorer) = tauri::async_runtime::block_on(async {
+                let (_, registry) = load_profiles(app.handle())?;
+                let db = open_database(app.handle(), registry.active_path()).await?;
+                let scorer = load_scorer(&db, &settings).await?;
+                Ok::<_, CommandError>((db, scorer))
+            })?;
+            app.manage(AppState {
+                scorer: Mutex::new(scorer),
+                watcher: Mutex::new(None),
+                db: Mutex::new(db),
+                settings: Mutex::new(settings),
+                scheduler: Mutex::new(interval.map(|interval| start_scheduler(app.handle().clone(), interval))),
+                confirmations: Mutex::new(ConfirmationTokens::new()),
+            });
+            build_tray(app.handle())?;
+
+            // Installed bundles register the scheme; development builds do it at runtime
+            #[cfg(any(target_os = "linux", all(debug_assertions, windows)))]
+            app.deep_link().register_all()?;
+            let handle = app.handle().clone();
+            app.deep_link().on_open_url(move |event| {
+                open_links(&handle, event.urls().into_iter().map(|url| url.to_string()));
+            });
+            Ok(())
         })
         .invoke_handler(
             create_specta_builder()
@@ -0,0 +1,76 @@
+//! Markdown reports for sharing scores in chat or PR comments: a score table,
+//! then each session's failed rules with their suggestions and evidence.
+
+use crate::SessionScore;
+
+/// A Markdown report on `scores`, leaving out duplicates of other sessions
+pub fn markdown_report(scores: &[SessionScore]) -> String {
+    let scores: Vec<_> = scores.iter().filter(|score| score.duplicate_of.is_none()).collect();
+    let mut md = String::from("# Behavior report\n\n");
+    if scores.is_empty() {
+        md.push_str("No sessions scored.\n");
+        return md;
+    }
+
+    md.push_str("| Session | Score | Passed |\n|---|---:|---:|\n");
+    for score in &scores {
+        md.push_str(&format!(
+            "| {} | {:.1}% | {}/{} |\n",
+            table_cell(&score.session_id),
+            score.score_percentage,
+            score.passed_rules,
+            score.total_rules
+        ));
+    }
+    if scores.len() > 1 {
+        let average = scores.iter().map(|score| score.score_percentage).sum::<f64>() / scores.len() as f64;
+        md.push_str(&format!("\n**Average:** {:.1}% across {} sessions\n", average, scores.len()));
+    }
+
+    for score in &scores {
+        md.push_str(&format!("\n## {} ({:.1}%)\n\n{}\n", score.session_id, score.score_percentage, score.summary));
+        let (passed, failed): (Vec<_>, Vec<_>) = score.rules.iter().partition(|check| check.passed);
+        if !failed.is_empty() {
+            md.push_str("\n### Failed rules\n");
+        }
+        for check in failed {
+            md.push_str(&format!("\n- **{}** (`{}`)\n", check.rule_name, check.rule_id));
+            if let Some(suggestion) = &check.suggestion {
+                md.push_str(&format!("  {}\n", suggestion));
+            }
+            if let Some(evidence) = &check.evidence {
+                md.push_str(&code_block(evidence, "  "));
+            }
+        }
+        if !passed.is_empty() {
+            let names: Vec<_> = passed.iter().map(|check| check.rule_name.as_str()).collect();
+            md.push_str(&format!("\n**Passed:** {}\n", names.join(", ")));
+        }
+        for warning in &score.warnings {
+            md.push_str(&format!("\n> Warning: {}\n", warning));
+        }
+    }
+    md
+}
+
+/// Text safe to put in a table cell
+fn table_cell(text: &str) -> String {
+    text.replace('|', "\\|").replace('\n', " ")
+}
+
+/// `text` fenced as a code block indented by `indent`, with a fence longer than any
+/// backtick run inside it
+fn code_block(text: &str, indent: &str) -> String {
+    let longest_run = text
+        .split(|c| c != '`')
+        .map(str::len)
+        .max()
+        .unwrap_or_default();
+    let fence = "`".repeat(longest_run.max(2) + 1);
+    let mut block = format!("\n{}{}\n", indent, fence);
+    for line in text.lines() {
+        block.push_str(&format!("{}{}\n", indent, line));
+    }
+    block.push_str(&format!("{}{}\n", indent, fence));
+    block
+}
@@ -0,0 +1,83 @@
+use crate::{RuleCategory, RuleDefinition};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// How rule weights are combined into a session percentage
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(tag = "mode", rename_all = "snake_case")]
+pub enum NormalizationMode {
+    /// Passed weight over total weight
+    #[default]
+    Raw,
+    /// Every category counts equally, regardless of how many rules or how much weight it has.
+    /// Categories with no configured rules score 0 so gaps in the rule set can't inflate the result;
+    /// a rule filter narrows the average to the categories it leaves.
+    CategoryEqual,
+    /// Weights are clamped to the [percentile, 100 - percentile] range of configured weights
+    /// before the raw calculation, so a single outlier weight can't dominate
+    Winsorized { percentile: f64 },
+}
+
+/// Combine per-rule outcomes into a 0-100 score. `passed` is index-aligned with `rules`, and
+/// `categories` are those `CategoryEqual` averages over.
+pub fn score_percentage(
+    rules: &[RuleDefinition],
+    passed: &[bool],
+    mode: NormalizationMode,
+    categories: &[RuleCategory],
+) -> f64 {
+    match mode {
+        NormalizationMode::Raw => weighted_percentage(rules.iter().map(|r| r.weight), passed),
+        NormalizationMode::CategoryEqual => {
+            let total: f64 = categories
+                .iter()
+                .map(|category| {
+                    let (weights, outcomes): (Vec<f64>, Vec<bool>) = rules
+                        .iter()
+                        .zip(passed)
+                        .filter(|(rule, _)| rule.category == *category)
+                        .map(|(rule, passed)| (rule.weight, *passed))
+                        .unzip();
+                    weighted_percentage(weights.into_iter(), &outcomes)
+                })
+                .sum();
+            total / categories.len().max(1) as f64
+        }
+        NormalizationMode::Winsorized { percentile } => {
+            let mut sorted: Vec<f64> = rules.iter().map(|r| r.weight).collect();
+            sorted.sort_by(f64::total_cmp);
+            let percentile = percentile.clamp(0.0, 50.0);
+            let low = interpolated_percentile(&sorted, percentile);
+            let high = interpolated_percentile(&sorted, 100.0 - percentile);
+            weighted_percentage(rules.iter().map(|r| r.weight.clamp(low, high)), passed)
+        }
+    }
+}
+
+fn weighted_percentage(weights: impl Iterator<Item = f64>, passed: &[bool]) -> f64 {
+    let (total, passed_weight) = weights
+        .zip(passed)
+        .fold((0.0, 0.0), |(total, passed_weight), (weight, passed)| {
+            (total + weight, if *passed { passed_weight + weight } else { passed_weight })
+        });
+    
+    if total > 0.0 {
+        (passed_weight / total) * 100.0
+    } else {
+        0.0
+    }
+}
+
+/// Linear-interpolated percentile of an ascending slice
+fn interpolated_percentile(sorted: &[f64], percentile: f64) -> f64 {
+    match sorted.len() {
+        0 => 0.0,
+        1 => sorted[0],
+        len => {
+            let rank = percentile / 100.0 * (len - 1) as f64;
+            let lower = rank.floor() as usize;
+            let upper = rank.ceil() as usize;
+            sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64)
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Transcript parser registry. Parsers are looked up by session source (e.g.
+//! `codex`) or file extension, so new formats can be registered without changing
+//! the scanner or scoring entry points.
+
+use crate::transcript::{self, JsonlSession, Transcript, TranscriptError};
+use std::collections::HashMap;
+use std::sync::{Arc, OnceLock};
+
+/// Parses one transcript format
+pub trait TranscriptParser: Send + Sync {
+    /// Short name of the format, e.g. `jsonl`
+    fn name(&self) -> &'static str;
+
+    /// Parse raw content into a transcript
+    fn parse(&self, content: &str) -> Result<Transcript, TranscriptError>;
+
+    /// Split content holding several sessions into parts scored on their own, or `None`
+    /// when it holds one session
+    fn split_sessions(&self, _content: &str) -> Option<Vec<JsonlSession>> {
+        None
+    }
+}
+
+/// JSONL event streams (Claude Code, Codex, bare role/content lines)
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonlParser;
+
+impl TranscriptParser for JsonlParser {
+    fn name(&self) -> &'static str {
+        "jsonl"
+    }
+
+    fn parse(&self, content: &str) -> Result<Transcript, TranscriptError> {
+        transcript::parse_jsonl(content)
+    }
+
+    fn split_sessions(&self, content: &str) -> Option<Vec<JsonlSession>> {
+        transcript::split_jsonl_sessions(content)
+    }
+}
+
+/// Chat-completions exports. Other JSON is treated as plain text, since `.json`
+/// files aren't always transcripts.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ChatJsonParser;
+
+impl TranscriptParser for ChatJsonParser {
+    fn name(&self) -> &'static str {
+        "chat_json"
+    }
+
+    fn parse(&self, content: &str) -> Result<Transcript, TranscriptError> {
+        Ok(transcript::parse_chat_json(content).unwrap_or_else(|_| Transcript::from_plain_text(content)))
+    }
+}
+
+/// Markdown or unstructured text, split on speaker labels, with optional YAML frontmatter
+#[derive(Debug, Clone, Copy, Default)]
+pub struct PlainTextParser;
+
+impl TranscriptParser for PlainTextParser {
+    fn name(&self) -> &'static str {
+        "plain_text"
+    }
+
+    fn parse(&self, content: &str) -> Result<Transcript, TranscriptError> {
+        Ok(Transcript::from_plain_text(content))
+    }
+}
+
+/// Parsers keyed by session source and by file extension, with a fallback for
+/// anything unregistered
+#[derive(Clone)]
+pub struct ParserRegistry {
+    by_source: HashMap<String, Arc<dyn TranscriptParser>>,
+    by_extension: HashMap<String, Arc<dyn TranscriptParser>>,
+    fallback: Arc<dyn TranscriptParser>,
+}
+
+impl Default for ParserRegistry {
+    /// The built-in parsers: JSONL for `.jsonl` and the `claude-code`/`codex` sources,
+    /// chat JSON for `.json`, and plain text for Markdown and everything else
+    fn default() -> Self {
+        let plain_text: Arc<dyn TranscriptParser> = Arc::new(PlainTextParser);
+        let jsonl: Arc<dyn TranscriptParser> = Arc::new(JsonlParser);
+
+        Self::empty()
+            .with_extension("md", plain_text.clone())
+            .with_extension("markdown", plain_text.clone())
+            .with_extension("jsonl", jsonl.clone())
+            .with_extension("json", Arc::new(ChatJsonParser))
+            .with_extension("txt", plain_text)
+            .with_source("claude-code", jsonl.clone())
+            .with_source("codex", jsonl)
+    }
+}
+
+impl ParserRegistry {
+    /// A registry with no parsers registered, falling back to plain text
+    pub fn empty() -> Self {
+        Self {
+            by_source: HashMap::new(),
+            by_extension: HashMap::new(),
+            fallback: Arc::new(PlainTextParser),
+        }
+    }
+
+    /// Shared registry of the built-in parsers
+    pub fn builtin() -> &'static ParserRegistry {
+        static BUILTIN: OnceLock<ParserRegistry> = OnceLock::new();
+        BUILTIN.get_or_init(ParserRegistry::default)
+    }
+
+    /// Register a parser for a file extension (without the dot; case-insensitive)
+    pub fn with_extension(mut self, extension: &str, parser: Arc<dyn TranscriptParser>) -> Self {
+        self.by_extension.insert(normalize_key(extension), parser);
+        self
+    }
+
+    /// Register a parser for a session source such as `codex`
+    pub fn with_source(mut self, source: &str, parser: Arc<dyn TranscriptParser>) -> Self {
+        self.by_source.insert(normalize_key(source), parser);
+        self
+    }
+
+    /// Parser used for content with no registered source or extension
+    pub fn with_fallback(mut self, parser: Arc<dyn TranscriptParser>) -> Self {
+        self.fallback = parser;
+        self
+    }
+
+    /// Parser registered for a file extension, or the fallback
+    pub fn for_extension(&self, extension: Option<&str>) -> &dyn TranscriptParser {
+        extension
+            .and_then(|ext| self.by_extension.get(&normalize_key(ext)))
+            .unwrap_or(&self.fallback)
+            .as_ref()
+    }
+
+    /// Parser registered for a session source, if any
+    pub fn for_source(&self, source: &str) -> Option<&dyn TranscriptParser> {
+        self.by_source.get(&normalize_key(source)).map(|parser| parser.as_ref())
+    }
+
+    /// Parse content, preferring the source's parser over the extension's
+    pub fn parse(
+        &self,
+        content: &str,
+        source: Option<&str>,
+        extension: Option<&str>,
+    ) -> Result<Transcript, TranscriptError> {
+        source
+            .and_then(|source| self.for_source(source))
+            .unwrap_or_else(|| self.for_extension(extension))
+            .parse(content)
+    }
+}
+
+fn normalize_key(key: &str) -> String {
+    key.trim().trim_start_matches('.').to_ascii_lowercase()
+}
@@ -0,0 +1,87 @@
+//! Live rule previews for the rule editor: a pattern is compiled the way the scorer
+//! compiles it and matched against sample text.
+
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// Most matches reported for one preview
+pub const MAX_PREVIEW_MATCHES: usize = 500;
+
+/// Inline flags accepted by `preview_rule`
+const PREVIEW_FLAGS: &str = "imsxU";
+
+/// One match in the sample, with offsets in UTF-16 code units so they index
+/// JavaScript strings directly
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct MatchSpan {
+    pub start: usize,
+    pub end: usize,
+    pub text: String,
+}
+
+/// Matches of a pattern in sample text, or why it doesn't compile
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct RulePreview {
+    /// Pattern with the flags folded in as an inline group, as a rule should store it
+    pub pattern: String,
+    pub matches: Vec<MatchSpan>,
+    /// More than `MAX_PREVIEW_MATCHES` matches were found
+    pub truncated: bool,
+    pub error: Option<String>,
+}
+
+/// Compile `pattern` with inline `flags` (any of `imsxU`) and find its matches in `sample`
+pub fn preview_rule(pattern: &str, flags: &str, sample: &str) -> RulePreview {
+    let mut flags: Vec<char> = flags.chars().filter(|flag| !flag.is_whitespace()).collect();
+    flags.sort_unstable();
+    flags.dedup();
+    let pattern = if flags.is_empty() {
+        pattern.to_string()
+    } else {
+        format!("(?{}){}", flags.iter().collect::<String>(), pattern)
+    };
+    let failed = |error: String| RulePreview {
+        pattern: pattern.clone(),
+        error: Some(error),
+        ..RulePreview::default()
+    };
+
+    if let Some(flag) = flags.iter().find(|flag| !PREVIEW_FLAGS.contains(**flag)) {
+        return failed(format!("Unknown flag: {}", flag));
+    }
+    if pattern.is_empty() {
+        return failed("Pattern must not be empty".to_string());
+    }
+    let regex = match Regex::new(&pattern) {
+        Ok(regex) => regex,
+        Err(e) => return failed(e.to_string()),
+    };
+
+    // Convert byte offsets to UTF-16 as matches arrive, in order
+    let mut matches = Vec::new();
+    let mut truncated = false;
+    let (mut byte_pos, mut utf16_pos) = (0, 0);
+    let mut advance = |to: usize| {
+        utf16_pos += sample[byte_pos..to].encode_utf16().count();
+        byte_pos = to;
+        utf16_pos
+    };
+    for mat in regex.find_iter(sample) {
+        if matches.len() == MAX_PREVIEW_MATCHES {
+            truncated = true;
+            break;
+        }
+        matches.push(MatchSpan {
+            start: advance(mat.start()),
+            end: advance(mat.end()),
+            text: mat.as_str().to_string(),
+        });
+    }
+    RulePreview {
+        pattern,
+        matches,
+        truncated,
+        error: None,
+    }
+}
@@ -0,0 +1,197 @@
+//! Named database profiles, e.g. one database per project or agent. The registry
+//! maps profile names to database paths and records which one is active; the app
+//! and the CLI share it through a JSON file next to the default database.
+
+use crate::db::{Database, DbStats};
+use crate::keys::KeyProvider;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+
+/// Profile created for the app's original database
+pub const DEFAULT_PROFILE: &str = "default";
+
+/// Registry file name, in the app's config directory
+pub const REGISTRY_FILE: &str = "profiles.json";
+
+/// Database file name of the default profile, in the app's config directory
+pub const DEFAULT_DB_FILE: &str = "behavior.db";
+
+/// The app's Tauri config, whose bundle identifier names its config directory
+const TAURI_CONFIG: &str = include_str!("../tauri.conf.json");
+
+/// Named database paths and the active profile
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct ProfileRegistry {
+    pub active: String,
+    /// Database path per profile name
+    pub profiles: BTreeMap<String, String>,
+}
+
+/// One profile's stats, or why they couldn't be read
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ProfileStats {
+    pub name: String,
+    pub path: String,
+    pub stats: Option<DbStats>,
+    pub error: Option<String>,
+}
+
+/// Totals across every readable profile
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct AggregateStats {
+    pub profiles: Vec<ProfileStats>,
+    pub sessions: i64,
+    pub scores: i64,
+    pub rule_checks: i64,
+    /// Average score percentage, weighted by each profile's score count
+    pub avg_score: f64,
+}
+
+impl ProfileRegistry {
+    /// A registry holding only the default profile
+    pub fn new(default_db: impl AsRef<Path>) -> Self {
+        Self {
+            active: DEFAULT_PROFILE.to_string(),
+            profiles: BTreeMap::from([(DEFAULT_PROFILE.to_string(), default_db.as_ref().to_string_lossy().into_owned())]),
+        }
+    }
+
+    /// Read the registry file, or start a new registry around `default_db` if there
+    /// isn't one yet
+    pub fn load_or_default(registry: &Path, default_db: impl AsRef<Path>) -> Result<Self, String> {
+        if !registry.exists() {
+            return Ok(Self::new(default_db));
+        }
+        let content = std::fs::read_to_string(registry).map_err(|e| format!("Failed to read profiles: {}", e))?;
+        let loaded: Self = serde_json::from_str(&content).map_err(|e| format!("Invalid profiles file: {}", e))?;
+        if !loaded.profiles.contains_key(&loaded.active) {
+            return Err(format!("Active profile {} isn't registered", loaded.active));
+        }
+        Ok(loaded)
+    }
+
+    /// Write the registry file, replacing it atomically
+    pub fn save(&self, registry: &Path) -> Result<(), String> {
+        if let Some(parent) = registry.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let staging = registry.with_extension("json.tmp");
+        std::fs::write(&staging, json).map_err(|e| format!("Failed to write profiles: {}", e))?;
+        std::fs::rename(&staging, registry).map_err(|e| format!("Failed to write profiles: {}", e))
+    }
+
+    /// Register a database under a new name. The file is created when first opened.
+    pub fn add(&mut self, name: &str, db_path: impl AsRef<Path>) -> Result<(), String> {
+        validate_name(name)?;
+        if self.profiles.contains_key(name) {
+            return Err(format!("Profile {} already exists", name));
+        }
+        self.profiles.insert(name.to_string(), db_path.as_ref().to_string_lossy().into_owned());
+        Ok(())
+    }
+
+    /// Forget a profile. Its database file is left in place; the active profile
+    /// can't be removed.
+    pub fn remove(&mut self, name: &str) -> Result<(), String> {
+        if name == self.active {
+            return Err(format!("Profile {} is active; switch to another first", name));
+        }
+        self.profiles
+            .remove(name)
+            .map(|_| ())
+            .ok_or_else(|| format!("Unknown profile: {}", name))
+    }
+
+    /// Make a registered profile the active one
+    pub fn set_active(&mut self, name: &str) -> Result<(), String> {
+        if !self.profiles.contains_key(name) {
+            return Err(format!("Unknown profile: {}", name));
+        }
+        self.active = name.to_string();
+        Ok(())
+    }
+
+    /// Database path of the active profile
+    pub fn active_path(&self) -> &str {
+        &self.profiles[&self.active]
+    }
+
+    /// Stats from every profile's database, opened read-only, with totals across
+    /// those that could be read
+    pub async fn aggregate_stats(&self) -> AggregateStats {
+        self.aggregate_stats_with(|_| None).await
+    }
+
+    /// Stats like `aggregate_stats`, opening each database with the key `keys`
+    /// returns for its path, or unencrypted when it returns `None`
+    pub async fn aggregate_stats_with(&self, keys: impl Fn(&str) -> Option<Box<dyn KeyProvider>>) -> AggregateStats {
+        let mut profiles = Vec::with_capacity(self.profiles.len());
+        for (name, path) in &self.profiles {
+            let db = match keys(path) {
+                Some(keys) => Database::open_read_only_encrypted(path, keys.as_ref()).await,
+                None => Database::open_read_only(path).await,
+            };
+            let stats = match db {
+                Ok(db) => db.get_stats().await,
+                Err(e) => Err(e),
+            };
+            profiles.push(ProfileStats {
+                name: name.clone(),
+                path: path.clone(),
+                error: stats.as_ref().err().map(|e| e.to_string()),
+                stats: stats.ok(),
+            });
+        }
+
+        let readable = || profiles.iter().filter_map(|profile| profile.stats.as_ref());
+        let scores: i64 = readable().map(|stats| stats.scores).sum();
+        let weighted: f64 = readable().map(|stats| stats.avg_score * stats.scores as f64).sum();
+        AggregateStats {
+            sessions: readable().map(|stats| stats.sessions).sum(),
+            rule_checks: readable().map(|stats| stats.rule_checks).sum(),
+            avg_score: if scores > 0 { weighted / scores as f64 } else { 0.0 },
+            scores,
+            profiles,
+        }
+    }
+}
+
+/// Profile names are used in file names and CLI arguments, so keep them plain
+fn validate_name(name: &str) -> Result<(), String> {
+    let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    if valid {
+        Ok(())
+    } else {
+        Err(format!("Invalid profile name: {}", name))
+    }
+}
+
+/// The desktop app's config directory, for the CLI to find the shared registry
+pub fn app_config_dir() -> Option<PathBuf> {
+    let home = std::env::var_os("HOME").map(PathBuf::from);
+    let base = if cfg!(target_os = "macos") {
+        home.map(|home| home.join("Library").join("Application Support"))
+    } else if cfg!(windows) {
+        std::env::var_os("APPDATA").map(PathBuf::from)
+    } else {
+        std::env::var_os("XDG_CONFIG_HOME")
+            .map(PathBuf::from)
+            .or_else(|| home.map(|home| home.join(".config")))
+    };
+    base.zip(app_identifier()).map(|(base, identifier)| base.join(identifier))
+}
+
+/// Bundle identifier from tauri.conf.json
+fn app_identifier() -> Option<&'static str> {
+    static IDENTIFIER: OnceLock<Option<String>> = OnceLock::new();
+    IDENTIFIER
+        .get_or_init(|| {
+            let config: serde_json::Value = serde_json::from_str(TAURI_CONFIG).ok()?;
+            config["identifier"].as_str().map(str::to_string)
+        })
+        .as_deref()
+}
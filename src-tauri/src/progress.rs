@@ -0,0 +1,60 @@
+//! Progress bars for CLI scans and imports: sessions handled, rate, and ETA on stderr,
+//! fed from `ScanEvent`s. Hidden bars still count, so callers needn't branch on them.
+
+use crate::scan::{self, ScanEvent};
+use indicatif::{ProgressBar, ProgressDrawTarget, ProgressState, ProgressStyle};
+use std::fmt::Write;
+
+const TEMPLATE: &str = "[{bar:30}] {pos}/{len} sessions  {rate}  ETA {eta}";
+
+/// A progress bar over the sessions of one scan
+pub struct ScanProgress {
+    bar: ProgressBar,
+}
+
+impl ScanProgress {
+    /// A bar drawn on stderr when `visible`, or one that only counts
+    pub fn new(visible: bool) -> Self {
+        let target = if visible {
+            ProgressDrawTarget::stderr()
+        } else {
+            ProgressDrawTarget::hidden()
+        };
+        let bar = ProgressBar::with_draw_target(None, target);
+        bar.set_style(
+            ProgressStyle::with_template(TEMPLATE)
+                .expect("valid progress template")
+                .with_key("rate", |state: &ProgressState, w: &mut dyn Write| {
+                    let _ = write!(w, "{:.1}/s", state.per_sec());
+                })
+                .progress_chars("=> "),
+        );
+        Self { bar }
+    }
+
+    /// Advance the bar for `event`, logging it above the bar so the log doesn't tear it
+    pub fn update(&self, event: &ScanEvent) {
+        match event {
+            ScanEvent::Discovered { sessions, .. } => self.bar.set_length(*sessions as u64),
+            ScanEvent::Scored { done, .. } | ScanEvent::Failed { done, .. } => self.bar.set_position(*done as u64),
+            ScanEvent::Skipped { .. } => {}
+        }
+        self.bar.suspend(|| scan::log_scan_event(event));
+    }
+
+    /// Move the bar to `done` of `total` sessions, for work that doesn't send ScanEvents
+    pub fn set(&self, done: usize, total: usize) {
+        self.bar.set_length(total as u64);
+        self.bar.set_position(done as u64);
+    }
+
+    /// Sessions handled so far, and the total once discovered
+    pub fn position(&self) -> (u64, Option<u64>) {
+        (self.bar.position(), self.bar.length())
+    }
+
+    /// Clear the bar, leaving the terminal as it was
+    pub fn finish(&self) {
+        self.bar.finish_and_clear();
+    }
+}
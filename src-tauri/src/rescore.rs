@@ -0,0 +1,70 @@
+//! Re-scoring of stored transcripts against the current rules, so rule edits can be
+//! applied to history. Each session gets a new score record, or its latest one is
+//! refreshed when the result is unchanged; earlier scores are kept.
+
+use crate::db::Database;
+use crate::BehaviorScorer;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+
+/// Progress after each session is re-scored
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct RescoreProgress {
+    /// Sessions handled so far, including this one
+    pub done: usize,
+    pub total: usize,
+    pub session_id: String,
+    /// New score percentage, unless re-scoring failed
+    pub score_percentage: Option<f64>,
+    pub error: Option<String>,
+}
+
+/// Outcome of a re-score run
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct RescoreReport {
+    pub rescored: usize,
+    /// Sessions that couldn't be scored or stored, as `session: reason`
+    pub failed: Vec<String>,
+}
+
+impl BehaviorScorer {
+    /// Score every stored transcript outside the trash with the current rules and
+    /// store the results, calling `on_progress` after each session
+    pub async fn rescore_all(
+        &self,
+        db: &Database,
+        mut on_progress: impl FnMut(&RescoreProgress),
+    ) -> Result<RescoreReport, String> {
+        let sessions = db.list_sessions_with_transcripts().await.map_err(|e| e.to_string())?;
+        let total = sessions.len();
+        let mut report = RescoreReport::default();
+        for (i, session) in sessions.into_iter().enumerate() {
+            let scored = match db.get_transcript(&session.id).await {
+                Ok(transcript) => self.score_transcript(&session.id, &transcript),
+                Err(e) => Err(e.to_string()),
+            };
+            // The session keeps its original source and transcript path
+            let stored = match scored {
+                Ok(score) => db
+                    .save_session_score(&score, &session.source, None)
+                    .await
+                    .map(|stored| stored.score_percentage)
+                    .map_err(|e| e.to_string()),
+                Err(e) => Err(e),
+            };
+
+            match &stored {
+                Ok(_) => report.rescored += 1,
+                Err(e) => report.failed.push(format!("{}: {}", session.id, e)),
+            }
+            on_progress(&RescoreProgress {
+                done: i + 1,
+                total,
+                session_id: session.id,
+                score_percentage: stored.as_ref().ok().copied(),
+                error: stored.err(),
+            });
+        }
+        Ok(report)
+    }
+}
@@ -0,0 +1,59 @@
+//! Rule subsets for a quick look at part of the rule set, e.g. only the Safety rules
+//! during an incident review. Scores are computed over the rules that remain.
+
+use crate::{BehaviorScorer, RuleCategory, RuleDefinition};
+
+/// Which rules to keep: those listed in `only` (all when empty) and in one of
+/// `categories` (any when empty), less those in `skip`
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RuleFilter {
+    pub only: Vec<String>,
+    pub skip: Vec<String>,
+    pub categories: Vec<RuleCategory>,
+}
+
+impl RuleFilter {
+    pub fn is_empty(&self) -> bool {
+        self.only.is_empty() && self.skip.is_empty() && self.categories.is_empty()
+    }
+
+    fn keeps(&self, rule: &RuleDefinition) -> bool {
+        (self.only.is_empty() || self.only.contains(&rule.id))
+            && (self.categories.is_empty() || self.categories.contains(&rule.category))
+            && !self.skip.contains(&rule.id)
+    }
+
+    /// The rules this filter keeps, in their original order. Naming a rule that isn't in
+    /// `rules`, or leaving none, is an error rather than an empty score.
+    pub fn apply(&self, rules: &[RuleDefinition]) -> Result<Vec<RuleDefinition>, String> {
+        if let Some(unknown) = self
+            .only
+            .iter()
+            .chain(&self.skip)
+            .find(|id| !rules.iter().any(|rule| &rule.id == *id))
+        {
+            return Err(format!("Unknown rule: {}", unknown));
+        }
+        let kept: Vec<RuleDefinition> = rules.iter().filter(|rule| self.keeps(rule)).cloned().collect();
+        if kept.is_empty() {
+            return Err("No rules left to score after filtering".to_string());
+        }
+        Ok(kept)
+    }
+}
+
+impl BehaviorScorer {
+    /// Score with only the rules `filter` keeps. Category-equal scores average over the
+    /// categories those rules cover.
+    pub fn with_rule_filter(mut self, filter: &RuleFilter) -> Result<Self, String> {
+        if !filter.is_empty() {
+            let rules = filter.apply(&self.config().rules)?;
+            self.categories = RuleCategory::ALL
+                .into_iter()
+                .filter(|category| rules.iter().any(|rule| rule.category == *category))
+                .collect();
+            self.set_rules(rules);
+        }
+        Ok(self)
+    }
+}
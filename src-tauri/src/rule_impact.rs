@@ -0,0 +1,139 @@
+//! Impact of a rule set change across a corpus, for `behavior-scorer diff-rules`: the
+//! same sessions scored under the old and new rules, compared rule by rule so an edit
+//! can be judged before it's adopted.
+
+use crate::compare::compare_scores;
+use crate::{RuleDefinition, SessionScore};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::{BTreeMap, HashMap};
+
+/// How the new rules move one session
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct SessionImpact {
+    pub session_id: String,
+    pub percentage_old: f64,
+    pub percentage_new: f64,
+    /// New percentage minus old
+    pub percentage_delta: f64,
+    /// Rules the session now passes but didn't before, by ID
+    pub fixed: Vec<String>,
+    /// Rules the session now fails but didn't before, by ID
+    pub regressed: Vec<String>,
+}
+
+/// How the new rules move one rule's outcome across the corpus
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct RuleImpact {
+    pub rule_id: String,
+    pub rule_name: String,
+    /// Sessions that now pass the rule but didn't before
+    pub fixed: Vec<String>,
+    /// Sessions that now fail the rule but didn't before
+    pub regressed: Vec<String>,
+}
+
+/// Outcome changes between scoring a corpus under the old rules and the new ones
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct RuleSetImpact {
+    /// Rules only in the new set, by ID
+    pub added_rules: Vec<String>,
+    /// Rules only in the old set, by ID
+    pub removed_rules: Vec<String>,
+    /// Rules in both sets whose definitions differ, by ID
+    pub edited_rules: Vec<String>,
+    /// Sessions scored under both rule sets
+    pub sessions: usize,
+    pub average_old: f64,
+    pub average_new: f64,
+    /// Sessions whose percentage or any rule outcome changed, largest drop first
+    pub changed_sessions: Vec<SessionImpact>,
+    /// Rules whose outcome changed for any session, by rule ID
+    pub changed_rules: Vec<RuleImpact>,
+}
+
+/// Compare `old_scores`, scored under `old_rules`, with `new_scores` of the same sessions
+/// under `new_rules`. A rule that's only in the new set counts its passes as fixed and
+/// its failures as regressed; one that was removed changes no outcomes.
+pub fn rule_set_impact(
+    old_rules: &[RuleDefinition],
+    new_rules: &[RuleDefinition],
+    old_scores: &[SessionScore],
+    new_scores: &[SessionScore],
+) -> RuleSetImpact {
+    let old_by_id: HashMap<&str, &RuleDefinition> = old_rules.iter().map(|rule| (rule.id.as_str(), rule)).collect();
+    let new_ids: Vec<&str> = new_rules.iter().map(|rule| rule.id.as_str()).collect();
+    let mut impact = RuleSetImpact {
+        added_rules: new_rules
+            .iter()
+            .filter(|rule| !old_by_id.contains_key(rule.id.as_str()))
+            .map(|rule| rule.id.clone())
+            .collect(),
+        removed_rules: old_rules
+            .iter()
+            .filter(|rule| !new_ids.contains(&rule.id.as_str()))
+            .map(|rule| rule.id.clone())
+            .collect(),
+        edited_rules: new_rules
+            .iter()
+            .filter(|rule| old_by_id.get(rule.id.as_str()).is_some_and(|old| old != rule))
+            .map(|rule| rule.id.clone())
+            .collect(),
+        ..RuleSetImpact::default()
+    };
+
+    let old_by_session: HashMap<&str, &SessionScore> =
+        old_scores.iter().map(|score| (score.session_id.as_str(), score)).collect();
+    let mut rules: BTreeMap<String, RuleImpact> = BTreeMap::new();
+    let (mut total_old, mut total_new) = (0.0, 0.0);
+    for new in new_scores {
+        let Some(old) = old_by_session.get(new.session_id.as_str()) else {
+            continue;
+        };
+        impact.sessions += 1;
+        total_old += old.score_percentage;
+        total_new += new.score_percentage;
+
+        let comparison = compare_scores(old, new);
+        let mut session = SessionImpact {
+            session_id: new.session_id.clone(),
+            percentage_old: old.score_percentage,
+            percentage_new: new.score_percentage,
+            percentage_delta: comparison.percentage_delta,
+            fixed: Vec::new(),
+            regressed: Vec::new(),
+        };
+        for change in comparison.differing {
+            let was_passing = change.a.as_ref().map(|outcome| outcome.passed);
+            let Some(now) = change.b else {
+                continue;
+            };
+            let rule = rules.entry(change.rule_id.clone()).or_insert_with(|| RuleImpact {
+                rule_id: change.rule_id.clone(),
+                rule_name: change.rule_name.clone(),
+                fixed: Vec::new(),
+                regressed: Vec::new(),
+            });
+            if now.passed && was_passing != Some(true) {
+                rule.fixed.push(new.session_id.clone());
+                session.fixed.push(change.rule_id);
+            } else if !now.passed && was_passing != Some(false) {
+                rule.regressed.push(new.session_id.clone());
+                session.regressed.push(change.rule_id);
+            }
+        }
+        if session.percentage_delta != 0.0 || !session.fixed.is_empty() || !session.regressed.is_empty() {
+            impact.changed_sessions.push(session);
+        }
+    }
+
+    if impact.sessions > 0 {
+        impact.average_old = total_old / impact.sessions as f64;
+        impact.average_new = total_new / impact.sessions as f64;
+    }
+    impact
+        .changed_sessions
+        .sort_by(|a, b| a.percentage_delta.total_cmp(&b.percentage_delta).then_with(|| a.session_id.cmp(&b.session_id)));
+    impact.changed_rules = rules.into_values().collect();
+    impact
+}
@@ -0,0 +1,169 @@
+//! Rules files: a `TrackerConfig` in TOML that the CLI scores with in place of the
+//! built-in rules. `behavior-scorer init` writes a commented starter to edit.
+
+use crate::{NormalizationMode, RuleDefinition, TrackerConfig};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashSet;
+use std::path::Path;
+
+/// Rules file name, in the app's config directory
+pub const RULES_FILE: &str = "rules.toml";
+
+const STARTER_HEADER: &str = "\
+# Rules for behavior-scorer, written by `behavior-scorer init`.
+#
+# Each [[rules]] table is one rule. A rule passes when its `pattern` (a Rust regex)
+# matches the transcript. `weight` is how much the rule counts toward the score,
+# and `category` is one of Startup, Response, Confidence, Safety, or Communication.
+# Edit, delete, or add rules freely; IDs must be unique.
+#
+# A rule can also list text it should pass and fail on, which
+# `behavior-scorer validate` checks:
+#   examples = { pass = [\"OBJECTIVE: ship it\"], fail = [\"Done.\"] }
+#
+# `normalization.mode` sets how weights combine into a percentage: \"raw\",
+# \"category_equal\", or \"winsorized\" with a `percentile` (e.g. 10.0).
+";
+
+/// `config` as a commented TOML rules file, each rule headed by its name
+pub fn starter_rules(config: &TrackerConfig) -> Result<String, String> {
+    let mut out = format!("{}\n[normalization]\n{}", STARTER_HEADER, to_toml(&config.normalization)?);
+    for rule in &config.rules {
+        out.push_str(&format!("\n# {}\n[[rules]]\n{}", rule.name, to_toml(rule)?));
+    }
+    Ok(out)
+}
+
+fn to_toml(value: &impl Serialize) -> Result<String, String> {
+    toml::to_string(value).map_err(|e| e.to_string())
+}
+
+/// Parse a TOML rules file
+pub fn parse_rules(content: &str) -> Result<TrackerConfig, String> {
+    let config: TrackerConfig = toml::from_str(content).map_err(|e| format!("Invalid rules file: {}", e))?;
+    let mut ids = HashSet::new();
+    if let Some(duplicate) = config.rules.iter().find(|rule| !ids.insert(rule.id.as_str())) {
+        return Err(format!("Invalid rules file: rule ID {} is used more than once", duplicate.id));
+    }
+    Ok(config)
+}
+
+/// Read the rules file at `path`
+pub fn load_rules(path: &Path) -> Result<TrackerConfig, String> {
+    let content = std::fs::read_to_string(path)
+        .map_err(|e| format!("Failed to read rules file {}: {}", path.display(), e))?;
+    parse_rules(&content)
+}
+
+/// Text a rule should pass and fail on, checked by `validate_rules`
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct RuleExamples {
+    /// Text the pattern must match
+    pub pass: Vec<String>,
+    /// Text the pattern must not match
+    pub fail: Vec<String>,
+}
+
+/// A rule as written in a rules file
+#[derive(Debug, Clone, Deserialize)]
+struct FileRule {
+    #[serde(flatten)]
+    rule: RuleDefinition,
+    #[serde(default)]
+    examples: RuleExamples,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct RulesFile {
+    #[serde(default)]
+    normalization: NormalizationMode,
+    rules: Vec<FileRule>,
+}
+
+/// A problem found by `validate_rules`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct RuleProblem {
+    /// Rule the problem is in, or `None` for the file as a whole
+    pub rule_id: Option<String>,
+    pub message: String,
+}
+
+/// Result of checking a rules file
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct RulesValidation {
+    /// Rules in the file
+    pub rules: usize,
+    /// Example texts checked against their rule's pattern
+    pub examples: usize,
+    pub problems: Vec<RuleProblem>,
+}
+
+impl RulesValidation {
+    pub fn is_ok(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+/// Check a TOML rules file: every rule must be valid (see `RuleDefinition::validate`)
+/// with a unique ID, its pattern must match each `pass` example and no `fail` example,
+/// and a winsorized percentile must be in range rather than silently clamped
+pub fn validate_rules(content: &str) -> RulesValidation {
+    let file: RulesFile = match toml::from_str(content) {
+        Ok(file) => file,
+        Err(e) => {
+            return RulesValidation {
+                problems: vec![RuleProblem {
+                    rule_id: None,
+                    message: format!("Invalid rules file: {}", e),
+                }],
+                ..RulesValidation::default()
+            }
+        }
+    };
+
+    let mut validation = RulesValidation {
+        rules: file.rules.len(),
+        ..RulesValidation::default()
+    };
+    if let NormalizationMode::Winsorized { percentile } = file.normalization {
+        if !(0.0..=50.0).contains(&percentile) {
+            validation.problems.push(RuleProblem {
+                rule_id: None,
+                message: "Winsorized percentile must be between 0 and 50".to_string(),
+            });
+        }
+    }
+    let mut ids = HashSet::new();
+    for FileRule { rule, examples } in &file.rules {
+        let mut problem = |message: String| {
+            validation.problems.push(RuleProblem {
+                rule_id: Some(rule.id.clone()),
+                message,
+            })
+        };
+        if !ids.insert(rule.id.as_str()) {
+            problem("Rule ID is used more than once".to_string());
+        }
+        for issue in rule.validate() {
+            problem(format!("{}: {}", issue.field, issue.message));
+        }
+        let Ok(regex) = Regex::new(&rule.pattern) else {
+            continue;
+        };
+        for text in &examples.pass {
+            if !regex.is_match(text) {
+                problem(format!("Should pass but doesn't match: {:?}", text));
+            }
+        }
+        for text in &examples.fail {
+            if regex.is_match(text) {
+                problem(format!("Should fail but matches: {:?}", text));
+            }
+        }
+        validation.examples += examples.pass.len() + examples.fail.len();
+    }
+    validation
+}
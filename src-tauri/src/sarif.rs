@@ -0,0 +1,118 @@
+//! SARIF 2.1.0 output, so code scanning tools can show failed rules as findings
+//! against the transcript files they came from.
+
+use crate::scan::read_transcript_file;
+use crate::{content_hash, RuleCategory, RuleDefinition, SessionScore, SCORER_VERSION};
+use serde_json::{json, Value};
+use std::path::Path;
+
+/// SARIF schema the log conforms to
+pub const SARIF_SCHEMA: &str = "https://json.schemastore.org/sarif-2.1.0.json";
+
+/// Findings for Safety rules are errors; the rest are warnings
+fn level(category: RuleCategory) -> &'static str {
+    match category {
+        RuleCategory::Safety => "error",
+        _ => "warning",
+    }
+}
+
+/// A SARIF log with one result per failed rule in `scores`. Transcript paths under
+/// `root` are written relative to it, as code scanning expects for files in a checkout.
+pub fn sarif_log(scores: &[SessionScore], rules: &[RuleDefinition], root: Option<&Path>) -> Value {
+    let descriptors: Vec<Value> = rules
+        .iter()
+        .map(|rule| {
+            json!({
+                "id": rule.id,
+                "name": rule.name,
+                "shortDescription": { "text": rule.name },
+                "fullDescription": { "text": rule.description },
+                "defaultConfiguration": { "level": level(rule.category) },
+                "properties": { "category": format!("{:?}", rule.category), "weight": rule.weight },
+            })
+        })
+        .collect();
+
+    let mut results = Vec::new();
+    for score in scores.iter().filter(|score| score.duplicate_of.is_none()) {
+        let source = score.source_path.as_deref();
+        // Only failures that carry evidence get a region, so only then is the file read
+        let located = score.rules.iter().any(|check| !check.passed && check.evidence.is_some());
+        let text = source
+            .filter(|_| located)
+            .and_then(|path| read_transcript_file(Path::new(path)).ok())
+            .map(|decoded| decoded.text);
+        for check in score.rules.iter().filter(|check| !check.passed) {
+            let rule_index = rules.iter().position(|rule| rule.id == check.rule_id);
+            let category = rule_index.map(|index| rules[index].category).unwrap_or(RuleCategory::Response);
+            let mut location = json!({
+                "logicalLocations": [{ "name": score.session_id, "kind": "module" }],
+            });
+            if let Some(path) = source {
+                let mut physical = json!({ "artifactLocation": { "uri": artifact_uri(Path::new(path), root) } });
+                let line = check.evidence.as_deref().zip(text.as_deref()).and_then(|(evidence, text)| line_of(text, evidence));
+                if let Some(line) = line {
+                    physical["region"] = json!({ "startLine": line });
+                }
+                location["physicalLocation"] = physical;
+            }
+            let mut message = format!("{} failed in session {}", check.rule_name, score.session_id);
+            if let Some(suggestion) = &check.suggestion {
+                message.push_str(&format!(". {}", suggestion));
+            }
+            let mut result = json!({
+                "ruleId": check.rule_id,
+                "level": level(category),
+                "message": { "text": message },
+                "locations": [location],
+                "partialFingerprints": {
+                    "sessionRule/v1": content_hash(&format!("{}\0{}", score.session_id, check.rule_id))[..16],
+                },
+                "properties": { "sessionId": score.session_id, "scorePercentage": score.score_percentage },
+            });
+            if let Some(index) = rule_index {
+                result["ruleIndex"] = json!(index);
+            }
+            results.push(result);
+        }
+    }
+
+    json!({
+        "$schema": SARIF_SCHEMA,
+        "version": "2.1.0",
+        "runs": [{
+            "tool": {
+                "driver": {
+                    "name": "behavior-scorer",
+                    "version": SCORER_VERSION,
+                    "rules": descriptors,
+                }
+            },
+            "results": results,
+        }]
+    })
+}
+
+/// `path` as a URI reference, relative to `root` when it's inside it
+fn artifact_uri(path: &Path, root: Option<&Path>) -> String {
+    let relative = root.and_then(|root| path.strip_prefix(root).ok()).unwrap_or(path);
+    let mut uri = String::new();
+    for byte in relative.to_string_lossy().replace('\\', "/").bytes() {
+        if byte.is_ascii_alphanumeric() || b"-._~/!:".contains(&byte) {
+            uri.push(byte as char);
+        } else {
+            uri.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    uri
+}
+
+/// 1-based line where `evidence` (a line of the transcript, possibly truncated) starts
+fn line_of(text: &str, evidence: &str) -> Option<usize> {
+    let snippet = evidence.strip_suffix("...").unwrap_or(evidence);
+    if snippet.is_empty() {
+        return None;
+    }
+    text.find(snippet).map(|start| text[..start].matches('\n').count() + 1)
+}
@@ -0,0 +1,916 @@
+//! Directory scanning: discovers session files under the allowed base path,
+//! assembles multi-part sessions, and scores each session.
+
+use crate::anomaly::{self, AnomalyThresholds};
+use crate::db::{Database, DbError, Outcomes, ScannedFile};
+use crate::encoding::{self, Decoded};
+use crate::error::InvalidPath;
+use crate::session_id::SessionIdStrategy;
+use crate::{content_hash, BehaviorScorer, SessionScore, SCORER_VERSION};
+use glob::Pattern;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::UNIX_EPOCH;
+use walkdir::WalkDir;
+
+/// Default grouping for split sessions: `session-x-part1.md`, `session-x_part_2.md`, ...
+pub const DEFAULT_PART_PATTERN: &str = r"(?i)^(?P<base>.+?)[-_. ]?part[-_ ]?(?P<part>\d+)$";
+
+/// Maximum size of a single session file as read (or decompressed). This is above the
+/// 10MB transcript limit because embedded blobs are stripped before that is checked.
+pub const MAX_FILE_SIZE: u64 = 40 * 1024 * 1024; // 40MB
+
+/// Maximum size of a single session file for a scorer that evaluates in chunks,
+/// matching its 100MB transcript limit
+pub const MAX_CHUNKED_FILE_SIZE: u64 = 100 * 1024 * 1024; // 100MB
+
+/// Compression suffixes that are decompressed transparently
+const COMPRESSED_EXTENSIONS: [&str; 2] = ["gz", "zst"];
+
+/// Directory levels walked by default
+pub const DEFAULT_MAX_DEPTH: usize = 2;
+
+/// Transcript extensions picked up by default
+pub const DEFAULT_EXTENSIONS: [&str; 3] = ["md", "json", "jsonl"];
+
+/// What a scan does with sessions whose content matches an earlier session
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum DuplicatePolicy {
+    /// Drop the duplicate from the results
+    Skip,
+    /// Keep the duplicate, with `duplicate_of` naming the first session
+    #[default]
+    Link,
+    /// Keep every session as-is
+    Keep,
+}
+
+/// Options controlling a directory scan
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct ScanOptions {
+    /// Regex matched against file stems to group multi-part sessions. Must define
+    /// `base` and `part` captures; `None` scores every file on its own.
+    pub part_pattern: Option<String>,
+    /// Globs matched against paths relative to the scanned directory; empty includes everything
+    pub include: Vec<String>,
+    /// Globs for paths to skip, applied after `include`
+    pub exclude: Vec<String>,
+    /// Maximum recursion depth below the scanned directory
+    pub max_depth: usize,
+    /// Transcript extensions to score, without the dot or compression suffix
+    pub extensions: Vec<String>,
+    /// Handling of sessions with identical content (e.g. the same log in two synced folders)
+    pub duplicates: DuplicatePolicy,
+    /// How session IDs are derived from files
+    pub session_ids: SessionIdStrategy,
+    /// Agent the sessions come from, e.g. `codex`; picks the rule profile from the
+    /// settings' `source_profiles`
+    pub source: Option<String>,
+    /// Sessions scored at once; 1 scores them one after another
+    pub jobs: usize,
+}
+
+impl ScanOptions {
+    /// Session source to record for scores this scan stores: the agent, if given, or `default`
+    pub fn source_or<'a>(&'a self, default: &'a str) -> &'a str {
+        self.source.as_deref().unwrap_or(default)
+    }
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            part_pattern: Some(DEFAULT_PART_PATTERN.to_string()),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            max_depth: DEFAULT_MAX_DEPTH,
+            extensions: DEFAULT_EXTENSIONS.iter().map(|e| e.to_string()).collect(),
+            duplicates: DuplicatePolicy::default(),
+            session_ids: SessionIdStrategy::default(),
+            source: None,
+            jobs: 1,
+        }
+    }
+}
+
+/// Progress reported while a directory scan runs
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScanEvent {
+    /// Transcript files found and the sessions they form, before any are scored
+    Discovered { files: usize, sessions: usize },
+    /// A file left out of the scan, e.g. for being over the size limit
+    Skipped { path: String, reason: String },
+    /// A session was scored; `done` counts sessions handled so far
+    Scored {
+        done: usize,
+        total: usize,
+        session_id: String,
+        score_percentage: f64,
+    },
+    /// A session couldn't be read or scored
+    Failed {
+        done: usize,
+        total: usize,
+        session: String,
+        error: String,
+    },
+}
+
+/// Outcome of a directory scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct ScanSummary {
+    /// Scores after the duplicate policy is applied
+    pub scores: Vec<SessionScore>,
+    /// Transcript files found
+    pub files: usize,
+    /// Sessions those files formed
+    pub sessions: usize,
+    /// Files left out of the scan
+    pub skipped: usize,
+    /// Sessions dropped as duplicates of an earlier one
+    pub duplicates: usize,
+    /// Sessions that couldn't be read or scored, as `session: reason`
+    pub failed: Vec<String>,
+    /// Why files were left out, for showing alongside the scores
+    pub report: ScanReport,
+}
+
+/// A file a scan couldn't use, and why
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize, Type)]
+pub struct ScanIssue {
+    pub path: String,
+    pub reason: String,
+}
+
+/// What a scan did with the transcript files it found
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct ScanReport {
+    /// Transcript files found, including those left out
+    pub files_seen: usize,
+    /// Sessions scored, before duplicates are dropped
+    pub scored: usize,
+    /// Files over `MAX_FILE_SIZE`, which are never read
+    pub too_large: Vec<ScanIssue>,
+    /// Sessions that couldn't be read, decoded, or parsed, under their first file
+    pub parse_failures: Vec<ScanIssue>,
+}
+
+impl ScanReport {
+    /// Every file left out, too large or failed, as `path: reason`
+    pub fn issues(&self) -> impl Iterator<Item = String> + '_ {
+        self.too_large
+            .iter()
+            .chain(&self.parse_failures)
+            .map(|issue| format!("{}: {}", issue.path, issue.reason))
+    }
+
+    /// A report for `files` usable files plus those discovery left out
+    fn discovered(files: usize, skipped: &[SkippedFile]) -> Self {
+        ScanReport {
+            files_seen: files + skipped.len(),
+            too_large: skipped
+                .iter()
+                .map(|file| ScanIssue {
+                    path: file.path.to_string_lossy().to_string(),
+                    reason: file.reason.clone(),
+                })
+                .collect(),
+            ..ScanReport::default()
+        }
+    }
+
+    /// Record a session that couldn't be read or parsed, under its first file
+    fn parse_failed(&mut self, session: &SessionFiles, reason: String) {
+        self.parse_failures.push(ScanIssue {
+            path: session.paths[0].to_string_lossy().to_string(),
+            reason,
+        });
+    }
+}
+
+/// Outcome of an incremental scan
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+pub struct IncrementalScan {
+    /// Scores after the duplicate policy is applied, stored or new
+    pub scores: Vec<SessionScore>,
+    /// Sessions scored on this pass because they were new or had changed
+    pub rescored: Vec<String>,
+    /// Files left out of this pass; `scored` counts only sessions scored on it
+    pub report: ScanReport,
+}
+
+/// A file left out during discovery
+struct SkippedFile {
+    path: PathBuf,
+    reason: String,
+}
+
+/// Parts collected for one (directory, base name) pair
+type PartGroup = (PathBuf, String, Vec<(u64, PathBuf)>);
+
+/// Scores from one session's files, and errors reading or scoring them
+#[derive(Debug, Default)]
+struct SessionResult {
+    scores: Vec<SessionScore>,
+    errors: Vec<String>,
+    /// Hash of each file's decoded text, in part order, for the scan cache
+    content_hashes: Vec<String>,
+}
+
+/// The files that make up one session, in part order
+#[derive(Debug)]
+struct SessionFiles {
+    /// File stem or multi-part base name, before ID derivation
+    name: String,
+    paths: Vec<PathBuf>,
+}
+
+impl BehaviorScorer {
+    /// SECURITY: Scan directory for session logs with path validation
+    pub fn scan_with_options(
+        &self,
+        dir_path: &Path,
+        options: &ScanOptions,
+    ) -> Result<Vec<SessionScore>, String> {
+        let summary = self.scan_with_progress(dir_path, options, log_scan_event)?;
+        Ok(summary.scores)
+    }
+    
+    /// SECURITY: Scan directory for session logs, calling `on_event` as files are found
+    /// and each session is scored
+    pub fn scan_with_progress(
+        &self,
+        dir_path: &Path,
+        options: &ScanOptions,
+        mut on_event: impl FnMut(&ScanEvent),
+    ) -> Result<ScanSummary, String> {
+        let (sessions, skipped) = self.discover_sessions(dir_path, options)?;
+        let total = sessions.len();
+        let mut summary = ScanSummary {
+            files: sessions.iter().map(|session| session.paths.len()).sum(),
+            sessions: total,
+            skipped: skipped.len(),
+            ..ScanSummary::default()
+        };
+        summary.report = ScanReport::discovered(summary.files, &skipped);
+        on_event(&ScanEvent::Discovered {
+            files: summary.files,
+            sessions: total,
+        });
+        for file in &summary.report.too_large {
+            on_event(&ScanEvent::Skipped {
+                path: file.path.clone(),
+                reason: file.reason.clone(),
+            });
+        }
+        
+        let mut done = 0;
+        let results = self.score_sessions(&sessions, options.session_ids, options.jobs, |index, result| {
+            done += 1;
+            report_session(&mut on_event, done, total, &sessions[index].name, result);
+        });
+        let mut scores = Vec::new();
+        for (session, result) in sessions.iter().zip(results) {
+            for error in result.errors {
+                summary.failed.push(format!("{}: {}", session.name, error));
+                summary.report.parse_failed(session, error);
+            }
+            scores.extend(result.scores);
+        }
+        
+        let found = scores.len();
+        summary.report.scored = found;
+        summary.scores = apply_duplicate_policy(scores, options.duplicates);
+        summary.duplicates = found - summary.scores.len();
+        Ok(summary)
+    }
+    
+    /// SECURITY: Incremental scan that only scores new or changed sessions, returning
+    /// stored scores for sessions whose files (and the rule set) are unchanged
+    pub async fn scan_incremental(
+        &self,
+        db: &Database,
+        dir_path: &Path,
+        options: &ScanOptions,
+    ) -> Result<Vec<SessionScore>, String> {
+        let scan = self.scan_incremental_changes(db, dir_path, options).await?;
+        for issue in scan.report.too_large.iter().chain(&scan.report.parse_failures) {
+            tracing::warn!(path = %issue.path, reason = %issue.reason, "Skipping file");
+        }
+        Ok(scan.scores)
+    }
+    
+    /// SECURITY: Incremental scan that also reports which sessions were scored on this pass
+    pub async fn scan_incremental_changes(
+        &self,
+        db: &Database,
+        dir_path: &Path,
+        options: &ScanOptions,
+    ) -> Result<IncrementalScan, String> {
+        self.scan_incremental_with_progress(db, dir_path, options, |_| {}).await
+    }
+    
+    /// SECURITY: Like `scan_incremental_changes`, calling `on_event` as files are found and
+    /// each session is handled. Unchanged sessions report their stored scores as `Scored`.
+    pub async fn scan_incremental_with_progress(
+        &self,
+        db: &Database,
+        dir_path: &Path,
+        options: &ScanOptions,
+        mut on_event: impl FnMut(&ScanEvent),
+    ) -> Result<IncrementalScan, String> {
+        let (sessions, skipped) = self.discover_sessions(dir_path, options)?;
+        let files = sessions.iter().map(|session| session.paths.len()).sum();
+        let total = sessions.len();
+        let mut report = ScanReport::discovered(files, &skipped);
+        on_event(&ScanEvent::Discovered { files, sessions: total });
+        for file in &report.too_large {
+            on_event(&ScanEvent::Skipped {
+                path: file.path.clone(),
+                reason: file.reason.clone(),
+            });
+        }
+        
+        // Stored scores for unchanged sessions, in scan order; `None` marks a changed one
+        let mut slots = Vec::new();
+        let mut changed = Vec::new();
+        let mut done = 0;
+        for session in sessions {
+            let source = session.paths[0].to_string_lossy().to_string();
+            let stored = self
+                .unchanged_scores(db, &session, &source, options.session_ids)
+                .await
+                .map_err(|e| e.to_string())?;
+            match stored {
+                Some(stored) => {
+                    done += 1;
+                    let result = SessionResult {
+                        scores: stored,
+                        ..SessionResult::default()
+                    };
+                    report_session(&mut on_event, done, total, &session.name, &result);
+                    slots.push(Some(result.scores));
+                }
+                None => {
+                    changed.push(session);
+                    slots.push(None);
+                }
+            }
+        }
+        let results = self.score_sessions(&changed, options.session_ids, options.jobs, |index, result| {
+            done += 1;
+            report_session(&mut on_event, done, total, &changed[index].name, result);
+        });
+        let mut results = changed.iter().zip(results);
+        let baselines = db.get_rule_baselines(Outcomes::Raw).await.map_err(|e| e.to_string())?;
+        
+        let mut scores = Vec::new();
+        let mut rescored = Vec::new();
+        for slot in slots {
+            if let Some(stored) = slot {
+                scores.extend(stored);
+                continue;
+            }
+            let Some((session, result)) = results.next() else {
+                break;
+            };
+            let source = session.paths[0].to_string_lossy().to_string();
+            for error in result.errors {
+                report.parse_failed(session, error);
+            }
+            let mut session_scores = result.scores;
+            for score in &mut session_scores {
+                anomaly::flag_anomalies(score, &baselines, &AnomalyThresholds::default());
+            }
+            let Some(score) = session_scores.first() else {
+                continue;
+            };
+            let files: Vec<ScannedFile> = session
+                .paths
+                .iter()
+                .zip(result.content_hashes)
+                .filter_map(|(path, content_hash)| {
+                    let stamp = FileStamp::read(path)?;
+                    Some(ScannedFile {
+                        path: path.to_string_lossy().to_string(),
+                        source_path: source.clone(),
+                        session_id: score.session_id.clone(),
+                        mtime_ms: stamp.mtime_ms,
+                        size: stamp.size,
+                        content_hash,
+                    })
+                })
+                .collect();
+            db.replace_scanned_files(&source, &files).await.map_err(|e| e.to_string())?;
+            // Files are tracked under the first session; later sessions in a multi-session
+            // file are mapped from `path#1`, `path#2`, ...
+            db.remove_session_id_mappings(&source).await.map_err(|e| e.to_string())?;
+            for (index, score) in session_scores.iter().enumerate() {
+                db.save_session_id_mapping(&part_source(&source, index), &score.session_id, options.session_ids)
+                    .await
+                    .map_err(|e| e.to_string())?;
+                db.save_scan_result(&part_source(&source, index), score).await.map_err(|e| e.to_string())?;
+            }
+            rescored.extend(session_scores.iter().map(|score| score.session_id.clone()));
+            scores.extend(session_scores);
+        }
+        
+        report.scored = rescored.len();
+        Ok(IncrementalScan {
+            scores: apply_duplicate_policy(scores, options.duplicates),
+            rescored,
+            report,
+        })
+    }
+    
+    /// The stored scores for a session's files, if its parts, ID strategy, and the rule set
+    /// haven't changed since it was scored. Files whose mtime moved but whose content is
+    /// identical count as unchanged, and their new mtime is recorded.
+    async fn unchanged_scores(
+        &self,
+        db: &Database,
+        session: &SessionFiles,
+        source: &str,
+        strategy: SessionIdStrategy,
+    ) -> Result<Option<Vec<SessionScore>>, DbError> {
+        let Some(mapping) = db.get_session_id_mapping(source).await? else {
+            return Ok(None);
+        };
+        if mapping.strategy != strategy.as_str() {
+            return Ok(None);
+        }
+        
+        let tracked = db.get_scanned_files(source).await?;
+        let mut tracked_paths: Vec<&str> = tracked.iter().map(|f| f.path.as_str()).collect();
+        let mut current_paths: Vec<String> = session.paths.iter().map(|p| p.to_string_lossy().to_string()).collect();
+        tracked_paths.sort_unstable();
+        current_paths.sort_unstable();
+        if tracked_paths != current_paths {
+            return Ok(None);
+        }
+        
+        let mut stored = Vec::new();
+        let mut next = Some(mapping);
+        while let Some(mapping) = next {
+            if mapping.strategy != strategy.as_str() {
+                return Ok(None);
+            }
+            let Some(score) = db.get_scan_result(&part_source(source, stored.len())).await? else {
+                return Ok(None);
+            };
+            if score.rule_set_hash != self.rule_set_hash || score.scorer_version != SCORER_VERSION {
+                return Ok(None);
+            }
+            stored.push(score);
+            next = db.get_session_id_mapping(&part_source(source, stored.len())).await?;
+        }
+        
+        for file in &tracked {
+            let path = Path::new(&file.path);
+            let Some(stamp) = FileStamp::read(path) else {
+                return Ok(None);
+            };
+            if stamp.mtime_ms == file.mtime_ms && stamp.size == file.size {
+                continue;
+            }
+            let Ok(decoded) = read_transcript_file_within(path, self.max_file_size()) else {
+                return Ok(None);
+            };
+            if content_hash(&decoded.text) != file.content_hash {
+                return Ok(None);
+            }
+            db.touch_scanned_file(&file.path, stamp.mtime_ms, stamp.size).await?;
+        }
+        
+        Ok(Some(stored))
+    }
+    
+    /// SECURITY: Find session files under a validated directory, grouped into sessions,
+    /// along with the files left out
+    fn discover_sessions(
+        &self,
+        dir_path: &Path,
+        options: &ScanOptions,
+    ) -> Result<(Vec<SessionFiles>, Vec<SkippedFile>), String> {
+        self.validate_directory(dir_path)?;
+        
+        let part_pattern = options
+            .part_pattern
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| format!("Invalid part pattern: {}", e))?;
+        let include = compile_globs(&options.include)?;
+        let exclude = compile_globs(&options.exclude)?;
+        
+        let mut files = Vec::new();
+        let mut skipped = Vec::new();
+        for entry in WalkDir::new(dir_path).max_depth(options.max_depth).into_iter().flatten() {
+            if !entry.file_type().is_file() {
+                continue;
+            }
+            let relative = entry.path().strip_prefix(dir_path).unwrap_or(entry.path());
+            if !include.is_empty() && !include.iter().any(|g| g.matches_path(relative)) {
+                continue;
+            }
+            if exclude.iter().any(|g| g.matches_path(relative)) {
+                continue;
+            }
+            if has_transcript_extension(entry.path(), &options.extensions) {
+                // SECURITY: Validate file size before reading
+                if let Ok(metadata) = fs::metadata(entry.path()) {
+                    if metadata.len() > self.max_file_size() {
+                        skipped.push(SkippedFile {
+                            path: entry.into_path(),
+                            reason: format!("Larger than {} bytes", self.max_file_size()),
+                        });
+                        continue;
+                    }
+                }
+                files.push(entry.into_path());
+            }
+        }
+        files.sort();
+        
+        Ok((group_parts(files, part_pattern.as_ref()), skipped))
+    }
+    
+    /// Score `sessions` on up to `jobs` threads, returning each one's scores and errors in
+    /// input order. `on_scored` runs on the calling thread as each session finishes.
+    fn score_sessions(
+        &self,
+        sessions: &[SessionFiles],
+        strategy: SessionIdStrategy,
+        jobs: usize,
+        mut on_scored: impl FnMut(usize, &SessionResult),
+    ) -> Vec<SessionResult> {
+        let jobs = jobs.clamp(1, sessions.len().max(1));
+        if jobs == 1 {
+            return sessions
+                .iter()
+                .enumerate()
+                .map(|(index, session)| {
+                    let result = self.score_session_files(session, strategy);
+                    on_scored(index, &result);
+                    result
+                })
+                .collect();
+        }
+        
+        let next = AtomicUsize::new(0);
+        let mut results: Vec<Option<SessionResult>> = sessions.iter().map(|_| None).collect();
+        thread::scope(|scope| {
+            let (tx, rx) = mpsc::channel();
+            for _ in 0..jobs {
+                let tx = tx.clone();
+                let next = &next;
+                scope.spawn(move || loop {
+                    let index = next.fetch_add(1, Ordering::Relaxed);
+                    let Some(session) = sessions.get(index) else {
+                        break;
+                    };
+                    if tx.send((index, self.score_session_files(session, strategy))).is_err() {
+                        break;
+                    }
+                });
+            }
+            drop(tx);
+            for (index, result) in rx {
+                on_scored(index, &result);
+                results[index] = Some(result);
+            }
+        });
+        results.into_iter().map(Option::unwrap_or_default).collect()
+    }
+    
+    /// Read and score one session's files with the parser registered for their extension,
+    /// returning the scores and any read or scoring errors. Files the parser splits into
+    /// several sessions, like multi-session JSONL, produce one score per session.
+    fn score_session_files(&self, session: &SessionFiles, strategy: SessionIdStrategy) -> SessionResult {
+        let (content, warnings, content_hashes) = match read_parts(&session.paths, self.max_file_size()) {
+            Ok(parts) => parts,
+            Err(e) => {
+                return SessionResult {
+                    errors: vec![e],
+                    ..SessionResult::default()
+                }
+            }
+        };
+        let path = transcript_path(&session.paths[0]);
+        
+        let parser = self.parsers.for_extension(path.extension().and_then(|ext| ext.to_str()));
+        let results = match parser.split_sessions(&content) {
+            Some(parts) => parts
+                .iter()
+                .enumerate()
+                .map(|(index, part)| {
+                    let session_id = strategy.derive_part(&session.name, index, part);
+                    self.score_text(&session_id, &part.content, Some(part.format))
+                })
+                .collect(),
+            None => {
+                let session_id = strategy.derive(&session.name, &path, &content);
+                vec![self.score_parsed_content(&session_id, parser, &content)]
+            }
+        };
+        
+        let mut scores = Vec::new();
+        let mut errors = Vec::new();
+        for result in results {
+            match result {
+                Ok(mut score) => {
+                    score.warnings.extend(warnings.iter().cloned());
+                    score.source_path = Some(session.paths[0].to_string_lossy().to_string());
+                    scores.push(score);
+                }
+                Err(e) => errors.push(e),
+            }
+        }
+        SessionResult {
+            scores,
+            errors,
+            content_hashes,
+        }
+    }
+    
+    /// Score a single transcript file, deriving its ID from the file stem or content
+    pub(crate) fn score_path(&self, path: &Path, strategy: SessionIdStrategy) -> Result<SessionScore, String> {
+        let decoded = read_transcript_file_within(path, self.max_file_size())?;
+        let logical = transcript_path(path);
+        let name = logical.file_stem().unwrap_or_default().to_string_lossy();
+        let session_id = strategy.derive(&name, &logical, &decoded.text);
+        let mut score = self.score_file_content(&session_id, &logical, &decoded.text)?;
+        score.warnings.extend(decoded.warning);
+        score.source_path = Some(path.to_string_lossy().to_string());
+        Ok(score)
+    }
+    
+    /// Largest session file this scorer reads: raised to `MAX_CHUNKED_FILE_SIZE` when it
+    /// evaluates in chunks
+    pub fn max_file_size(&self) -> u64 {
+        if self.chunking.is_some() {
+            MAX_CHUNKED_FILE_SIZE
+        } else {
+            MAX_FILE_SIZE
+        }
+    }
+    
+    /// SECURITY: Ensure a directory is within the allowed base path
+    pub fn validate_directory(&self, dir_path: &Path) -> Result<(), InvalidPath> {
+        let canonical_base = self.base_path.canonicalize()
+            .map_err(|e| InvalidPath(format!("Invalid base path: {}", e)))?;
+        
+        let canonical_dir = dir_path.canonicalize()
+            .map_err(|e| InvalidPath(format!("Invalid directory path: {}", e)))?;
+        
+        if !canonical_dir.starts_with(&canonical_base) {
+            return Err(InvalidPath("Directory path is outside allowed base path".to_string()));
+        }
+        
+        Ok(())
+    }
+    
+    /// SECURITY: Ensure a file about to be read exists within the allowed base path,
+    /// returning its canonical path
+    pub fn validate_input_file(&self, path: &Path) -> Result<PathBuf, InvalidPath> {
+        let canonical_base = self.base_path.canonicalize()
+            .map_err(|e| InvalidPath(format!("Invalid base path: {}", e)))?;
+        let canonical = path.canonicalize()
+            .map_err(|e| InvalidPath(format!("Invalid file path: {}", e)))?;
+        
+        if !canonical.starts_with(&canonical_base) {
+            return Err(InvalidPath("File path is outside allowed base path".to_string()));
+        }
+        if !canonical.is_file() {
+            return Err(InvalidPath("Not a file".to_string()));
+        }
+        Ok(canonical)
+    }
+    
+    /// SECURITY: Ensure a file about to be written is in an existing directory within the
+    /// allowed base path, returning its path with the directory canonicalized
+    pub fn validate_output_file(&self, path: &Path) -> Result<PathBuf, InvalidPath> {
+        let name = path.file_name().ok_or_else(|| InvalidPath("Output path has no file name".to_string()))?;
+        let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+        self.validate_directory(parent)?;
+        
+        let target = parent.canonicalize().map_err(|e| InvalidPath(format!("Invalid directory path: {}", e)))?.join(name);
+        if target.is_dir() {
+            return Err(InvalidPath("Output path is a directory".to_string()));
+        }
+        Ok(target)
+    }
+}
+
+/// Whether a file's transcript extension (ignoring any compression suffix) is in `extensions`
+pub fn has_transcript_extension(path: &Path, extensions: &[String]) -> bool {
+    let Some(ext) = transcript_path(path).extension().and_then(|e| e.to_str()).map(str::to_string) else {
+        return false;
+    };
+    extensions.iter().any(|allowed| allowed.trim_start_matches('.').eq_ignore_ascii_case(&ext))
+}
+
+/// Compile glob patterns, reporting the first invalid one
+pub(crate) fn compile_globs(patterns: &[String]) -> Result<Vec<Pattern>, String> {
+    patterns
+        .iter()
+        .map(|p| Pattern::new(p).map_err(|e| format!("Invalid glob '{}': {}", p, e)))
+        .collect()
+}
+
+/// Skip or link sessions whose content matches an earlier session in the list
+fn apply_duplicate_policy(scores: Vec<SessionScore>, policy: DuplicatePolicy) -> Vec<SessionScore> {
+    if policy == DuplicatePolicy::Keep {
+        return scores;
+    }
+    
+    let mut seen: HashMap<String, String> = HashMap::new();
+    let mut kept = Vec::with_capacity(scores.len());
+    for mut score in scores {
+        if let Some(original) = seen.get(&score.content_hash) {
+            if policy == DuplicatePolicy::Skip {
+                continue;
+            }
+            score.duplicate_of = Some(original.clone());
+        } else {
+            seen.insert(score.content_hash.clone(), score.session_id.clone());
+            score.duplicate_of = None;
+        }
+        kept.push(score);
+    }
+    kept
+}
+
+/// Mapping key for the session at `index` in a source file
+fn part_source(source: &str, index: usize) -> String {
+    if index == 0 {
+        source.to_string()
+    } else {
+        format!("{}#{}", source, index)
+    }
+}
+
+/// Modification time and size, used to skip unchanged files without reading them
+struct FileStamp {
+    mtime_ms: i64,
+    size: i64,
+}
+
+impl FileStamp {
+    fn read(path: &Path) -> Option<Self> {
+        let metadata = fs::metadata(path).ok()?;
+        let mtime = metadata.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+        Some(Self {
+            mtime_ms: mtime.as_millis() as i64,
+            size: metadata.len() as i64,
+        })
+    }
+}
+
+/// Group files whose stems match `pattern` by directory and `base`, ordered by `part`.
+/// Files that don't match are their own session, named by their transcript file stem.
+fn group_parts(files: Vec<PathBuf>, pattern: Option<&Regex>) -> Vec<SessionFiles> {
+    let mut sessions: Vec<SessionFiles> = Vec::new();
+    let mut grouped: Vec<PartGroup> = Vec::new();
+    
+    for path in files {
+        let part = pattern.and_then(|pattern| {
+            let stem = transcript_path(&path).file_stem()?.to_str()?.to_string();
+            let caps = pattern.captures(&stem)?;
+            Some((caps.name("base")?.as_str().to_string(), caps.name("part")?.as_str().parse::<u64>().ok()?))
+        });
+        
+        match part {
+            Some((base, number)) => {
+                let dir = path.parent().map(Path::to_path_buf).unwrap_or_default();
+                match grouped.iter_mut().find(|(d, b, _)| *d == dir && *b == base) {
+                    Some((_, _, parts)) => parts.push((number, path)),
+                    None => grouped.push((dir, base, vec![(number, path)])),
+                }
+            }
+            None => sessions.push(SessionFiles {
+                name: transcript_path(&path).file_stem().unwrap_or_default().to_string_lossy().to_string(),
+                paths: vec![path],
+            }),
+        }
+    }
+    
+    for (_, base, mut parts) in grouped {
+        parts.sort_by_key(|(number, _)| *number);
+        sessions.push(SessionFiles {
+            name: base,
+            paths: parts.into_iter().map(|(_, path)| path).collect(),
+        });
+    }
+    
+    sessions
+}
+
+/// Path with any compression suffix removed, which selects the transcript parser
+pub fn transcript_path(path: &Path) -> PathBuf {
+    match path.extension().and_then(|e| e.to_str()) {
+        Some(ext) if COMPRESSED_EXTENSIONS.contains(&ext) => path.with_extension(""),
+        _ => path.to_path_buf(),
+    }
+}
+
+/// Report a handled session's scores and errors to `on_event`
+fn report_session(on_event: &mut impl FnMut(&ScanEvent), done: usize, total: usize, name: &str, result: &SessionResult) {
+    for score in &result.scores {
+        on_event(&ScanEvent::Scored {
+            done,
+            total,
+            session_id: score.session_id.clone(),
+            score_percentage: score.score_percentage,
+        });
+    }
+    for error in &result.errors {
+        on_event(&ScanEvent::Failed {
+            done,
+            total,
+            session: name.to_string(),
+            error: error.clone(),
+        });
+    }
+}
+
+/// Log a scan event: discovery at info, scored sessions at debug, and skipped files and
+/// failed sessions as warnings
+pub(crate) fn log_scan_event(event: &ScanEvent) {
+    match event {
+        ScanEvent::Discovered { files, sessions } => tracing::info!(files, sessions, "Discovered transcripts"),
+        ScanEvent::Skipped { path, reason } => tracing::warn!(path = %path, reason = %reason, "Skipping file"),
+        ScanEvent::Scored { done, total, session_id, score_percentage } => {
+            tracing::debug!(done, total, session = %session_id, score = score_percentage, "Scored session")
+        }
+        ScanEvent::Failed { done, total, session, error } => {
+            tracing::warn!(done, total, session = %session, error = %error, "Failed to score session")
+        }
+    }
+}
+
+/// SECURITY: Read a session file, decompressing `.gz` and `.zst` archives.
+/// Decompressed content is held to the same size limit as plain files, and
+/// non-UTF-8 text is decoded with a warning rather than rejected.
+pub fn read_transcript_file(path: &Path) -> Result<Decoded, String> {
+    read_transcript_file_within(path, MAX_FILE_SIZE)
+}
+
+/// Read a session file as `read_transcript_file` does, with a size limit of `limit` bytes
+pub fn read_transcript_file_within(path: &Path, limit: u64) -> Result<Decoded, String> {
+    let file = fs::File::open(path).map_err(|e| format!("Failed to open {}: {}", path.display(), e))?;
+    let reader: Box<dyn Read> = match path.extension().and_then(|e| e.to_str()) {
+        Some("gz") => Box::new(flate2::read::MultiGzDecoder::new(file)),
+        Some("zst") => Box::new(
+            zstd::stream::read::Decoder::new(file).map_err(|e| format!("Invalid zstd stream: {}", e))?,
+        ),
+        _ => Box::new(file),
+    };
+    
+    let mut bytes = Vec::new();
+    reader
+        .take(limit + 1)
+        .read_to_end(&mut bytes)
+        .map_err(|e| format!("Failed to read {}: {}", path.display(), e))?;
+    if bytes.len() as u64 > limit {
+        return Err(format!("Transcript file exceeds maximum size of {}MB", limit / (1024 * 1024)));
+    }
+    
+    Ok(encoding::decode(bytes))
+}
+
+/// Read and concatenate session parts, failing if any part is unreadable, along with
+/// decoding warnings and each part's content hash. Warnings for multi-part sessions
+/// name the part they came from.
+fn read_parts(paths: &[PathBuf], limit: u64) -> Result<(String, Vec<String>, Vec<String>), String> {
+    let mut content = String::new();
+    let mut warnings = Vec::new();
+    let mut hashes = Vec::with_capacity(paths.len());
+    for path in paths {
+        let part = read_transcript_file_within(path, limit)?;
+        hashes.push(content_hash(&part.text));
+        if let Some(warning) = part.warning {
+            if paths.len() > 1 {
+                warnings.push(format!("{}: {}", path.file_name().unwrap_or_default().to_string_lossy(), warning));
+            } else {
+                warnings.push(warning);
+            }
+        }
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push('\n');
+        }
+        content.push_str(&part.text);
+    }
+    Ok((content, warnings, hashes))
+}
@@ -0,0 +1,64 @@
+//! Scheduled background scans: incremental scans of the configured session
+//! directories, run by the app at the interval set in its settings.
+
+use crate::db::{Database, DbError};
+use crate::scan::ScanOptions;
+use crate::{BehaviorScorer, SessionScore};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::path::Path;
+
+/// Session source recorded for scores stored by scheduled scans
+pub const SCHEDULED_SOURCE: &str = "scheduled";
+
+/// Outcome of one scheduled pass over the scan directories
+#[derive(Debug, Clone, Serialize, Deserialize, Type)]
+pub struct ScheduledScan {
+    pub scanned_at: DateTime<Utc>,
+    pub directories: usize,
+    /// Scores stored for sessions that were new or had changed
+    pub new_scores: Vec<SessionScore>,
+    /// Directories that couldn't be scanned, files that couldn't be parsed, and sessions
+    /// that couldn't be stored, as `directory: reason`, `path: reason`, or `session: reason`
+    pub failed: Vec<String>,
+}
+
+impl BehaviorScorer {
+    /// Incrementally scan each directory, storing a score for each new or changed
+    /// session. A directory that can't be scanned is reported and the others still run.
+    pub async fn scheduled_scan(&self, db: &Database, directories: &[String], options: &ScanOptions) -> ScheduledScan {
+        let mut report = ScheduledScan {
+            scanned_at: Utc::now(),
+            directories: directories.len(),
+            new_scores: Vec::new(),
+            failed: Vec::new(),
+        };
+        for directory in directories {
+            let scan = match self.scan_incremental_changes(db, Path::new(directory), options).await {
+                Ok(scan) => scan,
+                Err(e) => {
+                    report.failed.push(format!("{}: {}", directory, e));
+                    continue;
+                }
+            };
+            report.failed.extend(scan.report.parse_failures.iter().map(|issue| format!("{}: {}", issue.path, issue.reason)));
+            for score in scan.scores.iter().filter(|score| scan.rescored.contains(&score.session_id)) {
+                match store_scheduled_score(db, score, options.source_or(SCHEDULED_SOURCE)).await {
+                    Ok(()) => report.new_scores.push(score.clone()),
+                    Err(e) => report.failed.push(format!("{}: {}", score.session_id, e)),
+                }
+            }
+        }
+        report
+    }
+}
+
+/// Store a scanned session's score under `source`, recording the first of its files as
+/// the transcript
+async fn store_scheduled_score(db: &Database, score: &SessionScore, source: &str) -> Result<(), DbError> {
+    let files = db.get_session_scanned_files(&score.session_id).await?;
+    let path = files.first().map(|file| file.path.as_str());
+    db.save_session_score(score, source, path).await?;
+    Ok(())
+}
@@ -0,0 +1,115 @@
+//! Session ID derivation for scanned files. IDs must pass the alphanumeric,
+//! hyphen, and underscore validator, which raw file names often don't.
+
+use crate::transcript::JsonlSession;
+use crate::{content_hash, Transcript};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use std::path::Path;
+
+/// Longest ID, in bytes, produced by sanitizing a name
+const MAX_SANITIZED_LEN: usize = 128;
+
+/// Hex characters of the content SHA-256 used as an ID
+const CONTENT_HASH_ID_LEN: usize = 16;
+
+/// Hex characters of the namespace SHA-256 prefixed to namespaced IDs
+const NAMESPACE_HASH_LEN: usize = 8;
+
+/// Metadata keys checked, in order, by the frontmatter strategy
+const FRONTMATTER_ID_KEYS: [&str; 3] = ["session_id", "id", "session"];
+
+/// How a scanned session's ID is derived
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionIdStrategy {
+    /// File name (or multi-part base name) with invalid characters replaced
+    #[default]
+    FileName,
+    /// `session_id` / `id` from the transcript's frontmatter or metadata (or a multi-session
+    /// log's own session IDs), falling back to the file name
+    Frontmatter,
+    /// Prefix of a SHA-256 of the file content, so the same file gets the same ID anywhere
+    ContentHash,
+}
+
+impl SessionIdStrategy {
+    /// Name stored alongside ID mappings
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionIdStrategy::FileName => "file_name",
+            SessionIdStrategy::Frontmatter => "frontmatter",
+            SessionIdStrategy::ContentHash => "content_hash",
+        }
+    }
+    
+    /// Derive an ID for a session named `name` (file stem or part base) whose first
+    /// file is `path` and whose text is `content`
+    pub fn derive(&self, name: &str, path: &Path, content: &str) -> String {
+        match self {
+            SessionIdStrategy::FileName => sanitize(name),
+            SessionIdStrategy::Frontmatter => {
+                let extension = path.extension().and_then(|ext| ext.to_str());
+                Transcript::parse_for_extension(content, extension)
+                    .ok()
+                    .and_then(|transcript| {
+                        FRONTMATTER_ID_KEYS.iter().find_map(|key| match transcript.metadata.get(*key)? {
+                            Value::String(id) => Some(id.clone()),
+                            Value::Number(id) => Some(id.to_string()),
+                            _ => None,
+                        })
+                    })
+                    .map(|id| sanitize(&id))
+                    .unwrap_or_else(|| sanitize(name))
+            }
+            SessionIdStrategy::ContentHash => content_hash(content)[..CONTENT_HASH_ID_LEN].to_string(),
+        }
+    }
+    
+    /// Derive an ID for the session at `index` (from 0) in a multi-session file named `name`.
+    /// File names get a 1-based suffix, and the frontmatter strategy uses the ID recorded
+    /// in the log when there is one.
+    pub fn derive_part(&self, name: &str, index: usize, part: &JsonlSession) -> String {
+        let numbered = || sanitize(&format!("{}-{}", name, index + 1));
+        match self {
+            SessionIdStrategy::FileName => numbered(),
+            SessionIdStrategy::Frontmatter => part.id.as_deref().map(sanitize).unwrap_or_else(numbered),
+            SessionIdStrategy::ContentHash => content_hash(&part.content)[..CONTENT_HASH_ID_LEN].to_string(),
+        }
+    }
+}
+
+/// Whether `id` passes the session ID validator, so commands can reject it up front
+pub fn is_valid(id: &str) -> bool {
+    crate::security::validate_session_id(id)
+}
+
+/// ID for `raw` from `namespace` (e.g. the database it was imported from), prefixed with
+/// a hash of the namespace so equal raw IDs from different sources don't collide
+pub fn namespaced(namespace: &str, raw: &str) -> String {
+    sanitize(&format!("{}-{}", &content_hash(namespace)[..NAMESPACE_HASH_LEN], raw))
+}
+
+/// Replace runs of characters the session ID validator rejects with a single `-`
+pub fn sanitize(raw: &str) -> String {
+    let mut id = String::with_capacity(raw.len());
+    for c in raw.trim().chars() {
+        if c.is_alphanumeric() || c == '-' || c == '_' {
+            id.push(c);
+        } else if !id.ends_with('-') {
+            id.push('-');
+        }
+    }
+    
+    let mut id = id.trim_matches('-');
+    if id.len() > MAX_SANITIZED_LEN {
+        let end = (0..=MAX_SANITIZED_LEN).rev().find(|i| id.is_char_boundary(*i)).unwrap_or(0);
+        id = id[..end].trim_end_matches('-');
+    }
+    if id.is_empty() {
+        "session".to_string()
+    } else {
+        id.to_string()
+    }
+}
@@ -0,0 +1,139 @@
+//! App settings shared by every profile: where sessions live, the root that file
+//! access is confined to, and scoring preferences. Stored as JSON in the app's
+//! config directory next to the profile registry.
+
+use crate::alerts::AlertSettings;
+use crate::scan::{self, ScanOptions};
+use crate::BehaviorScorer;
+use serde::{Deserialize, Serialize};
+use specta::Type;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// Settings file name, in the app's config directory
+pub const SETTINGS_FILE: &str = "settings.json";
+
+/// Lowest percentages for each summary band
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, Type)]
+pub struct ScoreThresholds {
+    pub excellent: f64,
+    pub good: f64,
+    pub moderate: f64,
+}
+
+impl Default for ScoreThresholds {
+    fn default() -> Self {
+        Self {
+            excellent: 90.0,
+            good: 75.0,
+            moderate: 50.0,
+        }
+    }
+}
+
+/// Persisted app settings. Missing fields take their defaults, so older files load.
+#[derive(Debug, Clone, Default, Serialize, Deserialize, Type)]
+#[serde(default)]
+pub struct Settings {
+    /// Session directories offered for scanning and watching
+    pub scan_directories: Vec<String>,
+    /// Root that scanned, watched, imported, and dropped files must be under; the home
+    /// directory when unset
+    pub base_path: Option<String>,
+    pub thresholds: ScoreThresholds,
+    /// Options used when a scan or watch doesn't pass its own
+    pub scan_options: ScanOptions,
+    /// Record per-rule timings with each score
+    pub diagnostics: bool,
+    /// Minutes between background scans of `scan_directories`; off when unset
+    pub scan_interval_minutes: Option<u32>,
+    /// Desktop notifications for newly scored sessions
+    pub alerts: AlertSettings,
+    /// Profile whose rules judge sessions from each source, e.g. `codex` → `strict`.
+    /// Sources not listed are judged by the active profile.
+    pub source_profiles: BTreeMap<String, String>,
+}
+
+impl Settings {
+    /// Read the settings file, or the defaults if there isn't one yet
+    pub fn load_or_default(path: &Path) -> Result<Self, String> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let content = std::fs::read_to_string(path).map_err(|e| format!("Failed to read settings: {}", e))?;
+        serde_json::from_str(&content).map_err(|e| format!("Invalid settings file: {}", e))
+    }
+
+    /// Write the settings file, replacing it atomically
+    pub fn save(&self, path: &Path) -> Result<(), String> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent).map_err(|e| format!("Failed to create config directory: {}", e))?;
+        }
+        let json = serde_json::to_string_pretty(self).map_err(|e| e.to_string())?;
+        let staging = path.with_extension("json.tmp");
+        std::fs::write(&staging, json).map_err(|e| format!("Failed to write settings: {}", e))?;
+        std::fs::rename(&staging, path).map_err(|e| format!("Failed to write settings: {}", e))
+    }
+
+    /// Problems that would stop the settings from being applied
+    pub fn validate(&self) -> Result<(), String> {
+        let ScoreThresholds { excellent, good, moderate } = self.thresholds;
+        let ordered = 0.0 <= moderate && moderate <= good && good <= excellent && excellent <= 100.0;
+        if !ordered {
+            return Err("Thresholds must satisfy 0 <= moderate <= good <= excellent <= 100".to_string());
+        }
+        if !(0.0..=100.0).contains(&self.alerts.below_percentage) {
+            return Err("Alert threshold must be between 0 and 100".to_string());
+        }
+        if self.scan_interval_minutes == Some(0) {
+            return Err("Scan interval must be at least one minute".to_string());
+        }
+        if self.source_profiles.iter().any(|(source, profile)| source.trim().is_empty() || profile.trim().is_empty()) {
+            return Err("Source profiles need both a source and a profile name".to_string());
+        }
+        if let Some(base) = &self.base_path {
+            if !Path::new(base).is_dir() {
+                return Err(format!("Base path is not a directory: {}", base));
+            }
+        }
+        scan::compile_globs(&self.scan_options.include)?;
+        scan::compile_globs(&self.scan_options.exclude)?;
+        Ok(())
+    }
+
+    /// Root that file access is confined to
+    pub fn base_path(&self) -> PathBuf {
+        self.base_path.as_ref().map(PathBuf::from).unwrap_or_else(default_base_path)
+    }
+
+    /// Profile configured to judge sessions from `source`, if any
+    pub fn profile_for_source(&self, source: &str) -> Option<&str> {
+        self.source_profiles.get(source).map(String::as_str)
+    }
+
+    /// Time between background scans, if they're enabled and there's anything to scan
+    pub fn scan_interval(&self) -> Option<Duration> {
+        if self.scan_directories.is_empty() {
+            return None;
+        }
+        self.scan_interval_minutes
+            .map(|minutes| Duration::from_secs(u64::from(minutes) * 60))
+    }
+
+    /// Configure a scorer with these settings, keeping its rules
+    pub fn apply(&self, scorer: BehaviorScorer) -> BehaviorScorer {
+        scorer
+            .with_base_path(self.base_path())
+            .with_thresholds(self.thresholds)
+            .with_diagnostics(self.diagnostics)
+    }
+}
+
+/// The user's home directory, or the working directory if it can't be found
+pub fn default_base_path() -> PathBuf {
+    std::env::var_os("HOME")
+        .or_else(|| std::env::var_os("USERPROFILE"))
+        .map(PathBuf::from)
+        .unwrap_or_else(|| PathBuf::from("."))
+}
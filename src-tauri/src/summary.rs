@@ -0,0 +1,169 @@
+//! Terminal summaries for the CLI: a score badge colored by band, then rules as
+//! aligned columns in green or red. Without color the layout is the same.
+
+use crate::db::ScoreBand;
+use crate::SessionScore;
+
+const RESET: &str = "\x1b[0m";
+const BOLD: &str = "\x1b[1m";
+const DIM: &str = "\x1b[2m";
+const RED: &str = "\x1b[31m";
+const GREEN: &str = "\x1b[32m";
+const YELLOW: &str = "\x1b[33m";
+const BADGE_GREEN: &str = "\x1b[1;30;42m";
+const BADGE_YELLOW: &str = "\x1b[1;30;43m";
+const BADGE_RED: &str = "\x1b[1;37;41m";
+
+/// Wraps text in ANSI styles, or leaves it alone when color is off
+#[derive(Debug, Clone, Copy)]
+pub struct Style {
+    color: bool,
+}
+
+impl Style {
+    pub fn new(color: bool) -> Self {
+        Self { color }
+    }
+
+    fn paint(self, code: &str, text: &str) -> String {
+        if self.color {
+            format!("{}{}{}", code, text, RESET)
+        } else {
+            text.to_string()
+        }
+    }
+
+    /// The percentage as a badge: background colored by band, bracketed without color
+    pub fn badge(self, percentage: f64) -> String {
+        let code = match ScoreBand::of(percentage) {
+            ScoreBand::Excellent | ScoreBand::Good => BADGE_GREEN,
+            ScoreBand::Moderate => BADGE_YELLOW,
+            ScoreBand::Poor => BADGE_RED,
+        };
+        if self.color {
+            self.paint(code, &format!(" {:.1}% ", percentage))
+        } else {
+            format!("[{:.1}%]", percentage)
+        }
+    }
+
+    /// The percentage right-aligned to `width`, colored by band
+    pub fn percentage(self, percentage: f64, width: usize) -> String {
+        let code = match ScoreBand::of(percentage) {
+            ScoreBand::Excellent | ScoreBand::Good => GREEN,
+            ScoreBand::Moderate => YELLOW,
+            ScoreBand::Poor => RED,
+        };
+        self.paint(code, &format!("{:>width$}", format!("{:.1}%", percentage), width = width))
+    }
+
+    pub fn status(self, passed: bool) -> String {
+        if passed {
+            self.paint(GREEN, "PASS")
+        } else {
+            self.paint(RED, "FAIL")
+        }
+    }
+
+    pub fn heading(self, text: &str) -> String {
+        self.paint(BOLD, text)
+    }
+
+    pub fn dim(self, text: &str) -> String {
+        self.paint(DIM, text)
+    }
+
+    pub fn warning(self, text: &str) -> String {
+        self.paint(YELLOW, text)
+    }
+}
+
+/// Width of the widest of `texts`, in characters
+fn column_width<'a>(texts: impl Iterator<Item = &'a str>) -> usize {
+    texts.map(|text| text.chars().count()).max().unwrap_or_default()
+}
+
+/// One session's score: badge, pass count, warnings, summary, then one aligned row per
+/// rule with its status and confidence, failures followed by their suggestion
+pub fn score_summary(score: &SessionScore, style: Style) -> String {
+    let mut out = format!(
+        "{} {}  {}/{} rules passed\n",
+        style.heading(&score.session_id),
+        style.badge(score.score_percentage),
+        score.passed_rules,
+        score.total_rules
+    );
+    out.push_str(&style.dim(&format!("Scorer v{} (rules {})", score.scorer_version, score.rule_set_hash)));
+    out.push('\n');
+    for warning in &score.warnings {
+        out.push_str(&style.warning(&format!("Warning: {}", warning)));
+        out.push('\n');
+    }
+    out.push_str(&format!("\n{}\n", score.summary));
+
+    if !score.rules.is_empty() {
+        out.push_str(&format!("\n{}\n", style.heading("Rule Details:")));
+    }
+    let name_width = column_width(score.rules.iter().map(|check| check.rule_name.as_str()));
+    for check in &score.rules {
+        out.push_str(&format!(
+            "  {}  {:<width$}  {}\n",
+            style.status(check.passed),
+            check.rule_name,
+            style.dim(&format!("{:>4.0}%", check.confidence * 100.0)),
+            width = name_width
+        ));
+        if let Some(suggestion) = check.suggestion.as_ref().filter(|_| !check.passed) {
+            out.push_str(&format!("        {}\n", style.dim(suggestion)));
+        }
+    }
+
+    if let Some(diagnostics) = &score.diagnostics {
+        out.push_str(&format!(
+            "\n{}\n",
+            style.heading(&format!("Rule Timings ({:.3}ms total):", diagnostics.total_ms))
+        ));
+        for timing in diagnostics.slowest() {
+            out.push_str(&format!("  {:>10.3}ms  {}\n", timing.elapsed_ms, timing.rule_id));
+        }
+    }
+    out
+}
+
+/// A scan's average badge, then one aligned row per session with its percentage and
+/// pass count, noting duplicates
+pub fn scan_summary(scores: &[SessionScore], style: Style) -> String {
+    let average = if scores.is_empty() {
+        0.0
+    } else {
+        scores.iter().map(|score| score.score_percentage).sum::<f64>() / scores.len() as f64
+    };
+    let mut out = format!("Scanned {} sessions\nAverage score: {}\n", scores.len(), style.badge(average));
+    if scores.is_empty() {
+        return out;
+    }
+
+    out.push_str(&format!("\n{}\n", style.heading("Individual Scores:")));
+    let id_width = column_width(scores.iter().map(|score| score.session_id.as_str()));
+    let passed: Vec<String> = scores
+        .iter()
+        .map(|score| format!("{}/{}", score.passed_rules, score.total_rules))
+        .collect();
+    let passed_width = column_width(passed.iter().map(String::as_str));
+    for (score, passed) in scores.iter().zip(&passed) {
+        let mut row = format!(
+            "  {:<id_width$}  {}  {:>passed_width$}",
+            score.session_id,
+            style.percentage(score.score_percentage, 6),
+            passed,
+            id_width = id_width,
+            passed_width = passed_width
+        );
+        if let Some(original) = &score.duplicate_of {
+            row.push_str(&format!("  {}", style.dim(&format!("(duplicate of {})", original))));
+        }
+        out.push_str(&row);
+        out.push('\n');
+    }
+    out
+}
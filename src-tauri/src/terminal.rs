@@ -0,0 +1,60 @@
+//! Terminal output cleanup: transcripts captured from TUIs carry ANSI color codes,
+//! cursor movement, and carriage-return progress redraws that break rule matching.
+
+use regex::Regex;
+use std::borrow::Cow;
+use std::sync::OnceLock;
+
+/// CSI sequences (colors, cursor movement), OSC sequences (titles, hyperlinks),
+/// the remaining two-byte escapes, and any ESC left over, so cleaned text has none
+fn escape_sequence() -> &'static Regex {
+    static ESCAPE: OnceLock<Regex> = OnceLock::new();
+    ESCAPE.get_or_init(|| {
+        Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(?:\x07|\x1b\\)|\x1b[@-Z\\-_]|\x1b")
+            .expect("escape sequence pattern is valid")
+    })
+}
+
+/// Whether text contains anything `clean_terminal_output` would change
+pub fn has_terminal_artifacts(text: &str) -> bool {
+    text.contains('\x1b') || text.split('\n').any(|line| line.trim_end_matches('\r').contains('\r'))
+}
+
+/// Strip escape sequences and collapse carriage-return overwrites to what the
+/// terminal would have shown. Text without either is returned unchanged.
+pub fn clean_terminal_output(text: &str) -> Cow<'_, str> {
+    if !has_terminal_artifacts(text) {
+        return Cow::Borrowed(text);
+    }
+    
+    let stripped = escape_sequence().replace_all(text, "");
+    let lines: Vec<Cow<str>> = stripped.split('\n').map(collapse_overwrites).collect();
+    Cow::Owned(lines.join("\n"))
+}
+
+/// Replay `\r` redraws on a single line: each segment overwrites the start of the
+/// line, leaving any longer earlier output visible. A trailing `\r` (CRLF) is kept.
+fn collapse_overwrites(line: &str) -> Cow<'_, str> {
+    let (body, crlf) = match line.strip_suffix('\r') {
+        Some(body) => (body, true),
+        None => (line, false),
+    };
+    if !body.contains('\r') {
+        return Cow::Borrowed(line);
+    }
+    
+    let mut screen: Vec<char> = Vec::new();
+    for segment in body.split('\r') {
+        for (column, c) in segment.chars().enumerate() {
+            match screen.get_mut(column) {
+                Some(cell) => *cell = c,
+                None => screen.push(c),
+            }
+        }
+    }
+    let mut collapsed: String = screen.into_iter().collect();
+    if crlf {
+        collapsed.push('\r');
+    }
+    Cow::Owned(collapsed)
+}
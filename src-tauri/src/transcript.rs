@@ -0,0 +1,636 @@
+//! Structured transcript model and parsers
+//!
+//! Session logs arrive as JSONL event streams (Claude Code, Codex) where each
+//! line is a message, tool call, or metadata event, or as chat-completions JSON
+//! exports. Parsers normalize them into a `Transcript` of role-tagged turns that
+//! the scorer can evaluate. Legacy markdown/plain-text sessions fall back to a
+//! heuristic split on speaker labels that renders back to the original text.
+
+use crate::parsers::ParserRegistry;
+use chrono::{DateTime, Utc};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use specta::Type;
+use std::collections::BTreeMap;
+use std::sync::OnceLock;
+use thiserror::Error;
+
+/// Transcript parsing errors
+#[derive(Debug, Error)]
+pub enum TranscriptError {
+    #[error("Invalid JSON on line {line}: {message}")]
+    InvalidJson { line: usize, message: String },
+    #[error("Invalid chat JSON: {0}")]
+    InvalidChat(String),
+    #[error("No conversation turns found")]
+    Empty,
+}
+
+/// Speaker of a turn
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum Role {
+    System,
+    User,
+    Assistant,
+    Tool,
+    /// Unlabelled text in a plain-text transcript
+    Unknown,
+}
+
+impl Role {
+    pub(crate) fn parse(value: &str) -> Option<Self> {
+        match value.to_ascii_lowercase().as_str() {
+            "system" | "developer" => Some(Role::System),
+            "user" | "human" => Some(Role::User),
+            "assistant" | "ai" | "model" => Some(Role::Assistant),
+            "tool" | "function" => Some(Role::Tool),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::System => "system",
+            Role::User => "user",
+            Role::Assistant => "assistant",
+            Role::Tool => "tool",
+            Role::Unknown => "unknown",
+        }
+    }
+}
+
+/// A tool invocation made during a turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct ToolCall {
+    pub name: String,
+    pub arguments: Option<String>,
+}
+
+/// A single message in a conversation
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, Type)]
+pub struct Turn {
+    pub role: Role,
+    pub content: String,
+    pub timestamp: Option<DateTime<Utc>>,
+    #[serde(default)]
+    pub tool_calls: Vec<ToolCall>,
+}
+
+/// Session details inferred from a transcript, for filtering and analytics
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct SessionInfo {
+    pub model: Option<String>,
+    pub started_at: Option<DateTime<Utc>>,
+    pub ended_at: Option<DateTime<Utc>>,
+    pub duration_seconds: Option<i64>,
+}
+
+/// Source format a transcript was parsed from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, Type)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptFormat {
+    /// Markdown or plain text, split heuristically on speaker labels
+    #[default]
+    PlainText,
+    Jsonl,
+    ChatJson,
+}
+
+/// A parsed session transcript
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, Type)]
+pub struct Transcript {
+    #[serde(default)]
+    pub format: TranscriptFormat,
+    pub turns: Vec<Turn>,
+    /// Session metadata such as YAML frontmatter (date, source, model)
+    #[serde(default)]
+    pub metadata: BTreeMap<String, Value>,
+}
+
+impl Transcript {
+    /// Build a transcript from plain text. Leading YAML frontmatter is moved into
+    /// `metadata`. Turns start at speaker-labelled lines (`User:`, `**Assistant**:`,
+    /// `## Human:` ...); text before the first label, or text with no labels at all,
+    /// becomes a single `Role::Unknown` turn.
+    pub fn from_plain_text(text: &str) -> Self {
+        let (metadata, text) = split_frontmatter(text);
+        let markers: Vec<(usize, Role)> = speaker_marker()
+            .captures_iter(text)
+            .filter_map(|caps| Some((caps.get(0)?.start(), Role::parse(&caps[1])?)))
+            .collect();
+
+        let mut turns = Vec::with_capacity(markers.len() + 1);
+        let first_marker = markers.first().map(|(start, _)| *start).unwrap_or(text.len());
+        if first_marker > 0 || markers.is_empty() {
+            turns.push(Turn::plain(Role::Unknown, &text[..first_marker]));
+        }
+        for (index, (start, role)) in markers.iter().enumerate() {
+            let end = markers.get(index + 1).map(|(next, _)| *next).unwrap_or(text.len());
+            turns.push(Turn::plain(*role, &text[*start..end]));
+        }
+
+        Self {
+            format: TranscriptFormat::PlainText,
+            turns,
+            metadata,
+        }
+    }
+
+    /// Parse content according to a file extension: `.jsonl` event streams, chat
+    /// exports in `.json` (other JSON falls back to plain text), and plain text otherwise
+    pub fn parse_for_extension(content: &str, extension: Option<&str>) -> Result<Self, TranscriptError> {
+        ParserRegistry::builtin().for_extension(extension).parse(content)
+    }
+
+    /// Parse content as a known format
+    pub fn parse_as(content: &str, format: TranscriptFormat) -> Result<Self, TranscriptError> {
+        match format {
+            TranscriptFormat::PlainText => Ok(Self::from_plain_text(content)),
+            TranscriptFormat::Jsonl => parse_jsonl(content),
+            TranscriptFormat::ChatJson => parse_chat_json(content),
+        }
+    }
+
+    /// Parse content with no file extension to go on: JSON-looking input is tried as
+    /// a chat export and then as JSONL, and anything else is plain text
+    pub fn detect(content: &str) -> Self {
+        let trimmed = content.trim_start();
+        if trimmed.starts_with('{') || trimmed.starts_with('[') {
+            if let Ok(transcript) = parse_chat_json(content).or_else(|_| parse_jsonl(content)) {
+                return transcript;
+            }
+        }
+        Self::from_plain_text(content)
+    }
+
+    /// Infer model and timing. Turn timestamps take precedence; otherwise metadata keys
+    /// (`model`, `started_at`/`start`/`date`, `ended_at`/`end`) and `Model:` lines are used.
+    pub fn infer_session_info(&self) -> SessionInfo {
+        let metadata_str = |keys: &[&str]| {
+            keys.iter()
+                .find_map(|key| self.metadata.get(*key).and_then(Value::as_str))
+                .map(str::trim)
+                .filter(|value| !value.is_empty())
+        };
+
+        let model = metadata_str(&["model", "model_name"])
+            .map(str::to_string)
+            .or_else(|| {
+                static MODEL_LINE: OnceLock<Regex> = OnceLock::new();
+                let model_line = MODEL_LINE.get_or_init(|| {
+                    Regex::new(r"(?im)^\s*model\s*:\s*(\S+)").expect("model line pattern is valid")
+                });
+                self.turns
+                    .iter()
+                    .find_map(|turn| model_line.captures(&turn.content))
+                    .map(|caps| caps[1].to_string())
+            });
+
+        let timestamps = self.turns.iter().filter_map(|turn| turn.timestamp);
+        let started_at = timestamps
+            .clone()
+            .min()
+            .or_else(|| metadata_str(&["started_at", "start", "date"]).and_then(parse_loose_timestamp));
+        let ended_at = timestamps
+            .max()
+            .or_else(|| metadata_str(&["ended_at", "end"]).and_then(parse_loose_timestamp));
+        let duration_seconds = match (started_at, ended_at) {
+            (Some(start), Some(end)) if end >= start => Some((end - start).num_seconds()),
+            _ => None,
+        };
+
+        SessionInfo {
+            model,
+            started_at,
+            ended_at,
+            duration_seconds,
+        }
+    }
+
+    /// Number of turns with a known speaker
+    pub fn labelled_turn_count(&self) -> usize {
+        self.turns.iter().filter(|turn| turn.role != Role::Unknown).count()
+    }
+
+    /// Render turns to the text rules are evaluated against, returning the text and each
+    /// turn's byte offset. Plain-text transcripts render back to the original text;
+    /// structured ones render as `role: content` blocks.
+    pub fn render(&self) -> (String, Vec<usize>) {
+        let mut text = String::new();
+        let mut offsets = Vec::with_capacity(self.turns.len());
+
+        if self.format == TranscriptFormat::PlainText {
+            for turn in &self.turns {
+                offsets.push(text.len());
+                text.push_str(&turn.content);
+            }
+            return (text, offsets);
+        }
+
+        for turn in &self.turns {
+            if !text.is_empty() {
+                text.push_str("\n\n");
+            }
+            offsets.push(text.len());
+            text.push_str(turn.role.as_str());
+            text.push_str(": ");
+            text.push_str(&turn.content);
+            for call in &turn.tool_calls {
+                text.push_str("\n[tool: ");
+                text.push_str(&call.name);
+                text.push(']');
+                if let Some(arguments) = &call.arguments {
+                    text.push(' ');
+                    text.push_str(arguments);
+                }
+            }
+        }
+
+        (text, offsets)
+    }
+}
+
+impl Turn {
+    fn plain(role: Role, content: &str) -> Self {
+        Self {
+            role,
+            content: content.to_string(),
+            timestamp: None,
+            tool_calls: Vec::new(),
+        }
+    }
+}
+
+/// Split a leading `---` delimited YAML frontmatter block from the body. Text without
+/// frontmatter, or whose frontmatter isn't a YAML mapping, is returned unchanged.
+fn split_frontmatter(text: &str) -> (BTreeMap<String, Value>, &str) {
+    let unchanged = (BTreeMap::new(), text);
+    let Some(rest) = text
+        .trim_start_matches('\u{feff}')
+        .strip_prefix("---")
+        .and_then(|rest| rest.strip_prefix("\r\n").or_else(|| rest.strip_prefix('\n')))
+    else {
+        return unchanged;
+    };
+
+    // Find the closing delimiter line
+    let mut offset = 0;
+    for line in rest.split_inclusive('\n') {
+        let trimmed = line.trim_end();
+        if trimmed == "---" || trimmed == "..." {
+            let yaml = &rest[..offset];
+            let body = &rest[offset + line.len()..];
+            return match serde_yaml::from_str::<BTreeMap<String, Value>>(yaml) {
+                Ok(metadata) => (metadata, body),
+                Err(_) => unchanged,
+            };
+        }
+        offset += line.len();
+    }
+
+    unchanged
+}
+
+/// Line-start speaker labels such as `User:`, `**Assistant**:`, or `## Human:`
+pub(crate) fn speaker_marker() -> &'static Regex {
+    static SPEAKER_MARKER: OnceLock<Regex> = OnceLock::new();
+    SPEAKER_MARKER.get_or_init(|| {
+        Regex::new(r"(?im)^\s*(?:#+\s*|\*\*|>\s*)?(user|assistant|human|system|tool|ai)\b(?:\*\*)?\s*:")
+            .expect("speaker marker pattern is valid")
+    })
+}
+
+/// Parse a JSONL event stream into a transcript.
+///
+/// Understands Claude Code records (`{"type":"user","message":{...}}`), Codex rollout
+/// records (`{"type":"response_item","payload":{...}}`), and bare
+/// `{"role":...,"content":...}` lines. Metadata events without a role are skipped.
+pub fn parse_jsonl(input: &str) -> Result<Transcript, TranscriptError> {
+    let mut turns = Vec::new();
+    let mut metadata = BTreeMap::new();
+
+    for (index, line) in input.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let event: Value = serde_json::from_str(line).map_err(|e| TranscriptError::InvalidJson {
+            line: index + 1,
+            message: e.to_string(),
+        })?;
+
+        if !metadata.contains_key("model") {
+            // Claude Code puts the model on assistant messages, Codex on turn_context payloads
+            let model = ["message", "payload"]
+                .iter()
+                .find_map(|key| event.get(*key)?.get("model")?.as_str())
+                .or_else(|| event.get("model")?.as_str());
+            if let Some(model) = model {
+                metadata.insert("model".to_string(), Value::String(model.to_string()));
+            }
+        }
+
+        if let Some(turn) = turn_from_event(&event) {
+            turns.push(turn);
+        }
+    }
+
+    if turns.is_empty() {
+        return Err(TranscriptError::Empty);
+    }
+
+    Ok(Transcript {
+        format: TranscriptFormat::Jsonl,
+        turns,
+        metadata,
+    })
+}
+
+/// Parse an OpenAI chat-completions style export: `{"messages":[{"role":...,"content":...}]}`
+/// or a bare array of messages.
+pub fn parse_chat_json(input: &str) -> Result<Transcript, TranscriptError> {
+    let document: Value =
+        serde_json::from_str(input).map_err(|e| TranscriptError::InvalidChat(e.to_string()))?;
+
+    let messages = match &document {
+        Value::Array(messages) => messages,
+        Value::Object(_) => document
+            .get("messages")
+            .and_then(Value::as_array)
+            .ok_or_else(|| TranscriptError::InvalidChat("missing \"messages\" array".to_string()))?,
+        _ => return Err(TranscriptError::InvalidChat("expected an object or array".to_string())),
+    };
+
+    let turns: Vec<Turn> = messages.iter().filter_map(turn_from_message).collect();
+    if turns.is_empty() {
+        return Err(TranscriptError::Empty);
+    }
+
+    let mut metadata = BTreeMap::new();
+    if let Some(model) = document.get("model").filter(|model| model.is_string()) {
+        metadata.insert("model".to_string(), model.clone());
+    }
+
+    Ok(Transcript {
+        format: TranscriptFormat::ChatJson,
+        turns,
+        metadata,
+    })
+}
+
+/// JSONL event types that begin a new session in a concatenated log
+const SESSION_START_TYPES: [&str; 3] = ["session_start", "session.start", "session_meta"];
+
+/// Per-event fields naming the session an event belongs to
+const EVENT_SESSION_KEYS: [&str; 2] = ["session_id", "sessionId"];
+
+/// One session split out of a multi-session JSONL file
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonlSession {
+    /// Session ID recorded in the log, if any
+    pub id: Option<String>,
+    /// `Jsonl` for event runs, `ChatJson` for a line holding a whole chat export
+    pub format: TranscriptFormat,
+    pub content: String,
+}
+
+/// Split a JSONL file holding several sessions. A line that is a complete chat export
+/// (`{"messages":[...]}`) is a session of its own; other events are grouped into
+/// sessions started by `session_start`/`session_meta` events or by a change in the
+/// events' `sessionId`. Runs without any conversation turns are dropped. Returns `None`
+/// when the file holds at most one session or isn't valid JSONL.
+pub fn split_jsonl_sessions(input: &str) -> Option<Vec<JsonlSession>> {
+    fn flush(current: &mut Option<(JsonlSession, bool)>, sessions: &mut Vec<JsonlSession>) {
+        if let Some((session, true)) = current.take() {
+            sessions.push(session);
+        }
+    }
+    let mut sessions = Vec::new();
+    // The session being collected, and whether it has any turns yet
+    let mut current: Option<(JsonlSession, bool)> = None;
+
+    for line in input.lines() {
+        let trimmed = line.trim();
+        if trimmed.is_empty() {
+            continue;
+        }
+        let event: Value = serde_json::from_str(trimmed).ok()?;
+
+        if event.get("messages").is_some_and(Value::is_array) {
+            flush(&mut current, &mut sessions);
+            sessions.push(JsonlSession {
+                id: string_field(&event, &["session_id", "sessionId", "id"]),
+                format: TranscriptFormat::ChatJson,
+                content: trimmed.to_string(),
+            });
+            continue;
+        }
+
+        let is_start = event
+            .get("type")
+            .and_then(Value::as_str)
+            .is_some_and(|kind| SESSION_START_TYPES.contains(&kind));
+        let id = if is_start {
+            string_field(&event, &["session_id", "sessionId", "id"])
+        } else {
+            string_field(&event, &EVENT_SESSION_KEYS)
+        };
+        let id_changed = match (&current, &id) {
+            (Some((session, _)), Some(id)) => session.id.as_ref().is_some_and(|current| current != id),
+            _ => false,
+        };
+        if is_start || id_changed {
+            flush(&mut current, &mut sessions);
+        }
+
+        let (session, has_turns) = current.get_or_insert_with(|| {
+            (
+                JsonlSession {
+                    id: None,
+                    format: TranscriptFormat::Jsonl,
+                    content: String::new(),
+                },
+                false,
+            )
+        });
+        if session.id.is_none() {
+            session.id = id;
+        }
+        *has_turns |= turn_from_event(&event).is_some();
+        session.content.push_str(trimmed);
+        session.content.push('\n');
+    }
+    flush(&mut current, &mut sessions);
+
+    (sessions.len() > 1).then_some(sessions)
+}
+
+/// First string (or number) value among `keys`, on the event or its `payload`
+fn string_field(event: &Value, keys: &[&str]) -> Option<String> {
+    keys.iter().find_map(|key| {
+        match event.get(*key).or_else(|| event.get("payload")?.get(*key))? {
+            Value::String(value) if !value.is_empty() => Some(value.clone()),
+            Value::Number(value) => Some(value.to_string()),
+            _ => None,
+        }
+    })
+}
+
+/// Extract a turn from one JSONL event, if it carries a message or tool call
+fn turn_from_event(event: &Value) -> Option<Turn> {
+    let timestamp = parse_timestamp(event.get("timestamp"));
+
+    // Messages are nested under `message` (Claude Code) or `payload` (Codex), or inline
+    let message = event
+        .get("message")
+        .filter(|m| m.is_object())
+        .or_else(|| event.get("payload").filter(|p| p.is_object()))
+        .unwrap_or(event);
+
+    let mut turn = turn_from_message(message)?;
+    turn.timestamp = turn.timestamp.or(timestamp);
+    Some(turn)
+}
+
+/// Build a turn from a message object
+fn turn_from_message(message: &Value) -> Option<Turn> {
+    let kind = message.get("type").and_then(Value::as_str).unwrap_or_default();
+    let timestamp = parse_timestamp(message.get("timestamp"));
+
+    // Codex function calls are standalone records rather than message content
+    match kind {
+        "function_call" | "custom_tool_call" | "local_shell_call" => {
+            return Some(Turn {
+                role: Role::Assistant,
+                content: String::new(),
+                timestamp,
+                tool_calls: vec![ToolCall {
+                    name: message.get("name").and_then(Value::as_str).unwrap_or(kind).to_string(),
+                    arguments: message
+                        .get("arguments")
+                        .or_else(|| message.get("input"))
+                        .or_else(|| message.get("action"))
+                        .map(value_to_text),
+                }],
+            });
+        }
+        "function_call_output" | "custom_tool_call_output" => {
+            return Some(Turn {
+                role: Role::Tool,
+                content: message.get("output").map(value_to_text).unwrap_or_default(),
+                timestamp,
+                tool_calls: Vec::new(),
+            });
+        }
+        _ => {}
+    }
+
+    let role = message
+        .get("role")
+        .and_then(Value::as_str)
+        .and_then(Role::parse)
+        .or_else(|| Role::parse(kind))?;
+
+    let mut content = String::new();
+    let mut tool_calls = Vec::new();
+    match message.get("content") {
+        Some(Value::String(text)) => content.push_str(text),
+        Some(Value::Array(blocks)) => {
+            for block in blocks {
+                collect_block(block, &mut content, &mut tool_calls);
+            }
+        }
+        _ => {}
+    }
+
+    // OpenAI-style tool_calls: [{"function": {"name", "arguments"}}]
+    if let Some(Value::Array(calls)) = message.get("tool_calls") {
+        for call in calls {
+            let function = call.get("function").unwrap_or(call);
+            if let Some(name) = function.get("name").and_then(Value::as_str) {
+                tool_calls.push(ToolCall {
+                    name: name.to_string(),
+                    arguments: function.get("arguments").map(value_to_text),
+                });
+            }
+        }
+    }
+
+    Some(Turn {
+        role,
+        content,
+        timestamp,
+        tool_calls,
+    })
+}
+
+/// Append a content block's text or tool call to the turn being built
+fn collect_block(block: &Value, content: &mut String, tool_calls: &mut Vec<ToolCall>) {
+    let push_text = |content: &mut String, text: &str| {
+        if !content.is_empty() {
+            content.push('\n');
+        }
+        content.push_str(text);
+    };
+
+    match block {
+        Value::String(text) => push_text(content, text),
+        Value::Object(_) => match block.get("type").and_then(Value::as_str) {
+            Some("tool_use") => tool_calls.push(ToolCall {
+                name: block.get("name").and_then(Value::as_str).unwrap_or("tool").to_string(),
+                arguments: block.get("input").map(value_to_text),
+            }),
+            Some("tool_result") => {
+                if let Some(result) = block.get("content") {
+                    push_text(content, &value_to_text(result));
+                }
+            }
+            _ => {
+                if let Some(text) = block.get("text").and_then(Value::as_str) {
+                    push_text(content, text);
+                }
+            }
+        },
+        _ => {}
+    }
+}
+
+/// Flatten a JSON value to text: strings as-is, text blocks joined, anything else serialized
+fn value_to_text(value: &Value) -> String {
+    match value {
+        Value::String(text) => text.clone(),
+        Value::Array(items) => items
+            .iter()
+            .map(|item| match item.get("text").and_then(Value::as_str) {
+                Some(text) => text.to_string(),
+                None => value_to_text(item),
+            })
+            .collect::<Vec<_>>()
+            .join("\n"),
+        other => other.to_string(),
+    }
+}
+
+/// Parse RFC 3339, `YYYY-MM-DD HH:MM:SS`, or a bare date (midnight UTC)
+pub(crate) fn parse_loose_timestamp(raw: &str) -> Option<DateTime<Utc>> {
+    if let Ok(dt) = DateTime::parse_from_rfc3339(raw) {
+        return Some(dt.with_timezone(&Utc));
+    }
+    if let Ok(naive) = chrono::NaiveDateTime::parse_from_str(raw, "%Y-%m-%d %H:%M:%S") {
+        return Some(naive.and_utc());
+    }
+    chrono::NaiveDate::parse_from_str(raw, "%Y-%m-%d")
+        .ok()
+        .and_then(|date| date.and_hms_opt(0, 0, 0))
+        .map(|naive| naive.and_utc())
+}
+
+fn parse_timestamp(value: Option<&Value>) -> Option<DateTime<Utc>> {
+    let raw = value?.as_str()?;
+    DateTime::parse_from_rfc3339(raw)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
@@ -0,0 +1,321 @@
+//! Terminal dashboard for `behavior-scorer tui`: a sortable session list that updates
+//! as watched transcripts are scored, with a drill-down into each session's rules.
+
+use crate::db::ScoreBand;
+use crate::SessionScore;
+use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use ratatui::layout::{Constraint, Layout, Rect};
+use ratatui::style::{Color, Modifier, Style};
+use ratatui::text::{Line, Span};
+use ratatui::widgets::{Block, Cell, Paragraph, Row, Table, TableState, Wrap};
+use ratatui::{DefaultTerminal, Frame};
+use std::io;
+use std::sync::mpsc::Receiver;
+use std::time::Duration;
+
+/// How often the dashboard checks for new scores while waiting for a key
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// Column the session list is sorted by
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SortKey {
+    Score,
+    Session,
+    Recent,
+}
+
+impl SortKey {
+    fn next(self) -> Self {
+        match self {
+            SortKey::Score => SortKey::Session,
+            SortKey::Session => SortKey::Recent,
+            SortKey::Recent => SortKey::Score,
+        }
+    }
+
+    fn label(self) -> &'static str {
+        match self {
+            SortKey::Score => "score",
+            SortKey::Session => "session",
+            SortKey::Recent => "recent",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum View {
+    Sessions,
+    Rules,
+}
+
+/// Dashboard state: sessions in display order, the selection, and which view is open
+pub struct Dashboard {
+    sessions: Vec<SessionScore>,
+    sort: SortKey,
+    reversed: bool,
+    view: View,
+    session_table: TableState,
+    rule_table: TableState,
+    status: String,
+}
+
+impl Dashboard {
+    /// A dashboard over `scores`, lowest score first so problems are on top
+    pub fn new(scores: Vec<SessionScore>) -> Self {
+        let mut dashboard = Self {
+            sessions: Vec::new(),
+            sort: SortKey::Score,
+            reversed: false,
+            view: View::Sessions,
+            session_table: TableState::default(),
+            rule_table: TableState::default(),
+            status: format!("Loaded {} sessions", scores.len()),
+        };
+        for score in scores {
+            dashboard.insert(score);
+        }
+        dashboard.sort();
+        dashboard.session_table.select((!dashboard.sessions.is_empty()).then_some(0));
+        dashboard
+    }
+
+    /// Sessions in display order
+    pub fn sessions(&self) -> &[SessionScore] {
+        &self.sessions
+    }
+
+    pub fn selected_session(&self) -> Option<&SessionScore> {
+        self.session_table.selected().and_then(|index| self.sessions.get(index))
+    }
+
+    /// Whether the rule drill-down is open
+    pub fn showing_rules(&self) -> bool {
+        self.view == View::Rules
+    }
+
+    /// Add or replace a session's score from the watcher, keeping the selection on the
+    /// same session. Errors are shown in the status line.
+    pub fn update(&mut self, result: Result<SessionScore, String>) {
+        let score = match result {
+            Ok(score) => score,
+            Err(e) => {
+                self.status = e;
+                return;
+            }
+        };
+        self.status = format!(
+            "{} scored {} at {:.1}%",
+            score.timestamp.format("%H:%M:%S"),
+            score.session_id,
+            score.score_percentage
+        );
+        // Inserting replaces in place or appends, so the selected index still holds
+        self.insert(score);
+        self.resort();
+        if self.session_table.selected().is_none() {
+            self.session_table.select(Some(0));
+        }
+    }
+
+    fn insert(&mut self, score: SessionScore) {
+        match self.sessions.iter_mut().find(|session| session.session_id == score.session_id) {
+            Some(existing) => *existing = score,
+            None => self.sessions.push(score),
+        }
+    }
+
+    fn sort(&mut self) {
+        match self.sort {
+            SortKey::Score => self.sessions.sort_by(|a, b| a.score_percentage.total_cmp(&b.score_percentage)),
+            SortKey::Session => self.sessions.sort_by(|a, b| a.session_id.cmp(&b.session_id)),
+            SortKey::Recent => self.sessions.sort_by_key(|session| std::cmp::Reverse(session.timestamp)),
+        }
+        if self.reversed {
+            self.sessions.reverse();
+        }
+    }
+
+    /// Resort, keeping the selected session selected
+    fn resort(&mut self) {
+        let selected = self.selected_session().map(|session| session.session_id.clone());
+        self.sort();
+        if let Some(id) = selected {
+            let index = self.sessions.iter().position(|session| session.session_id == id);
+            self.session_table.select(index);
+        }
+    }
+
+    /// Handle a key press, returning true when the dashboard should close
+    pub fn handle_key(&mut self, key: KeyCode) -> bool {
+        match (self.view, key) {
+            (_, KeyCode::Char('q')) | (View::Sessions, KeyCode::Esc) => return true,
+            (View::Sessions, KeyCode::Down | KeyCode::Char('j')) => self.session_table.select_next(),
+            (View::Sessions, KeyCode::Up | KeyCode::Char('k')) => self.session_table.select_previous(),
+            (View::Sessions, KeyCode::Char('s')) => {
+                self.sort = self.sort.next();
+                self.resort();
+            }
+            (View::Sessions, KeyCode::Char('r')) => {
+                self.reversed = !self.reversed;
+                self.resort();
+            }
+            (View::Sessions, KeyCode::Enter | KeyCode::Right | KeyCode::Char('l')) if self.selected_session().is_some() => {
+                self.view = View::Rules;
+                self.rule_table.select(Some(0));
+            }
+            (View::Rules, KeyCode::Down | KeyCode::Char('j')) => self.rule_table.select_next(),
+            (View::Rules, KeyCode::Up | KeyCode::Char('k')) => self.rule_table.select_previous(),
+            (View::Rules, KeyCode::Esc | KeyCode::Left | KeyCode::Char('h') | KeyCode::Backspace) => {
+                self.view = View::Sessions;
+            }
+            _ => {}
+        }
+        // select_next moves past the end, so keep both selections on a row
+        let sessions = self.sessions.len();
+        let rules = self.selected_session().map(|session| session.rules.len()).unwrap_or_default();
+        self.session_table.select(self.session_table.selected().map(|index| index.min(sessions.saturating_sub(1))));
+        self.rule_table.select(self.rule_table.selected().map(|index| index.min(rules.saturating_sub(1))));
+        false
+    }
+
+    /// Draw the dashboard
+    pub fn render(&mut self, frame: &mut Frame) {
+        let [header, body, footer] =
+            Layout::vertical([Constraint::Length(1), Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+        frame.render_widget(Paragraph::new(self.header()), header);
+        match self.view {
+            View::Sessions => self.render_sessions(frame, body),
+            View::Rules => self.render_rules(frame, body),
+        }
+        let keys = match self.view {
+            View::Sessions => "↑↓ select  enter rules  s sort  r reverse  q quit",
+            View::Rules => "↑↓ select  esc back  q quit",
+        };
+        let footer_line = Line::from(vec![
+            Span::styled(keys, Style::new().add_modifier(Modifier::DIM)),
+            Span::raw("  "),
+            Span::raw(self.status.as_str()),
+        ]);
+        frame.render_widget(Paragraph::new(footer_line), footer);
+    }
+
+    fn header(&self) -> Line<'_> {
+        let scored: Vec<_> = self.sessions.iter().filter(|session| session.duplicate_of.is_none()).collect();
+        let mut spans = vec![Span::styled("behavior-scorer", Style::new().add_modifier(Modifier::BOLD))];
+        spans.push(Span::raw(format!("  {} sessions", self.sessions.len())));
+        if !scored.is_empty() {
+            let average = scored.iter().map(|session| session.score_percentage).sum::<f64>() / scored.len() as f64;
+            spans.push(Span::raw("  average "));
+            spans.push(Span::styled(format!("{:.1}%", average), band_style(average)));
+        }
+        let direction = if self.reversed { " (reversed)" } else { "" };
+        spans.push(Span::raw(format!("  sorted by {}{}", self.sort.label(), direction)));
+        Line::from(spans)
+    }
+
+    fn render_sessions(&mut self, frame: &mut Frame, area: Rect) {
+        let rows = self.sessions.iter().map(|session| {
+            let note = match &session.duplicate_of {
+                Some(original) => format!("duplicate of {}", original),
+                None => session.warnings.first().cloned().unwrap_or_default(),
+            };
+            Row::new(vec![
+                Cell::from(session.session_id.as_str()),
+                Cell::from(format!("{:>6.1}%", session.score_percentage)).style(band_style(session.score_percentage)),
+                Cell::from(format!("{:>3}/{}", session.passed_rules, session.total_rules)),
+                Cell::from(session.timestamp.format("%Y-%m-%d %H:%M").to_string()),
+                Cell::from(note),
+            ])
+        });
+        let table = Table::new(
+            rows,
+            [
+                Constraint::Fill(2),
+                Constraint::Length(7),
+                Constraint::Length(7),
+                Constraint::Length(16),
+                Constraint::Fill(1),
+            ],
+        )
+        .header(Row::new(["Session", "Score", "Passed", "Scored", "Note"]).style(Style::new().add_modifier(Modifier::BOLD)))
+        .block(Block::bordered().title(" Sessions "))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, area, &mut self.session_table);
+    }
+
+    fn render_rules(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(session) = self.session_table.selected().and_then(|index| self.sessions.get(index)) else {
+            return;
+        };
+        let [list, detail] = Layout::vertical([Constraint::Min(0), Constraint::Length(8)]).areas(area);
+        let rows = session.rules.iter().map(|check| {
+            let (status, color) = if check.passed { ("PASS", Color::Green) } else { ("FAIL", Color::Red) };
+            Row::new(vec![
+                Cell::from(status).style(Style::new().fg(color)),
+                Cell::from(check.rule_name.as_str()),
+                Cell::from(format!("{:>4.0}%", check.confidence * 100.0)),
+                Cell::from(format!("{:>7}", check.match_count)),
+            ])
+        });
+        let title = format!(" {} ({:.1}%) ", session.session_id, session.score_percentage);
+        let table = Table::new(
+            rows,
+            [Constraint::Length(4), Constraint::Fill(1), Constraint::Length(5), Constraint::Length(7)],
+        )
+        .header(Row::new(["", "Rule", "Conf", "Matches"]).style(Style::new().add_modifier(Modifier::BOLD)))
+        .block(Block::bordered().title(title))
+        .row_highlight_style(Style::new().add_modifier(Modifier::REVERSED));
+        frame.render_stateful_widget(table, list, &mut self.rule_table);
+
+        let mut lines = Vec::new();
+        if let Some(check) = self.rule_table.selected().and_then(|index| session.rules.get(index)) {
+            let bold = Style::new().add_modifier(Modifier::BOLD);
+            lines.push(Line::from(check.description.as_str()));
+            if let Some(evidence) = &check.evidence {
+                lines.push(Line::from(vec![Span::styled("Evidence: ", bold), Span::raw(evidence.as_str())]));
+            }
+            if let Some(suggestion) = check.suggestion.as_ref().filter(|_| !check.passed) {
+                lines.push(Line::from(vec![Span::styled("Suggestion: ", bold), Span::raw(suggestion.as_str())]));
+            }
+        }
+        let paragraph = Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .block(Block::bordered().title(" Rule "));
+        frame.render_widget(paragraph, detail);
+    }
+
+    /// Draw and handle keys until the user quits, applying scores from `updates` as they arrive
+    pub fn run(mut self, terminal: &mut DefaultTerminal, updates: &Receiver<Result<SessionScore, String>>) -> io::Result<()> {
+        loop {
+            terminal.draw(|frame| self.render(frame))?;
+            if event::poll(POLL_INTERVAL)? {
+                if let Event::Key(key) = event::read()? {
+                    if key.kind == KeyEventKind::Press && self.handle_key(key.code) {
+                        return Ok(());
+                    }
+                }
+            }
+            for result in updates.try_iter() {
+                self.update(result);
+            }
+        }
+    }
+}
+
+/// Green for good scores, yellow for moderate, red for poor
+fn band_style(percentage: f64) -> Style {
+    let color = match ScoreBand::of(percentage) {
+        ScoreBand::Excellent | ScoreBand::Good => Color::Green,
+        ScoreBand::Moderate => Color::Yellow,
+        ScoreBand::Poor => Color::Red,
+    };
+    Style::new().fg(color)
+}
+
+/// Take over the terminal and run the dashboard over `scores`, restoring the terminal on exit
+pub fn run(scores: Vec<SessionScore>, updates: Receiver<Result<SessionScore, String>>) -> io::Result<()> {
+    let mut terminal = ratatui::try_init()?;
+    let result = Dashboard::new(scores).run(&mut terminal, &updates);
+    ratatui::restore();
+    result
+}
@@ -0,0 +1,127 @@
+//! Live watch mode: monitors session directories and scores transcript files
+//! once writes to them have settled.
+
+use crate::anomaly::{self, AnomalyThresholds};
+use crate::db::{Database, DbError, Score};
+use crate::scan::{self, ScanOptions};
+use crate::session_id::SessionIdStrategy;
+use crate::{BehaviorScorer, SessionScore};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::collections::BTreeSet;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, RecvTimeoutError};
+use std::thread;
+use std::time::Duration;
+
+/// Quiet period before a changed file is scored
+pub const DEFAULT_DEBOUNCE_MS: u64 = 500;
+
+/// Session source recorded for watched files
+pub const WATCH_SOURCE: &str = "watch";
+
+/// Handle for a running watcher; dropping it stops watching
+pub struct SessionWatcher {
+    _watcher: RecommendedWatcher,
+    directories: Vec<PathBuf>,
+}
+
+impl SessionWatcher {
+    /// Directories being watched
+    pub fn directories(&self) -> &[PathBuf] {
+        &self.directories
+    }
+}
+
+impl BehaviorScorer {
+    /// SECURITY: Watch directories within the base path and score transcript files as they
+    /// are created or modified. Bursts of events for a file are debounced into one score.
+    pub fn watch<F>(
+        &self,
+        directories: &[PathBuf],
+        options: &ScanOptions,
+        debounce: Duration,
+        mut on_score: F,
+    ) -> Result<SessionWatcher, String>
+    where
+        F: FnMut(&Path, Result<SessionScore, String>) + Send + 'static,
+    {
+        for dir in directories {
+            self.validate_directory(dir)?;
+        }
+        let include = scan::compile_globs(&options.include)?;
+        let exclude = scan::compile_globs(&options.exclude)?;
+        
+        let (tx, rx) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(tx).map_err(|e| format!("Failed to start watcher: {}", e))?;
+        let mode = if options.max_depth > 1 { RecursiveMode::Recursive } else { RecursiveMode::NonRecursive };
+        for dir in directories {
+            watcher
+                .watch(dir, mode)
+                .map_err(|e| format!("Failed to watch {}: {}", dir.display(), e))?;
+        }
+        
+        let scorer = self.clone();
+        let roots = directories.to_vec();
+        let extensions = options.extensions.clone();
+        let strategy = options.session_ids;
+        let wanted = move |path: &Path| {
+            let Some(relative) = roots.iter().find_map(|root| path.strip_prefix(root).ok()) else {
+                return false;
+            };
+            path.is_file()
+                && scan::has_transcript_extension(path, &extensions)
+                && (include.is_empty() || include.iter().any(|g| g.matches_path(relative)))
+                && !exclude.iter().any(|g| g.matches_path(relative))
+        };
+        
+        thread::spawn(move || {
+            let mut pending = BTreeSet::new();
+            loop {
+                // Block until something changes, then keep collecting until it goes quiet
+                let event = if pending.is_empty() {
+                    rx.recv().map_err(|_| RecvTimeoutError::Disconnected)
+                } else {
+                    rx.recv_timeout(debounce)
+                };
+                match event {
+                    Ok(Ok(event)) => {
+                        if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                            pending.extend(event.paths);
+                        }
+                    }
+                    Ok(Err(e)) => tracing::warn!(error = %e, "Watch error"),
+                    Err(RecvTimeoutError::Timeout) => {
+                        for path in std::mem::take(&mut pending) {
+                            if wanted(&path) {
+                                on_score(&path, scorer.score_path(&path, strategy));
+                            }
+                        }
+                    }
+                    Err(RecvTimeoutError::Disconnected) => break,
+                }
+            }
+        });
+        
+        Ok(SessionWatcher {
+            _watcher: watcher,
+            directories: directories.to_vec(),
+        })
+    }
+}
+
+/// Persist a watched file's score under `source`: the session (linked to any earlier
+/// session with the same content), its ID mapping, score row, rule checks, and stats.
+/// Failures the history says are unusual are flagged on `score` first.
+pub async fn persist_score(
+    db: &Database,
+    score: &mut SessionScore,
+    path: &Path,
+    strategy: SessionIdStrategy,
+    source: &str,
+) -> Result<Score, DbError> {
+    let path = path.to_string_lossy();
+    anomaly::flag_anomalies_from_history(db, score, &AnomalyThresholds::default()).await?;
+    let stored = db.save_session_score(score, source, Some(path.as_ref())).await?;
+    db.save_session_id_mapping(&path, &score.session_id, strategy).await?;
+    Ok(stored)
+}